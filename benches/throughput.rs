@@ -0,0 +1,151 @@
+//! Throughput benchmarks for decoding, the rate limiter and crate streams.
+//!
+//! Requests are served by the local [`MockServer`], so the numbers measure
+//! the overhead of the client and not the network. For rate limited
+//! requests, the configured wait is subtracted, so only the time the client
+//! spends beyond the rate limit is reported:
+//!
+//! ```sh
+//! cargo bench --features testing
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crates_io_api::testing::MockServer;
+use crates_io_api::{
+    AsyncClient, Crate, CrateResponse, CratesPage, CratesQuery, SyncClient, Version,
+};
+use futures::StreamExt;
+
+/// Run `f` `iterations` times and print the throughput.
+fn measure(name: &str, iterations: u32, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    report(name, iterations, start.elapsed());
+}
+
+fn report(name: &str, iterations: u32, elapsed: Duration) {
+    println!(
+        "{:<40} {:>10.0} ops/s {:>10.1} us/op",
+        name,
+        f64::from(iterations) / elapsed.as_secs_f64(),
+        elapsed.as_secs_f64() * 1e6 / f64::from(iterations),
+    );
+}
+
+/// The rate limit of the rate limited benchmarks.
+const RATE_LIMIT: Duration = Duration::from_millis(5);
+
+/// Print the overhead of `iterations` requests beyond [`RATE_LIMIT`].
+///
+/// The first request doesn't wait, every following one waits for the rate
+/// limit.
+fn report_rate_limited(name: &str, iterations: u32, elapsed: Duration) {
+    let waited = RATE_LIMIT * (iterations - 1);
+    report(name, iterations, elapsed.saturating_sub(waited));
+}
+
+fn crates_page() -> String {
+    let crates = (0..100)
+        .map(|i| Crate::new(&format!("crate-{}", i), "1.0.0"))
+        .collect();
+    serde_json::to_string(&CratesPage::new(crates, 10_000)).unwrap()
+}
+
+fn crate_response() -> String {
+    let versions = (0..500)
+        .map(|i| Version::new("foo", &format!("0.{}.0", i)))
+        .collect();
+    let response = CrateResponse::new(Crate::new("foo", "0.499.0"), versions);
+    serde_json::to_string(&response).unwrap()
+}
+
+fn bench_decoding() {
+    let page = crates_page();
+    measure("decode crates page (100 crates)", 1_000, || {
+        serde_json::from_str::<CratesPage>(&page).unwrap();
+    });
+    let krate = crate_response();
+    measure("decode crate (500 versions)", 200, || {
+        serde_json::from_str::<CrateResponse>(&krate).unwrap();
+    });
+}
+
+fn bench_sync_requests(server: &MockServer) {
+    let client = server.sync_client();
+    measure("sync requests, no rate limit", 500, || {
+        client.get_crate("foo").unwrap();
+    });
+
+    let client = SyncClient::from_config(&server.config().rate_limit(RATE_LIMIT)).unwrap();
+    let start = Instant::now();
+    for _ in 0..100 {
+        client.get_crate("foo").unwrap();
+    }
+    report_rate_limited("sync requests, rate limit overhead", 100, start.elapsed());
+}
+
+async fn bench_async(server: &MockServer) {
+    let client = server.async_client();
+
+    let start = Instant::now();
+    for _ in 0..500 {
+        client.get_crate("foo").await.unwrap();
+    }
+    report("async requests, no rate limit", 500, start.elapsed());
+
+    let start = Instant::now();
+    futures::future::try_join_all((0..500).map(|_| client.get_crate("foo")))
+        .await
+        .unwrap();
+    report(
+        "async concurrent requests, no rate limit",
+        500,
+        start.elapsed(),
+    );
+
+    let client = AsyncClient::from_config(&server.config().rate_limit(RATE_LIMIT)).unwrap();
+    let start = Instant::now();
+    for _ in 0..100 {
+        client.get_crate("foo").await.unwrap();
+    }
+    report_rate_limited("async requests, rate limit overhead", 100, start.elapsed());
+
+    let start = Instant::now();
+    futures::future::try_join_all((0..100).map(|_| client.get_crate("foo")))
+        .await
+        .unwrap();
+    report_rate_limited(
+        "async concurrent, rate limit overhead",
+        100,
+        start.elapsed(),
+    );
+    let client = server.async_client();
+
+    // Every page is the same, so the stream never ends on its own.
+    let start = Instant::now();
+    let count = client
+        .crates_stream(CratesQuery::builder().page_size(100).build())
+        .take(10_000)
+        .count()
+        .await;
+    report("crates stream items", count as u32, start.elapsed());
+}
+
+fn main() {
+    bench_decoding();
+
+    let server = MockServer::start()
+        .unwrap()
+        .with_crate("foo", &["1.0.0"])
+        .with_response("crates", 200, crates_page());
+    // The blocking client can't be used within a runtime.
+    bench_sync_requests(&server);
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(bench_async(&server));
+}
@@ -0,0 +1,291 @@
+//! Traits abstracting over the clients, for dependency injection.
+//!
+//! Code written against [`CratesIoApi`] or [`SyncCratesIoApi`] instead of a
+//! concrete client can be tested with a fake implementation, without network
+//! access:
+//!
+//! ```rust
+//! use crates_io_api::{CratesIoApi, Error};
+//!
+//! async fn newest_version(api: &impl CratesIoApi, name: &str) -> Result<String, Error> {
+//!     Ok(api.get_crate(name).await?.crate_data.max_version)
+//! }
+//! ```
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+use crate::types::*;
+use crate::{AsyncClient, Error, SyncClient};
+
+/// The read methods of [`AsyncClient`].
+///
+/// See the inherent methods of the client for details.
+pub trait CratesIoApi: Send + Sync {
+    /// Retrieve a summary containing crates.io wide information.
+    fn summary<'a>(&'a self) -> BoxFuture<'a, Result<Summary, Error>>;
+
+    /// Retrieve information of a crate.
+    fn get_crate<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<CrateResponse, Error>>;
+
+    /// Check if a crate exists.
+    fn crate_exists<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<bool, Error>>;
+
+    /// Check if a specific version of a crate exists.
+    fn version_exists<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<bool, Error>>;
+
+    /// Retrieve a single version of a crate.
+    fn crate_version<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Version, Error>>;
+
+    /// Retrieve download stats for a crate.
+    fn crate_downloads<'a>(
+        &'a self,
+        crate_name: &'a str,
+    ) -> BoxFuture<'a, Result<CrateDownloads, Error>>;
+
+    /// Retrieve the owners of a crate.
+    fn crate_owners<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<Vec<User>, Error>>;
+
+    /// Get a single page of reverse dependencies.
+    fn crate_reverse_dependencies_page<'a>(
+        &'a self,
+        crate_name: &'a str,
+        page: u64,
+    ) -> BoxFuture<'a, Result<ReverseDependencies, Error>>;
+
+    /// Retrieve the authors for a crate version.
+    fn crate_authors<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Authors, Error>>;
+
+    /// Retrieve the dependencies of a crate version.
+    fn crate_dependencies<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Dependency>, Error>>;
+
+    /// Retrieve a page of crates, optionally constrained by a query.
+    fn crates<'a>(&'a self, query: CratesQuery) -> BoxFuture<'a, Result<CratesPage, Error>>;
+
+    /// Retrieves a user by username.
+    fn user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<User, Error>>;
+
+    /// Retrieves a team by login.
+    fn team<'a>(&'a self, login: &'a str) -> BoxFuture<'a, Result<Team, Error>>;
+}
+
+/// The read methods of [`SyncClient`].
+///
+/// See the inherent methods of the client for details.
+pub trait SyncCratesIoApi {
+    /// Retrieve a summary containing crates.io wide information.
+    fn summary(&self) -> Result<Summary, Error>;
+
+    /// Retrieve information of a crate.
+    fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, Error>;
+
+    /// Check if a crate exists.
+    fn crate_exists(&self, crate_name: &str) -> Result<bool, Error>;
+
+    /// Check if a specific version of a crate exists.
+    fn version_exists(&self, crate_name: &str, version: &str) -> Result<bool, Error>;
+
+    /// Retrieve a single version of a crate.
+    fn crate_version(&self, crate_name: &str, version: &str) -> Result<Version, Error>;
+
+    /// Retrieve download stats for a crate.
+    fn crate_downloads(&self, crate_name: &str) -> Result<CrateDownloads, Error>;
+
+    /// Retrieve the owners of a crate.
+    fn crate_owners(&self, crate_name: &str) -> Result<Vec<User>, Error>;
+
+    /// Get a single page of reverse dependencies.
+    fn crate_reverse_dependencies_page(
+        &self,
+        crate_name: &str,
+        page: u64,
+    ) -> Result<ReverseDependencies, Error>;
+
+    /// Retrieve the authors for a crate version.
+    fn crate_authors(&self, crate_name: &str, version: &str) -> Result<Authors, Error>;
+
+    /// Retrieve the dependencies of a crate version.
+    fn crate_dependencies(&self, crate_name: &str, version: &str)
+        -> Result<Vec<Dependency>, Error>;
+
+    /// Retrieve a page of crates, optionally constrained by a query.
+    fn crates(&self, query: CratesQuery) -> Result<CratesPage, Error>;
+
+    /// Retrieves a user by username.
+    fn user(&self, username: &str) -> Result<User, Error>;
+
+    /// Retrieves a team by login.
+    fn team(&self, login: &str) -> Result<Team, Error>;
+}
+
+impl CratesIoApi for AsyncClient {
+    fn summary<'a>(&'a self) -> BoxFuture<'a, Result<Summary, Error>> {
+        AsyncClient::summary(self).boxed()
+    }
+
+    fn get_crate<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<CrateResponse, Error>> {
+        AsyncClient::get_crate(self, crate_name).boxed()
+    }
+
+    fn crate_exists<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<bool, Error>> {
+        AsyncClient::crate_exists(self, crate_name).boxed()
+    }
+
+    fn version_exists<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        AsyncClient::version_exists(self, crate_name, version).boxed()
+    }
+
+    fn crate_version<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Version, Error>> {
+        AsyncClient::crate_version(self, crate_name, version).boxed()
+    }
+
+    fn crate_downloads<'a>(
+        &'a self,
+        crate_name: &'a str,
+    ) -> BoxFuture<'a, Result<CrateDownloads, Error>> {
+        AsyncClient::crate_downloads(self, crate_name).boxed()
+    }
+
+    fn crate_owners<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<Vec<User>, Error>> {
+        AsyncClient::crate_owners(self, crate_name).boxed()
+    }
+
+    fn crate_reverse_dependencies_page<'a>(
+        &'a self,
+        crate_name: &'a str,
+        page: u64,
+    ) -> BoxFuture<'a, Result<ReverseDependencies, Error>> {
+        AsyncClient::crate_reverse_dependencies_page(self, crate_name, page).boxed()
+    }
+
+    fn crate_authors<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Authors, Error>> {
+        AsyncClient::crate_authors(self, crate_name, version).boxed()
+    }
+
+    fn crate_dependencies<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Dependency>, Error>> {
+        AsyncClient::crate_dependencies(self, crate_name, version).boxed()
+    }
+
+    fn crates<'a>(&'a self, query: CratesQuery) -> BoxFuture<'a, Result<CratesPage, Error>> {
+        AsyncClient::crates(self, query).boxed()
+    }
+
+    fn user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<User, Error>> {
+        AsyncClient::user(self, username).boxed()
+    }
+
+    fn team<'a>(&'a self, login: &'a str) -> BoxFuture<'a, Result<Team, Error>> {
+        AsyncClient::team(self, login).boxed()
+    }
+}
+
+impl SyncCratesIoApi for SyncClient {
+    fn summary(&self) -> Result<Summary, Error> {
+        SyncClient::summary(self)
+    }
+
+    fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, Error> {
+        SyncClient::get_crate(self, crate_name)
+    }
+
+    fn crate_exists(&self, crate_name: &str) -> Result<bool, Error> {
+        SyncClient::crate_exists(self, crate_name)
+    }
+
+    fn version_exists(&self, crate_name: &str, version: &str) -> Result<bool, Error> {
+        SyncClient::version_exists(self, crate_name, version)
+    }
+
+    fn crate_version(&self, crate_name: &str, version: &str) -> Result<Version, Error> {
+        SyncClient::crate_version(self, crate_name, version)
+    }
+
+    fn crate_downloads(&self, crate_name: &str) -> Result<CrateDownloads, Error> {
+        SyncClient::crate_downloads(self, crate_name)
+    }
+
+    fn crate_owners(&self, crate_name: &str) -> Result<Vec<User>, Error> {
+        SyncClient::crate_owners(self, crate_name)
+    }
+
+    fn crate_reverse_dependencies_page(
+        &self,
+        crate_name: &str,
+        page: u64,
+    ) -> Result<ReverseDependencies, Error> {
+        SyncClient::crate_reverse_dependencies_page(self, crate_name, page)
+    }
+
+    fn crate_authors(&self, crate_name: &str, version: &str) -> Result<Authors, Error> {
+        SyncClient::crate_authors(self, crate_name, version)
+    }
+
+    fn crate_dependencies(
+        &self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<Vec<Dependency>, Error> {
+        SyncClient::crate_dependencies(self, crate_name, version)
+    }
+
+    fn crates(&self, query: CratesQuery) -> Result<CratesPage, Error> {
+        SyncClient::crates(self, query)
+    }
+
+    fn user(&self, username: &str) -> Result<User, Error> {
+        SyncClient::user(self, username)
+    }
+
+    fn team(&self, login: &str) -> Result<Team, Error> {
+        SyncClient::team(self, login)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_clients_implement_traits() {
+        fn assert_async<T: CratesIoApi>() {}
+        fn assert_sync<T: SyncCratesIoApi>() {}
+        assert_async::<AsyncClient>();
+        assert_sync::<SyncClient>();
+
+        // Both traits can be used as trait objects.
+        let _: Option<Box<dyn CratesIoApi>> = None;
+        let _: Option<Box<dyn SyncCratesIoApi>> = None;
+    }
+}
@@ -1,31 +1,69 @@
 use futures::future::BoxFuture;
 use futures::prelude::*;
+use futures::stream::BoxStream;
 use futures::{future::try_join_all, try_join};
 use reqwest::{header, Client as HttpClient, StatusCode, Url};
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use super::Error;
-use crate::error::JsonDecodeError;
+use crate::endpoints::{self, Endpoint, Paginated};
+use crate::error::decode_json_checked;
+use crate::graph::ReverseDependencyWalk;
+#[cfg(feature = "semver")]
+use crate::graph::{DependencyTreeWalk, TreeRequest};
+use crate::snapshot::SnapshotProgress;
 use crate::types::*;
+use crate::{
+    CategorySnapshot, CrateName, Date, DependencyGraph, GraphLimits, SnapshotEntry, SnapshotOptions,
+};
+#[cfg(feature = "semver")]
+use crate::{DependencyFilter, DependencyTree};
 
 /// Asynchronous client for the crates.io API.
+///
+/// All requests are cancellation safe: dropping a request future at any
+/// point, eg in `select!` or because of a timeout, leaves the rate limiter
+/// consistent. A request that was already sent still counts against the rate
+/// limit, and a request that was cancelled while waiting for the rate limit
+/// still counts against the [`Budget`](crate::Budget). See
+/// [`RequestOptions::deadline`] and [`Self::send_until`] for built-in ways to
+/// give up on requests.
 #[derive(Clone)]
 pub struct Client {
     client: HttpClient,
     rate_limit: std::time::Duration,
-    last_request_time: std::sync::Arc<tokio::sync::Mutex<Option<tokio::time::Instant>>>,
+    request_lock: std::sync::Arc<crate::request_lock::RequestLock>,
+    /// Whether the requests of this client wait for all others.
+    low_priority: bool,
     base_url: Url,
+    unknown_fields: UnknownFields,
+    #[cfg(feature = "fixtures")]
+    fixtures: Option<std::sync::Arc<crate::fixtures::Fixtures>>,
+    #[cfg(feature = "faults")]
+    faults: Option<std::sync::Arc<crate::faults::Faults>>,
+    budget: Option<std::sync::Arc<crate::Budget>>,
+    limiter_state: Option<std::sync::Arc<crate::LimiterState>>,
+    cache: Option<(std::sync::Arc<dyn crate::CacheStore>, std::time::Duration)>,
+    shared_rate_limit: Option<std::sync::Arc<dyn crate::CacheStore>>,
+    query_defaults: QueryDefaults,
+    autocomplete: std::sync::Arc<crate::autocomplete::Autocomplete>,
 }
 
+/// A stream over the crates matching a query.
+///
+/// Returned by [`Client::crates_stream`]. Stops after the first error.
 pub struct CrateStream {
     client: Client,
     filter: CratesQuery,
 
     closed: bool,
+    last_page: bool,
     items: VecDeque<Crate>,
     next_page_fetch: Option<BoxFuture<'static, Result<CratesPage, Error>>>,
+    stable_order: Option<StableOrder>,
+    total: Option<u64>,
 }
 
 impl CrateStream {
@@ -34,10 +72,90 @@ impl CrateStream {
             client,
             filter,
             closed: false,
+            last_page: false,
             items: VecDeque::new(),
             next_page_fetch: None,
+            stable_order: None,
+            total: None,
         }
     }
+
+    /// Get the total count of crates matching the query.
+    ///
+    /// Returns `None` until the first page was fetched.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Crawl in a stable order, for crawls of many pages.
+    ///
+    /// Page numbers shift when crates are published or deleted during a
+    /// crawl, which skips or repeats crates. Instead, the crates are sorted
+    /// alphabetically and every page continues after the last crate of the
+    /// previous one, using the seek cursor of the API. Crates that are
+    /// yielded again anyway are skipped and recorded in
+    /// [`Self::duplicates`].
+    ///
+    /// The sort and page of the query are ignored. Must be called before
+    /// the stream is polled.
+    #[must_use]
+    pub fn stable_order(mut self) -> Self {
+        StableOrder::start(&mut self.filter);
+        self.stable_order = Some(StableOrder::default());
+        self
+    }
+
+    /// Get the names of the crates that were skipped because they were
+    /// already yielded, see [`Self::stable_order`].
+    pub fn duplicates(&self) -> &[String] {
+        self.stable_order
+            .as_ref()
+            .map_or(&[], |order| &order.duplicates)
+    }
+}
+
+/// The state of a crawl in a stable order, see [`CrateStream::stable_order`].
+#[derive(Debug, Default)]
+pub(crate) struct StableOrder {
+    seen: HashSet<String>,
+    pub(crate) duplicates: Vec<String>,
+    /// Whether the server sent pagination links for any page.
+    links: bool,
+}
+
+impl StableOrder {
+    /// Prepare the query of the first page.
+    pub(crate) fn start(query: &mut CratesQuery) {
+        query.set_sort(Sort::Alphabetical);
+        query.page = 1;
+        query.seek = Some(String::new());
+    }
+
+    /// Continue the query of the next page after a page with `meta`.
+    ///
+    /// Returns `false` if that was the last page, since the server sent
+    /// pagination links before but no next page now. Servers that never
+    /// send links are crawled by page number, which the caller increments
+    /// after every page.
+    pub(crate) fn advance(&mut self, query: &mut CratesQuery, meta: &Meta) -> bool {
+        self.links |= meta.next_page.is_some() || meta.prev_page.is_some();
+        query.seek = meta.next_seek();
+        !self.links || meta.has_next_page()
+    }
+
+    /// Drop and record the crates of a page that were already yielded.
+    pub(crate) fn dedup(&mut self, crates: Vec<Crate>) -> Vec<Crate> {
+        crates
+            .into_iter()
+            .filter(|krate| {
+                let new = self.seen.insert(krate.name.clone());
+                if !new {
+                    self.duplicates.push(krate.name.clone());
+                }
+                new
+            })
+            .collect()
+    }
 }
 
 impl futures::stream::Stream for CrateStream {
@@ -61,15 +179,33 @@ impl futures::stream::Stream for CrateStream {
             return match fut.poll_unpin(cx) {
                 std::task::Poll::Ready(res) => match res {
                     Ok(page) if page.crates.is_empty() => {
+                        inner.total.get_or_insert(page.meta.total);
                         inner.closed = true;
                         std::task::Poll::Ready(None)
                     }
                     Ok(page) => {
-                        let mut iter = page.crates.into_iter();
+                        // The first page is the most accurate, since it was
+                        // fetched before the others.
+                        inner.total.get_or_insert(page.meta.total);
+                        let crates = match &mut inner.stable_order {
+                            Some(order) => {
+                                inner.last_page = !order.advance(&mut inner.filter, &page.meta);
+                                order.dedup(page.crates)
+                            }
+                            None => page.crates,
+                        };
+                        let mut iter = crates.into_iter();
                         let next = iter.next();
                         inner.items.extend(iter);
 
-                        std::task::Poll::Ready(next.map(Ok))
+                        match next {
+                            Some(krate) => std::task::Poll::Ready(Some(Ok(krate))),
+                            // Only duplicates, continue with the next page.
+                            None => {
+                                cx.waker().wake_by_ref();
+                                std::task::Poll::Pending
+                            }
+                        }
                     }
                     Err(err) => {
                         inner.closed = true;
@@ -83,17 +219,18 @@ impl futures::stream::Stream for CrateStream {
             };
         }
 
+        if inner.last_page {
+            inner.closed = true;
+            return std::task::Poll::Ready(None);
+        }
+
         let filter = inner.filter.clone();
         inner.filter.page += 1;
 
         let c = inner.client.clone();
-        let mut f = Box::pin(async move { c.crates(filter).await });
-        assert!(matches!(f.poll_unpin(cx), std::task::Poll::Pending));
-        inner.next_page_fetch = Some(f);
-
-        cx.waker().clone().wake();
-
-        std::task::Poll::Pending
+        // Polled right away, since cached pages are ready without waiting.
+        inner.next_page_fetch = Some(Box::pin(async move { c.crates(filter).await }));
+        std::pin::Pin::new(inner).poll_next(cx)
     }
 }
 
@@ -147,35 +284,309 @@ impl Client {
     /// The guidelines suggest 1 per second or less.
     /// (Only one request is executed concurrenly, even if the given Duration is 0).
     pub fn with_http_client(client: HttpClient, rate_limit: std::time::Duration) -> Self {
-        let limiter = std::sync::Arc::new(tokio::sync::Mutex::new(None));
-
         Self {
             rate_limit,
-            last_request_time: limiter,
+            request_lock: Default::default(),
+            low_priority: false,
             client,
             base_url: Url::parse("https://crates.io/api/v1/").unwrap(),
+            unknown_fields: UnknownFields::Ignore,
+            #[cfg(feature = "fixtures")]
+            fixtures: None,
+            #[cfg(feature = "faults")]
+            faults: None,
+            budget: None,
+            limiter_state: None,
+            cache: None,
+            shared_rate_limit: None,
+            query_defaults: QueryDefaults::default(),
+            autocomplete: Default::default(),
+        }
+    }
+
+    /// Instantiate a new client from a configuration.
+    ///
+    /// Returns an error if the user agent, token or base URL is invalid.
+    pub fn from_config(config: &crate::ClientConfig) -> Result<Self, Error> {
+        let mut builder = HttpClient::builder().default_headers(config.headers()?);
+        if let Some(timeout) = config.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = config.connect_timeout {
+            builder = builder.connect_timeout(timeout);
         }
+        let mut client = Self::with_http_client(builder.build()?, config.rate_limit)
+            .with_base_url(&config.base_url)?
+            .unknown_fields(config.unknown_fields);
+        if let Some(budget) = &config.budget {
+            client = client.with_budget(budget.clone());
+        }
+        if let Some(state) = &config.limiter_state {
+            client = client.with_limiter_state(state.clone());
+        }
+        if let Some((cache, ttl)) = &config.cache {
+            client = client.with_cache(cache.clone(), *ttl);
+        }
+        if let Some(store) = &config.shared_rate_limit {
+            client = client.with_shared_rate_limit(store.clone());
+        }
+        client.query_defaults = config.query_defaults;
+        Ok(client)
+    }
+
+    /// Use a different API root, eg a mirror or a mock server.
+    ///
+    /// Returns an error if the URL is invalid.
+    pub fn with_base_url(mut self, base_url: &str) -> Result<Self, Error> {
+        let mut base_url = Url::parse(base_url)?;
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        self.base_url = base_url;
+        Ok(self)
+    }
+
+    /// Limit the number of requests, see [`Budget`](crate::Budget).
+    #[must_use]
+    pub fn with_budget(mut self, budget: std::sync::Arc<crate::Budget>) -> Self {
+        self.budget = Some(budget);
+        self.restore_budget();
+        self
+    }
+
+    /// Answer requests from a cache, see [`CacheStore`](crate::CacheStore).
+    ///
+    /// Responses are cached for `ttl`.
+    #[must_use]
+    pub fn with_cache(
+        mut self,
+        cache: std::sync::Arc<dyn crate::CacheStore>,
+        ttl: std::time::Duration,
+    ) -> Self {
+        self.cache = Some((cache, ttl));
+        self
+    }
+
+    /// Share the rate limit with all clients using the same store, eg
+    /// replicas of a bot sharing a `RedisStore` (requires the `redis` feature).
+    ///
+    /// Every request claims a key in the store for the duration of the rate
+    /// limit, see [`CacheStore::claim`](crate::CacheStore::claim).
+    #[must_use]
+    pub fn with_shared_rate_limit(mut self, store: std::sync::Arc<dyn crate::CacheStore>) -> Self {
+        self.shared_rate_limit = Some(store);
+        self
+    }
+
+    /// Sort the crate queries passed to this client that don't choose a sort
+    /// with `sort`, rather than [`Sort::RecentUpdates`].
+    ///
+    /// A sort set on the query, eg with [`CratesQueryBuilder::sort`], takes
+    /// precedence. The default doesn't apply to endpoints called with
+    /// [`Self::send`].
+    #[must_use]
+    pub fn with_default_sort(mut self, sort: Sort) -> Self {
+        self.query_defaults.sort = Some(sort);
+        self
+    }
+
+    /// Fetch the crate queries passed to this client that don't choose a
+    /// page size in pages of `page_size`, rather than 30.
+    ///
+    /// Crawls with the maximum of [`CratesQuery::MAX_PAGE_SIZE`] need the
+    /// fewest requests. A page size set on the query, eg with
+    /// [`CratesQueryBuilder::page_size`], takes precedence. The default
+    /// doesn't apply to endpoints called with [`Self::send`].
+    #[must_use]
+    pub fn with_default_page_size(mut self, page_size: u64) -> Self {
+        self.query_defaults.page_size = Some(page_size);
+        self
+    }
+
+    /// Cache the results of [`Self::autocomplete`] for `ttl`, separately from
+    /// the cache of other requests.
+    ///
+    /// A short TTL of a few minutes keeps typeahead snappy when users delete
+    /// and retype characters.
+    #[must_use]
+    pub fn with_autocomplete_cache(mut self, ttl: std::time::Duration) -> Self {
+        self.autocomplete = std::sync::Arc::new(crate::autocomplete::Autocomplete::with_cache(ttl));
+        self
+    }
+
+    /// Save the rate limiter and budget state to disk, see
+    /// [`LimiterState`](crate::LimiterState).
+    ///
+    /// The counters of an attached budget are restored from the state.
+    #[must_use]
+    pub fn with_limiter_state(mut self, state: std::sync::Arc<crate::LimiterState>) -> Self {
+        self.limiter_state = Some(state);
+        self.restore_budget();
+        self
+    }
+
+    fn restore_budget(&self) {
+        if let (Some(budget), Some(state)) = (&self.budget, &self.limiter_state) {
+            state.restore_budget(budget);
+        }
+    }
+
+    /// Inject faults into a share of the requests.
+    ///
+    /// Requires the `faults` feature. See the [`faults`](crate::faults)
+    /// module.
+    #[cfg(feature = "faults")]
+    #[must_use]
+    pub fn with_faults(mut self, faults: std::sync::Arc<crate::faults::Faults>) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
+    /// Record responses to, or replay them from, a fixture file.
+    ///
+    /// Requires the `fixtures` feature. See the [`fixtures`](crate::fixtures)
+    /// module.
+    #[cfg(feature = "fixtures")]
+    #[must_use]
+    pub fn with_fixtures(mut self, fixtures: std::sync::Arc<crate::fixtures::Fixtures>) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Set how fields in API responses that are not modelled by this crate
+    /// are treated.
+    ///
+    /// By default, they are ignored.
+    #[must_use]
+    pub fn unknown_fields(mut self, mode: UnknownFields) -> Self {
+        self.unknown_fields = mode;
+        self
+    }
+
+    async fn get<T: DeserializeOwned + Serialize + Send + 'static>(
+        &self,
+        url: &Url,
+    ) -> Result<T, Error> {
+        self.get_with(url, &RequestOptions::default()).await
+    }
+
+    async fn get_with<T: DeserializeOwned + Serialize + Send + 'static>(
+        &self,
+        url: &Url,
+        options: &RequestOptions,
+    ) -> Result<T, Error> {
+        let deadline = match options.deadline {
+            Some(deadline) => tokio::time::Instant::from_std(deadline),
+            None => return self.get_uncancelled(url, options).await,
+        };
+        tokio::time::timeout_at(deadline, self.get_uncancelled(url, options))
+            .await
+            .unwrap_or_else(|_| {
+                Err(Error::Cancelled(crate::error::CancelledError::new(
+                    "deadline exceeded",
+                )))
+            })
     }
 
-    async fn get<T: DeserializeOwned>(&self, url: &Url) -> Result<T, Error> {
-        let mut lock = self.last_request_time.clone().lock_owned().await;
+    async fn get_uncancelled<T: DeserializeOwned + Serialize + Send + 'static>(
+        &self,
+        url: &Url,
+        options: &RequestOptions,
+    ) -> Result<T, Error> {
+        let url = options.apply_query(url);
+
+        #[cfg(feature = "fixtures")]
+        if let Some(fixtures) = self.fixtures.as_ref().filter(|f| f.is_replay()) {
+            let content = fixtures.replay_response(&url)?;
+            return decode_response(&content, self.unknown_fields);
+        }
+
+        // Headers can change the response, so requests with extra headers
+        // neither use nor fill the cache.
+        let cache = self.cache.as_ref().filter(|_| options.headers.is_empty());
+
+        // Cache hits neither wait for nor count against any limit, so they are
+        // answered before the budget and the rate limiter are touched.
+        if let Some((cache, _)) = cache {
+            let key = url.to_string();
+            if let Some(content) = crate::cache::call(cache, move |c| c.get(&key)).await {
+                return decode_response(&content, self.unknown_fields);
+            }
+        }
+
+        if let Some(budget) = &self.budget {
+            budget.acquire()?;
+        }
+
+        let mut lock = self
+            .request_lock
+            .lock(self.rate_limit, self.low_priority)
+            .await;
 
-        if let Some(last_request_time) = lock.take() {
+        if let Some(last_request_time) = *lock {
             if last_request_time.elapsed() < self.rate_limit {
                 tokio::time::sleep(self.rate_limit - last_request_time.elapsed()).await;
             }
         }
+        if let Some(state) = &self.limiter_state {
+            tokio::time::sleep(state.wait_time(self.rate_limit)).await;
+        }
+        if let Some(store) = &self.shared_rate_limit {
+            let rate_limit = self.rate_limit;
+            while let Some(wait) = crate::cache::call(store, move |s| {
+                s.claim(crate::cache::RATE_LIMIT_KEY, rate_limit)
+            })
+            .await
+            {
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        #[cfg(feature = "faults")]
+        if let Some(faults) = &self.faults {
+            let injected = faults.inject();
+            if let Some(delay) = injected.delay {
+                tokio::time::sleep(delay).await;
+            }
+            if let Some(status) = injected.error {
+                return Err(crate::faults::status_error(&url, status));
+            }
+        }
+
+        if let Some(state) = &self.limiter_state {
+            if state.record_request(self.budget.as_deref()) {
+                let state = state.clone();
+                tokio::task::spawn_blocking(move || state.save_logged());
+            }
+        }
 
-        let time = tokio::time::Instant::now();
-        let res = self.client.get(url.clone()).send().await?;
+        // The rate limit counts from the start of the request, regardless of
+        // its outcome or of the future being dropped.
+        *lock = Some(tokio::time::Instant::now());
+        let res = self
+            .client
+            .get(url.clone())
+            .headers(options.headers.clone())
+            .send()
+            .await?;
 
         if !res.status().is_success() {
             let err = match res.status() {
-                StatusCode::NOT_FOUND => Error::NotFound(super::error::NotFoundError {
-                    url: url.to_string(),
-                }),
+                StatusCode::NOT_FOUND => {
+                    #[cfg(feature = "fixtures")]
+                    if let Some(fixtures) = &self.fixtures {
+                        fixtures.record_response(&url, StatusCode::NOT_FOUND, "");
+                    }
+                    Error::NotFound(super::error::NotFoundError {
+                        url: url.to_string(),
+                    })
+                }
                 StatusCode::FORBIDDEN => {
                     let reason = res.text().await.unwrap_or_default();
+                    #[cfg(feature = "fixtures")]
+                    if let Some(fixtures) = &self.fixtures {
+                        fixtures.record_response(&url, StatusCode::FORBIDDEN, &reason);
+                    }
                     Error::PermissionDenied(super::error::PermissionDeniedError { reason })
                 }
                 _ => Error::from(res.error_for_status().unwrap_err()),
@@ -184,50 +595,466 @@ impl Client {
             return Err(err);
         }
 
-        let content = res.text().await?;
+        #[cfg(feature = "stream-decode")]
+        if self.streams_bodies() {
+            let value = crate::stream_decode::decode_body(res).await;
+            drop(lock);
+            return value;
+        }
+
+        let content = body_text(res).await?;
 
         // Free up the lock
-        (*lock) = Some(time);
+        drop(lock);
 
-        // First, check for api errors.
+        #[cfg(feature = "fixtures")]
+        if let Some(fixtures) = &self.fixtures {
+            fixtures.record_response(&url, StatusCode::OK, &content);
+        }
 
-        if let Ok(errors) = serde_json::from_str::<ApiErrors>(&content) {
-            return Err(Error::Api(errors));
+        #[cfg(feature = "faults")]
+        let content = match &self.faults {
+            Some(faults) => faults.corrupt(content),
+            None => content,
+        };
+
+        let value = decode_response(&content, self.unknown_fields)?;
+        if let Some((cache, ttl)) = cache {
+            let (key, ttl) = (url.to_string(), *ttl);
+            crate::cache::call(cache, move |c| c.put(&key, content, ttl)).await;
         }
+        Ok(value)
+    }
 
-        let jd = &mut serde_json::Deserializer::from_str(&content);
-        serde_path_to_error::deserialize::<_, T>(jd).map_err(|err| {
-            Error::JsonDecode(JsonDecodeError {
-                message: format!("Could not decode JSON: {err} (path: {})", err.path()),
-            })
-        })
+    /// Check if response bodies can be decoded while they are received, which
+    /// requires that nothing else needs their text.
+    #[cfg(feature = "stream-decode")]
+    fn streams_bodies(&self) -> bool {
+        #[allow(unused_mut)]
+        let mut streams = self.cache.is_none() && self.unknown_fields == UnknownFields::Ignore;
+        #[cfg(feature = "fixtures")]
+        {
+            streams &= self.fixtures.is_none();
+        }
+        #[cfg(feature = "faults")]
+        {
+            streams &= self.faults.is_none();
+        }
+        streams
+    }
+
+    /// Call a typed endpoint, see the [`endpoints`](crate::endpoints) module.
+    pub async fn send<E: Endpoint>(&self, endpoint: &E) -> Result<E::Output, Error> {
+        let url = endpoint.url(&self.base_url)?;
+        self.get::<E::Response>(&url).await.map(E::output)
+    }
+
+    /// Call a typed endpoint, giving up with [`Error::Cancelled`] as soon as
+    /// `cancel` completes.
+    ///
+    /// Any future can be passed, eg the `cancelled()` future of a
+    /// `tokio_util::sync::CancellationToken`.
+    pub async fn send_until<E, F>(&self, endpoint: &E, cancel: F) -> Result<E::Output, Error>
+    where
+        E: Endpoint,
+        F: Future<Output = ()>,
+    {
+        futures::pin_mut!(cancel);
+        futures::select_biased! {
+            _ = cancel.fuse() => Err(Error::Cancelled(crate::error::CancelledError::new(
+                "cancelled by the caller",
+            ))),
+            output = self.send(endpoint).fuse() => output,
+        }
+    }
+
+    /// Call a typed endpoint with extra headers and query parameters.
+    pub async fn send_with<E: Endpoint>(
+        &self,
+        endpoint: &E,
+        options: &RequestOptions,
+    ) -> Result<E::Output, Error> {
+        let url = endpoint.url(&self.base_url)?;
+        self.get_with::<E::Response>(&url, options)
+            .await
+            .map(E::output)
+    }
+
+    /// Fetch `n` pages of a paginated endpoint, starting at its page.
+    ///
+    /// Stops early at an empty page, which ends the results. The returned
+    /// `next_page` can be set on the endpoint to collect the following pages
+    /// later.
+    pub async fn collect_pages<E: Paginated>(
+        &self,
+        endpoint: &E,
+        n: u64,
+    ) -> Result<CollectedPages<E::Item>, Error> {
+        let mut pages = CollectedPages::new(endpoint.page());
+        for _ in 0..n {
+            let page = match pages.next_page {
+                Some(page) => page,
+                None => break,
+            };
+            let url = endpoint.page_url(&self.base_url, page)?;
+            let output = self.get::<E::Response>(&url).await.map(E::output)?;
+            pages.push(E::items(output));
+        }
+        Ok(pages)
+    }
+
+    /// Send a GET request to an arbitrary API path and return the raw JSON.
+    ///
+    /// This is an escape hatch for endpoints that don't have a typed method
+    /// yet. The path is relative to the API root, eg
+    /// `"crates/serde/reverse_dependencies"`, and its segments are escaped.
+    /// The request is rate limited like all others.
+    pub async fn get_raw(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<serde_json::Value, Error> {
+        let url = build_raw_url(&self.base_url, path, query)?;
+        self.get(&url).await
+    }
+
+    /// Like [`Self::get_raw`], with the query in the options.
+    #[cfg(feature = "tower")]
+    pub(crate) async fn get_raw_with(
+        &self,
+        path: &str,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, Error> {
+        let url = build_raw_url(&self.base_url, path, &[])?;
+        self.get_with(&url, options).await
     }
 
     /// Retrieve a summary containing crates.io wide information.
     pub async fn summary(&self) -> Result<Summary, Error> {
-        let url = self.base_url.join("summary").unwrap();
-        self.get(&url).await
+        self.send(&endpoints::GetSummary).await
     }
 
     /// Retrieve information of a crate.
     ///
     /// If you require detailed information, consider using [full_crate]().
     pub async fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, Error> {
-        let url = build_crate_url(&self.base_url, crate_name)?;
+        self.send(&endpoints::GetCrate { crate_name }).await
+    }
 
-        self.get(&url).await
+    /// Retrieve information of a crate, tolerating `-` vs `_` mismatches.
+    ///
+    /// If the crate can't be found under the given name, the lookup is retried
+    /// with all separators replaced by `_` and then by `-`.
+    /// Use [`CrateResponse::crate_data`] to get the canonical name.
+    pub async fn get_crate_normalized(&self, crate_name: &str) -> Result<CrateResponse, Error> {
+        let mut candidates = crate::crate_name::lookup_candidates(crate_name).into_iter();
+        let mut res = self.get_crate(&candidates.next().unwrap()).await;
+        for name in candidates {
+            match res {
+                Err(Error::NotFound(_)) => res = self.get_crate(&name).await,
+                _ => break,
+            }
+        }
+        res
+    }
+
+    /// Get the canonical name of a crate, as reported by the API.
+    ///
+    /// See [`Self::get_crate_normalized`].
+    pub async fn canonical_crate_name(&self, crate_name: &str) -> Result<String, Error> {
+        self.get_crate_normalized(crate_name)
+            .await
+            .map(|res| res.crate_data.name)
+    }
+
+    /// Retrieve information for multiple crates.
+    ///
+    /// Returns a stream that yields each crate name together with its result,
+    /// in completion order.
+    /// At most `concurrency` requests are in flight at the same time, and all
+    /// of them still go through the rate limiter.
+    pub fn get_many_crates<I, S>(
+        &self,
+        names: I,
+        concurrency: usize,
+    ) -> BoxStream<'_, (String, Result<CrateResponse, Error>)>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        stream::iter(names)
+            .map(move |name| async move {
+                let res = self.get_crate(&name).await;
+                (name, res)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .boxed()
+    }
+
+    /// Check for updates of a list of dependencies.
+    ///
+    /// Takes pairs of crate names and version requirements, and returns the
+    /// newest matching and newest stable version for each, in the same order.
+    /// Crate names are looked up with [`Self::get_crate_normalized`].
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub async fn check_updates<N, R>(&self, deps: &[(N, R)]) -> Result<Vec<UpdateCheck>, Error>
+    where
+        N: AsRef<str>,
+        R: AsRef<str>,
+    {
+        let reqs = deps
+            .iter()
+            .map(|(_, req)| crate::semver::parse_req(req.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        try_join_all(
+            deps.iter()
+                .zip(&reqs)
+                .map(|((name, req), parsed)| async move {
+                    let krate = self.get_crate_normalized(name.as_ref()).await?;
+                    Ok::<_, Error>(UpdateCheck::new(req.as_ref(), parsed, &krate))
+                }),
+        )
+        .await
+    }
+
+    /// Suggest updates for a list of dependencies.
+    ///
+    /// Takes pairs of crate names and current versions, and returns the
+    /// newest compatible and newest incompatible version for each, in the
+    /// same order. Crate names are looked up with
+    /// [`Self::get_crate_normalized`].
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub async fn suggest_updates<N, V>(
+        &self,
+        deps: &[(N, V)],
+    ) -> Result<Vec<UpdateSuggestion>, Error>
+    where
+        N: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let versions = deps
+            .iter()
+            .map(|(_, version)| crate::semver::parse_version(version.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        try_join_all(
+            deps.iter()
+                .zip(&versions)
+                .map(|((name, _), current)| async move {
+                    let krate = self.get_crate_normalized(name.as_ref()).await?;
+                    Ok::<_, Error>(UpdateSuggestion::new(current, &krate))
+                }),
+        )
+        .await
+    }
+
+    /// Check the crates.io packages of a `Cargo.lock` for yanked versions.
+    ///
+    /// Requires the `lockfile` feature.
+    /// Every crate is only requested once, even if multiple versions of it
+    /// are locked.
+    #[cfg(feature = "lockfile")]
+    pub async fn audit_yanked(
+        &self,
+        lockfile: &crate::lockfile::Lockfile,
+    ) -> Result<crate::lockfile::LockfileAudit, Error> {
+        let crates = lockfile.crates_io_packages();
+        let mut audit = crate::lockfile::LockfileAudit::default();
+        let mut results = self.get_many_crates(crates.keys().copied(), 4);
+        while let Some((name, res)) = results.next().await {
+            audit.add(&crates[name.as_str()], res)?;
+        }
+        Ok(audit.finish())
+    }
+
+    /// Enrich the crates.io packages of a `Cargo.lock` with the live metadata
+    /// of their crates: the latest version, yanked status, downloads and
+    /// owners.
+    ///
+    /// Every crate takes two requests, even if multiple versions of it are
+    /// locked. The crates are fetched concurrently, each with its owners, so
+    /// only the rate limit of the client bounds how fast they are fetched.
+    ///
+    /// Requires the `lockfile` feature.
+    #[cfg(feature = "lockfile")]
+    pub async fn enrich_lockfile(
+        &self,
+        lockfile: &crate::lockfile::Lockfile,
+    ) -> Result<crate::lockfile::LockfileReport, Error> {
+        let crates = lockfile.crates_io_packages();
+        let mut report = crate::lockfile::LockfileReport::default();
+        let mut results = stream::iter(crates.keys().copied())
+            .map(|name| async move {
+                let res = self.get_crate(name).await;
+                // Owners are only fetched for crates that exist.
+                let owners = match &res {
+                    Ok(_) => Some(self.crate_owners(name).await),
+                    Err(_) => None,
+                };
+                (name, res, owners)
+            })
+            .buffer_unordered(crates.len().max(1));
+        while let Some((name, res, owners)) = results.next().await {
+            report.add(&crates[name], res, owners)?;
+        }
+        Ok(report.finish())
+    }
+
+    /// Fetch crates into the cache in the background, so that later calls of
+    /// [`Self::get_crate`] are answered without waiting.
+    ///
+    /// The returned future must be polled to make progress, eg by spawning
+    /// it. Prefetching has low priority: its requests are only sent while no
+    /// other request of the client or its clones waits for the rate limit,
+    /// so they delay those by at most one request. Errors are logged and
+    /// otherwise ignored. Without a cache, nothing is fetched.
+    pub fn prefetch<I, S>(&self, crate_names: I) -> BoxFuture<'static, ()>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut client = self.clone();
+        client.low_priority = true;
+        let crate_names: Vec<String> = crate_names.into_iter().map(Into::into).collect();
+        async move {
+            if client.cache.is_none() {
+                return;
+            }
+            for name in crate_names {
+                if let Err(err) = client.get_crate(&name).await {
+                    log::warn!("Could not prefetch {}: {}", name, err);
+                }
+            }
+        }
+        .boxed()
+    }
+
+    /// Check if a crate exists.
+    ///
+    /// Only fetches the bare crate record, without versions, keywords or
+    /// categories.
+    pub async fn crate_exists(&self, crate_name: &str) -> Result<bool, Error> {
+        // Invalid names can't exist, so they aren't an error here.
+        let res = match build_crate_exists_url(&self.base_url, crate_name) {
+            Ok(url) => self.get::<serde_json::Value>(&url).await,
+            Err(err) => Err(err),
+        };
+        exists(res)
+    }
+
+    /// Check if a specific version of a crate exists.
+    ///
+    /// Yanked versions exist.
+    pub async fn version_exists(&self, crate_name: &str, version: &str) -> Result<bool, Error> {
+        let res = match build_crate_version_url(&self.base_url, crate_name, version) {
+            Ok(url) => self.get::<serde_json::Value>(&url).await,
+            Err(err) => Err(err),
+        };
+        exists(res)
+    }
+
+    /// Retrieve a single version of a crate.
+    pub async fn crate_version(&self, crate_name: &str, version: &str) -> Result<Version, Error> {
+        self.send(&endpoints::GetVersion {
+            crate_name,
+            version,
+        })
+        .await
+    }
+
+    /// Compare the key metrics of multiple crates.
+    ///
+    /// The crates themselves are fetched in batches of up to 100, while the
+    /// release and reverse dependency data requires two more requests for
+    /// every crate. Crates that are named more than once are compared once.
+    pub async fn compare_crates<S: AsRef<str>>(
+        &self,
+        crate_names: &[S],
+    ) -> Result<CrateComparison, Error> {
+        let mut found = Vec::new();
+        for chunk in crate_names.chunks(CratesQuery::MAX_PAGE_SIZE as usize) {
+            found.extend(self.crates(CrateComparison::query(chunk)).await?.crates);
+        }
+        let (crates, missing) = CrateComparison::match_crates(crate_names, found);
+
+        let crates = try_join_all(crates.into_iter().map(|krate| async move {
+            let (version, reverse_dependencies) = try_join!(
+                self.crate_version(&krate.name, CrateComparison::compared_version(&krate)),
+                self.crate_reverse_dependency_count(&krate.name)
+            )?;
+            Ok::<_, Error>(CrateMetrics::new(krate, version, reverse_dependencies))
+        }))
+        .await?;
+        Ok(CrateComparison { crates, missing })
     }
 
     /// Retrieve download stats for a crate.
     pub async fn crate_downloads(&self, crate_name: &str) -> Result<CrateDownloads, Error> {
-        let url = build_crate_downloads_url(&self.base_url, crate_name)?;
-        self.get(&url).await
+        self.send(&endpoints::GetCrateDownloads { crate_name })
+            .await
+    }
+
+    /// Retrieve download stats for a crate, restricted to the days from `from`
+    /// to `to`, inclusive.
+    ///
+    /// Note: crates.io only provides the downloads of the last 90 days.
+    pub async fn downloads_between(
+        &self,
+        crate_name: &str,
+        from: Date,
+        to: Date,
+    ) -> Result<CrateDownloads, Error> {
+        let downloads = self.crate_downloads(crate_name).await?;
+        Ok(downloads.between(from, to))
+    }
+
+    /// Retrieve download stats for a single crate version.
+    ///
+    /// Unlike [`Self::crate_downloads`], this also works for versions whose
+    /// downloads are only included in the `extra_downloads`.
+    pub async fn crate_version_downloads(
+        &self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<Vec<VersionDownloads>, Error> {
+        self.send(&endpoints::GetVersionDownloads {
+            crate_name,
+            version,
+        })
+        .await
     }
 
     /// Retrieve the owners of a crate.
     pub async fn crate_owners(&self, name: &str) -> Result<Vec<User>, Error> {
-        let url = build_crate_owners_url(&self.base_url, name)?;
-        self.get::<Owners>(&url).await.map(|data| data.users)
+        self.send(&endpoints::GetOwners { crate_name: name }).await
+    }
+
+    /// Retrieve the owners of a crate, with teams told apart from users.
+    pub async fn crate_owners_typed(&self, name: &str) -> Result<Vec<Owner>, Error> {
+        let owners = self.crate_owners(name).await?;
+        Ok(owners.into_iter().map(Owner::from).collect())
+    }
+
+    /// Retrieve the owners of multiple crates, to find the users and teams
+    /// they have in common.
+    ///
+    /// The requests are performed concurrently, but still go through the
+    /// rate limiter.
+    pub async fn shared_owners<S: AsRef<str>>(
+        &self,
+        crate_names: &[S],
+    ) -> Result<OwnershipOverlap, Error> {
+        let owners = try_join_all(crate_names.iter().map(|name| async move {
+            let name = name.as_ref();
+            let owners = self.crate_owners(name).await?;
+            Ok::<_, Error>((name.to_string(), owners))
+        }))
+        .await?;
+        Ok(OwnershipOverlap::new(owners))
     }
 
     /// Get a single page of reverse dependencies.
@@ -238,19 +1065,8 @@ impl Client {
         crate_name: &str,
         page: u64,
     ) -> Result<ReverseDependencies, Error> {
-        // If page is zero, bump it to 1.
-        let page = page.max(1);
-
-        let url = build_crate_reverse_deps_url(&self.base_url, crate_name, page)?;
-        let page = self.get::<ReverseDependenciesAsReceived>(&url).await?;
-
-        let mut deps = ReverseDependencies {
-            dependencies: Vec::new(),
-            meta: Meta { total: 0 },
-        };
-        deps.meta.total = page.meta.total;
-        deps.extend(page);
-        Ok(deps)
+        self.send(&endpoints::GetReverseDependencies { crate_name, page })
+            .await
     }
 
     /// Load all reverse dependencies of a crate.
@@ -264,7 +1080,7 @@ impl Client {
     ) -> Result<ReverseDependencies, Error> {
         let mut deps = ReverseDependencies {
             dependencies: Vec::new(),
-            meta: Meta { total: 0 },
+            meta: Meta::new(0),
         };
 
         for page_number in 1.. {
@@ -282,116 +1098,344 @@ impl Client {
     }
 
     /// Get the total count of reverse dependencies for a given crate.
+    ///
+    /// Only a single reverse dependency is fetched.
     pub async fn crate_reverse_dependency_count(&self, crate_name: &str) -> Result<u64, Error> {
-        let page = self.crate_reverse_dependencies_page(crate_name, 1).await?;
-        Ok(page.meta.total)
+        self.send(&endpoints::CountReverseDependencies { crate_name })
+            .await
     }
 
-    /// Retrieve the authors for a crate version.
-    pub async fn crate_authors(&self, crate_name: &str, version: &str) -> Result<Authors, Error> {
-        let url = build_crate_authors_url(&self.base_url, crate_name, version)?;
-        self.get::<AuthorsResponse>(&url).await.map(|res| Authors {
-            names: res.meta.names,
-        })
+    /// Get the total count of reverse dependencies for multiple crates.
+    ///
+    /// Only a single reverse dependency is fetched for each crate.
+    /// The requests are performed concurrently, but still go through the
+    /// rate limiter.
+    pub async fn reverse_dependency_counts<S: AsRef<str>>(
+        &self,
+        crate_names: &[S],
+    ) -> Result<HashMap<String, u64>, Error> {
+        let counts = try_join_all(crate_names.iter().map(|name| async move {
+            let name = name.as_ref();
+            let count = self.crate_reverse_dependency_count(name).await?;
+            Ok::<_, Error>((name.to_string(), count))
+        }))
+        .await?;
+        Ok(counts.into_iter().collect())
     }
 
-    /// Retrieve the dependencies of a crate version.
-    pub async fn crate_dependencies(
+    /// Build a graph of all crates that transitively depend on the given
+    /// crate, ie all crates that would be affected if it broke.
+    ///
+    /// The graph is walked breadth-first within the given [`GraphLimits`].
+    /// Note: every crate in the graph requires at least one request, since
+    /// all of its reverse dependencies are fetched.
+    pub async fn reverse_dependency_graph(
         &self,
         crate_name: &str,
-        version: &str,
-    ) -> Result<Vec<Dependency>, Error> {
-        let url = build_crate_dependencies_url(&self.base_url, crate_name, version)?;
-        self.get::<Dependencies>(&url)
-            .await
-            .map(|res| res.dependencies)
-    }
-
-    async fn full_version(&self, version: Version) -> Result<FullVersion, Error> {
-        let authors_fut = self.crate_authors(&version.crate_name, &version.num);
-        let deps_fut = self.crate_dependencies(&version.crate_name, &version.num);
-
-        try_join!(authors_fut, deps_fut).map(|(authors, deps)| FullVersion {
-            created_at: version.created_at,
-            updated_at: version.updated_at,
-            dl_path: version.dl_path,
-            downloads: version.downloads,
-            features: version.features,
-            id: version.id,
-            num: version.num,
-            yanked: version.yanked,
-            license: version.license,
-            links: version.links,
-            readme_path: version.readme_path,
-
-            author_names: authors.names,
-            dependencies: deps,
-        })
+        limits: GraphLimits,
+    ) -> Result<DependencyGraph, Error> {
+        let mut walk = ReverseDependencyWalk::new(crate_name, limits);
+        while let Some(name) = walk.next_crate() {
+            let deps = self.crate_reverse_dependencies(&name).await?;
+            walk.add(&name, deps);
+        }
+        Ok(walk.finish())
     }
 
-    /// Retrieve all available information for a crate, including download
-    /// stats,  owners and reverse dependencies.
+    /// Compare the dependencies of two versions of a crate.
+    pub async fn diff_dependencies(
+        &self,
+        crate_name: &str,
+        old_version: &str,
+        new_version: &str,
+    ) -> Result<DependencyDiff, Error> {
+        let (old, new) = try_join!(
+            self.crate_dependencies(crate_name, old_version),
+            self.crate_dependencies(crate_name, new_version)
+        )?;
+        Ok(DependencyDiff::new(&old, &new))
+    }
+
+    /// Resolve the dependency tree of a crate version.
+    ///
+    /// Every dependency that passes the [`DependencyFilter`] is resolved to
+    /// the newest non-yanked version matching its requirement, and expanded
+    /// until `max_depth` levels of the tree are resolved.
+    /// Crate versions that appear multiple times are only expanded once.
+    ///
+    /// Note: every crate and crate version in the tree requires a request.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub async fn dependency_tree(
+        &self,
+        crate_name: &str,
+        version: &str,
+        max_depth: usize,
+        filter: &DependencyFilter,
+    ) -> Result<DependencyTree, Error> {
+        let mut walk = DependencyTreeWalk::new(crate_name, version, max_depth, filter.clone());
+        while let Some(request) = walk.next_request() {
+            match request {
+                TreeRequest::Dependencies { name, version } => {
+                    let deps = self.crate_dependencies(&name, &version).await?;
+                    walk.add_dependencies(&name, &version, deps);
+                }
+                TreeRequest::Versions(name) => {
+                    let versions = match self.get_crate(&name).await {
+                        Ok(res) => res.versions,
+                        Err(Error::NotFound(_)) => Vec::new(),
+                        Err(err) => return Err(err),
+                    };
+                    walk.add_versions(&name, &versions);
+                }
+            }
+        }
+        Ok(walk.finish())
+    }
+
+    /// Retrieve the authors for a crate version.
+    ///
+    /// If crates.io no longer provides the authors, an empty list is
+    /// returned, see [`Authors::unavailable`].
+    pub async fn crate_authors(&self, crate_name: &str, version: &str) -> Result<Authors, Error> {
+        Authors::from_response(
+            self.send(&endpoints::GetAuthors {
+                crate_name,
+                version,
+            })
+            .await,
+        )
+    }
+
+    /// Retrieve the dependencies of a crate version.
+    pub async fn crate_dependencies(
+        &self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<Vec<Dependency>, Error> {
+        self.send(&endpoints::GetDependencies {
+            crate_name,
+            version,
+        })
+        .await
+    }
+
+    async fn full_version(
+        &self,
+        version: Version,
+        options: &FullCrateOptions,
+    ) -> Result<FullVersion, Error> {
+        let authors_fut = async {
+            if options.authors {
+                self.crate_authors(&version.crate_name, &version.num)
+                    .await
+                    .map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        let deps_fut = async {
+            if options.dependencies {
+                self.crate_dependencies(&version.crate_name, &version.num)
+                    .await
+            } else {
+                Ok(Vec::new())
+            }
+        };
+
+        let (authors, dependencies) = try_join!(authors_fut, deps_fut)?;
+        Ok(FullVersion::new(version, authors, dependencies))
+    }
+
+    /// Retrieve all available information for a crate, including download
+    /// stats,  owners and reverse dependencies.
     ///
     /// The `all_versions` argument controls the retrieval of detailed version
     /// information.
     /// If false, only the data for the latest version will be fetched, if true,
     /// detailed information for all versions will be available.
     /// Note: Each version requires two extra requests.
+    ///
+    /// See [`Self::full_crate_with_options`] for more control over the
+    /// requests that are made.
     pub async fn full_crate(&self, name: &str, all_versions: bool) -> Result<FullCrate, Error> {
+        let options =
+            FullCrateOptions::new().max_versions(if all_versions { None } else { Some(1) });
+        self.full_crate_with_options(name, &options).await
+    }
+
+    /// Retrieve all available information for a crate, with version details
+    /// only for the versions selected by the filter.
+    pub async fn full_crate_versions(
+        &self,
+        name: &str,
+        filter: VersionFilter,
+    ) -> Result<FullCrate, Error> {
+        let options = FullCrateOptions::new().versions(filter);
+        self.full_crate_with_options(name, &options).await
+    }
+
+    /// Retrieve information for a crate, including only the parts selected
+    /// by the given [`FullCrateOptions`].
+    ///
+    /// Parts that are skipped are left empty in the returned [`FullCrate`].
+    pub async fn full_crate_with_options(
+        &self,
+        name: &str,
+        options: &FullCrateOptions,
+    ) -> Result<FullCrate, Error> {
+        let plan = self.full_crate_plan(name, options).await?;
+        self.full_crate_from_plan(plan, options).await
+    }
+
+    /// Fetch a crate and plan the requests needed to retrieve the rest of
+    /// the information selected by the options.
+    pub async fn full_crate_plan(
+        &self,
+        name: &str,
+        options: &FullCrateOptions,
+    ) -> Result<FullCratePlan, Error> {
         let krate = self.get_crate(name).await?;
-        let versions = if !all_versions {
-            self.full_version(krate.versions[0].clone())
-                .await
-                .map(|v| vec![v])
-        } else {
-            try_join_all(
-                krate
-                    .versions
-                    .clone()
-                    .into_iter()
-                    .map(|v| self.full_version(v)),
-            )
-            .await
-        }?;
+        Ok(FullCratePlan::new(krate, options, self.rate_limit))
+    }
+
+    /// Retrieve the rest of the information for a planned crate, see
+    /// [`Self::full_crate_plan`].
+    ///
+    /// The options should be the ones the plan was made with.
+    pub async fn full_crate_from_plan(
+        &self,
+        plan: FullCratePlan,
+        options: &FullCrateOptions,
+    ) -> Result<FullCrate, Error> {
+        let (krate, license, selected) = plan.into_parts();
+        let name = krate.crate_data.name.clone();
+        let name = name.as_str();
+        let total = selected.len();
+        let completed = std::sync::atomic::AtomicUsize::new(0);
+        let completed = &completed;
+        let versions = stream::iter(selected)
+            .map(|v| async move {
+                let version = self.full_version(v, options).await?;
+                options.report_progress(completed, total, &version.num);
+                Ok::<_, Error>(version)
+            })
+            .buffered(options.concurrency.unwrap_or(usize::MAX))
+            .try_collect()
+            .await?;
         let dls_fut = self.crate_downloads(name);
         let owners_fut = self.crate_owners(name);
-        let reverse_dependencies_fut = self.crate_reverse_dependencies(name);
-        try_join!(dls_fut, owners_fut, reverse_dependencies_fut).map(
-            |(dls, owners, reverse_dependencies)| {
-                let data = krate.crate_data;
-                FullCrate {
-                    id: data.id,
-                    name: data.name,
-                    description: data.description,
-                    license: krate.versions[0].license.clone(),
-                    documentation: data.documentation,
-                    homepage: data.homepage,
-                    repository: data.repository,
-                    total_downloads: data.downloads,
-                    recent_downloads: data.recent_downloads,
-                    max_version: data.max_version,
-                    max_stable_version: data.max_stable_version,
-                    created_at: data.created_at,
-                    updated_at: data.updated_at,
-                    categories: krate.categories,
-                    keywords: krate.keywords,
-                    downloads: dls,
-                    owners,
-                    reverse_dependencies,
-                    versions,
-                }
-            },
-        )
+        let reverse_dependencies_fut = async {
+            if options.reverse_dependencies {
+                self.crate_reverse_dependencies(name).await.map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        let (dls, owners, reverse_dependencies) =
+            try_join!(dls_fut, owners_fut, reverse_dependencies_fut)?;
+        Ok(FullCrate::new(
+            krate,
+            license,
+            dls,
+            owners,
+            reverse_dependencies,
+            versions,
+        ))
+    }
+
+    /// Estimate how many requests an operation sends, so that callers can
+    /// warn users or pick cheaper options before committing to it.
+    ///
+    /// Estimating sends requests itself: one for reverse dependencies and
+    /// crawls, and two for full crates. They are not part of the estimate.
+    pub async fn estimate_cost(
+        &self,
+        operation: &crate::Operation<'_>,
+    ) -> Result<crate::CostEstimate, Error> {
+        let requests = match *operation {
+            crate::Operation::FullCrate {
+                crate_name,
+                options,
+            } => {
+                let plan = self.full_crate_plan(crate_name, options).await?;
+                // The plan counts a single page of reverse dependencies.
+                let reverse_dependencies = if options.reverse_dependencies {
+                    let total = self.crate_reverse_dependency_count(crate_name).await?;
+                    crate::cost::reverse_dependency_pages(total) - 1
+                } else {
+                    0
+                };
+                1 + plan.requests() + reverse_dependencies
+            }
+            crate::Operation::ReverseDependencies { crate_name } => {
+                let total = self.crate_reverse_dependency_count(crate_name).await?;
+                crate::cost::reverse_dependency_pages(total)
+            }
+            crate::Operation::Crawl { query } => {
+                let total = self.crates_count(query).await?;
+                crate::cost::crawl_pages(&self.query_defaults.apply(query.clone()), total)
+            }
+        };
+        Ok(crate::CostEstimate::new(requests, self.rate_limit))
     }
 
     /// Retrieve a page of crates, optionally constrained by a query.
     ///
     /// If you want to get all results without worrying about paging,
     /// use [`all_crates`].
+    ///
+    /// Returns [`Error::InvalidQuery`] without performing a request if the
+    /// query is invalid (see [`CratesQuery::validate`]).
     pub async fn crates(&self, query: CratesQuery) -> Result<CratesPage, Error> {
-        let mut url = self.base_url.join("crates").unwrap();
-        query.build(url.query_pairs_mut());
-        self.get(&url).await
+        let query = self.query_defaults.apply(query);
+        self.send(&endpoints::ListCrates { query: &query }).await
+    }
+
+    /// Get the total count of crates matching a query.
+    ///
+    /// Only a single crate is fetched, the page and page size of the query
+    /// are ignored.
+    pub async fn crates_count(&self, query: &CratesQuery) -> Result<u64, Error> {
+        self.send(&endpoints::CountCrates { query }).await
+    }
+
+    /// Get the `limit` most relevant crates matching the beginning of a
+    /// name, for typeahead.
+    ///
+    /// At most [`CratesQuery::MAX_PAGE_SIZE`] crates are returned, and an
+    /// empty prefix is answered without a request. Starting another
+    /// autocomplete with this client or one of its clones cancels this one:
+    /// it fails with [`Error::Cancelled`] right away, so that only the
+    /// latest prefix waits for the rate limit.
+    pub async fn autocomplete(&self, prefix: &str, limit: u64) -> Result<Vec<Crate>, Error> {
+        if prefix.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some(crates) = self.autocomplete.cached(prefix, limit) {
+            return Ok(crates);
+        }
+        let number = self.autocomplete.start();
+        let query = crate::autocomplete::Autocomplete::query(prefix, limit);
+        let superseded = self.autocomplete.superseded(number, prefix);
+        futures::pin_mut!(superseded);
+        let crates = futures::select_biased! {
+            err = superseded.fuse() => return Err(err),
+            page = self.crates(query).fuse() => page?.crates,
+        };
+        self.autocomplete.store(prefix, limit, &crates);
+        Ok(crates)
+    }
+
+    /// Search for a crate with exactly the given name.
+    ///
+    /// Relies on the exact match detection of the server, which ignores case
+    /// and `-` vs `_` differences. Returns `None` if there is no exact match.
+    pub async fn find_crate_exact(&self, name: &str) -> Result<Option<Crate>, Error> {
+        let query = exact_match_query(name);
+        let page = self.crates(query).await?;
+        Ok(page.crates.into_iter().find(Crate::is_exact_match))
     }
 
     /// Get a stream over all crates matching the given [`CratesQuery`].
@@ -399,20 +1443,366 @@ impl Client {
         CrateStream::new(self.clone(), filter)
     }
 
+    /// Get a stream of all crates, newest first.
+    ///
+    /// Unlike [`Summary::new_crates`], which is limited to 10 entries, this
+    /// pages through the whole crates list. Crates that are published while
+    /// the stream is consumed shift the pages, so crates that were already
+    /// yielded are skipped.
+    pub fn new_crates_stream(&self) -> BoxStream<'static, Result<Crate, Error>> {
+        feed_stream(self.crates_stream(feed_query(Sort::NewlyAdded)))
+    }
+
+    /// Get a stream of all crates, most recently updated first.
+    ///
+    /// Unlike [`Summary::just_updated`], which is limited to 10 entries, this
+    /// pages through the whole crates list. See [`Self::new_crates_stream`].
+    pub fn just_updated_stream(&self) -> BoxStream<'static, Result<Crate, Error>> {
+        feed_stream(self.crates_stream(feed_query(Sort::RecentUpdates)))
+    }
+
+    /// Enumerate the names of all crates on crates.io, alphabetically.
+    ///
+    /// Crawls pages of [`CratesQuery::MAX_PAGE_SIZE`] crates in a stable
+    /// order (see [`CrateStream::stable_order`](crate::CrateStream::stable_order)),
+    /// so crates published or deleted during the crawl don't shift the
+    /// others. At one request per second, the crawl takes about half an
+    /// hour. Analyses that don't need live data can read the names from the
+    /// database dump (requires the `db-dump` feature) with a single download
+    /// instead.
+    pub fn all_crate_names(&self) -> BoxStream<'static, Result<String, Error>> {
+        self.crates_stream(all_crates_query())
+            .stable_order()
+            .map_ok(|krate| krate.name)
+            .boxed()
+    }
+
+    /// Get a stream of [`FullCrate`]s for all crates matching the given
+    /// [`CratesQuery`].
+    ///
+    /// Crates are enriched one at a time, so all requests go through the rate
+    /// limiter in order. Use the [`FullCrateOptions`] to limit the number of
+    /// requests per crate.
+    pub fn full_crates_stream(
+        &self,
+        query: CratesQuery,
+        options: FullCrateOptions,
+    ) -> BoxStream<'static, Result<FullCrate, Error>> {
+        let client = self.clone();
+        self.crates_stream(query)
+            .and_then(move |krate| {
+                let client = client.clone();
+                let options = options.clone();
+                async move { client.full_crate_with_options(&krate.name, &options).await }
+            })
+            .boxed()
+    }
+
+    /// Like [`Self::full_crates_stream`], but crates that are listed and
+    /// then not found when their details are fetched are reported as
+    /// [`CrawlEvent::Skipped`] instead of ending the stream with
+    /// [`Error::NotFound`].
+    ///
+    /// Crates are deleted or renamed all the time, so long crawls should
+    /// expect a few of them.
+    pub fn full_crates_events(
+        &self,
+        query: CratesQuery,
+        options: FullCrateOptions,
+    ) -> BoxStream<'static, Result<CrawlEvent<FullCrate>, Error>> {
+        let client = self.clone();
+        self.crates_stream(query)
+            .and_then(move |krate| {
+                let client = client.clone();
+                let options = options.clone();
+                async move {
+                    let res = client.full_crate_with_options(&krate.name, &options).await;
+                    CrawlEvent::from_result(krate.name, res)
+                }
+            })
+            .boxed()
+    }
+
     /// Retrieves a user by username.
     pub async fn user(&self, username: &str) -> Result<User, Error> {
-        let url = self.base_url.join(&format!("users/{}", username)).unwrap();
-        self.get::<UserResponse>(&url).await.map(|res| res.user)
+        self.send(&endpoints::GetUser { username }).await
+    }
+
+    /// Retrieves a team by login.
+    ///
+    /// Team logins have the form `github:<org>:<team>`.
+    pub async fn team(&self, login: &str) -> Result<Team, Error> {
+        self.send(&endpoints::GetTeam { login }).await
+    }
+
+    /// Get a stream over all crates owned by the user with the given login,
+    /// alphabetically.
+    ///
+    /// The user is resolved first, so an unknown login fails with
+    /// [`Error::NotFound`] instead of yielding no crates.
+    pub async fn crates_by_user(&self, login: &str) -> Result<CrateStream, Error> {
+        let user = self.user(login).await?;
+        Ok(self.crates_stream(user_crates_query(user.id)))
+    }
+
+    /// Retrieve all crates owned by the team with the given login.
+    ///
+    /// Note: this first resolves the team, and then pages through the crates
+    /// list, which will result in multiple requests.
+    pub async fn crates_owned_by_team(&self, team_login: &str) -> Result<Vec<Crate>, Error> {
+        let team = self.team(team_login).await?;
+        let query = CratesQuery::builder()
+            .team_id(team.id)
+            .page_size(100)
+            .build();
+        self.crates_stream(query).try_collect().await
+    }
+
+    /// Retrieves a keyword by its id.
+    pub async fn keyword(&self, id: &str) -> Result<Keyword, Error> {
+        self.send(&endpoints::GetKeyword { id }).await
+    }
+
+    /// Retrieves a category by its slug.
+    pub async fn category(&self, slug: &str) -> Result<Category, Error> {
+        self.send(&endpoints::GetCategory { slug }).await
+    }
+
+    /// Get a stream over all crates with a keyword, by recent downloads.
+    ///
+    /// The keyword is checked first, so an unknown keyword fails with
+    /// [`Error::NotFound`] instead of yielding no crates.
+    pub async fn crates_with_keyword(&self, keyword: &str) -> Result<CrateStream, Error> {
+        self.keyword(keyword).await?;
+        let query = CratesQuery::builder()
+            .keyword(keyword)
+            .sort(Sort::RecentDownloads)
+            .page_size(CratesQuery::MAX_PAGE_SIZE)
+            .build();
+        Ok(self.crates_stream(query))
+    }
+
+    /// Get a stream over all crates in a category, by recent downloads.
+    ///
+    /// The category is checked first, so an unknown slug fails with
+    /// [`Error::NotFound`] instead of yielding no crates.
+    pub async fn crates_in_category(&self, slug: &str) -> Result<CrateStream, Error> {
+        self.category(slug).await?;
+        let query = CratesQuery::builder()
+            .category(slug)
+            .sort(Sort::RecentDownloads)
+            .page_size(CratesQuery::MAX_PAGE_SIZE)
+            .build();
+        Ok(self.crates_stream(query))
+    }
+
+    /// Retrieve the `n` crates with the most downloads of all time.
+    ///
+    /// Unlike [`Summary::most_downloaded`], `n` isn't limited to 10.
+    pub async fn most_downloaded(&self, n: usize) -> Result<Vec<Crate>, Error> {
+        self.top_crates(n, Sort::Downloads).await
+    }
+
+    /// Retrieve the `n` crates with the most downloads in the last 90 days.
+    ///
+    /// Unlike [`Summary::most_recently_downloaded`], `n` isn't limited to 10.
+    pub async fn most_recently_downloaded(&self, n: usize) -> Result<Vec<Crate>, Error> {
+        self.top_crates(n, Sort::RecentDownloads).await
+    }
+
+    /// Retrieve the `n` most recently published new crates.
+    ///
+    /// Unlike [`Summary::new_crates`], `n` isn't limited to 10.
+    pub async fn newest_crates(&self, n: usize) -> Result<Vec<Crate>, Error> {
+        self.top_crates(n, Sort::NewlyAdded).await
+    }
+
+    /// Retrieve the first `n` crates in a sort order, across as many pages as
+    /// needed.
+    async fn top_crates(&self, n: usize, sort: Sort) -> Result<Vec<Crate>, Error> {
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        let query = leaderboard_query(n, sort);
+        self.crates_stream(query).take(n).try_collect().await
+    }
+
+    /// Take a snapshot of all crates in a category, with their downloads,
+    /// dependents and latest release.
+    ///
+    /// Takes two requests per crate on top of the pages of the category, all
+    /// under the rate limit. Crates that are deleted while the snapshot is
+    /// taken are left out. See [`SnapshotOptions::resumable`] to continue a
+    /// failed snapshot instead of starting over.
+    pub async fn category_snapshot(
+        &self,
+        slug: &str,
+        options: &SnapshotOptions,
+    ) -> Result<CategorySnapshot, Error> {
+        self.category(slug).await?;
+        let mut progress = SnapshotProgress::load(slug, options).await;
+        let mut crates = self.crates_stream(progress.query());
+        while !progress.is_full() {
+            let krate = match crates.try_next().await? {
+                Some(krate) => krate,
+                None => break,
+            };
+            if progress.contains(&krate.name) {
+                continue;
+            }
+            let details = try_join!(
+                self.get_crate(&krate.name),
+                self.crate_reverse_dependency_count(&krate.name)
+            );
+            match details {
+                Ok((response, dependents)) => {
+                    progress
+                        .push(SnapshotEntry::new(krate, &response, dependents))
+                        .await;
+                }
+                Err(Error::NotFound(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(progress.finish().await)
+    }
+
+    /// Retrieve the top `n` crates in a category, ordered by `sort`.
+    ///
+    /// Fetches as many pages as needed. Returns fewer than `n` crates only if
+    /// the category doesn't contain enough crates.
+    pub async fn top_crates_in_category(
+        &self,
+        category_slug: &str,
+        n: usize,
+        sort: Sort,
+    ) -> Result<Vec<Crate>, Error> {
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        let query = top_crates_query(category_slug, n, sort);
+        self.crates_stream(query).take(n).try_collect().await
     }
 }
 
-pub(crate) fn build_crate_url(base: &Url, crate_name: &str) -> Result<Url, Error> {
-    let mut url = base.join("crates")?;
-    url.path_segments_mut().unwrap().push(crate_name);
+/// Build the query for a feed of all crates.
+fn feed_query(sort: Sort) -> CratesQuery {
+    CratesQuery::builder()
+        .sort(sort)
+        .page_size(CratesQuery::MAX_PAGE_SIZE)
+        .build()
+}
+
+/// Skip crates that were already yielded by a feed, since new entries shift
+/// the pages while the feed is consumed.
+fn feed_stream<S>(crates: S) -> BoxStream<'static, Result<Crate, Error>>
+where
+    S: Stream<Item = Result<Crate, Error>> + Send + 'static,
+{
+    let mut seen = std::collections::HashSet::new();
+    crates
+        .try_filter(move |krate| future::ready(seen.insert(krate.name.clone())))
+        .boxed()
+}
+
+/// Build the query for enumerating all crates.
+pub(crate) fn all_crates_query() -> CratesQuery {
+    CratesQuery::builder()
+        .page_size(CratesQuery::MAX_PAGE_SIZE)
+        .build()
+}
+
+/// Build the query for all crates owned by a user.
+pub(crate) fn user_crates_query(user_id: u64) -> CratesQuery {
+    CratesQuery::builder()
+        .user_id(user_id)
+        .sort(Sort::Alphabetical)
+        .page_size(CratesQuery::MAX_PAGE_SIZE)
+        .build()
+}
+
+/// Build the query for the first `n` crates in a sort order.
+pub(crate) fn leaderboard_query(n: usize, sort: Sort) -> CratesQuery {
+    CratesQuery::builder()
+        .sort(sort)
+        .page_size((n as u64).clamp(1, CratesQuery::MAX_PAGE_SIZE))
+        .build()
+}
+
+/// Build the query for the top `n` crates in a category.
+pub(crate) fn top_crates_query(category_slug: &str, n: usize, sort: Sort) -> CratesQuery {
+    CratesQuery::builder()
+        .category(category_slug)
+        .sort(sort)
+        .page_size((n as u64).min(CratesQuery::MAX_PAGE_SIZE))
+        .build()
+}
+
+/// Build the query used to find an exact name match.
+///
+/// The exact match is always sorted first by relevance.
+pub(crate) fn exact_match_query(name: &str) -> CratesQuery {
+    CratesQuery::builder()
+        .search(name)
+        .sort(Sort::Relevance)
+        .page_size(1)
+        .build()
+}
+
+/// Read the body of a response as text.
+///
+/// Unlike `Response::text`, which decodes into a copy of the body, the buffer
+/// of the body is reused, so large responses are held in memory only once.
+/// Invalid UTF-8 is replaced like `Response::text` does.
+pub(crate) async fn body_text(res: reqwest::Response) -> Result<String, Error> {
+    let body = Vec::from(res.bytes().await?);
+    Ok(String::from_utf8(body)
+        .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()))
+}
 
-    // Guard against slashes in the crate name.
-    // The API returns a nonsensical error in this case.
-    if crate_name.contains('/') {
+/// Decode a successful response, checking for API errors first.
+pub(crate) fn decode_response<T: DeserializeOwned + Serialize>(
+    content: &str,
+    unknown_fields: UnknownFields,
+) -> Result<T, Error> {
+    // Only bodies with an `errors` key can be errors, which is much cheaper to
+    // check than decoding large responses twice.
+    if content.contains("\"errors\"") {
+        if let Ok(errors) = serde_json::from_str::<ApiErrors>(content) {
+            return Err(Error::Api(errors));
+        }
+    }
+
+    Ok(decode_json_checked(content, unknown_fields)?)
+}
+
+/// Map a not found error to `false`, and any response to `true`.
+pub(crate) fn exists<T>(res: Result<T, Error>) -> Result<bool, Error> {
+    match res {
+        Ok(_) => Ok(true),
+        Err(Error::NotFound(_)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Build an API url by appending the given path segments to the base url.
+///
+/// Every segment is percent-encoded, so user supplied values like crate names
+/// or versions can't escape into other endpoints.
+pub(crate) fn build_url(base: &Url, segments: &[&str]) -> Result<Url, Error> {
+    let mut url = base.clone();
+    url.path_segments_mut()
+        .map_err(|_| Error::Url(url::ParseError::RelativeUrlWithCannotBeABaseBase))?
+        .pop_if_empty()
+        .extend(segments);
+
+    // Guard against slashes and dot segments.
+    // The url crate silently drops dot segments, and the API returns a
+    // nonsensical error for escaped slashes.
+    let invalid = segments
+        .iter()
+        .any(|s| s.is_empty() || s.contains('/') || *s == "." || *s == "..");
+    if invalid {
         Err(Error::NotFound(crate::error::NotFoundError {
             url: url.to_string(),
         }))
@@ -421,13 +1811,32 @@ pub(crate) fn build_crate_url(base: &Url, crate_name: &str) -> Result<Url, Error
     }
 }
 
-fn build_crate_url_nested(base: &Url, crate_name: &str) -> Result<Url, Error> {
-    let mut url = base.join("crates")?;
-    url.path_segments_mut().unwrap().push(crate_name).push("/");
+pub(crate) fn build_raw_url(base: &Url, path: &str, query: &[(&str, &str)]) -> Result<Url, Error> {
+    let segments: Vec<_> = path.trim_matches('/').split('/').collect();
+    let mut url = build_url(base, &segments)?;
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    Ok(url)
+}
+
+pub(crate) fn build_summary_url(base: &Url) -> Result<Url, Error> {
+    build_url(base, &["summary"])
+}
+
+pub(crate) fn build_crates_url(base: &Url) -> Result<Url, Error> {
+    build_url(base, &["crates"])
+}
+
+/// Build a url for a crate endpoint.
+///
+/// The crate name is validated with [`CrateName`] rules: an invalid name can
+/// never exist, so a [`Error::NotFound`] is returned without a request.
+fn build_crate_path_url(base: &Url, crate_name: &str, rest: &[&str]) -> Result<Url, Error> {
+    let segments = [&["crates", crate_name][..], rest].concat();
+    let url = build_url(base, &segments)?;
 
-    // Guard against slashes in the crate name.
-    // The API returns a nonsensical error in this case.
-    if crate_name.contains('/') {
+    if CrateName::validate(crate_name).is_err() {
         Err(Error::NotFound(crate::error::NotFoundError {
             url: url.to_string(),
         }))
@@ -436,16 +1845,54 @@ fn build_crate_url_nested(base: &Url, crate_name: &str) -> Result<Url, Error> {
     }
 }
 
+pub(crate) fn build_crate_url(base: &Url, crate_name: &str) -> Result<Url, Error> {
+    build_crate_path_url(base, crate_name, &[])
+}
+
+pub(crate) fn build_crate_exists_url(base: &Url, crate_name: &str) -> Result<Url, Error> {
+    let mut url = build_crate_url(base, crate_name)?;
+    url.query_pairs_mut().append_pair("include", "");
+    Ok(url)
+}
+
+pub(crate) fn build_crate_version_url(
+    base: &Url,
+    crate_name: &str,
+    version: &str,
+) -> Result<Url, Error> {
+    build_crate_path_url(base, crate_name, &[version])
+}
+
+pub(crate) fn build_user_url(base: &Url, username: &str) -> Result<Url, Error> {
+    build_url(base, &["users", username])
+}
+
+pub(crate) fn build_team_url(base: &Url, login: &str) -> Result<Url, Error> {
+    build_url(base, &["teams", login])
+}
+
+pub(crate) fn build_keyword_url(base: &Url, id: &str) -> Result<Url, Error> {
+    build_url(base, &["keywords", id])
+}
+
+pub(crate) fn build_category_url(base: &Url, slug: &str) -> Result<Url, Error> {
+    build_url(base, &["categories", slug])
+}
+
 pub(crate) fn build_crate_downloads_url(base: &Url, crate_name: &str) -> Result<Url, Error> {
-    build_crate_url_nested(base, crate_name)?
-        .join("downloads")
-        .map_err(Error::from)
+    build_crate_path_url(base, crate_name, &["downloads"])
+}
+
+pub(crate) fn build_crate_version_downloads_url(
+    base: &Url,
+    crate_name: &str,
+    version: &str,
+) -> Result<Url, Error> {
+    build_crate_path_url(base, crate_name, &[version, "downloads"])
 }
 
 pub(crate) fn build_crate_owners_url(base: &Url, crate_name: &str) -> Result<Url, Error> {
-    build_crate_url_nested(base, crate_name)?
-        .join("owners")
-        .map_err(Error::from)
+    build_crate_path_url(base, crate_name, &["owners"])
 }
 
 pub(crate) fn build_crate_reverse_deps_url(
@@ -453,9 +1900,24 @@ pub(crate) fn build_crate_reverse_deps_url(
     crate_name: &str,
     page: u64,
 ) -> Result<Url, Error> {
-    build_crate_url_nested(base, crate_name)?
-        .join(&format!("reverse_dependencies?per_page=100&page={page}"))
-        .map_err(Error::from)
+    let mut url = build_crate_path_url(base, crate_name, &["reverse_dependencies"])?;
+    url.query_pairs_mut()
+        .append_pair("per_page", "100")
+        .append_pair("page", &page.to_string());
+    Ok(url)
+}
+
+/// Build the URL of a single reverse dependency, which is the cheapest way to
+/// get their total count.
+pub(crate) fn build_crate_reverse_deps_count_url(
+    base: &Url,
+    crate_name: &str,
+) -> Result<Url, Error> {
+    let mut url = build_crate_path_url(base, crate_name, &["reverse_dependencies"])?;
+    url.query_pairs_mut()
+        .append_pair("per_page", "1")
+        .append_pair("page", "1");
+    Ok(url)
 }
 
 pub(crate) fn build_crate_authors_url(
@@ -463,9 +1925,7 @@ pub(crate) fn build_crate_authors_url(
     crate_name: &str,
     version: &str,
 ) -> Result<Url, Error> {
-    build_crate_url_nested(base, crate_name)?
-        .join(&format!("{version}/authors"))
-        .map_err(Error::from)
+    build_crate_path_url(base, crate_name, &[version, "authors"])
 }
 
 pub(crate) fn build_crate_dependencies_url(
@@ -473,14 +1933,14 @@ pub(crate) fn build_crate_dependencies_url(
     crate_name: &str,
     version: &str,
 ) -> Result<Url, Error> {
-    build_crate_url_nested(base, crate_name)?
-        .join(&format!("{version}/dependencies"))
-        .map_err(Error::from)
+    build_crate_path_url(base, crate_name, &[version, "dependencies"])
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::testing::MockServer;
+    use std::sync::{Arc, Mutex};
 
     fn build_test_client() -> Client {
         Client::new(
@@ -494,14 +1954,14 @@ mod test {
     async fn test_summary_async() -> Result<(), Error> {
         let client = build_test_client();
         let summary = client.summary().await?;
-        assert!(summary.most_downloaded.len() > 0);
-        assert!(summary.just_updated.len() > 0);
-        assert!(summary.new_crates.len() > 0);
-        assert!(summary.most_recently_downloaded.len() > 0);
+        assert!(!summary.most_downloaded.is_empty());
+        assert!(!summary.just_updated.is_empty());
+        assert!(!summary.new_crates.is_empty());
+        assert!(!summary.most_recently_downloaded.is_empty());
         assert!(summary.num_crates > 0);
         assert!(summary.num_downloads > 0);
-        assert!(summary.popular_categories.len() > 0);
-        assert!(summary.popular_keywords.len() > 0);
+        assert!(!summary.popular_categories.is_empty());
+        assert!(!summary.popular_keywords.is_empty());
         Ok(())
     }
 
@@ -509,10 +1969,7 @@ mod test {
     async fn test_crates_stream_async() {
         let client = build_test_client();
 
-        let mut stream = client.crates_stream(CratesQuery {
-            per_page: 10,
-            ..Default::default()
-        });
+        let mut stream = client.crates_stream(CratesQuery::builder().page_size(10).build());
 
         for _ in 0..40 {
             let _krate = stream.next().await.unwrap().unwrap();
@@ -543,11 +2000,12 @@ mod test {
         let user = client.user("theduke").await?;
 
         let res = client
-            .crates(CratesQuery {
-                user_id: Some(user.id),
-                per_page: 20,
-                ..Default::default()
-            })
+            .crates(
+                CratesQuery::builder()
+                    .user_id(user.id)
+                    .page_size(20)
+                    .build(),
+            )
             .await?;
 
         assert!(!res.crates.is_empty());
@@ -567,11 +2025,12 @@ mod test {
         let category = "wasm".to_string();
 
         let res = client
-            .crates(CratesQuery {
-                category: Some(category.clone()),
-                per_page: 3,
-                ..Default::default()
-            })
+            .crates(
+                CratesQuery::builder()
+                    .category(category.clone())
+                    .page_size(3)
+                    .build(),
+            )
             .await?;
 
         assert!(!res.crates.is_empty());
@@ -595,6 +2054,112 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_build_url_encodes_segments() {
+        let base = Url::parse("https://crates.io/api/v1/").unwrap();
+
+        let url = build_crate_dependencies_url(&base, "serde", "1.0.0+build?x#y").unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://crates.io/api/v1/crates/serde/1.0.0+build%3Fx%23y/dependencies"
+        );
+
+        let url = build_team_url(&base, "github:rust-lang:libs").unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://crates.io/api/v1/teams/github:rust-lang:libs"
+        );
+
+        let url = build_crate_reverse_deps_url(&base, "serde", 2).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://crates.io/api/v1/crates/serde/reverse_dependencies?per_page=100&page=2"
+        );
+    }
+
+    #[test]
+    fn test_build_raw_url() {
+        let base = Url::parse("https://crates.io/api/v1/").unwrap();
+
+        let url = build_raw_url(&base, "/crates/serde/versions", &[("sort", "date")]).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://crates.io/api/v1/crates/serde/versions?sort=date"
+        );
+        let url = build_raw_url(&base, "site_metadata", &[]).unwrap();
+        assert_eq!(url.as_str(), "https://crates.io/api/v1/site_metadata");
+        assert!(build_raw_url(&base, "crates/../summary", &[]).is_err());
+    }
+
+    #[test]
+    fn test_decode_response() {
+        let errors = r#"{"errors": [{"detail": "Not Found"}]}"#;
+        assert!(matches!(
+            decode_response::<serde_json::Value>(errors, UnknownFields::Ignore),
+            Err(Error::Api(_))
+        ));
+        // Values named `errors` are not errors.
+        let value: serde_json::Value =
+            decode_response(r#"{"keywords": ["errors"]}"#, UnknownFields::Ignore).unwrap();
+        assert_eq!(value["keywords"][0], "errors");
+        match decode_response::<Summary>(r#"{"num_crates": "many"}"#, UnknownFields::Ignore) {
+            Err(Error::JsonDecode(err)) => assert_eq!(err.path(), Some("num_crates")),
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_build_url_rejects_path_traversal() {
+        let base = Url::parse("https://crates.io/api/v1/").unwrap();
+
+        for version in ["..", ".", "", "../../summary"] {
+            assert!(matches!(
+                build_crate_authors_url(&base, "serde", version),
+                Err(Error::NotFound(_))
+            ));
+        }
+        assert!(matches!(
+            build_user_url(&base, "a/b"),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_build_url_rejects_invalid_crate_names() {
+        let base = Url::parse("https://crates.io/api/v1/").unwrap();
+
+        for name in ["", "..", "1abc", "a b", "std"] {
+            assert!(matches!(
+                build_crate_owners_url(&base, name),
+                Err(Error::NotFound(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_top_crates_query() {
+        assert_eq!(top_crates_query("parsing", 5, Sort::Downloads).per_page, 5);
+        let query = top_crates_query("parsing", 250, Sort::Downloads);
+        assert_eq!(query.per_page, CratesQuery::MAX_PAGE_SIZE);
+        assert_eq!(query.category.as_deref(), Some("parsing"));
+        assert_eq!(query.sort, Sort::Downloads);
+
+        assert_eq!(leaderboard_query(0, Sort::NewlyAdded).per_page, 1);
+        assert_eq!(leaderboard_query(250, Sort::NewlyAdded).per_page, 100);
+    }
+
+    #[tokio::test]
+    async fn test_feed_stream_skips_duplicates() {
+        let krate = |name: &str| -> Result<Crate, Error> { Ok(Crate::new(name, "1.0.0")) };
+        let crates = stream::iter(vec![krate("c"), krate("b"), krate("b"), krate("a")]);
+        let names: Vec<_> = feed_stream(crates)
+            .map_ok(|krate| krate.name)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(names, ["c", "b", "a"]);
+    }
+
     /// Regression test for https://github.com/theduke/crates-io-api/issues/44
     #[tokio::test]
     async fn test_get_crate_with_slash() {
@@ -606,4 +2171,495 @@ mod test {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_mock_server_leaderboards() {
+        let page = |names: &[&str]| {
+            let crates = names.iter().map(|name| Crate::new(name, "1.0.0")).collect();
+            CratesPage::new(crates, 5)
+        };
+        let server = MockServer::start()
+            .unwrap()
+            .with_json(
+                "crates?page=1&per_page=2&sort=downloads",
+                &page(&["a", "b"]),
+            )
+            .with_json("crates?page=1&per_page=3&sort=new", &page(&["x", "y", "z"]));
+        let client = server.async_client();
+
+        let names =
+            |crates: Vec<Crate>| -> Vec<_> { crates.into_iter().map(|krate| krate.name).collect() };
+        assert_eq!(names(client.most_downloaded(2).await.unwrap()), ["a", "b"]);
+        assert_eq!(
+            names(client.newest_crates(3).await.unwrap()),
+            ["x", "y", "z"]
+        );
+        assert!(client.most_recently_downloaded(0).await.unwrap().is_empty());
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_stable_order_async() {
+        let server = crate::testing::stable_order_server();
+        let client = server.async_client();
+        let query = CratesQuery::builder().page_size(2).build();
+
+        let mut crates = client.crates_stream(query).stable_order();
+        let mut names = Vec::new();
+        while let Some(krate) = futures::StreamExt::next(&mut crates).await {
+            names.push(krate.unwrap().name);
+        }
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(crates.duplicates(), ["b"]);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_lookups() {
+        let mut exact = Crate::new("crates_io_api", "0.8.0");
+        exact.exact_match = Some(true);
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("crates_io_api", &["0.7.3", "0.8.0"])
+            .with_crate("serde", &["1.0.0"])
+            .with_json(
+                "crates",
+                &CratesPage::new(vec![Crate::new("crates-io", "0.1.0"), exact], 2),
+            );
+        let client = server.async_client();
+
+        let name = client.canonical_crate_name("crates-io-api").await.unwrap();
+        assert_eq!(name, "crates_io_api");
+        assert!(client.crate_exists("crates_io_api").await.unwrap());
+        assert!(!client.crate_exists("nope").await.unwrap());
+        assert!(!client.crate_exists("a/b").await.unwrap());
+        assert!(client
+            .version_exists("crates_io_api", "0.8.0")
+            .await
+            .unwrap());
+        assert!(!client
+            .version_exists("crates_io_api", "0.0.999")
+            .await
+            .unwrap());
+        assert!(!client.version_exists("crates_io_api", "..").await.unwrap());
+
+        let krate = client.find_crate_exact("crates-io-api").await.unwrap();
+        assert_eq!(krate.unwrap().name, "crates_io_api");
+
+        let mut results = client
+            .get_many_crates(vec!["serde", "a/b", "crates_io_api"], 2)
+            .collect::<Vec<_>>()
+            .await;
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[0].1, Err(Error::NotFound(_))));
+        assert_eq!(
+            results[1].1.as_ref().unwrap().crate_data.name,
+            "crates_io_api"
+        );
+        assert_eq!(results[2].1.as_ref().unwrap().crate_data.name, "serde");
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_crates_owned_by_team() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_json(
+                "teams/github:rust-lang:libs",
+                &TeamResponse {
+                    team: Team::new(3, "github:rust-lang:libs"),
+                },
+            )
+            .with_json(
+                "crates?page=1&per_page=100&sort=recent-updates&team_id=3",
+                &CratesPage::new(vec![Crate::new("libc", "0.2.0")], 1),
+            )
+            .with_json("crates", &CratesPage::new(Vec::new(), 1));
+        let client = server.async_client();
+
+        let team = client.team("github:rust-lang:libs").await.unwrap();
+        assert_eq!(team.id, 3);
+        let crates = client.crates_owned_by_team(&team.login).await.unwrap();
+        let names: Vec<_> = crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["libc"]);
+        assert!(matches!(
+            client.crates_owned_by_team("github:nope:nope").await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_full_crate_options() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["0.1.0", "0.2.0", "0.3.0"])
+            .with_crate("bar", &["1.0.0"])
+            .with_json(
+                "crates",
+                &CratesPage::new(
+                    vec![Crate::new("foo", "0.3.0"), Crate::new("bar", "1.0.0")],
+                    2,
+                ),
+            );
+        let client = server.async_client();
+
+        let options = FullCrateOptions::new()
+            .max_versions(Some(2))
+            .authors(false)
+            .reverse_dependencies(false);
+        let krate = client
+            .full_crate_with_options("foo", &options)
+            .await
+            .unwrap();
+        assert_eq!(krate.versions.len(), 2);
+        assert!(krate.versions.iter().all(|v| v.author_names.is_empty()));
+        assert!(krate.reverse_dependencies.dependencies.is_empty());
+        assert!(!krate.reverse_dependencies_fetched);
+        let requests = server.requests();
+        assert!(!requests.iter().any(|r| r.contains("authors")));
+        assert!(!requests.iter().any(|r| r.contains("reverse_dependencies")));
+
+        let query = CratesQuery::builder().page_size(2).build();
+        let options = FullCrateOptions::new()
+            .max_versions(Some(1))
+            .reverse_dependencies(false);
+        let crates: Vec<_> = client
+            .full_crates_stream(query, options)
+            .take(3)
+            .try_collect()
+            .await
+            .unwrap();
+        let names: Vec<_> = crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["foo", "bar", "foo"]);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_full_crate_authors() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["0.1.0", "0.2.0"])
+            .with_json(
+                "crates/foo/0.2.0/authors",
+                &AuthorsResponse {
+                    meta: AuthorsMeta {
+                        names: vec!["Ferris".to_string()],
+                    },
+                },
+            )
+            .with_error("crates/foo/0.1.0/authors", 410, "Gone");
+        let client = server.async_client();
+        let options = FullCrateOptions::new().reverse_dependencies(false);
+
+        let krate = client
+            .full_crate_with_options("foo", &options)
+            .await
+            .unwrap();
+        assert_eq!(krate.versions[0].author_names, ["Ferris"]);
+        assert!(!krate.versions[0].authors_unavailable);
+        assert!(krate.versions[1].author_names.is_empty());
+        assert!(krate.versions[1].authors_unavailable);
+
+        // Other failures are not mistaken for missing authors.
+        let _server = server.with_error("crates/foo/0.1.0/authors", 500, "Internal Server Error");
+        assert!(client
+            .full_crate_with_options("foo", &options)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_reverse_dependency_graph() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_reverse_dependencies("foo", &[("bar", "1.0.0"), ("baz", "1.0.0")])
+            .with_reverse_dependencies("bar", &[("qux", "1.0.0")]);
+        let client = server.async_client();
+
+        let counts = client
+            .reverse_dependency_counts(&["foo", "bar"])
+            .await
+            .unwrap();
+        assert_eq!(counts["foo"], 2);
+        assert_eq!(counts["bar"], 1);
+
+        let graph = client
+            .reverse_dependency_graph("foo", GraphLimits::new())
+            .await
+            .unwrap();
+        assert_eq!(graph.crates.len(), 3);
+        assert!(graph.crates.values().all(|depth| *depth <= 1));
+        assert!(!graph.crates.contains_key("qux"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_dependencies() {
+        let mut dev = Dependency::new("tokio", "^1");
+        dev.kind = DependencyKind::Dev;
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["0.7.3", "0.8.0"])
+            .with_crate("serde", &["1.0.0", "1.0.1"])
+            .with_json(
+                "crates/foo/0.7.3/dependencies",
+                &Dependencies {
+                    dependencies: vec![Dependency::new("serde", "^1")],
+                },
+            )
+            .with_json(
+                "crates/foo/0.8.0/dependencies",
+                &Dependencies {
+                    dependencies: vec![Dependency::new("serde", "^1"), dev],
+                },
+            );
+        let client = server.async_client();
+
+        let diff = client
+            .diff_dependencies("foo", "0.7.3", "0.8.0")
+            .await
+            .unwrap();
+        assert!(!diff.is_empty());
+
+        #[cfg(feature = "semver")]
+        {
+            let tree = client
+                .dependency_tree("foo", "0.8.0", 2, &crate::DependencyFilter::new())
+                .await
+                .unwrap();
+            assert_eq!(tree.dependencies.len(), 1);
+            assert_eq!(tree.dependencies[0].name, "serde");
+            assert_eq!(tree.dependencies[0].version.as_deref(), Some("1.0.1"));
+
+            let checks = client
+                .check_updates(&[("serde", "^1"), ("foo", "^0.7")])
+                .await
+                .unwrap();
+            assert_eq!(checks[0].latest_matching.as_deref(), Some("1.0.1"));
+            assert_eq!(checks[1].name, "foo");
+            assert!(checks[1].is_outdated());
+            assert!(matches!(
+                client.check_updates(&[("serde", "not a req")]).await,
+                Err(Error::InvalidVersion(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_downloads() {
+        let day = |d: u32| crate::Date::from_ymd(2020, 1, d).unwrap();
+        let downloads: Vec<_> = (1..=10)
+            .map(|d| VersionDownloads {
+                date: day(d),
+                downloads: 10,
+                version: 1,
+            })
+            .collect();
+        let server = MockServer::start()
+            .unwrap()
+            .with_json(
+                "crates/foo/downloads",
+                &CrateDownloads {
+                    version_downloads: downloads.clone(),
+                    meta: CrateDownloadsMeta {
+                        extra_downloads: Vec::new(),
+                    },
+                },
+            )
+            .with_json(
+                "crates/foo/1.0.0/downloads",
+                &VersionDownloadsResponse {
+                    version_downloads: downloads,
+                },
+            );
+        let client = server.async_client();
+
+        let downloads = client
+            .downloads_between("foo", day(2), day(8))
+            .await
+            .unwrap();
+        assert_eq!(downloads.daily().len(), 7);
+        assert_eq!(downloads.total(), 70);
+        let version_downloads = client
+            .crate_version_downloads("foo", "1.0.0")
+            .await
+            .unwrap();
+        assert!(version_downloads.iter().all(|d| d.version == 1));
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_crate_lists() {
+        let krate = |name: &str, downloads: u64, day: u32| {
+            let mut krate = Crate::new(name, "1.0.0");
+            krate.downloads = downloads;
+            krate.created_at = crate::types::timestamp(&format!("2020-01-{:02}T00:00:00Z", day));
+            krate.updated_at = krate.created_at;
+            krate
+        };
+        let crates = vec![
+            krate("reqwest", 300, 3),
+            krate("ureq", 200, 2),
+            krate("hyper", 100, 1),
+        ];
+        let server = MockServer::start()
+            .unwrap()
+            .with_json("crates", &CratesPage::new(crates, 3))
+            .with_crate("reqwest", &["1.0.0"])
+            .with_crate("ureq", &["1.0.0"])
+            .with_reverse_dependencies("reqwest", &[("foo", "1.0.0")]);
+        let client = server.async_client();
+
+        let top = client
+            .top_crates_in_category("web", 2, Sort::Downloads)
+            .await
+            .unwrap();
+        assert_eq!(top.len(), 2);
+        assert!(top[0].downloads >= top[1].downloads);
+
+        let crates: Vec<_> = client
+            .new_crates_stream()
+            .take(3)
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(crates[0].created_at >= crates[1].created_at);
+        let crates: Vec<_> = client
+            .just_updated_stream()
+            .take(3)
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(crates[0].updated_at >= crates[1].updated_at);
+
+        let comparison = client
+            .compare_crates(&["reqwest", "ureq", "does-not-exist"])
+            .await
+            .unwrap();
+        let names: Vec<_> = comparison.crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["reqwest", "ureq"]);
+        assert_eq!(comparison.missing, ["does-not-exist"]);
+        assert_eq!(comparison.get("reqwest").unwrap().reverse_dependencies, 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_async() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["1.0.0"])
+            .with_error("summary", 200, "the API is sad");
+        let client = server.async_client();
+
+        assert!(client.crate_exists("foo").await.unwrap());
+        assert_eq!(
+            client.crate_version("foo", "1.0.0").await.unwrap().num,
+            "1.0.0"
+        );
+
+        let completed = Arc::new(Mutex::new(Vec::new()));
+        let options = {
+            let completed = completed.clone();
+            FullCrateOptions::new()
+                .concurrency(1)
+                .on_progress(move |p| completed.lock().unwrap().push(p.version.clone()))
+        };
+        let plan = client.full_crate_plan("foo", &options).await.unwrap();
+        assert_eq!(plan.requests(), 5);
+        client.full_crate_from_plan(plan, &options).await.unwrap();
+        assert_eq!(*completed.lock().unwrap(), ["1.0.0"]);
+        assert!(matches!(client.summary().await, Err(Error::Api(_))));
+
+        let cache = std::sync::Arc::new(crate::MemoryCache::new());
+        let client = client.with_cache(cache.clone(), std::time::Duration::from_secs(60));
+        client.prefetch(vec!["foo"]).await;
+        assert_eq!(cache.len(), 1);
+
+        let budget = std::sync::Arc::new(crate::Budget::new().max_requests(1));
+        let client = Client::from_config(&server.config().budget(budget.clone())).unwrap();
+        assert!(client.crate_exists("foo").await.unwrap());
+        assert!(matches!(
+            client.crate_exists("foo").await,
+            Err(Error::BudgetExhausted(_))
+        ));
+        assert_eq!(budget.used(), 1);
+        assert_eq!(server.requests()[0], "crates/foo?include=");
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_cache_headers() {
+        let server = MockServer::start().unwrap().with_crate("foo", &["1.0.0"]);
+        let cache = Arc::new(crate::MemoryCache::new());
+        let client = server
+            .async_client()
+            .with_cache(cache.clone(), std::time::Duration::from_secs(60));
+        let endpoint = crate::endpoints::GetCrate { crate_name: "foo" };
+        let options = crate::RequestOptions::new().header(
+            header::ACCEPT_LANGUAGE,
+            header::HeaderValue::from_static("de"),
+        );
+
+        client.send_with(&endpoint, &options).await.unwrap();
+        assert_eq!(cache.len(), 0);
+        client.send(&endpoint).await.unwrap();
+        client.send(&endpoint).await.unwrap();
+        client.send_with(&endpoint, &options).await.unwrap();
+        assert_eq!(server.requests(), ["crates/foo"; 3]);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_cancellation() {
+        use std::time::{Duration, Instant};
+
+        let server = MockServer::start().unwrap().with_crate("foo", &["1.0.0"]);
+        let config = server.config().rate_limit(Duration::from_millis(300));
+        let client = Client::from_config(&config).unwrap();
+        let endpoint = crate::endpoints::GetCrate { crate_name: "foo" };
+
+        let start = Instant::now();
+        client.send(&endpoint).await.unwrap();
+        // Dropping a request while it waits for the rate limit keeps the
+        // time of the previous one.
+        let timeout = tokio::time::timeout(Duration::from_millis(10), client.send(&endpoint));
+        assert!(timeout.await.is_err());
+        let options =
+            crate::RequestOptions::new().deadline(Instant::now() + Duration::from_millis(50));
+        assert!(matches!(
+            client.send_with(&endpoint, &options).await,
+            Err(Error::Cancelled(_))
+        ));
+        assert!(matches!(
+            client.send_until(&endpoint, future::ready(())).await,
+            Err(Error::Cancelled(_))
+        ));
+        client.send(&endpoint).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_autocomplete() {
+        use std::time::Duration;
+
+        let server = MockServer::start().unwrap().with_json(
+            "crates",
+            &CratesPage::new(vec![Crate::new("serde", "1.0.0")], 1),
+        );
+        let config = server.config().rate_limit(Duration::from_millis(200));
+        let client = Client::from_config(&config)
+            .unwrap()
+            .with_autocomplete_cache(Duration::from_secs(60));
+        assert_eq!(client.autocomplete("s", 5).await.unwrap().len(), 1);
+
+        // The stale request is superseded while it waits for the rate limit,
+        // so it is never sent.
+        let (stale, latest) =
+            futures::join!(client.autocomplete("se", 5), client.autocomplete("ser", 5));
+        assert!(matches!(stale, Err(Error::Cancelled(_))));
+        assert_eq!(latest.unwrap()[0].name, "serde");
+        assert_eq!(client.autocomplete("ser", 5).await.unwrap().len(), 1);
+        assert!(client.autocomplete("", 5).await.unwrap().is_empty());
+        assert_eq!(
+            server.requests(),
+            [
+                "crates?page=1&per_page=5&sort=relevance&q=s",
+                "crates?page=1&per_page=5&sort=relevance&q=ser",
+            ]
+        );
+    }
 }
@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::error::CancelledError;
+use crate::{CacheStore, Crate, CratesQuery, Error, MemoryCache, Sort};
+
+/// State shared by the autocomplete requests of a client and its clones.
+#[derive(Debug, Default)]
+pub(crate) struct Autocomplete {
+    /// The number of the latest request.
+    latest: AtomicU64,
+    superseded: tokio::sync::Notify,
+    cache: Option<(MemoryCache, Duration)>,
+}
+
+impl Autocomplete {
+    pub(crate) fn with_cache(ttl: Duration) -> Self {
+        Self {
+            cache: Some((MemoryCache::new(), ttl)),
+            ..Self::default()
+        }
+    }
+
+    /// Build the query for the first `limit` crates matching `prefix`.
+    pub(crate) fn query(prefix: &str, limit: u64) -> CratesQuery {
+        CratesQuery::builder()
+            .search(prefix)
+            .sort(Sort::Relevance)
+            .page_size(limit.clamp(1, CratesQuery::MAX_PAGE_SIZE))
+            .build()
+    }
+
+    fn key(prefix: &str, limit: u64) -> String {
+        format!("{}\n{}", limit, prefix)
+    }
+
+    pub(crate) fn cached(&self, prefix: &str, limit: u64) -> Option<Vec<Crate>> {
+        let (cache, _) = self.cache.as_ref()?;
+        let content = cache.get(&Self::key(prefix, limit))?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub(crate) fn store(&self, prefix: &str, limit: u64, crates: &[Crate]) {
+        if let Some((cache, ttl)) = &self.cache {
+            let content = serde_json::to_string(crates).unwrap();
+            cache.put(&Self::key(prefix, limit), content, *ttl);
+        }
+    }
+
+    /// Register a new request, superseding all pending ones, and get its
+    /// number.
+    pub(crate) fn start(&self) -> u64 {
+        let number = self.latest.fetch_add(1, Ordering::SeqCst) + 1;
+        self.superseded.notify_waiters();
+        number
+    }
+
+    /// Wait until a newer request than `number` is started.
+    pub(crate) async fn superseded(&self, number: u64, prefix: &str) -> Error {
+        loop {
+            let notified = self.superseded.notified();
+            if self.latest.load(Ordering::SeqCst) != number {
+                return Error::Cancelled(CancelledError::new(format!(
+                    "autocomplete for {:?} was superseded",
+                    prefix
+                )));
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::FutureExt;
+
+    #[test]
+    fn test_autocomplete() {
+        let query = Autocomplete::query("ser", 500);
+        assert_eq!(query.page_size(), CratesQuery::MAX_PAGE_SIZE);
+        assert_eq!(*query.sort(), Sort::Relevance);
+
+        let autocomplete = Autocomplete::with_cache(Duration::from_secs(60));
+        autocomplete.store("ser", 5, &[Crate::new("serde", "1.0.0")]);
+        assert_eq!(autocomplete.cached("ser", 5).unwrap()[0].name, "serde");
+        assert!(autocomplete.cached("ser", 6).is_none());
+
+        let first = autocomplete.start();
+        let mut superseded = Box::pin(autocomplete.superseded(first, "s"));
+        assert!((&mut superseded).now_or_never().is_none());
+        autocomplete.start();
+        let err = superseded.now_or_never().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Request cancelled: autocomplete for \"s\" was superseded"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::BudgetExhaustedError;
+use crate::Error;
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// A hard cap on the number of requests sent by one or more clients.
+///
+/// Attach it to clients with `with_budget()` or [`ClientConfig::budget`].
+/// Requests beyond the budget are not sent, and fail with
+/// [`Error::BudgetExhausted`] instead. Scheduled crawlers can use this to
+/// make sure a bug can't hammer crates.io for hours.
+///
+/// ```rust
+/// use crates_io_api::{Budget, SyncClient};
+/// use std::sync::Arc;
+///
+/// let budget = Arc::new(Budget::new().max_requests(10_000).max_requests_per_hour(1_000));
+/// let client = SyncClient::new("my_bot (help@my_bot.com)", std::time::Duration::from_secs(1))
+///     .unwrap()
+///     .with_budget(budget.clone());
+/// ```
+///
+/// [`ClientConfig::budget`]: crate::ClientConfig::budget
+#[derive(Debug, Default)]
+pub struct Budget {
+    max_requests: Option<u64>,
+    max_requests_per_hour: Option<u64>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    used: u64,
+    /// Times of the requests within the last hour, oldest first.
+    recent: VecDeque<Instant>,
+}
+
+impl Budget {
+    /// Construct an unlimited budget.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow at most `n` requests in total.
+    #[must_use]
+    pub fn max_requests(mut self, n: u64) -> Self {
+        self.max_requests = Some(n);
+        self
+    }
+
+    /// Allow at most `n` requests within any hour.
+    #[must_use]
+    pub fn max_requests_per_hour(mut self, n: u64) -> Self {
+        self.max_requests_per_hour = Some(n);
+        self
+    }
+
+    /// Get the number of requests used so far.
+    pub fn used(&self) -> u64 {
+        self.state.lock().unwrap().used
+    }
+
+    /// Get the number of requests left in total, if limited.
+    pub fn remaining(&self) -> Option<u64> {
+        self.max_requests.map(|max| max.saturating_sub(self.used()))
+    }
+
+    /// Get the number of used requests and the times of the requests within
+    /// the last hour.
+    pub(crate) fn snapshot(&self) -> (u64, Vec<Instant>) {
+        let state = self.state.lock().unwrap();
+        (state.used, state.recent.iter().copied().collect())
+    }
+
+    /// Replace the counters, eg with the ones of a previous run.
+    pub(crate) fn restore(&self, used: u64, recent: Vec<Instant>) {
+        let mut state = self.state.lock().unwrap();
+        state.used = used;
+        state.recent = recent.into();
+    }
+
+    /// Use up one request, or fail if the budget is exhausted.
+    pub(crate) fn acquire(&self) -> Result<(), Error> {
+        self.acquire_at(Instant::now())
+    }
+
+    fn acquire_at(&self, now: Instant) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(max) = self.max_requests {
+            if state.used >= max {
+                return Err(Error::BudgetExhausted(BudgetExhaustedError::new(format!(
+                    "all {} requests used",
+                    max
+                ))));
+            }
+        }
+        if let Some(max) = self.max_requests_per_hour {
+            while let Some(&time) = state.recent.front() {
+                if now.duration_since(time) < HOUR {
+                    break;
+                }
+                state.recent.pop_front();
+            }
+            if state.recent.len() as u64 >= max {
+                return Err(Error::BudgetExhausted(BudgetExhaustedError::new(format!(
+                    "{} requests used within the last hour",
+                    max
+                ))));
+            }
+            state.recent.push_back(now);
+        }
+        state.used += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_budget() {
+        let budget = Budget::new().max_requests(3).max_requests_per_hour(2);
+        let start = Instant::now();
+        assert!(budget.acquire_at(start).is_ok());
+        assert!(budget.acquire_at(start + Duration::from_secs(1)).is_ok());
+        match budget.acquire_at(start + Duration::from_secs(2)) {
+            Err(err @ Error::BudgetExhausted(_)) => assert_eq!(
+                err.to_string(),
+                "Request budget exhausted: 2 requests used within the last hour"
+            ),
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        // The hourly budget recovers.
+        assert!(budget.acquire_at(start + HOUR).is_ok());
+        assert_eq!(budget.remaining(), Some(0));
+        assert!(matches!(
+            budget.acquire_at(start + 3 * HOUR),
+            Err(Error::BudgetExhausted(_))
+        ));
+        assert_eq!(budget.used(), 3);
+
+        assert!(Budget::new().acquire().is_ok());
+    }
+}
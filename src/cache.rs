@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The key claimed by clients sharing a rate limit.
+pub(crate) const RATE_LIMIT_KEY: &str = "crates_io_api/rate_limit";
+
+/// Storage for cached API responses.
+///
+/// Attach a store to clients with `with_cache()` or [`ClientConfig::cache`].
+/// Successful responses are stored under their URL, and answered from the
+/// store without a request until they expire. Cached responses don't count
+/// against the rate limit or the [`Budget`](crate::Budget).
+///
+/// Implementations are called from async code, so they should be fast, eg
+/// by keeping the values in memory. Stores that wait for I/O must return
+/// `true` from [`blocking`](Self::blocking).
+///
+/// [`ClientConfig::cache`]: crate::ClientConfig::cache
+pub trait CacheStore: Send + Sync {
+    /// Get the value stored under `key`, unless it expired.
+    fn get(&self, key: &str) -> Option<String>;
+
+    /// Store a value under `key`, expiring after `ttl`.
+    ///
+    /// Values stored with [`Duration::MAX`] never expire.
+    fn put(&self, key: &str, value: String, ttl: Duration);
+
+    /// Claim `key` for `ttl`, unless it is claimed already. Returns `None` if
+    /// the claim succeeded, and otherwise how long the current claim lasts.
+    ///
+    /// Clients attached with `with_shared_rate_limit()` claim a key for
+    /// every request, so that all of them together keep to one rate limit.
+    /// Stores shared by several processes must claim atomically. The default
+    /// implementation doesn't coordinate at all.
+    fn claim(&self, key: &str, ttl: Duration) -> Option<Duration> {
+        let _ = (key, ttl);
+        None
+    }
+
+    /// Whether the methods of the store block on I/O, eg on a network
+    /// round trip.
+    ///
+    /// Async clients call blocking stores on the blocking thread pool of
+    /// tokio, so that they don't stall the runtime. Defaults to `false`.
+    fn blocking(&self) -> bool {
+        false
+    }
+}
+
+/// Call a store from async code, on the blocking thread pool if the store
+/// blocks.
+pub(crate) async fn call<T, F>(store: &Arc<dyn CacheStore>, f: F) -> T
+where
+    F: FnOnce(&dyn CacheStore) -> T + Send + 'static,
+    T: Send + 'static,
+{
+    if !store.blocking() {
+        return f(store.as_ref());
+    }
+    let store = store.clone();
+    match tokio::task::spawn_blocking(move || f(store.as_ref())).await {
+        Ok(value) => value,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// Expired values are removed once the cache holds at least this many.
+const MIN_SWEEP: usize = 64;
+
+/// A [`CacheStore`] keeping the values in memory.
+///
+/// Expired values are removed when they are read, and in bulk whenever the
+/// number of stored values doubled since the last sweep. Bound the cache with
+/// [`Self::max_entries`] to also evict the least recently used values.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    max_entries: Option<usize>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    /// Counts the uses of values, to find the least recently used one.
+    clock: u64,
+    /// Expired values are removed once `entries` has this many.
+    sweep_at: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// The expiry, or `None` if the value doesn't expire.
+    expires_at: Option<Instant>,
+    value: String,
+    /// The `clock` of the last use.
+    used: u64,
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+impl State {
+    fn insert(&mut self, key: &str, expires_at: Option<Instant>, value: String) {
+        self.clock += 1;
+        let entry = Entry {
+            expires_at,
+            value,
+            used: self.clock,
+        };
+        self.entries.insert(key.to_string(), entry);
+        if self.entries.len() >= self.sweep_at.max(MIN_SWEEP) {
+            let now = Instant::now();
+            self.entries.retain(|_, entry| !entry.is_expired(now));
+            self.sweep_at = self.entries.len() * 2;
+        }
+    }
+
+    /// Evict the least recently used values until at most `max` are left.
+    fn evict(&mut self, max: usize) {
+        if self.entries.len() > max {
+            let now = Instant::now();
+            self.entries.retain(|_, entry| !entry.is_expired(now));
+        }
+        while self.entries.len() > max {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone())
+                .unwrap();
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+impl MemoryCache {
+    /// Construct an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `max` values, evicting the least recently used ones.
+    ///
+    /// Evicting a value takes time linear in `max`, so this is meant for
+    /// bounds of up to a few thousand values.
+    #[must_use]
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = Some(max);
+        self
+    }
+
+    /// Get the number of stored values, including expired ones.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Check whether no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all values.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.sweep_at = 0;
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        match state.entries.get_mut(key) {
+            Some(entry) if !entry.is_expired(Instant::now()) => {
+                entry.used = clock;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                state.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn put(&self, key: &str, value: String, ttl: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.insert(key, Instant::now().checked_add(ttl), value);
+        if let Some(max) = self.max_entries {
+            state.evict(max);
+        }
+    }
+
+    fn claim(&self, key: &str, ttl: Duration) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        match state.entries.get(key).map(|entry| entry.expires_at) {
+            Some(None) => Some(Duration::MAX),
+            Some(Some(expires_at)) if expires_at > now => Some(expires_at - now),
+            _ => {
+                state.insert(key, now.checked_add(ttl), String::new());
+                if let Some(max) = self.max_entries {
+                    state.evict(max);
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_memory_cache() {
+        let cache = MemoryCache::new();
+        cache.put("a", "1".to_string(), Duration::from_secs(60));
+        cache.put("b", "2".to_string(), Duration::ZERO);
+        cache.put("c", "3".to_string(), Duration::MAX);
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        assert_eq!(cache.get("c").as_deref(), Some("3"));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("d"), None);
+        cache.clear();
+        assert!(cache.is_empty());
+
+        assert_eq!(cache.claim("lock", Duration::from_secs(60)), None);
+        let wait = cache.claim("lock", Duration::from_secs(60)).unwrap();
+        assert!(wait > Duration::from_secs(50), "{:?}", wait);
+        assert_eq!(cache.claim("other", Duration::ZERO), None);
+        assert_eq!(cache.claim("other", Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_memory_cache_eviction() {
+        // Expired values are swept on put, even if they are never read.
+        let cache = MemoryCache::new();
+        for i in 0..1000 {
+            cache.put(&i.to_string(), String::new(), Duration::ZERO);
+        }
+        assert!(cache.len() < 2 * MIN_SWEEP, "{}", cache.len());
+
+        let cache = MemoryCache::new().max_entries(2);
+        cache.put("a", "1".to_string(), Duration::MAX);
+        cache.put("b", "2".to_string(), Duration::MAX);
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        cache.put("c", "3".to_string(), Duration::MAX);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        assert_eq!(cache.get("c").as_deref(), Some("3"));
+
+        // Expired values go first.
+        cache.put("d", "4".to_string(), Duration::ZERO);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        assert_eq!(cache.get("c").as_deref(), Some("3"));
+    }
+
+    /// A store recording the threads it is called on.
+    struct ThreadStore {
+        blocking: bool,
+        threads: Mutex<Vec<std::thread::ThreadId>>,
+    }
+
+    impl CacheStore for ThreadStore {
+        fn get(&self, _key: &str) -> Option<String> {
+            self.threads
+                .lock()
+                .unwrap()
+                .push(std::thread::current().id());
+            None
+        }
+
+        fn put(&self, _key: &str, _value: String, _ttl: Duration) {}
+
+        fn blocking(&self) -> bool {
+            self.blocking
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_blocking_store() {
+        for blocking in [false, true] {
+            let store = Arc::new(ThreadStore {
+                blocking,
+                threads: Mutex::new(Vec::new()),
+            });
+            let dyn_store: Arc<dyn CacheStore> = store.clone();
+            assert_eq!(call(&dyn_store, |s| s.get("a")).await, None);
+            let threads = store.threads.lock().unwrap();
+            assert_eq!(threads[0] != std::thread::current().id(), blocking);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{AsyncClient, Error, Summary};
+
+/// A cached copy of the crates.io [`Summary`].
+///
+/// The summary is refreshed at most once per `max_age`, and served from the
+/// cache in between. Concurrent callers share a single refresh, and while it
+/// is in flight, callers that find an old copy get that one instead of
+/// waiting for the rate limiter and the network.
+///
+/// Cloning is cheap, and all clones share the same cache.
+#[derive(Clone)]
+pub struct CachedSummary {
+    client: AsyncClient,
+    max_age: Duration,
+    entry: Arc<Mutex<Option<Entry>>>,
+    /// Held while a refresh is in flight.
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    fetched_at: Instant,
+    summary: Arc<Summary>,
+}
+
+impl Entry {
+    fn is_fresh(&self, max_age: Duration) -> bool {
+        self.fetched_at.elapsed() < max_age
+    }
+}
+
+impl CachedSummary {
+    /// Construct an empty cache.
+    ///
+    /// The summary is fetched on the first call to [`Self::get`].
+    pub fn new(client: AsyncClient, max_age: Duration) -> Self {
+        Self {
+            client,
+            max_age,
+            entry: Arc::new(Mutex::new(None)),
+            refreshing: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Get the summary, refreshing it if the cached copy is too old.
+    ///
+    /// If another caller is refreshing the summary already, the old copy is
+    /// returned right away. If the refresh fails, the error is returned and
+    /// the old copy is kept.
+    pub async fn get(&self) -> Result<Arc<Summary>, Error> {
+        let stale = match self.entry() {
+            Some(entry) if entry.is_fresh(self.max_age) => return Ok(entry.summary),
+            stale => stale,
+        };
+        match (stale, self.refreshing.try_lock()) {
+            // Another caller may have finished a refresh since the check
+            // above.
+            (_, Ok(_refreshing)) => match self.entry() {
+                Some(entry) if entry.is_fresh(self.max_age) => Ok(entry.summary),
+                _ => self.fetch().await,
+            },
+            (Some(stale), Err(_)) => Ok(stale.summary),
+            (None, Err(_)) => self.refresh().await,
+        }
+    }
+
+    /// Get the cached summary without refreshing it, even if it is too old.
+    ///
+    /// Never waits for a refresh.
+    pub fn cached(&self) -> Option<Arc<Summary>> {
+        self.entry().map(|entry| entry.summary)
+    }
+
+    /// Fetch a new summary, regardless of the age of the cached copy.
+    ///
+    /// If another caller is refreshing the summary already, its result is
+    /// shared.
+    pub async fn refresh(&self) -> Result<Arc<Summary>, Error> {
+        let requested_at = Instant::now();
+        let _refreshing = self.refreshing.lock().await;
+        match self.entry() {
+            Some(entry) if entry.fetched_at >= requested_at => Ok(entry.summary),
+            _ => self.fetch().await,
+        }
+    }
+
+    fn entry(&self) -> Option<Entry> {
+        self.entry.lock().unwrap().clone()
+    }
+
+    /// Fetch the summary, which callers only do while holding `refreshing`.
+    async fn fetch(&self) -> Result<Arc<Summary>, Error> {
+        let summary = Arc::new(self.client.summary().await?);
+        *self.entry.lock().unwrap() = Some(Entry {
+            fetched_at: Instant::now(),
+            summary: summary.clone(),
+        });
+        Ok(summary)
+    }
+
+    /// Refresh the summary every `max_age`, forever.
+    ///
+    /// Meant to be spawned as a background task, so that [`Self::get`]
+    /// always finds a fresh copy:
+    ///
+    /// ```rust,no_run
+    /// # use crates_io_api::{AsyncClient, CachedSummary};
+    /// # async fn run(client: AsyncClient) {
+    /// let cache = CachedSummary::new(client, std::time::Duration::from_secs(600));
+    /// tokio::spawn(cache.clone().refresh_periodically());
+    /// # }
+    /// ```
+    ///
+    /// Failed refreshes are logged and retried after `max_age`.
+    pub async fn refresh_periodically(self) {
+        loop {
+            if let Err(err) = self.refresh().await {
+                log::warn!("Could not refresh crates.io summary: {}", err);
+            }
+            tokio::time::sleep(self.max_age).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockServer;
+
+    #[test]
+    fn test_entry_is_fresh() {
+        let entry = Entry {
+            fetched_at: Instant::now(),
+            summary: Arc::new(Summary::default()),
+        };
+        assert!(entry.is_fresh(Duration::from_secs(60)));
+        assert!(!entry.is_fresh(Duration::from_secs(0)));
+    }
+
+    fn summary(num_crates: u64) -> Summary {
+        Summary {
+            num_crates,
+            ..Summary::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_summary_fresh() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_json("summary", &summary(1));
+        let cache = CachedSummary::new(server.async_client(), Duration::from_secs(60));
+        assert!(cache.cached().is_none());
+
+        assert_eq!(cache.get().await.unwrap().num_crates, 1);
+        assert_eq!(cache.get().await.unwrap().num_crates, 1);
+        assert_eq!(cache.cached().unwrap().num_crates, 1);
+        assert_eq!(server.requests(), ["summary"]);
+    }
+
+    #[tokio::test]
+    async fn test_cached_summary_stale_during_refresh() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_json("summary", &summary(1));
+        // Every refresh after the first waits for the rate limit.
+        let config = server.config().rate_limit(Duration::from_secs(60));
+        let client = AsyncClient::from_config(&config).unwrap();
+        let cache = CachedSummary::new(client, Duration::ZERO);
+        assert_eq!(cache.get().await.unwrap().num_crates, 1);
+
+        let refresh = tokio::spawn({
+            let cache = cache.clone();
+            async move { cache.refresh().await }
+        });
+        tokio::task::yield_now().await;
+        // The old copy is served while the refresh waits.
+        let stale = tokio::time::timeout(Duration::from_secs(1), cache.get()).await;
+        assert_eq!(stale.unwrap().unwrap().num_crates, 1);
+        refresh.abort();
+        assert_eq!(server.requests(), ["summary"]);
+    }
+
+    #[tokio::test]
+    async fn test_cached_summary_refresh_error() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_json("summary", &summary(1));
+        let cache = CachedSummary::new(server.async_client(), Duration::ZERO);
+        assert_eq!(cache.get().await.unwrap().num_crates, 1);
+
+        let server = server.with_error("summary", 500, "Internal Server Error");
+        assert!(cache.get().await.is_err());
+        assert!(cache.refresh().await.is_err());
+        assert_eq!(cache.cached().unwrap().num_crates, 1);
+
+        let server = server.with_json("summary", &summary(2));
+        assert_eq!(cache.get().await.unwrap().num_crates, 2);
+        assert_eq!(server.requests(), ["summary"; 4]);
+    }
+}
@@ -0,0 +1,309 @@
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::InvalidConfigError;
+use crate::types::QueryDefaults;
+use crate::{Budget, CacheStore, Error, LimiterState, Sort, UnknownFields};
+
+/// Configuration for [`AsyncClient::from_config`] and
+/// [`SyncClient::from_config`].
+///
+/// Both clients are built from the same configuration, so options are added
+/// here instead of to the constructors of both clients.
+///
+/// ```rust
+/// use crates_io_api::{ClientConfig, SyncClient};
+/// use std::time::Duration;
+///
+/// let config = ClientConfig::new("my_bot (help@my_bot.com)")
+///     .rate_limit(Duration::from_millis(500))
+///     .timeout(Duration::from_secs(30));
+/// let client = SyncClient::from_config(&config).unwrap();
+/// ```
+///
+/// [`AsyncClient::from_config`]: crate::AsyncClient::from_config
+/// [`SyncClient::from_config`]: crate::SyncClient::from_config
+#[derive(Clone)]
+pub struct ClientConfig {
+    pub(crate) user_agent: String,
+    pub(crate) rate_limit: Duration,
+    pub(crate) base_url: String,
+    pub(crate) token: Option<String>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) unknown_fields: UnknownFields,
+    pub(crate) budget: Option<Arc<Budget>>,
+    pub(crate) limiter_state: Option<Arc<LimiterState>>,
+    pub(crate) cache: Option<(Arc<dyn CacheStore>, Duration)>,
+    pub(crate) shared_rate_limit: Option<Arc<dyn CacheStore>>,
+    pub(crate) query_defaults: QueryDefaults,
+}
+
+impl std::fmt::Debug for ClientConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientConfig")
+            .field("user_agent", &self.user_agent)
+            .field("rate_limit", &self.rate_limit)
+            .field("base_url", &self.base_url)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("unknown_fields", &self.unknown_fields)
+            .field("budget", &self.budget)
+            .field("limiter_state", &self.limiter_state)
+            .field("cache", &self.cache.as_ref().map(|(_, ttl)| ttl))
+            .field("shared_rate_limit", &self.shared_rate_limit.is_some())
+            .field("query_defaults", &self.query_defaults)
+            .finish()
+    }
+}
+
+impl ClientConfig {
+    /// Construct a configuration with the given user agent.
+    ///
+    /// To respect the offical [Crawler Policy](https://crates.io/policies#crawlers),
+    /// the user agent must be descriptive, eg `"my_bot (help@my_bot.com)"`.
+    /// The rate limit defaults to one request per second.
+    pub fn new(user_agent: impl Into<String>) -> Self {
+        Self {
+            user_agent: user_agent.into(),
+            rate_limit: Duration::from_secs(1),
+            base_url: "https://crates.io/api/v1/".to_string(),
+            token: None,
+            timeout: None,
+            connect_timeout: None,
+            unknown_fields: UnknownFields::Ignore,
+            budget: None,
+            limiter_state: None,
+            cache: None,
+            shared_rate_limit: None,
+            query_defaults: QueryDefaults::default(),
+        }
+    }
+
+    /// Read the configuration from environment variables.
+    ///
+    /// * `CRATES_IO_API_USER_AGENT`: the user agent, required
+    /// * `CRATES_IO_API_RATE_LIMIT_MS`: the minimum interval between
+    ///   requests in milliseconds
+    /// * `CRATES_IO_BASE_URL`: the API root
+    /// * `CRATES_IO_TOKEN`: an API token
+    ///
+    /// Unset variables keep their defaults. Returns
+    /// [`Error::InvalidConfig`] if the user agent is missing or a variable
+    /// can't be parsed.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let user_agent = var("CRATES_IO_API_USER_AGENT").ok_or_else(|| {
+            Error::InvalidConfig(InvalidConfigError::new(
+                "CRATES_IO_API_USER_AGENT",
+                "a user agent is required",
+            ))
+        })?;
+        let mut config = Self::new(user_agent);
+        if let Some(millis) = var("CRATES_IO_API_RATE_LIMIT_MS") {
+            let millis = millis.trim().parse().map_err(|_| {
+                Error::InvalidConfig(InvalidConfigError::new(
+                    "CRATES_IO_API_RATE_LIMIT_MS",
+                    format!("'{}' is not a number of milliseconds", millis),
+                ))
+            })?;
+            config.rate_limit = Duration::from_millis(millis);
+        }
+        if let Some(base_url) = var("CRATES_IO_BASE_URL") {
+            config.base_url = base_url;
+        }
+        config.token = var("CRATES_IO_TOKEN");
+        Ok(config)
+    }
+
+    /// Set the minimum interval between requests.
+    #[must_use]
+    pub fn rate_limit(mut self, rate_limit: Duration) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Use a different API root, eg a mirror or a mock server.
+    #[must_use]
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Authenticate requests with a crates.io API token.
+    #[must_use]
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set a timeout for whole requests, from connecting until the response
+    /// body has been read.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set a timeout for connecting to the server.
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how fields in API responses that are not modelled by this crate
+    /// are treated.
+    #[must_use]
+    pub fn unknown_fields(mut self, mode: UnknownFields) -> Self {
+        self.unknown_fields = mode;
+        self
+    }
+
+    /// Limit the number of requests, see [`Budget`].
+    ///
+    /// The budget can be shared by multiple clients.
+    #[must_use]
+    pub fn budget(mut self, budget: Arc<Budget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Save the rate limiter and budget state to disk, see [`LimiterState`].
+    #[must_use]
+    pub fn limiter_state(mut self, state: Arc<LimiterState>) -> Self {
+        self.limiter_state = Some(state);
+        self
+    }
+
+    /// Answer requests from a cache, see [`CacheStore`].
+    ///
+    /// Responses are cached for `ttl`.
+    #[must_use]
+    pub fn cache(mut self, cache: Arc<dyn CacheStore>, ttl: Duration) -> Self {
+        self.cache = Some((cache, ttl));
+        self
+    }
+
+    /// Share the rate limit with all clients using the same store, see
+    /// [`CacheStore::claim`].
+    #[must_use]
+    pub fn shared_rate_limit(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.shared_rate_limit = Some(store);
+        self
+    }
+
+    /// Sort crate queries that don't choose a sort with `sort`, see
+    /// [`AsyncClient::with_default_sort`](crate::AsyncClient::with_default_sort).
+    #[must_use]
+    pub fn default_sort(mut self, sort: Sort) -> Self {
+        self.query_defaults.sort = Some(sort);
+        self
+    }
+
+    /// Fetch crate queries that don't choose a page size in pages of
+    /// `page_size`, see
+    /// [`AsyncClient::with_default_page_size`](crate::AsyncClient::with_default_page_size).
+    #[must_use]
+    pub fn default_page_size(mut self, page_size: u64) -> Self {
+        self.query_defaults.page_size = Some(page_size);
+        self
+    }
+
+    /// Build the headers sent with every request.
+    pub(crate) fn headers(&self) -> Result<HeaderMap, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, HeaderValue::from_str(&self.user_agent)?);
+        if let Some(token) = &self.token {
+            let mut value = HeaderValue::from_str(token)?;
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockServer;
+    use crate::SyncClient;
+
+    #[test]
+    fn test_client_config() {
+        let config = ClientConfig::new("test (test@example.com)").token("secret");
+        let headers = config.headers().unwrap();
+        assert_eq!(headers[header::USER_AGENT], "test (test@example.com)");
+        assert!(headers[header::AUTHORIZATION].is_sensitive());
+        assert!(!format!("{:?}", config).contains("secret"));
+
+        let config = ClientConfig::new("invalid\nagent");
+        assert!(matches!(config.headers(), Err(Error::InvalidHeader(_))));
+        assert!(matches!(
+            crate::SyncClient::from_config(&config.base_url("not a url")),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            crate::AsyncClient::from_config(&ClientConfig::new("test").base_url("not a url")),
+            Err(Error::Url(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_config_from_vars() {
+        let vars = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        let config = ClientConfig::from_vars(vars(&[
+            ("CRATES_IO_API_USER_AGENT", "ci (ci@example.com)"),
+            ("CRATES_IO_API_RATE_LIMIT_MS", "250"),
+            ("CRATES_IO_TOKEN", "secret"),
+        ]))
+        .unwrap();
+        assert_eq!(config.user_agent, "ci (ci@example.com)");
+        assert_eq!(config.rate_limit, Duration::from_millis(250));
+        assert_eq!(config.token.as_deref(), Some("secret"));
+        assert_eq!(config.base_url, "https://crates.io/api/v1/");
+
+        assert!(matches!(
+            ClientConfig::from_vars(vars(&[])),
+            Err(Error::InvalidConfig(_))
+        ));
+        let err = ClientConfig::from_vars(vars(&[
+            ("CRATES_IO_API_USER_AGENT", "ci"),
+            ("CRATES_IO_API_RATE_LIMIT_MS", "1s"),
+        ]))
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration in CRATES_IO_API_RATE_LIMIT_MS: '1s' is not a number of milliseconds"
+        );
+    }
+
+    #[test]
+    fn test_mock_server_shared_rate_limit() {
+        let server = MockServer::start().unwrap().with_crate("foo", &["1.0.0"]);
+        let store = std::sync::Arc::new(crate::MemoryCache::new());
+        let config = server
+            .config()
+            .rate_limit(std::time::Duration::from_millis(200))
+            .shared_rate_limit(store);
+        let first = SyncClient::from_config(&config).unwrap();
+        let second = SyncClient::from_config(&config).unwrap();
+
+        let start = std::time::Instant::now();
+        assert!(first.crate_exists("foo").unwrap());
+        assert!(second.crate_exists("foo").unwrap());
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use crate::{CratesQuery, FullCrateOptions};
+
+/// The page size of reverse dependencies.
+const REVERSE_DEPENDENCIES_PAGE_SIZE: u64 = 100;
+
+/// An operation whose cost can be estimated with `estimate_cost()` on the
+/// clients.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Operation<'a> {
+    /// Retrieving a crate with `full_crate_with_options()`.
+    FullCrate {
+        /// The crate name.
+        crate_name: &'a str,
+        /// The options of the retrieval.
+        options: &'a FullCrateOptions,
+    },
+    /// Retrieving all reverse dependencies with
+    /// `crate_reverse_dependencies()`.
+    ReverseDependencies {
+        /// The crate name.
+        crate_name: &'a str,
+    },
+    /// Crawling all pages of a query from its current page on, eg with
+    /// `crates_stream()` or `all_crates()`.
+    Crawl {
+        /// The query.
+        query: &'a CratesQuery,
+    },
+}
+
+/// The estimated cost of an [`Operation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CostEstimate {
+    /// The number of requests the operation sends.
+    pub requests: u64,
+    /// How long the requests take at the rate limit of the client, without
+    /// network latency.
+    pub eta: Duration,
+}
+
+impl CostEstimate {
+    pub(crate) fn new(requests: u64, rate_limit: Duration) -> Self {
+        Self {
+            requests,
+            eta: rate_limit * requests.min(u64::from(u32::MAX)) as u32,
+        }
+    }
+}
+
+/// Get the number of requests for crawling `total` items, including the
+/// empty page that ends the crawl.
+pub(crate) fn pages(total: u64, page_size: u64) -> u64 {
+    total.div_ceil(page_size.max(1)) + 1
+}
+
+/// Get the number of requests for retrieving `total` reverse dependencies.
+pub(crate) fn reverse_dependency_pages(total: u64) -> u64 {
+    pages(total, REVERSE_DEPENDENCIES_PAGE_SIZE)
+}
+
+/// Get the number of requests for crawling a query with `total` results.
+pub(crate) fn crawl_pages(query: &CratesQuery, total: u64) -> u64 {
+    let skipped = (query.page().max(1) - 1).saturating_mul(query.page_size());
+    pages(total.saturating_sub(skipped), query.page_size())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pages() {
+        assert_eq!(reverse_dependency_pages(0), 1);
+        assert_eq!(reverse_dependency_pages(100), 2);
+        assert_eq!(reverse_dependency_pages(101), 3);
+
+        let query = CratesQuery::builder().page_size(10).page(3).build();
+        assert_eq!(crawl_pages(&query, 45), 4);
+        assert_eq!(crawl_pages(&query, 5), 1);
+
+        let estimate = CostEstimate::new(3, Duration::from_secs(1));
+        assert_eq!(estimate.eta, Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,242 @@
+//! Validated crate names.
+
+use crate::error::InvalidCrateNameError;
+use std::convert::TryFrom;
+
+/// Names that are reserved on crates.io and can never be used for a crate.
+///
+/// This is only part of the list: crates.io keeps its reserved names in its
+/// database and can add more at any time, without exposing them in the API.
+/// Only names that are certain to stay reserved are listed, so a valid
+/// [`CrateName`] may still be reserved, but a reserved one is never a crate.
+const RESERVED_NAMES: &[&str] = &[
+    // Rust sysroot crates.
+    "alloc",
+    "core",
+    "proc_macro",
+    "std",
+    // Windows device names.
+    "aux",
+    "com1",
+    "com2",
+    "com3",
+    "com4",
+    "com5",
+    "com6",
+    "com7",
+    "com8",
+    "com9",
+    "con",
+    "lpt1",
+    "lpt2",
+    "lpt3",
+    "lpt4",
+    "lpt5",
+    "lpt6",
+    "lpt7",
+    "lpt8",
+    "lpt9",
+    "nul",
+    "prn",
+];
+
+/// A crate name that follows the crates.io naming rules.
+///
+/// A valid name:
+/// * is between 1 and [`CrateName::MAX_LENGTH`] characters long
+/// * starts with an ASCII letter
+/// * only contains ASCII letters, digits, `-` and `_`
+/// * is not one of the well-known reserved names, like `std` or `nul`
+///
+/// crates.io reserves more names than are checked here, so a valid name can
+/// still be rejected at publish time.
+///
+/// Client methods validate crate names with the same rules, so invalid names
+/// fail locally instead of wasting a request on a guaranteed 404.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CrateName(String);
+
+impl CrateName {
+    /// The maximum length of a crate name.
+    pub const MAX_LENGTH: usize = 64;
+
+    /// Validate the given name.
+    pub fn new(name: impl Into<String>) -> Result<Self, InvalidCrateNameError> {
+        let name = name.into();
+        Self::validate(&name)?;
+        Ok(Self(name))
+    }
+
+    /// Get the name as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Convert into the inner [`String`].
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+
+    /// Get the normalized form of the name.
+    ///
+    /// crates.io treats names that only differ in case or in `-` vs `_` as the
+    /// same crate, so `Foo-Bar` and `foo_bar` share the normalized name
+    /// `foo_bar`.
+    pub fn normalized(&self) -> String {
+        normalize(&self.0)
+    }
+
+    /// Check if two names refer to the same crate on crates.io.
+    pub fn is_equivalent(&self, other: &CrateName) -> bool {
+        self.normalized() == other.normalized()
+    }
+
+    pub(crate) fn validate(name: &str) -> Result<(), InvalidCrateNameError> {
+        let err = |reason: &str| Err(InvalidCrateNameError::new(name, reason));
+
+        match name.chars().next() {
+            None => return err("name is empty"),
+            Some(c) if !c.is_ascii_alphabetic() => {
+                return err("name must start with an ASCII letter")
+            }
+            Some(_) => {}
+        }
+        if name.len() > Self::MAX_LENGTH {
+            return err("name is longer than 64 characters");
+        }
+        if let Some(c) = name
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+        {
+            return Err(InvalidCrateNameError::new(
+                name,
+                format!("invalid character '{c}'"),
+            ));
+        }
+
+        if RESERVED_NAMES.contains(&normalize(name).as_str()) {
+            return err("name is reserved");
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn normalize(name: &str) -> String {
+    name.to_ascii_lowercase().replace('-', "_")
+}
+
+/// Get the names to try when looking up a crate, in order.
+///
+/// Starts with the name itself, followed by the variants with all separators
+/// replaced by `_` and by `-`.
+pub(crate) fn lookup_candidates(name: &str) -> Vec<String> {
+    let mut candidates = vec![name.to_string()];
+    for variant in [name.replace('-', "_"), name.replace('_', "-")] {
+        if !candidates.contains(&variant) {
+            candidates.push(variant);
+        }
+    }
+    candidates
+}
+
+impl std::fmt::Display for CrateName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsRef<str> for CrateName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::str::FromStr for CrateName {
+    type Err = InvalidCrateNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<&str> for CrateName {
+    type Error = InvalidCrateNameError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<String> for CrateName {
+    type Error = InvalidCrateNameError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<CrateName> for String {
+    fn from(name: CrateName) -> Self {
+        name.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_crate_name_valid() {
+        for name in [
+            "serde",
+            "crates_io_api",
+            "tokio-util",
+            "a",
+            "Inflector",
+            "x86",
+        ] {
+            assert_eq!(CrateName::new(name).unwrap().as_str(), name);
+        }
+        assert!(CrateName::new("a".repeat(CrateName::MAX_LENGTH)).is_ok());
+    }
+
+    #[test]
+    fn test_crate_name_invalid() {
+        let too_long = "a".repeat(CrateName::MAX_LENGTH + 1);
+        for name in ["", "1abc", "-abc", "a/b", "a b", "ä", "..", &too_long] {
+            assert!(
+                CrateName::new(name).is_err(),
+                "{:?} should be invalid",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_crate_name_normalized() {
+        let a = CrateName::new("Foo-Bar").unwrap();
+        let b = CrateName::new("foo_bar").unwrap();
+        assert_eq!(a.normalized(), "foo_bar");
+        assert!(a.is_equivalent(&b));
+        assert!(!a.is_equivalent(&CrateName::new("foobar").unwrap()));
+    }
+
+    #[test]
+    fn test_lookup_candidates() {
+        assert_eq!(lookup_candidates("serde"), vec!["serde"]);
+        assert_eq!(lookup_candidates("foo-bar"), vec!["foo-bar", "foo_bar"]);
+        assert_eq!(lookup_candidates("a-b_c"), vec!["a-b_c", "a_b_c", "a-b-c"]);
+    }
+
+    #[test]
+    fn test_crate_name_reserved() {
+        for name in ["std", "proc-macro", "NUL", "com1"] {
+            assert!(
+                CrateName::new(name).is_err(),
+                "{:?} should be reserved",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,571 @@
+//! Timestamps and dates returned by the API.
+//!
+//! The response types use [`Timestamp`] and [`Date`] instead of the types of
+//! a date and time crate. The `chrono` and `time` features add conversions
+//! to `chrono::DateTime<Utc>` and `chrono::NaiveDate`, and to
+//! `time::OffsetDateTime` and `time::Date`. They can be enabled together
+//! without changing any types.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use crate::error::InvalidDateError;
+
+const SECS_PER_DAY: i64 = 86_400;
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// 0000-01-01, in days since the Unix epoch.
+const MIN_DAYS: i32 = -719_528;
+/// 9999-12-31, in days since the Unix epoch.
+const MAX_DAYS: i32 = 2_932_896;
+
+/// A point in time in UTC, with nanosecond precision.
+///
+/// Timestamps are parsed from and formatted as RFC 3339, and are limited to
+/// the years 0 to 9999. Convert them with `From` and `TryFrom`, to
+/// `chrono::DateTime<Utc>` with the `chrono` feature and to
+/// `time::OffsetDateTime` with the `time` feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Timestamp {
+    /// 1970-01-01T00:00:00Z.
+    pub const UNIX_EPOCH: Timestamp = Timestamp { secs: 0, nanos: 0 };
+
+    /// Construct a timestamp from seconds since the Unix epoch and
+    /// nanoseconds within the second.
+    pub fn from_unix_timestamp(secs: i64, nanos: u32) -> Result<Self, InvalidDateError> {
+        let min = i64::from(MIN_DAYS) * SECS_PER_DAY;
+        let max = i64::from(MAX_DAYS) * SECS_PER_DAY + SECS_PER_DAY - 1;
+        if nanos >= NANOS_PER_SEC || !(min..=max).contains(&secs) {
+            return Err(InvalidDateError::new(
+                format!("{}.{:09}", secs, nanos),
+                "out of range",
+            ));
+        }
+        Ok(Self { secs, nanos })
+    }
+
+    /// Seconds since the Unix epoch.
+    pub fn unix_timestamp(&self) -> i64 {
+        self.secs
+    }
+
+    /// Nanoseconds within the second.
+    pub fn nanosecond(&self) -> u32 {
+        self.nanos
+    }
+
+    /// The day of the timestamp.
+    pub fn date(&self) -> Date {
+        Date {
+            days: self.secs.div_euclid(SECS_PER_DAY) as i32,
+        }
+    }
+
+    /// The time elapsed from `earlier` to this timestamp, or `None` if
+    /// `earlier` is later.
+    pub fn duration_since(&self, earlier: Timestamp) -> Option<Duration> {
+        if earlier > *self {
+            return None;
+        }
+        let (secs, nanos) = if self.nanos >= earlier.nanos {
+            (self.secs - earlier.secs, self.nanos - earlier.nanos)
+        } else {
+            (
+                self.secs - earlier.secs - 1,
+                self.nanos + NANOS_PER_SEC - earlier.nanos,
+            )
+        };
+        Some(Duration::new(secs as u64, nanos))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.secs.rem_euclid(SECS_PER_DAY);
+        write!(
+            f,
+            "{}T{:02}:{:02}:{:02}",
+            self.date(),
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )?;
+        if self.nanos == 0 {
+        } else if self.nanos % 1_000_000 == 0 {
+            write!(f, ".{:03}", self.nanos / 1_000_000)?;
+        } else if self.nanos % 1000 == 0 {
+            write!(f, ".{:06}", self.nanos / 1000)?;
+        } else {
+            write!(f, ".{:09}", self.nanos)?;
+        }
+        f.write_str("Z")
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = InvalidDateError;
+
+    /// Parse an RFC 3339 timestamp.
+    ///
+    /// Like the timestamps of the database dump, the `T` may be a space, the
+    /// offset may leave out the minutes and a missing offset means UTC.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidDateError::new(s, "expected an RFC 3339 timestamp");
+        let bytes = s.as_bytes();
+        if bytes.len() < 19 || !matches!(bytes[10], b'T' | b't' | b' ') {
+            return Err(invalid());
+        }
+        let date: Date = s.get(..10).ok_or_else(invalid)?.parse()?;
+        let (hour, minute, second) = match &bytes[10..19] {
+            [_, h1, h2, b':', m1, m2, b':', s1, s2] => (
+                digits(&[*h1, *h2]).filter(|h| *h < 24),
+                digits(&[*m1, *m2]).filter(|m| *m < 60),
+                digits(&[*s1, *s2]).filter(|s| *s < 60),
+            ),
+            _ => return Err(invalid()),
+        };
+        let time = i64::from(hour.ok_or_else(invalid)?) * 3600
+            + i64::from(minute.ok_or_else(invalid)?) * 60
+            + i64::from(second.ok_or_else(invalid)?);
+
+        let mut rest = &bytes[19..];
+        let mut nanos = 0;
+        if let Some(fraction) = rest.strip_prefix(b".") {
+            let len = fraction.iter().take_while(|c| c.is_ascii_digit()).count();
+            if len == 0 {
+                return Err(invalid());
+            }
+            // Digits beyond nanoseconds are dropped.
+            for (i, digit) in fraction[..len.min(9)].iter().enumerate() {
+                nanos += u32::from(digit - b'0') * 10u32.pow(8 - i as u32);
+            }
+            rest = &fraction[len..];
+        }
+        let offset = match rest {
+            [] | [b'Z'] | [b'z'] => 0,
+            [sign @ (b'+' | b'-'), h1, h2, minutes @ ..] => {
+                let minutes = match minutes {
+                    [] => Some(0),
+                    [b':', m1, m2] | [m1, m2] => digits(&[*m1, *m2]).filter(|m| *m < 60),
+                    _ => None,
+                };
+                let hours = digits(&[*h1, *h2]).filter(|h| *h < 24);
+                let offset =
+                    i64::from(hours.ok_or_else(invalid)? * 60 + minutes.ok_or_else(invalid)?);
+                if *sign == b'-' {
+                    -offset * 60
+                } else {
+                    offset * 60
+                }
+            }
+            _ => return Err(invalid()),
+        };
+        let secs = i64::from(date.days) * SECS_PER_DAY + time - offset;
+        Self::from_unix_timestamp(secs, nanos).map_err(|err| InvalidDateError::new(s, err.reason))
+    }
+}
+
+impl TryFrom<SystemTime> for Timestamp {
+    type Error = InvalidDateError;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since) => i64::try_from(since.as_secs())
+                .map_err(|_| InvalidDateError::new(format!("{:?}", time), "out of range"))
+                .and_then(|secs| Self::from_unix_timestamp(secs, since.subsec_nanos())),
+            Err(err) => {
+                let before = err.duration();
+                let secs = -i64::try_from(before.as_secs()).unwrap_or(i64::MAX);
+                match before.subsec_nanos() {
+                    0 => Self::from_unix_timestamp(secs, 0),
+                    nanos => Self::from_unix_timestamp(secs - 1, NANOS_PER_SEC - nanos),
+                }
+            }
+        }
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Cow::<'de, str>::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A day of the proleptic Gregorian calendar, like the days of download
+/// counts.
+///
+/// Dates are parsed from and formatted as `YYYY-MM-DD`, and are limited to
+/// the years 0 to 9999. Convert them with `From` and `TryFrom`, to
+/// `chrono::NaiveDate` with the `chrono` feature and to `time::Date` with the
+/// `time` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// Days since the Unix epoch.
+    days: i32,
+}
+
+impl Date {
+    /// Construct a date from the year, the month (1 to 12) and the day of
+    /// the month (1 to 31).
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Result<Self, InvalidDateError> {
+        let date = Self {
+            days: days_from_civil(year.clamp(0, 9999), month.clamp(1, 12), day.clamp(1, 31)),
+        };
+        // Days past the end of the month are counted into the next one.
+        if date.ymd() != (year, month, day) {
+            return Err(InvalidDateError::new(
+                format!("{:04}-{:02}-{:02}", year, month, day),
+                "no such day",
+            ));
+        }
+        Ok(date)
+    }
+
+    /// The year.
+    pub fn year(&self) -> i32 {
+        self.ymd().0
+    }
+
+    /// The month, from 1 to 12.
+    pub fn month(&self) -> u32 {
+        self.ymd().1
+    }
+
+    /// The day of the month, from 1 to 31.
+    pub fn day(&self) -> u32 {
+        self.ymd().2
+    }
+
+    /// The number of days since the last Monday, from 0 to 6.
+    pub(crate) fn days_from_monday(&self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (self.days + 3).rem_euclid(7) as u32
+    }
+
+    pub(crate) fn add_days(&self, days: i32) -> Date {
+        Date {
+            days: self.days + days,
+        }
+    }
+
+    fn ymd(&self) -> (i32, u32, u32) {
+        civil_from_days(self.days)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = InvalidDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let [y1, y2, y3, y4, b'-', m1, m2, b'-', d1, d2] = s.as_bytes() {
+            let year = digits(&[*y1, *y2, *y3, *y4]);
+            let month = digits(&[*m1, *m2]);
+            let day = digits(&[*d1, *d2]);
+            if let (Some(year), Some(month), Some(day)) = (year, month, day) {
+                return Self::from_ymd(year as i32, month, day)
+                    .map_err(|err| InvalidDateError::new(s, err.reason));
+            }
+        }
+        Err(InvalidDateError::new(s, "expected a YYYY-MM-DD date"))
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Cow::<'de, str>::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn digits(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0, |value, digit| {
+        digit
+            .is_ascii_digit()
+            .then(|| value * 10 + u32::from(digit - b'0'))
+    })
+}
+
+/// The days since the Unix epoch of a day of the proleptic Gregorian
+/// calendar, after <http://howardhinnant.github.io/date_algorithms.html>.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i32 {
+    let (month, day) = (month as i32, day as i32);
+    let year = year - i32::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+fn civil_from_days(days: i32) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i32::from(month <= 2);
+    (year, month as u32, day as u32)
+}
+
+#[cfg(feature = "chrono")]
+impl From<Timestamp> for chrono::DateTime<chrono::Utc> {
+    fn from(timestamp: Timestamp) -> Self {
+        use chrono::TimeZone;
+        chrono::Utc
+            .timestamp_opt(timestamp.secs, timestamp.nanos)
+            .single()
+            .expect("the years 0 to 9999 are within the range of chrono")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::Utc>> for Timestamp {
+    type Error = InvalidDateError;
+
+    fn try_from(datetime: chrono::DateTime<chrono::Utc>) -> Result<Self, Self::Error> {
+        Self::from_unix_timestamp(datetime.timestamp(), datetime.timestamp_subsec_nanos())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Date> for chrono::NaiveDate {
+    fn from(date: Date) -> Self {
+        let (year, month, day) = date.ymd();
+        chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .expect("the years 0 to 9999 are within the range of chrono")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDate> for Date {
+    type Error = InvalidDateError;
+
+    fn try_from(date: chrono::NaiveDate) -> Result<Self, Self::Error> {
+        use chrono::Datelike;
+        Self::from_ymd(date.year(), date.month(), date.day())
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<Timestamp> for time::OffsetDateTime {
+    fn from(timestamp: Timestamp) -> Self {
+        let nanos =
+            i128::from(timestamp.secs) * i128::from(NANOS_PER_SEC) + i128::from(timestamp.nanos);
+        time::OffsetDateTime::from_unix_timestamp_nanos(nanos)
+            .expect("the years 0 to 9999 are within the range of time")
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<time::OffsetDateTime> for Timestamp {
+    type Error = InvalidDateError;
+
+    fn try_from(datetime: time::OffsetDateTime) -> Result<Self, Self::Error> {
+        Self::from_unix_timestamp(datetime.unix_timestamp(), datetime.nanosecond())
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<Date> for time::Date {
+    fn from(date: Date) -> Self {
+        let (year, month, day) = date.ymd();
+        time::Month::try_from(month as u8)
+            .and_then(|month| time::Date::from_calendar_date(year, month, day as u8))
+            .expect("the years 0 to 9999 are within the range of time")
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<time::Date> for Date {
+    type Error = InvalidDateError;
+
+    fn try_from(date: time::Date) -> Result<Self, Self::Error> {
+        Self::from_ymd(
+            date.year(),
+            u8::from(date.month()).into(),
+            date.day().into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let timestamp: Timestamp = "2015-05-12T03:33:07.080208+00:00".parse().unwrap();
+        assert_eq!(timestamp.unix_timestamp(), 1431401587);
+        assert_eq!(timestamp.nanosecond(), 80_208_000);
+        assert_eq!(timestamp.to_string(), "2015-05-12T03:33:07.080208Z");
+
+        // The formats of the database dump.
+        for value in [
+            "2015-05-12 03:33:07.080208",
+            "2015-05-12 05:33:07.080208+02",
+            "2015-05-11T22:03:07.0802080001-05:30",
+        ] {
+            assert_eq!(value.parse::<Timestamp>().unwrap(), timestamp, "{}", value);
+        }
+
+        let epoch: Timestamp = "1970-01-01T00:00:00Z".parse().unwrap();
+        assert_eq!(epoch, Timestamp::UNIX_EPOCH);
+        assert_eq!(epoch.to_string(), "1970-01-01T00:00:00Z");
+        let before: Timestamp = "1969-12-31T23:59:59.5Z".parse().unwrap();
+        assert_eq!(
+            (before.unix_timestamp(), before.nanosecond()),
+            (-1, 500_000_000)
+        );
+        assert_eq!(before.to_string(), "1969-12-31T23:59:59.500Z");
+        assert_eq!(
+            epoch.duration_since(before),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(before.duration_since(epoch), None);
+
+        for invalid in [
+            "",
+            "2015-05-12",
+            "2015-05-12T24:00:00Z",
+            "2015-02-30T00:00:00Z",
+            "2015-05-12T03:33:07.Z",
+            "2015-05-12T03:33:07+0",
+            "2015-05-12T03:33:07 UTC",
+            "0000-01-01T00:00:00+01:00",
+        ] {
+            assert!(invalid.parse::<Timestamp>().is_err(), "{}", invalid);
+        }
+        assert!(Timestamp::from_unix_timestamp(i64::MAX, 0).is_err());
+        assert!(Timestamp::from_unix_timestamp(0, NANOS_PER_SEC).is_err());
+    }
+
+    #[test]
+    fn test_timestamp_serde() {
+        let timestamp: Timestamp = serde_json::from_str(r#""2017-01-06T14:23:11Z""#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&timestamp).unwrap(),
+            r#""2017-01-06T14:23:11Z""#
+        );
+        assert!(serde_json::from_str::<Timestamp>("1483712591").is_err());
+    }
+
+    #[test]
+    fn test_system_time() {
+        let time = SystemTime::UNIX_EPOCH - Duration::from_millis(1500);
+        let timestamp = Timestamp::try_from(time).unwrap();
+        assert_eq!(timestamp.to_string(), "1969-12-31T23:59:58.500Z");
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1431401587);
+        assert_eq!(
+            Timestamp::try_from(time).unwrap().to_string(),
+            "2015-05-12T03:33:07Z"
+        );
+    }
+
+    #[test]
+    fn test_date() {
+        for (value, days) in [
+            ("0000-01-01", MIN_DAYS),
+            ("0000-03-01", MIN_DAYS + 60),
+            ("1969-12-31", -1),
+            ("1970-01-01", 0),
+            ("2000-02-29", 11_016),
+            ("9999-12-31", MAX_DAYS),
+        ] {
+            let date: Date = value.parse().unwrap();
+            assert_eq!(date.days, days, "{}", value);
+            assert_eq!(date.to_string(), value);
+        }
+        let date = Date::from_ymd(2024, 2, 29).unwrap();
+        assert_eq!((date.year(), date.month(), date.day()), (2024, 2, 29));
+        // A Thursday.
+        assert_eq!(date.days_from_monday(), 3);
+        assert_eq!(date.add_days(1).to_string(), "2024-03-01");
+        assert_eq!(serde_json::to_string(&date).unwrap(), r#""2024-02-29""#);
+
+        for invalid in [
+            "2023-02-29",
+            "1900-02-29",
+            "2024-13-01",
+            "2024-01-00",
+            "24-01-01",
+        ] {
+            assert!(invalid.parse::<Date>().is_err(), "{}", invalid);
+        }
+        assert!(Date::from_ymd(-1, 12, 31).is_err());
+        assert!(Date::from_ymd(10_000, 1, 1).is_err());
+        let timestamp: Timestamp = "1969-12-31T23:59:59Z".parse().unwrap();
+        assert_eq!(timestamp.date().to_string(), "1969-12-31");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono() {
+        let timestamp: Timestamp = "2015-05-12T03:33:07.080208Z".parse().unwrap();
+        let datetime = chrono::DateTime::<chrono::Utc>::from(timestamp);
+        assert_eq!(datetime.timestamp(), 1431401587);
+        assert_eq!(datetime.timestamp_subsec_nanos(), 80_208_000);
+        assert_eq!(Timestamp::try_from(datetime).unwrap(), timestamp);
+        let max = chrono::DateTime::<chrono::Utc>::MAX_UTC;
+        assert!(Timestamp::try_from(max).is_err());
+
+        let date = chrono::NaiveDate::from(timestamp.date());
+        assert_eq!(date.to_string(), "2015-05-12");
+        assert_eq!(Date::try_from(date).unwrap(), timestamp.date());
+        assert!(Date::try_from(chrono::NaiveDate::MIN).is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time() {
+        let timestamp: Timestamp = "2015-05-12T03:33:07.080208Z".parse().unwrap();
+        let datetime = time::OffsetDateTime::from(timestamp);
+        assert_eq!(datetime.unix_timestamp(), 1431401587);
+        assert_eq!(datetime.nanosecond(), 80_208_000);
+        assert_eq!(Timestamp::try_from(datetime).unwrap(), timestamp);
+        let before =
+            time::OffsetDateTime::from(Timestamp::UNIX_EPOCH) - time::Duration::days(800_000);
+        assert!(Timestamp::try_from(before).is_err());
+
+        let date = time::Date::from(timestamp.date());
+        assert_eq!(
+            (date.year(), u8::from(date.month()), date.day()),
+            (2015, 5, 12)
+        );
+        assert_eq!(Date::try_from(date).unwrap(), timestamp.date());
+        assert!(Date::try_from(time::Date::MIN).is_err());
+    }
+}
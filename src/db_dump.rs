@@ -0,0 +1,924 @@
+//! Reading the crates.io database dump.
+//!
+//! Requires the `db-dump` feature.
+//!
+//! crates.io publishes a daily dump of its database at [`DB_DUMP_URL`].
+//! For bulk analysis, the dump is far more polite than crawling the API.
+//!
+//! The dump is a gzip compressed tar archive. [`load_gz`] and [`visit_gz`]
+//! read it as downloaded, while [`load`] and [`visit`] read an archive that
+//! was already decompressed:
+//!
+//! ```rust,no_run
+//! # fn run() -> std::io::Result<()> {
+//! let file = std::fs::File::open("db-dump.tar.gz")?;
+//! let dump = crates_io_api::db_dump::load_gz(file)?;
+//! println!("{} crates", dump.crates.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The rows of the dump are read into the response types of the API:
+//! [`Crate`], [`Version`], [`Dependency`] and [`VersionDownloads`]. Fields
+//! that are not in the dump, like [`Crate::max_version`], are left empty.
+//!
+//! Note: the full dump is several gigabytes when decompressed, most of it
+//! download counts. Use [`visit_gz`] to process the records one by one
+//! instead of loading all of them into memory.
+
+use flate2::read::MultiGzDecoder;
+use futures::prelude::*;
+use serde_derive::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::error::InvalidQueryError;
+use crate::{
+    AsyncClient, Crate, CrateResponse, CratesQuery, Date, Dependency, DependencyKind, Error, Sort,
+    Timestamp, Version, VersionDownloads,
+};
+
+/// Location of the latest database dump.
+pub const DB_DUMP_URL: &str = "https://static.crates.io/db-dump.tar.gz";
+
+/// Metadata of a database dump.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DumpMetadata {
+    /// When the dump was created.
+    pub timestamp: Timestamp,
+}
+
+/// A single record of a database dump, see [`visit`].
+///
+/// The dump refers to crates by a numeric id, which the API doesn't have, so
+/// it's passed along. Versions and dependencies get the names of the crates
+/// that were read before them. crates.io lists the crates first, so names are
+/// only empty for dumps in a different order, see [`load`].
+#[derive(Debug, Clone)]
+pub enum DumpRecord {
+    /// The dump metadata.
+    Metadata(DumpMetadata),
+    /// A crate, with its id.
+    Crate(u64, Crate),
+    /// A crate version, with the id of its crate.
+    Version(u64, Version),
+    /// A dependency of a crate version, with the id of the crate depended on.
+    ///
+    /// The dependency kind is one of normal, build and dev. Renames of
+    /// dependencies are not kept, as [`Dependency`] has no field for them.
+    Dependency(u64, Dependency),
+    /// The downloads of a crate version on a single day.
+    VersionDownloads(VersionDownloads),
+}
+
+/// The contents of a database dump, see [`load`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DbDump {
+    /// The dump metadata, if present.
+    pub metadata: Option<DumpMetadata>,
+    /// All crates, by id.
+    pub crates: BTreeMap<u64, Crate>,
+    /// All crate versions.
+    pub versions: Vec<Version>,
+    /// All dependencies.
+    pub dependencies: Vec<Dependency>,
+    /// Daily downloads of all versions.
+    pub version_downloads: Vec<VersionDownloads>,
+}
+
+/// Changes on crates.io since a database dump, see [`changes_since`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DumpChanges {
+    /// All crates updated after the dump was created.
+    pub crates: Vec<CrateResponse>,
+    /// The dependencies of all versions that are not in the dump, by
+    /// version id.
+    pub dependencies: HashMap<u64, Vec<Dependency>>,
+    /// Crates that were updated after the dump was created, but deleted
+    /// before they could be fetched.
+    #[serde(default)]
+    pub removed: Vec<String>,
+}
+
+/// Summary of the changes applied by [`DbDump::apply`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Number of crates that were not in the dump.
+    pub new_crates: usize,
+    /// Number of crates that were updated.
+    pub updated_crates: usize,
+    /// Number of versions that were not in the dump.
+    pub new_versions: usize,
+    /// Number of crates that were removed from the dump.
+    pub removed_crates: usize,
+}
+
+/// Fetch all changes on crates.io since a database dump was created.
+///
+/// Pages through the recently updated crates until reaching the dump
+/// timestamp, and fetches every changed crate, plus the dependencies of every
+/// version that is not in the dump. Depending on the age of the dump, this
+/// still requires many requests, but far fewer than crawling all crates.
+///
+/// Crates that are deleted while the changes are fetched are listed in
+/// [`DumpChanges::removed`].
+///
+/// Fails with [`Error::InvalidQuery`] if the dump has no metadata.
+pub async fn changes_since(client: &AsyncClient, dump: &DbDump) -> Result<DumpChanges, Error> {
+    let since = match &dump.metadata {
+        Some(metadata) => metadata.timestamp,
+        None => {
+            return Err(InvalidQueryError::new("the dump has no metadata timestamp").into());
+        }
+    };
+    let query = CratesQuery::builder()
+        .sort(Sort::RecentUpdates)
+        .page_size(CratesQuery::MAX_PAGE_SIZE)
+        .build();
+    let mut seen = HashSet::new();
+    let names: Vec<String> = client
+        .crates_stream(query)
+        .try_take_while(|krate| future::ready(Ok(krate.updated_at >= since)))
+        .map_ok(|krate| krate.name)
+        .try_filter(|name| future::ready(seen.insert(name.clone())))
+        .try_collect()
+        .await?;
+
+    let known_versions: HashSet<_> = dump.versions.iter().map(|v| v.id).collect();
+    let mut changes = DumpChanges::default();
+    for name in names {
+        let krate = match client.get_crate(&name).await {
+            Ok(krate) => krate,
+            Err(Error::NotFound(_)) => {
+                changes.removed.push(name);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        for version in &krate.versions {
+            if !known_versions.contains(&version.id) {
+                let deps = client.crate_dependencies(&name, &version.num).await?;
+                changes.dependencies.insert(version.id, deps);
+            }
+        }
+        changes.crates.push(krate);
+    }
+    Ok(changes)
+}
+
+/// Update a database dump with all changes on crates.io since it was
+/// created, see [`changes_since`] and [`DbDump::apply`].
+pub async fn sync(client: &AsyncClient, dump: &mut DbDump) -> Result<SyncReport, Error> {
+    let changes = changes_since(client, dump).await?;
+    Ok(dump.apply(&changes))
+}
+
+impl DbDump {
+    /// Merge changes fetched from the API into the dump.
+    ///
+    /// Crates created after the dump are assigned ids above all crate ids in
+    /// the dump, since the API doesn't expose the numeric crate ids. These
+    /// won't match the ids in later dumps. Version and dependency ids are
+    /// the real ids.
+    ///
+    /// Removed crates are dropped along with their versions, dependencies
+    /// and downloads.
+    ///
+    /// The metadata timestamp is advanced to the newest update, so the dump
+    /// can be synced again later.
+    pub fn apply(&mut self, changes: &DumpChanges) -> SyncReport {
+        let mut report = SyncReport::default();
+        if !changes.removed.is_empty() {
+            report.removed_crates = self.remove_crates(&changes.removed);
+        }
+        let mut crate_ids: HashMap<_, _> = self
+            .crates
+            .iter()
+            .map(|(id, krate)| (krate.name.clone(), *id))
+            .collect();
+        let mut version_index: HashMap<_, _> = self
+            .versions
+            .iter()
+            .enumerate()
+            .map(|(index, version)| (version.id, index))
+            .collect();
+        let mut next_crate_id = self.crates.keys().next_back().map_or(1, |id| id + 1);
+
+        for res in &changes.crates {
+            let krate = &res.crate_data;
+            match crate_ids.get(&krate.name) {
+                Some(id) => {
+                    self.crates.insert(*id, krate.clone());
+                    report.updated_crates += 1;
+                }
+                None => {
+                    crate_ids.insert(krate.name.clone(), next_crate_id);
+                    self.crates.insert(next_crate_id, krate.clone());
+                    next_crate_id += 1;
+                    report.new_crates += 1;
+                }
+            }
+            if let Some(metadata) = &mut self.metadata {
+                metadata.timestamp = metadata.timestamp.max(krate.updated_at);
+            }
+
+            for version in &res.versions {
+                match version_index.get(&version.id) {
+                    Some(index) => {
+                        let existing = &mut self.versions[*index];
+                        existing.yanked = version.yanked;
+                        existing.downloads = version.downloads;
+                        existing.updated_at = version.updated_at;
+                    }
+                    None => {
+                        version_index.insert(version.id, self.versions.len());
+                        self.versions.push(version.clone());
+                        report.new_versions += 1;
+                    }
+                }
+            }
+        }
+
+        for (version_id, deps) in &changes.dependencies {
+            for dep in deps {
+                let mut dep = dep.clone();
+                dep.version_id = *version_id;
+                self.dependencies.push(dep);
+            }
+        }
+        report
+    }
+
+    /// Remove the given crates and everything that belongs to them, and get
+    /// the number of crates that were in the dump.
+    fn remove_crates(&mut self, names: &[String]) -> usize {
+        let names: HashSet<_> = names.iter().map(String::as_str).collect();
+        let crates = self.crates.len();
+        self.crates
+            .retain(|_, krate| !names.contains(krate.name.as_str()));
+        let versions: HashSet<_> = self
+            .versions
+            .iter()
+            .filter(|version| names.contains(version.crate_name.as_str()))
+            .map(|version| version.id)
+            .collect();
+        self.versions
+            .retain(|version| !versions.contains(&version.id));
+        self.dependencies
+            .retain(|dep| !versions.contains(&dep.version_id));
+        self.version_downloads
+            .retain(|downloads| !versions.contains(&downloads.version));
+        crates - self.crates.len()
+    }
+}
+
+/// Download the latest database dump into `writer`.
+///
+/// The dump is served from a CDN, so this request doesn't go through the
+/// rate limit of a client. Returns the number of bytes written.
+///
+/// This uses a blocking HTTP client, which panics when it is called on an
+/// async runtime. Run it with `tokio::task::spawn_blocking` there.
+pub fn download<W: io::Write>(user_agent: &str, writer: &mut W) -> Result<u64, Error> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(user_agent)
+        .build()?;
+    let mut res = client.get(DB_DUMP_URL).send()?.error_for_status()?;
+    Ok(res.copy_to(writer)?)
+}
+
+/// Load all records of a gzip compressed database dump into memory.
+pub fn load_gz<R: Read>(reader: R) -> io::Result<DbDump> {
+    let mut decoder = MultiGzDecoder::new(reader);
+    let dump = load(&mut decoder)?;
+    verify_gz(decoder)?;
+    Ok(dump)
+}
+
+/// Load all records of a decompressed database dump into memory.
+///
+/// Unlike [`visit`], the crate names of all versions and dependencies are
+/// filled in, regardless of the order of the files in the dump.
+pub fn load<R: Read>(reader: R) -> io::Result<DbDump> {
+    let mut dump = DbDump::default();
+    // Records that were read before their crate, by crate id.
+    let mut unnamed_versions = Vec::new();
+    let mut unnamed_dependencies = Vec::new();
+    visit(reader, |record| match record {
+        DumpRecord::Metadata(metadata) => dump.metadata = Some(metadata),
+        DumpRecord::Crate(id, krate) => {
+            dump.crates.insert(id, krate);
+        }
+        DumpRecord::Version(crate_id, version) => {
+            if version.crate_name.is_empty() {
+                unnamed_versions.push((dump.versions.len(), crate_id));
+            }
+            dump.versions.push(version);
+        }
+        DumpRecord::Dependency(crate_id, dep) => {
+            if dep.crate_id.is_empty() {
+                unnamed_dependencies.push((dump.dependencies.len(), crate_id));
+            }
+            dump.dependencies.push(dep);
+        }
+        DumpRecord::VersionDownloads(downloads) => dump.version_downloads.push(downloads),
+    })?;
+
+    let crates = &dump.crates;
+    let name = |crate_id: u64| {
+        crates
+            .get(&crate_id)
+            .map(|krate| krate.name.clone())
+            .ok_or_else(|| invalid_data(format!("unknown crate id {}", crate_id)))
+    };
+    for (index, crate_id) in unnamed_versions {
+        let name = name(crate_id)?;
+        dump.versions[index].crate_name = name;
+    }
+    for (index, crate_id) in unnamed_dependencies {
+        let name = name(crate_id)?;
+        dump.dependencies[index].crate_id = name;
+    }
+    Ok(dump)
+}
+
+/// Stream all records of a gzip compressed database dump, see [`visit`].
+pub fn visit_gz<R, F>(reader: R, visitor: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(DumpRecord),
+{
+    let mut decoder = MultiGzDecoder::new(reader);
+    visit(&mut decoder, visitor)?;
+    verify_gz(decoder)
+}
+
+/// Read past the end of the archive, so that the gzip trailer is verified.
+fn verify_gz<R: Read>(mut decoder: MultiGzDecoder<R>) -> io::Result<()> {
+    io::copy(&mut decoder, &mut io::sink())?;
+    Ok(())
+}
+
+/// Stream all records of a decompressed database dump.
+///
+/// Records are passed to `visitor` in archive order. Files of the dump that
+/// don't map to a [`DumpRecord`] are skipped.
+pub fn visit<R, F>(reader: R, mut visitor: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(DumpRecord),
+{
+    // The names of the crates read so far, by id.
+    let mut names = HashMap::new();
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let file_name = path.rsplit('/').next().unwrap_or_default();
+        let mut entry = BufReader::new(entry);
+        match file_name {
+            "metadata.json" => {
+                let metadata = serde_json::from_reader(&mut entry).map_err(invalid_data)?;
+                visitor(DumpRecord::Metadata(metadata));
+            }
+            "crates.csv" => read_csv(&mut entry, |row| {
+                let (id, krate) = parse_crate(row)?;
+                names.insert(id, krate.name.clone());
+                visitor(DumpRecord::Crate(id, krate));
+                Ok(())
+            })?,
+            "versions.csv" => read_csv(&mut entry, |row| {
+                let (crate_id, version) = parse_version(row, &names)?;
+                visitor(DumpRecord::Version(crate_id, version));
+                Ok(())
+            })?,
+            "dependencies.csv" => read_csv(&mut entry, |row| {
+                let (crate_id, dep) = parse_dependency(row, &names)?;
+                visitor(DumpRecord::Dependency(crate_id, dep));
+                Ok(())
+            })?,
+            "version_downloads.csv" => read_csv(&mut entry, |row| {
+                visitor(DumpRecord::VersionDownloads(parse_version_downloads(row)?));
+                Ok(())
+            })?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// A CSV row, keyed by the column names of the header.
+struct Row<'a> {
+    columns: &'a HashMap<String, usize>,
+    fields: &'a [String],
+}
+
+impl<'a> Row<'a> {
+    fn get(&self, column: &str) -> io::Result<&'a str> {
+        self.columns
+            .get(column)
+            .and_then(|index| self.fields.get(*index))
+            .map(String::as_str)
+            .ok_or_else(|| invalid_data(format!("missing column '{}'", column)))
+    }
+
+    fn opt(&self, column: &str) -> Option<String> {
+        self.get(column)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    }
+
+    fn u64(&self, column: &str) -> io::Result<u64> {
+        let value = self.get(column)?;
+        value
+            .parse()
+            .map_err(|_| invalid_data(format!("invalid number '{}' in '{}'", value, column)))
+    }
+
+    fn bool(&self, column: &str) -> io::Result<bool> {
+        match self.get(column)? {
+            "t" | "true" => Ok(true),
+            "f" | "false" => Ok(false),
+            other => Err(invalid_data(format!(
+                "invalid boolean '{}' in '{}'",
+                other, column
+            ))),
+        }
+    }
+
+    /// Parse a Postgres timestamp, which is in UTC if no offset is given.
+    fn timestamp(&self, column: &str) -> io::Result<Timestamp> {
+        self.get(column)?.parse().map_err(invalid_data)
+    }
+}
+
+/// Parse a one-dimensional Postgres text array like `{a,"b c","d\"e"}`.
+///
+/// Quoted elements may contain commas and braces, and escape quotes and
+/// backslashes with a backslash. Unquoted `NULL` elements are dropped.
+fn parse_array(value: &str) -> io::Result<Vec<String>> {
+    let err = || invalid_data(format!("invalid array '{}'", value));
+    let inner = value
+        .strip_prefix('{')
+        .and_then(|inner| inner.strip_suffix('}'))
+        .ok_or_else(err)?;
+    let mut items = Vec::new();
+    if inner.is_empty() {
+        return Ok(items);
+    }
+
+    let mut item = String::new();
+    let (mut quoted, mut was_quoted) = (false, false);
+    let mut chars = inner.chars();
+    let mut finish = |item: String, was_quoted: bool| {
+        if was_quoted {
+            items.push(item);
+        } else if item.trim() != "NULL" {
+            items.push(item.trim().to_string());
+        }
+    };
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '\\') => item.push(chars.next().ok_or_else(err)?),
+            (true, '"') => quoted = false,
+            (true, c) => item.push(c),
+            (false, '"') => {
+                quoted = true;
+                was_quoted = true;
+            }
+            (false, ',') => {
+                finish(std::mem::take(&mut item), was_quoted);
+                was_quoted = false;
+            }
+            (false, c) => item.push(c),
+        }
+    }
+    if quoted {
+        return Err(err());
+    }
+    finish(item, was_quoted);
+    Ok(items)
+}
+
+/// Look up the name of a crate read before, or leave it empty for [`load`].
+fn crate_name(names: &HashMap<u64, String>, crate_id: u64) -> String {
+    names.get(&crate_id).cloned().unwrap_or_default()
+}
+
+fn parse_crate(row: &Row<'_>) -> io::Result<(u64, Crate)> {
+    let mut krate = Crate::new(row.get("name")?, "");
+    krate.description = row.opt("description");
+    krate.homepage = row.opt("homepage");
+    krate.documentation = row.opt("documentation");
+    krate.repository = row.opt("repository");
+    krate.downloads = row.u64("downloads").unwrap_or(0);
+    krate.created_at = row.timestamp("created_at")?;
+    krate.updated_at = row.timestamp("updated_at")?;
+    Ok((row.u64("id")?, krate))
+}
+
+fn parse_version(row: &Row<'_>, names: &HashMap<u64, String>) -> io::Result<(u64, Version)> {
+    let crate_id = row.u64("crate_id")?;
+    let mut version = Version::new(&crate_name(names, crate_id), row.get("num")?);
+    version.id = row.u64("id")?;
+    version.downloads = row.u64("downloads").unwrap_or(0);
+    version.yanked = row.bool("yanked")?;
+    version.license = row.opt("license");
+    version.crate_size = row.opt("crate_size").and_then(|size| size.parse().ok());
+    version.checksum = row.opt("checksum");
+    version.rust_version = row.opt("rust_version");
+    version.edition = row.opt("edition");
+    if row.opt("has_lib").is_some() {
+        version.has_lib = Some(row.bool("has_lib")?);
+    }
+    version.bin_names = row
+        .opt("bin_names")
+        .map(|names| parse_array(&names))
+        .transpose()?;
+    if let Some(features) = row.opt("features") {
+        version.features = serde_json::from_str(&features).map_err(invalid_data)?;
+    }
+    version.created_at = row.timestamp("created_at")?;
+    version.updated_at = row.timestamp("updated_at")?;
+    Ok((crate_id, version))
+}
+
+fn parse_dependency(row: &Row<'_>, names: &HashMap<u64, String>) -> io::Result<(u64, Dependency)> {
+    let crate_id = row.u64("crate_id")?;
+    let mut dep = Dependency::new(&crate_name(names, crate_id), row.get("req")?);
+    dep.id = row.u64("id")?;
+    dep.version_id = row.u64("version_id")?;
+    dep.kind = match row.get("kind")? {
+        "0" => DependencyKind::Normal,
+        "1" => DependencyKind::Build,
+        "2" => DependencyKind::Dev,
+        other => return Err(invalid_data(format!("invalid dependency kind '{}'", other))),
+    };
+    dep.optional = row.bool("optional")?;
+    dep.default_features = row.bool("default_features")?;
+    dep.features = parse_array(row.get("features")?)?;
+    dep.target = row.opt("target");
+    Ok((crate_id, dep))
+}
+
+fn parse_version_downloads(row: &Row<'_>) -> io::Result<VersionDownloads> {
+    Ok(VersionDownloads {
+        date: row.get("date")?.parse::<Date>().map_err(invalid_data)?,
+        downloads: row.u64("downloads")?,
+        version: row.u64("version_id")?,
+    })
+}
+
+/// Read a CSV file with a header row, calling `f` for every other row.
+fn read_csv<R, F>(reader: &mut R, mut f: F) -> io::Result<()>
+where
+    R: BufRead,
+    F: FnMut(&Row<'_>) -> io::Result<()>,
+{
+    let header = match read_record(reader)? {
+        Some(header) => header,
+        None => return Ok(()),
+    };
+    let columns: HashMap<_, _> = header
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| (name, index))
+        .collect();
+    while let Some(fields) = read_record(reader)? {
+        f(&Row {
+            columns: &columns,
+            fields: &fields,
+        })?;
+    }
+    Ok(())
+}
+
+/// Read a single CSV record, which may span multiple lines if quoted.
+fn read_record<R: BufRead>(reader: &mut R) -> io::Result<Option<Vec<String>>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+
+    let mut fields = Vec::new();
+    let mut field = Vec::new();
+    let mut quoted = false;
+    let mut pos = 0;
+    loop {
+        if pos == line.len() {
+            if !quoted {
+                break;
+            }
+            // A quoted field continues on the next line.
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Err(invalid_data("unterminated quoted field"));
+            }
+            continue;
+        }
+        let byte = line[pos];
+        pos += 1;
+        match (quoted, byte) {
+            (true, b'"') if line.get(pos) == Some(&b'"') => {
+                field.push(b'"');
+                pos += 1;
+            }
+            (true, b'"') => quoted = false,
+            (true, _) => field.push(byte),
+            (false, b'"') => quoted = true,
+            (false, b',') => fields.push(String::from_utf8(std::mem::take(&mut field))),
+            (false, b'\n') => {}
+            (false, b'\r') if line.get(pos) == Some(&b'\n') => {}
+            (false, _) => field.push(byte),
+        }
+    }
+    fields.push(String::from_utf8(field));
+    fields
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .map(Some)
+        .map_err(invalid_data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Crate;
+
+    fn tar_entry(archive: &mut Vec<u8>, path: &str, contents: &str) {
+        let mut header = tar::Header::new_ustar();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        let mut builder = tar::Builder::new(Vec::new());
+        builder
+            .append_data(&mut header, path, contents.as_bytes())
+            .unwrap();
+        // Without the end of archive, so that more entries can follow.
+        let entry = builder.into_inner().unwrap();
+        archive.extend_from_slice(&entry[..entry.len() - 1024]);
+    }
+
+    fn archive() -> Vec<u8> {
+        let mut archive = Vec::new();
+        tar_entry(
+            &mut archive,
+            "2022-01-29-020016/metadata.json",
+            r#"{"timestamp": "2022-01-29T02:00:16Z", "crates_io_commit": "abc"}"#,
+        );
+        tar_entry(
+            &mut archive,
+            "2022-01-29-020016/data/crates.csv",
+            "created_at,description,downloads,id,name,updated_at\n\
+             2015-05-12 03:33:07.080208,\"A \"\"quoted\"\"\nmulti-line, description\",42,1,serde,2022-01-28 10:00:00+00\n\
+             2015-05-12 03:33:07,,0,2,empty,2015-05-12 03:33:07\n",
+        );
+        tar_entry(
+            &mut archive,
+            "2022-01-29-020016/data/versions.csv",
+            "checksum,crate_id,crate_size,created_at,downloads,features,id,license,num,updated_at,yanked\n\
+             abc,1,1024,2020-01-01 00:00:00,7,\"{\"\"std\"\":[]}\",10,MIT,1.0.0,2020-01-01 00:00:00,t\n",
+        );
+        tar_entry(&mut archive, "2022-01-29-020016/README.md", "ignored");
+        tar_entry(
+            &mut archive,
+            "2022-01-29-020016/data/dependencies.csv",
+            "crate_id,default_features,explicit_name,features,id,kind,optional,req,target,version_id\n\
+             2,f,,\"{derive,\"\"a,\\\"\"b\"\",NULL}\",100,2,t,^1.0,cfg(unix),10\n",
+        );
+        tar_entry(
+            &mut archive,
+            "2022-01-29-020016/data/version_downloads.csv",
+            "date,downloads,version_id\n2022-01-28,5,10\r\n",
+        );
+        archive.extend_from_slice(&[0u8; 1024]);
+        archive
+    }
+
+    #[test]
+    fn test_load_db_dump() {
+        let dump = load(&archive()[..]).unwrap();
+
+        let metadata = dump.metadata.unwrap();
+        // 2022-01-29T02:00:16Z
+        assert_eq!(metadata.timestamp.unix_timestamp(), 1643421616);
+
+        assert_eq!(dump.crates.len(), 2);
+        let serde = &dump.crates[&1];
+        assert_eq!(serde.name, "serde");
+        assert_eq!(
+            serde.description.as_deref(),
+            Some("A \"quoted\"\nmulti-line, description")
+        );
+        assert_eq!(serde.downloads, 42);
+        // 2022-01-28T10:00:00Z
+        assert_eq!(serde.updated_at.unix_timestamp(), 1643364000);
+        assert_eq!(dump.crates[&2].description, None);
+
+        let version = &dump.versions[0];
+        assert_eq!((version.id, version.crate_name.as_str()), (10, "serde"));
+        assert!(version.yanked);
+        assert_eq!(version.crate_size, Some(1024));
+        assert_eq!(version.features["std"], Vec::<String>::new());
+        assert_eq!(version.rust_version, None);
+
+        let dep = &dump.dependencies[0];
+        assert_eq!((dep.crate_id.as_str(), dep.version_id), ("empty", 10));
+        assert_eq!(dep.kind, DependencyKind::Dev);
+        assert!(dep.optional);
+        assert!(!dep.default_features);
+        assert_eq!(dep.features, ["derive", "a,\"b"]);
+        assert_eq!(dep.target.as_deref(), Some("cfg(unix)"));
+
+        assert_eq!(dump.version_downloads.len(), 1);
+        assert_eq!(dump.version_downloads[0].downloads, 5);
+        assert_eq!(dump.version_downloads[0].version, 10);
+    }
+
+    #[test]
+    fn test_load_gz_db_dump() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        io::Write::write_all(&mut gz, &archive()).unwrap();
+        let compressed = gz.finish().unwrap();
+
+        let dump = load_gz(&compressed[..]).unwrap();
+        assert_eq!(dump.crates.len(), 2);
+        assert_eq!(dump.versions.len(), 1);
+        assert_eq!(dump.version_downloads.len(), 1);
+
+        let mut records = 0;
+        visit_gz(&compressed[..], |_| records += 1).unwrap();
+        assert_eq!(records, 6);
+
+        assert!(load_gz(&archive()[..]).is_err());
+        assert!(load_gz(&compressed[..compressed.len() - 4]).is_err());
+    }
+
+    fn crate_response(
+        name: &str,
+        updated_at: &str,
+        versions: &[(u64, &str, bool)],
+    ) -> CrateResponse {
+        let updated_at = crate::types::timestamp(updated_at);
+        let versions = versions
+            .iter()
+            .map(|(id, num, yanked)| {
+                let mut version = Version::new(name, num);
+                version.id = *id;
+                version.created_at = updated_at;
+                version.updated_at = updated_at;
+                version.downloads = 3;
+                version.yanked = *yanked;
+                version
+            })
+            .collect();
+        let mut krate = Crate::new(name, "1.0.0");
+        krate.created_at = updated_at;
+        krate.updated_at = updated_at;
+        krate.downloads = 100;
+        CrateResponse::new(krate, versions)
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let mut dump = load(&archive()[..]).unwrap();
+        let mut dependency = Dependency::new("serde", "^1");
+        dependency.id = 200;
+        dependency.version_id = 20;
+        let changes = DumpChanges {
+            crates: vec![
+                crate_response(
+                    "serde",
+                    "2022-02-01T00:00:00Z",
+                    &[(11, "1.1.0", false), (10, "1.0.0", false)],
+                ),
+                crate_response("new-crate", "2022-02-02T00:00:00Z", &[(20, "0.1.0", false)]),
+            ],
+            dependencies: vec![(11, Vec::new()), (20, vec![dependency])]
+                .into_iter()
+                .collect(),
+            removed: Vec::new(),
+        };
+
+        let report = dump.apply(&changes);
+        assert_eq!(
+            report,
+            SyncReport {
+                new_crates: 1,
+                updated_crates: 1,
+                new_versions: 2,
+                removed_crates: 0,
+            }
+        );
+
+        assert_eq!(dump.crates[&1].downloads, 100);
+        assert_eq!(dump.crates[&3].name, "new-crate");
+        assert!(!dump.versions[0].yanked);
+        assert_eq!(dump.versions.len(), 3);
+        assert_eq!(dump.versions[2].crate_name, "new-crate");
+        let dep = dump.dependencies.last().unwrap();
+        assert_eq!((dep.id, dep.version_id), (200, 20));
+        assert_eq!(dep.crate_id, "serde");
+        // 2022-02-02T00:00:00Z
+        assert_eq!(
+            dump.metadata.unwrap().timestamp.unix_timestamp(),
+            1643760000
+        );
+    }
+
+    #[test]
+    fn test_apply_removed_crates() {
+        let mut dump = load(&archive()[..]).unwrap();
+        let changes = DumpChanges {
+            removed: vec!["serde".to_string(), "never-dumped".to_string()],
+            ..DumpChanges::default()
+        };
+
+        assert_eq!(dump.apply(&changes).removed_crates, 1);
+        let names: Vec<_> = dump.crates.values().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["empty"]);
+        assert!(dump.versions.is_empty());
+        assert!(dump.dependencies.is_empty());
+        assert!(dump.version_downloads.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_changes_since_deleted_crate() {
+        let mut gone = Crate::new("gone", "1.0.0");
+        gone.updated_at = crate::types::timestamp("2022-02-02T00:00:00Z");
+        let mut serde = Crate::new("serde", "1.0.0");
+        serde.updated_at = crate::types::timestamp("2022-02-01T00:00:00Z");
+        let server = crate::testing::MockServer::start()
+            .unwrap()
+            .with_json(
+                "crates?page=1&per_page=100&sort=recent-updates",
+                &crate::CratesPage::new(vec![gone, serde, Crate::new("old", "1.0.0")], 3),
+            )
+            .with_crate("serde", &["1.0.0"]);
+        let dump = load(&archive()[..]).unwrap();
+
+        let changes = changes_since(&server.async_client(), &dump).await.unwrap();
+        assert_eq!(changes.removed, ["gone"]);
+        assert_eq!(changes.crates.len(), 1);
+        assert_eq!(changes.crates[0].crate_data.name, "serde");
+    }
+
+    #[test]
+    fn test_parse_array() {
+        assert_eq!(parse_array("{}").unwrap(), Vec::<String>::new());
+        assert_eq!(parse_array("{a, b ,NULL}").unwrap(), ["a", "b"]);
+        assert_eq!(
+            parse_array(r#"{"a,b","{c}","d\"e\\f","NULL"}"#).unwrap(),
+            ["a,b", "{c}", "d\"e\\f", "NULL"]
+        );
+        assert!(parse_array("a,b").is_err());
+        assert!(parse_array(r#"{"a}"#).is_err());
+    }
+
+    #[test]
+    fn test_load_versions_before_crates() {
+        let mut archive = Vec::new();
+        tar_entry(
+            &mut archive,
+            "dump/data/versions.csv",
+            "crate_id,created_at,id,num,updated_at,yanked\n\
+             1,2020-01-01 00:00:00,10,1.0.0,2020-01-01 00:00:00,f\n",
+        );
+        tar_entry(
+            &mut archive,
+            "dump/data/crates.csv",
+            "created_at,id,name,updated_at\n\
+             2015-05-12 03:33:07,1,serde,2022-01-28 10:00:00\n",
+        );
+
+        let mut crate_ids = Vec::new();
+        visit(&archive[..], |record| {
+            if let DumpRecord::Version(crate_id, version) = record {
+                assert_eq!(version.crate_name, "");
+                crate_ids.push(crate_id);
+            }
+        })
+        .unwrap();
+        assert_eq!(crate_ids, [1]);
+        let dump = load(&archive[..]).unwrap();
+        assert_eq!(dump.versions[0].crate_name, "serde");
+    }
+
+    #[test]
+    fn test_invalid_csv() {
+        let mut archive = Vec::new();
+        tar_entry(
+            &mut archive,
+            "dump/data/crates.csv",
+            "id,name\nnot-a-number,serde\n",
+        );
+        let err = load(&archive[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,191 @@
+//! Documentation links and build status from [docs.rs](https://docs.rs).
+//!
+//! Requires the `docsrs` feature.
+//!
+//! ```rust
+//! use crates_io_api::docsrs;
+//!
+//! assert_eq!(
+//!     docsrs::docs_url("serde-json", "1.0.0"),
+//!     "https://docs.rs/serde-json/1.0.0/serde_json/"
+//! );
+//! ```
+
+use reqwest::{header, Client as HttpClient, StatusCode, Url};
+use serde_derive::*;
+
+use crate::error::{decode_json, NotFoundError};
+use crate::Error;
+
+/// The URL of docs.rs.
+pub const DOCS_RS_URL: &str = "https://docs.rs/";
+
+/// Get the URL of the documentation of a crate version.
+///
+/// Assumes the library target has the default name, ie the crate name with
+/// dashes replaced by underscores. Use `"latest"` as the version to link to
+/// the newest version.
+pub fn docs_url(crate_name: &str, version: &str) -> String {
+    format!(
+        "{}{}/{}/{}/",
+        DOCS_RS_URL,
+        crate_name,
+        version,
+        crate_name.replace('-', "_")
+    )
+}
+
+/// Get the URL of the docs.rs page of a crate version, with its build logs,
+/// features and source.
+pub fn crate_page_url(crate_name: &str, version: &str) -> String {
+    format!("{}crate/{}/{}", DOCS_RS_URL, crate_name, version)
+}
+
+/// Get the URL of the docs.rs badge of a crate version.
+pub fn badge_url(crate_name: &str, version: &str) -> String {
+    format!("{}crate/{}/{}/badge.svg", DOCS_RS_URL, crate_name, version)
+}
+
+/// The documentation build status of a crate version.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BuildStatus {
+    /// Whether the documentation was built successfully.
+    pub doc_status: bool,
+    /// The version that was built.
+    ///
+    /// Differs from the requested version if it was `"latest"` or a version
+    /// requirement.
+    pub version: String,
+}
+
+/// A client for docs.rs.
+#[derive(Clone)]
+pub struct DocsRsClient {
+    client: HttpClient,
+    base_url: Url,
+}
+
+impl DocsRsClient {
+    /// Instantiate a new client.
+    ///
+    /// Returns an [`Error`] if the given user agent is invalid.
+    pub fn new(user_agent: &str) -> Result<Self, reqwest::header::InvalidHeaderValue> {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::USER_AGENT,
+            header::HeaderValue::from_str(user_agent)?,
+        );
+
+        let client = HttpClient::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
+
+        Ok(Self::with_http_client(client))
+    }
+
+    /// Instantiate a new client.
+    pub fn with_http_client(client: HttpClient) -> Self {
+        Self {
+            client,
+            base_url: Url::parse(DOCS_RS_URL).unwrap(),
+        }
+    }
+
+    /// Use a different root than `https://docs.rs/`, eg a mock server.
+    ///
+    /// Returns an error if the URL is invalid.
+    pub fn with_base_url(mut self, base_url: &str) -> Result<Self, Error> {
+        self.base_url = Url::parse(base_url)?;
+        Ok(self)
+    }
+
+    /// Retrieve the documentation build status of a crate version.
+    ///
+    /// Returns [`Error::NotFound`] if docs.rs doesn't know the version, eg
+    /// because it was just published and the build is still queued.
+    pub async fn build_status(
+        &self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<BuildStatus, Error> {
+        let url = build_status_url(&self.base_url, crate_name, version)?;
+        let res = self.client.get(url.clone()).send().await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Err(Error::NotFound(NotFoundError {
+                url: url.to_string(),
+            }));
+        }
+        let content = crate::async_client::body_text(res.error_for_status()?).await?;
+        Ok(decode_json(&content)?)
+    }
+
+    /// Check whether the documentation of a crate version was built
+    /// successfully.
+    ///
+    /// Versions unknown to docs.rs are reported as not built.
+    pub async fn docs_built(&self, crate_name: &str, version: &str) -> Result<bool, Error> {
+        match self.build_status(crate_name, version).await {
+            Ok(status) => Ok(status.doc_status),
+            Err(Error::NotFound(_)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+fn build_status_url(base: &Url, crate_name: &str, version: &str) -> Result<Url, Error> {
+    let mut url = base.clone();
+    url.path_segments_mut().unwrap().pop_if_empty().extend([
+        "crate",
+        crate_name,
+        version,
+        "status.json",
+    ]);
+    Ok(url)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            docs_url("tokio-util", "latest"),
+            "https://docs.rs/tokio-util/latest/tokio_util/"
+        );
+        assert_eq!(
+            crate_page_url("serde", "1.0.0"),
+            "https://docs.rs/crate/serde/1.0.0"
+        );
+        assert_eq!(
+            badge_url("serde", "1.0.0"),
+            "https://docs.rs/crate/serde/1.0.0/badge.svg"
+        );
+        let base = Url::parse(DOCS_RS_URL).unwrap();
+        assert_eq!(
+            build_status_url(&base, "serde", "1.0.0").unwrap().as_str(),
+            "https://docs.rs/crate/serde/1.0.0/status.json"
+        );
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    #[tokio::test]
+    async fn test_build_status_async() {
+        let server = crate::testing::MockServer::start().unwrap().with_json(
+            "crate/serde/1.0.0/status.json",
+            &BuildStatus {
+                doc_status: true,
+                version: "1.0.0".to_string(),
+            },
+        );
+        let client = DocsRsClient::new("crates-io-api-continuous-integration")
+            .unwrap()
+            .with_base_url(&server.base_url().replace("api/v1/", ""))
+            .unwrap();
+        let status = client.build_status("serde", "1.0.0").await.unwrap();
+        assert_eq!(status.version, "1.0.0");
+        assert!(client.docs_built("serde", "1.0.0").await.unwrap());
+        assert!(!client.docs_built("serde", "0.1.0").await.unwrap());
+    }
+}
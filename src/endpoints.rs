@@ -0,0 +1,521 @@
+//! Typed, low-level API endpoints.
+//!
+//! Every endpoint is a small request type implementing [`Endpoint`], which
+//! knows its URL and response type. The methods of [`AsyncClient`] and
+//! [`SyncClient`] are built on top of these, and [`AsyncClient::send`] and
+//! [`SyncClient::send`] can be used to call them directly, eg to compose
+//! custom flows:
+//!
+//! ```rust,no_run
+//! # async fn run() -> Result<(), crates_io_api::Error> {
+//! use crates_io_api::{endpoints, AsyncClient};
+//!
+//! let client = AsyncClient::new("my_bot (help@my_bot.com)", std::time::Duration::from_secs(1))
+//!     .unwrap();
+//! let deps = client
+//!     .send(&endpoints::GetDependencies {
+//!         crate_name: "serde",
+//!         version: "1.0.0",
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`AsyncClient`]: crate::AsyncClient
+//! [`AsyncClient::send`]: crate::AsyncClient::send
+//! [`SyncClient`]: crate::SyncClient
+//! [`SyncClient::send`]: crate::SyncClient::send
+
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::async_client::{
+    build_category_url, build_crate_authors_url, build_crate_dependencies_url,
+    build_crate_downloads_url, build_crate_owners_url, build_crate_reverse_deps_count_url,
+    build_crate_reverse_deps_url, build_crate_url, build_crate_version_downloads_url,
+    build_crate_version_url, build_crates_url, build_keyword_url, build_summary_url,
+    build_team_url, build_user_url,
+};
+use crate::types::*;
+use crate::Error;
+
+/// A GET endpoint of the crates.io API.
+pub trait Endpoint {
+    /// The response body, as sent by the API.
+    type Response: DeserializeOwned + Serialize + Send + 'static;
+    /// The value returned to the caller.
+    type Output;
+
+    /// Build the URL of the request, relative to the API root `base`.
+    ///
+    /// Returns an error without performing a request if the parameters are
+    /// invalid.
+    fn url(&self, base: &Url) -> Result<Url, Error>;
+
+    /// Extract the output from the response body.
+    fn output(response: Self::Response) -> Self::Output;
+}
+
+/// An endpoint whose results are split into numbered pages, see
+/// `collect_pages()` on the clients.
+pub trait Paginated: Endpoint {
+    /// A single result.
+    type Item;
+
+    /// The page the endpoint requests, starting at 1.
+    fn page(&self) -> u64;
+
+    /// Build the URL of the request of another page, relative to the API
+    /// root `base`.
+    fn page_url(&self, base: &Url, page: u64) -> Result<Url, Error>;
+
+    /// Extract the results from the output of a page.
+    fn items(output: Self::Output) -> Vec<Self::Item>;
+}
+
+/// Retrieve a summary containing crates.io wide information.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GetSummary;
+
+impl Endpoint for GetSummary {
+    type Response = Summary;
+    type Output = Summary;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_summary_url(base)
+    }
+
+    fn output(response: Summary) -> Summary {
+        response
+    }
+}
+
+/// Retrieve a crate with its versions, keywords and categories.
+#[derive(Debug, Clone, Copy)]
+pub struct GetCrate<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+}
+
+impl Endpoint for GetCrate<'_> {
+    type Response = CrateResponse;
+    type Output = CrateResponse;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_url(base, self.crate_name)
+    }
+
+    fn output(response: CrateResponse) -> CrateResponse {
+        response
+    }
+}
+
+/// Retrieve a single version of a crate.
+#[derive(Debug, Clone, Copy)]
+pub struct GetVersion<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+    /// The version.
+    pub version: &'a str,
+}
+
+impl Endpoint for GetVersion<'_> {
+    type Response = VersionResponse;
+    type Output = Version;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_version_url(base, self.crate_name, self.version)
+    }
+
+    fn output(response: VersionResponse) -> Version {
+        response.version
+    }
+}
+
+/// Retrieve the download stats of a crate.
+#[derive(Debug, Clone, Copy)]
+pub struct GetCrateDownloads<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+}
+
+impl Endpoint for GetCrateDownloads<'_> {
+    type Response = CrateDownloads;
+    type Output = CrateDownloads;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_downloads_url(base, self.crate_name)
+    }
+
+    fn output(response: CrateDownloads) -> CrateDownloads {
+        response
+    }
+}
+
+/// Retrieve the download stats of a single crate version.
+#[derive(Debug, Clone, Copy)]
+pub struct GetVersionDownloads<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+    /// The version.
+    pub version: &'a str,
+}
+
+impl Endpoint for GetVersionDownloads<'_> {
+    type Response = VersionDownloadsResponse;
+    type Output = Vec<VersionDownloads>;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_version_downloads_url(base, self.crate_name, self.version)
+    }
+
+    fn output(response: VersionDownloadsResponse) -> Vec<VersionDownloads> {
+        response.version_downloads
+    }
+}
+
+/// Retrieve the owners of a crate.
+#[derive(Debug, Clone, Copy)]
+pub struct GetOwners<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+}
+
+impl Endpoint for GetOwners<'_> {
+    type Response = Owners;
+    type Output = Vec<User>;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_owners_url(base, self.crate_name)
+    }
+
+    fn output(response: Owners) -> Vec<User> {
+        response.users
+    }
+}
+
+/// Retrieve a page of up to 100 reverse dependencies of a crate.
+#[derive(Debug, Clone, Copy)]
+pub struct GetReverseDependencies<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+    /// The page, starting at 1. Page 0 is coerced to 1.
+    pub page: u64,
+}
+
+impl Endpoint for GetReverseDependencies<'_> {
+    type Response = ReverseDependenciesAsReceived;
+    type Output = ReverseDependencies;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_reverse_deps_url(base, self.crate_name, self.page.max(1))
+    }
+
+    fn output(response: ReverseDependenciesAsReceived) -> ReverseDependencies {
+        let mut deps = ReverseDependencies {
+            dependencies: Vec::new(),
+            meta: response.meta.clone(),
+        };
+        deps.extend(response);
+        deps
+    }
+}
+
+impl Paginated for GetReverseDependencies<'_> {
+    type Item = ReverseDependency;
+
+    fn page(&self) -> u64 {
+        self.page.max(1)
+    }
+
+    fn page_url(&self, base: &Url, page: u64) -> Result<Url, Error> {
+        build_crate_reverse_deps_url(base, self.crate_name, page.max(1))
+    }
+
+    fn items(output: ReverseDependencies) -> Vec<ReverseDependency> {
+        output.dependencies
+    }
+}
+
+/// Count the reverse dependencies of a crate, fetching only one of them.
+#[derive(Debug, Clone, Copy)]
+pub struct CountReverseDependencies<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+}
+
+impl Endpoint for CountReverseDependencies<'_> {
+    type Response = ReverseDependenciesAsReceived;
+    type Output = u64;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_reverse_deps_count_url(base, self.crate_name)
+    }
+
+    fn output(response: ReverseDependenciesAsReceived) -> u64 {
+        response.meta.total
+    }
+}
+
+/// Retrieve the author names of a crate version.
+///
+/// The endpoint is deprecated, see [`Authors::unavailable`].
+#[derive(Debug, Clone, Copy)]
+pub struct GetAuthors<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+    /// The version.
+    pub version: &'a str,
+}
+
+impl Endpoint for GetAuthors<'_> {
+    type Response = AuthorsResponse;
+    type Output = Vec<String>;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_authors_url(base, self.crate_name, self.version)
+    }
+
+    fn output(response: AuthorsResponse) -> Vec<String> {
+        response.meta.names
+    }
+}
+
+/// Retrieve the dependencies of a crate version.
+#[derive(Debug, Clone, Copy)]
+pub struct GetDependencies<'a> {
+    /// The crate name.
+    pub crate_name: &'a str,
+    /// The version.
+    pub version: &'a str,
+}
+
+impl Endpoint for GetDependencies<'_> {
+    type Response = Dependencies;
+    type Output = Vec<Dependency>;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_crate_dependencies_url(base, self.crate_name, self.version)
+    }
+
+    fn output(response: Dependencies) -> Vec<Dependency> {
+        response.dependencies
+    }
+}
+
+/// Retrieve a page of crates matching a query.
+#[derive(Debug, Clone, Copy)]
+pub struct ListCrates<'a> {
+    /// The query.
+    pub query: &'a CratesQuery,
+}
+
+impl Endpoint for ListCrates<'_> {
+    type Response = CratesPage;
+    type Output = CratesPage;
+
+    /// Returns [`Error::InvalidQuery`] if the query is invalid (see
+    /// [`CratesQuery::validate`]).
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        self.query.validate()?;
+        let mut url = build_crates_url(base)?;
+        self.query.build(url.query_pairs_mut());
+        Ok(url)
+    }
+
+    fn output(response: CratesPage) -> CratesPage {
+        response
+    }
+}
+
+impl Paginated for ListCrates<'_> {
+    type Item = Crate;
+
+    fn page(&self) -> u64 {
+        self.query.page.max(1)
+    }
+
+    fn page_url(&self, base: &Url, page: u64) -> Result<Url, Error> {
+        let mut query = self.query.clone();
+        query.page = page;
+        query.seek = None;
+        ListCrates { query: &query }.url(base)
+    }
+
+    fn items(output: CratesPage) -> Vec<Crate> {
+        output.crates
+    }
+}
+
+/// Count the crates matching a query, fetching only one of them.
+///
+/// The page and page size of the query are ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct CountCrates<'a> {
+    /// The query.
+    pub query: &'a CratesQuery,
+}
+
+impl Endpoint for CountCrates<'_> {
+    type Response = CratesPage;
+    type Output = u64;
+
+    /// Returns [`Error::InvalidQuery`] if the query is invalid (see
+    /// [`CratesQuery::validate`]).
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        let mut query = self.query.clone();
+        query.page = 1;
+        query.per_page = 1;
+        query.seek = None;
+        ListCrates { query: &query }.url(base)
+    }
+
+    fn output(response: CratesPage) -> u64 {
+        response.meta.total
+    }
+}
+
+/// Retrieve a user by username.
+#[derive(Debug, Clone, Copy)]
+pub struct GetUser<'a> {
+    /// The username.
+    pub username: &'a str,
+}
+
+impl Endpoint for GetUser<'_> {
+    type Response = UserResponse;
+    type Output = User;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_user_url(base, self.username)
+    }
+
+    fn output(response: UserResponse) -> User {
+        response.user
+    }
+}
+
+/// Retrieve a team by login, eg `github:rust-lang:libs`.
+#[derive(Debug, Clone, Copy)]
+pub struct GetTeam<'a> {
+    /// The team login.
+    pub login: &'a str,
+}
+
+impl Endpoint for GetTeam<'_> {
+    type Response = TeamResponse;
+    type Output = Team;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_team_url(base, self.login)
+    }
+
+    fn output(response: TeamResponse) -> Team {
+        response.team
+    }
+}
+
+/// Retrieve a keyword by its id.
+#[derive(Debug, Clone, Copy)]
+pub struct GetKeyword<'a> {
+    /// The keyword id, ie the keyword itself.
+    pub id: &'a str,
+}
+
+impl Endpoint for GetKeyword<'_> {
+    type Response = KeywordResponse;
+    type Output = Keyword;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_keyword_url(base, self.id)
+    }
+
+    fn output(response: KeywordResponse) -> Keyword {
+        response.keyword
+    }
+}
+
+/// Retrieve a category by its slug.
+#[derive(Debug, Clone, Copy)]
+pub struct GetCategory<'a> {
+    /// The category slug, eg `parser-implementations`.
+    pub slug: &'a str,
+}
+
+impl Endpoint for GetCategory<'_> {
+    type Response = CategoryResponse;
+    type Output = Category;
+
+    fn url(&self, base: &Url) -> Result<Url, Error> {
+        build_category_url(base, self.slug)
+    }
+
+    fn output(response: CategoryResponse) -> Category {
+        response.category
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_urls() {
+        let base = Url::parse("https://crates.io/api/v1/").unwrap();
+
+        let url = GetReverseDependencies {
+            crate_name: "serde",
+            page: 0,
+        }
+        .url(&base)
+        .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://crates.io/api/v1/crates/serde/reverse_dependencies?per_page=100&page=1"
+        );
+
+        let url = GetAuthors {
+            crate_name: "serde",
+            version: "1.0.0",
+        }
+        .url(&base)
+        .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://crates.io/api/v1/crates/serde/1.0.0/authors"
+        );
+
+        let url = CountReverseDependencies {
+            crate_name: "serde",
+        }
+        .url(&base)
+        .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://crates.io/api/v1/crates/serde/reverse_dependencies?per_page=1&page=1"
+        );
+
+        let query = CratesQuery::builder().page(3).search("tokio").build();
+        let url = CountCrates { query: &query }.url(&base).unwrap();
+        assert!(url.as_str().contains("?page=1&per_page=1&"), "{}", url);
+
+        let query = CratesQuery::builder().page_size(1000).build();
+        assert!(matches!(
+            ListCrates { query: &query }.url(&base),
+            Err(Error::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn test_endpoint_output() {
+        let response = ReverseDependenciesAsReceived {
+            dependencies: Vec::new(),
+            versions: Vec::new(),
+            meta: Meta::new(3),
+        };
+        let deps = GetReverseDependencies::output(response);
+        assert_eq!(deps.meta.total, 3);
+        assert!(deps.dependencies.is_empty());
+    }
+}
@@ -8,6 +8,8 @@ pub enum Error {
     Http(reqwest::Error),
     /// Invalid URL.
     Url(url::ParseError),
+    /// Invalid header value, eg a user agent or token.
+    InvalidHeader(reqwest::header::InvalidHeaderValue),
     /// Crate could not be found.
     NotFound(NotFoundError),
     /// No permission to access the resource.
@@ -16,6 +18,29 @@ pub enum Error {
     JsonDecode(JsonDecodeError),
     /// Error returned by the crates.io API directly.
     Api(crate::types::ApiErrors),
+    /// A query was rejected before it was sent.
+    InvalidQuery(InvalidQueryError),
+    /// A version or version requirement could not be parsed.
+    #[cfg(feature = "semver")]
+    InvalidVersion(InvalidVersionError),
+    /// The client configuration is invalid.
+    InvalidConfig(InvalidConfigError),
+    /// The request budget of the client is used up, see [`Budget`].
+    ///
+    /// [`Budget`]: crate::Budget
+    BudgetExhausted(BudgetExhaustedError),
+    /// A request was cancelled before it completed.
+    Cancelled(CancelledError),
+    /// No response was recorded for a replayed request.
+    ///
+    /// Requires the `fixtures` feature.
+    #[cfg(feature = "fixtures")]
+    MissingFixture(MissingFixtureError),
+    /// A downloaded `.crate` file could not be read.
+    ///
+    /// Requires the `source` feature.
+    #[cfg(feature = "source")]
+    InvalidArchive(InvalidArchiveError),
 }
 
 impl std::fmt::Display for Error {
@@ -23,6 +48,7 @@ impl std::fmt::Display for Error {
         match self {
             Error::Http(e) => e.fmt(f),
             Error::Url(e) => e.fmt(f),
+            Error::InvalidHeader(e) => e.fmt(f),
             Error::NotFound(e) => e.fmt(f),
             Error::PermissionDenied(e) => e.fmt(f),
             Error::Api(err) => {
@@ -38,7 +64,17 @@ impl std::fmt::Display for Error {
 
                 write!(f, "API Error ({})", inner)
             }
-            Error::JsonDecode(err) => write!(f, "Could not decode API JSON response: {err}"),
+            Error::JsonDecode(err) => err.fmt(f),
+            Error::InvalidQuery(err) => err.fmt(f),
+            #[cfg(feature = "semver")]
+            Error::InvalidVersion(err) => err.fmt(f),
+            Error::InvalidConfig(err) => err.fmt(f),
+            Error::BudgetExhausted(err) => err.fmt(f),
+            Error::Cancelled(err) => err.fmt(f),
+            #[cfg(feature = "fixtures")]
+            Error::MissingFixture(err) => err.fmt(f),
+            #[cfg(feature = "source")]
+            Error::InvalidArchive(err) => err.fmt(f),
         }
     }
 }
@@ -48,10 +84,21 @@ impl std::error::Error for Error {
         match self {
             Error::Http(e) => Some(e),
             Error::Url(e) => Some(e),
+            Error::InvalidHeader(e) => Some(e),
             Error::NotFound(_) => None,
             Error::PermissionDenied(_) => None,
             Error::Api(_) => None,
             Error::JsonDecode(err) => Some(err),
+            Error::InvalidQuery(err) => Some(err),
+            #[cfg(feature = "semver")]
+            Error::InvalidVersion(err) => Some(err),
+            Error::InvalidConfig(err) => Some(err),
+            Error::BudgetExhausted(_) => None,
+            Error::Cancelled(_) => None,
+            #[cfg(feature = "fixtures")]
+            Error::MissingFixture(_) => None,
+            #[cfg(feature = "source")]
+            Error::InvalidArchive(_) => None,
         }
     }
 
@@ -80,20 +127,398 @@ impl From<url::ParseError> for Error {
     }
 }
 
+impl From<reqwest::header::InvalidHeaderValue> for Error {
+    fn from(e: reqwest::header::InvalidHeaderValue) -> Self {
+        Error::InvalidHeader(e)
+    }
+}
+
+impl From<JsonDecodeError> for Error {
+    fn from(e: JsonDecodeError) -> Self {
+        Error::JsonDecode(e)
+    }
+}
+
+impl From<InvalidQueryError> for Error {
+    fn from(e: InvalidQueryError) -> Self {
+        Error::InvalidQuery(e)
+    }
+}
+
+#[cfg(feature = "semver")]
+impl From<InvalidVersionError> for Error {
+    fn from(e: InvalidVersionError) -> Self {
+        Error::InvalidVersion(e)
+    }
+}
+
 /// Error returned when the JSON returned by the API could not be decoded.
 #[derive(Debug)]
 pub struct JsonDecodeError {
     pub(crate) message: String,
+    pub(crate) path: Option<String>,
+    pub(crate) snippet: Option<String>,
+}
+
+impl JsonDecodeError {
+    /// Number of characters of the response shown on each side of the error.
+    const SNIPPET_CONTEXT: usize = 40;
+
+    pub(crate) fn new(err: serde_path_to_error::Error<serde_json::Error>, content: &str) -> Self {
+        let path = err.path().to_string();
+        let inner = err.into_inner();
+        Self {
+            message: inner.to_string(),
+            path: Some(path).filter(|path| path != "."),
+            snippet: snippet(content, inner.line(), inner.column()),
+        }
+    }
+
+    /// Construct an error without a path, eg for trailing characters.
+    #[cfg(feature = "stream-decode")]
+    pub(crate) fn from_json(err: serde_json::Error, content: &str) -> Self {
+        Self {
+            message: err.to_string(),
+            path: None,
+            snippet: snippet(content, err.line(), err.column()),
+        }
+    }
+
+    /// Get the path of the value that could not be decoded, eg
+    /// `crate.max_version`.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Get the part of the response around the value that could not be
+    /// decoded.
+    pub fn snippet(&self) -> Option<&str> {
+        self.snippet.as_deref()
+    }
+}
+
+/// Get the text around a line and column of `content`, both starting at 1.
+fn snippet(content: &str, line: usize, column: usize) -> Option<String> {
+    let text = content.lines().nth(line.checked_sub(1)?)?;
+    let chars: Vec<char> = text.chars().collect();
+    let position = column.min(chars.len());
+    let start = position.saturating_sub(JsonDecodeError::SNIPPET_CONTEXT);
+    let end = (position + JsonDecodeError::SNIPPET_CONTEXT).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    if start > 0 {
+        snippet.insert_str(0, "...");
+    }
+    if end < chars.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet).filter(|snippet| !snippet.trim().is_empty())
+}
+
+/// Decode JSON, reporting the path and the surroundings of any error.
+///
+/// Tracking the path slows down decoding, so it's only done again after
+/// decoding failed.
+pub(crate) fn decode_json<T: serde::de::DeserializeOwned>(
+    content: &str,
+) -> Result<T, JsonDecodeError> {
+    if let Ok(value) = serde_json::from_str(content) {
+        return Ok(value);
+    }
+    let jd = &mut serde_json::Deserializer::from_str(content);
+    serde_path_to_error::deserialize(jd).map_err(|err| JsonDecodeError::new(err, content))
+}
+
+/// Decode JSON like [`decode_json`], and check for fields that are not part of
+/// `T`.
+///
+/// Unknown fields are found by encoding the decoded value again and
+/// comparing it with the response, so `T` must encode to the same JSON.
+pub(crate) fn decode_json_checked<T>(
+    content: &str,
+    mode: crate::UnknownFields,
+) -> Result<T, JsonDecodeError>
+where
+    T: serde::de::DeserializeOwned + serde::Serialize,
+{
+    let value: T = decode_json(content)?;
+    if mode == crate::UnknownFields::Ignore {
+        return Ok(value);
+    }
+    let (raw, decoded) = match (
+        serde_json::from_str::<serde_json::Value>(content),
+        serde_json::to_value(&value),
+    ) {
+        (Ok(raw), Ok(decoded)) => (raw, decoded),
+        _ => return Ok(value),
+    };
+    let mut fields = std::collections::BTreeSet::new();
+    unknown_fields(&raw, &decoded, "", &mut fields);
+    if fields.is_empty() {
+        return Ok(value);
+    }
+    let fields: Vec<_> = fields.into_iter().collect();
+    if mode == crate::UnknownFields::Warn {
+        log::warn!("Unknown fields in API response: {}", fields.join(", "));
+        return Ok(value);
+    }
+    Err(JsonDecodeError {
+        message: format!("unknown fields {}", fields.join(", ")),
+        path: fields.first().cloned(),
+        snippet: None,
+    })
+}
+
+/// Collect the paths of all object keys in `raw` that are missing in
+/// `decoded`. Array indices are written as `[]`, so every field is reported
+/// once.
+fn unknown_fields(
+    raw: &serde_json::Value,
+    decoded: &serde_json::Value,
+    path: &str,
+    fields: &mut std::collections::BTreeSet<String>,
+) {
+    use serde_json::Value;
+
+    match (raw, decoded) {
+        (Value::Object(raw), Value::Object(decoded)) => {
+            for (key, raw) in raw {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match decoded.get(key) {
+                    Some(decoded) => unknown_fields(raw, decoded, &path, fields),
+                    None => {
+                        fields.insert(path);
+                    }
+                }
+            }
+        }
+        (Value::Array(raw), Value::Array(decoded)) => {
+            let path = format!("{}[]", path);
+            for (raw, decoded) in raw.iter().zip(decoded) {
+                unknown_fields(raw, decoded, &path, fields);
+            }
+        }
+        _ => {}
+    }
 }
 
 impl std::fmt::Display for JsonDecodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Could not decode JSON: {}", self.message)
+        write!(f, "Could not decode JSON: {}", self.message)?;
+        if let Some(path) = &self.path {
+            write!(f, " (path: {})", path)?;
+        }
+        if let Some(snippet) = &self.snippet {
+            write!(f, " near `{}`", snippet)?;
+        }
+        Ok(())
     }
 }
 
 impl std::error::Error for JsonDecodeError {}
 
+/// Error returned when a query is invalid.
+#[derive(Debug)]
+pub struct InvalidQueryError {
+    pub(crate) reason: String,
+}
+
+impl InvalidQueryError {
+    pub(crate) fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidQueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid query: {}", self.reason)
+    }
+}
+
+impl std::error::Error for InvalidQueryError {}
+
+/// Error returned when the client configuration is invalid.
+#[derive(Debug)]
+pub struct InvalidConfigError {
+    pub(crate) variable: String,
+    pub(crate) reason: String,
+}
+
+impl InvalidConfigError {
+    pub(crate) fn new(variable: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            variable: variable.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid configuration in {}: {}",
+            self.variable, self.reason
+        )
+    }
+}
+
+impl std::error::Error for InvalidConfigError {}
+
+/// Error returned when a request exceeds the request budget.
+#[derive(Debug)]
+pub struct BudgetExhaustedError {
+    pub(crate) reason: String,
+}
+
+impl BudgetExhaustedError {
+    pub(crate) fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for BudgetExhaustedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Request budget exhausted: {}", self.reason)
+    }
+}
+
+impl std::error::Error for BudgetExhaustedError {}
+
+/// Error returned when a request was cancelled before it completed.
+#[derive(Debug)]
+pub struct CancelledError {
+    pub(crate) reason: String,
+}
+
+impl CancelledError {
+    pub(crate) fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for CancelledError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Request cancelled: {}", self.reason)
+    }
+}
+
+impl std::error::Error for CancelledError {}
+
+/// Error returned when a crate name does not follow the crates.io naming rules.
+#[derive(Debug)]
+pub struct InvalidCrateNameError {
+    pub(crate) name: String,
+    pub(crate) reason: String,
+}
+
+impl InvalidCrateNameError {
+    pub(crate) fn new(name: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidCrateNameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid crate name '{}': {}", self.name, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidCrateNameError {}
+
+/// Error returned when a date or timestamp could not be parsed, or is outside
+/// the years 0 to 9999.
+#[derive(Debug)]
+pub struct InvalidDateError {
+    pub(crate) input: String,
+    pub(crate) reason: String,
+}
+
+impl InvalidDateError {
+    pub(crate) fn new(input: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidDateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid date '{}': {}", self.input, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidDateError {}
+
+/// Error returned when a version or version requirement could not be parsed.
+#[cfg(feature = "semver")]
+#[derive(Debug)]
+pub struct InvalidVersionError {
+    pub(crate) input: String,
+    pub(crate) reason: String,
+}
+
+#[cfg(feature = "semver")]
+impl InvalidVersionError {
+    pub(crate) fn new(input: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+#[cfg(feature = "semver")]
+impl std::fmt::Display for InvalidVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid version '{}': {}", self.input, self.reason)
+    }
+}
+
+#[cfg(feature = "semver")]
+impl std::error::Error for InvalidVersionError {}
+
+/// Error returned when a license expression could not be parsed.
+#[cfg(feature = "spdx")]
+#[derive(Debug)]
+pub struct InvalidLicenseError {
+    pub(crate) input: String,
+    pub(crate) reason: String,
+}
+
+#[cfg(feature = "spdx")]
+impl InvalidLicenseError {
+    pub(crate) fn new(input: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+#[cfg(feature = "spdx")]
+impl std::fmt::Display for InvalidLicenseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid license '{}': {}", self.input, self.reason)
+    }
+}
+
+#[cfg(feature = "spdx")]
+impl std::error::Error for InvalidLicenseError {}
+
 /// Error returned when a resource could not be found.
 #[derive(Debug)]
 pub struct NotFoundError {
@@ -106,6 +531,35 @@ impl std::fmt::Display for NotFoundError {
     }
 }
 
+/// Error returned when a replayed request has no recorded response.
+#[cfg(feature = "fixtures")]
+#[derive(Debug)]
+pub struct MissingFixtureError {
+    pub(crate) url: String,
+}
+
+#[cfg(feature = "fixtures")]
+impl std::fmt::Display for MissingFixtureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No fixture recorded for url '{}'", self.url)
+    }
+}
+
+/// Error returned when a downloaded `.crate` file is not a valid archive.
+#[cfg(feature = "source")]
+#[derive(Debug)]
+pub struct InvalidArchiveError {
+    pub(crate) url: String,
+    pub(crate) reason: String,
+}
+
+#[cfg(feature = "source")]
+impl std::fmt::Display for InvalidArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid archive at url '{}': {}", self.url, self.reason)
+    }
+}
+
 /// Error returned when a resource is not accessible.
 #[derive(Debug)]
 pub struct PermissionDeniedError {
@@ -117,3 +571,61 @@ impl std::fmt::Display for PermissionDeniedError {
         write!(f, "Permission denied: {}", self.reason)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_decode_error() {
+        #[derive(serde_derive::Deserialize, Debug)]
+        struct Krate {
+            #[allow(dead_code)]
+            downloads: u64,
+        }
+        #[derive(serde_derive::Deserialize, Debug)]
+        struct Response {
+            #[serde(rename = "crate")]
+            #[allow(dead_code)]
+            krate: Krate,
+        }
+
+        let padding = "x".repeat(100);
+        let content = format!(r#"{{"padding": "{padding}", "crate": {{"downloads": "many"}}}}"#);
+        let err = decode_json::<Response>(&content).unwrap_err();
+        assert_eq!(err.path(), Some("crate.downloads"));
+        let snippet = err.snippet().unwrap();
+        assert!(snippet.starts_with("...") && snippet.contains(r#""downloads": "many""#));
+        assert!(err.to_string().contains("(path: crate.downloads)"));
+
+        let err = decode_json::<Response>("").unwrap_err();
+        assert!(err.path().is_none() && err.snippet().is_none());
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        let content = r#"{"meta": {"total": 1, "next_page": "?page=2"},
+            "crates": [{"name": "a", "new": 1}, {"name": "b", "new": 2}]}"#;
+        let mut fields = std::collections::BTreeSet::new();
+        let decoded = serde_json::json!({
+            "meta": {"total": 1},
+            "crates": [{"name": "a"}, {"name": "b"}],
+        });
+        unknown_fields(
+            &serde_json::from_str(content).unwrap(),
+            &decoded,
+            "",
+            &mut fields,
+        );
+        assert_eq!(
+            fields.into_iter().collect::<Vec<_>>(),
+            ["crates[].new", "meta.next_page"]
+        );
+
+        // The last page of a listing sends `null` links, which are known.
+        let content = r#"{"total": 1, "next_page": null, "prev_page": "?page=1"}"#;
+        let meta: crate::Meta = decode_json_checked(content, crate::UnknownFields::Deny).unwrap();
+        assert_eq!(meta.total, 1);
+        assert!(!meta.has_next_page());
+    }
+}
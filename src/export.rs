@@ -0,0 +1,244 @@
+//! Exporting download series to CSV and newline delimited JSON.
+//!
+//! Requires the `export` feature.
+//!
+//! Every download count is written as one record with the stable columns
+//! `crate`, `date`, `version_id` and `downloads`. Extra downloads, which
+//! crates.io doesn't attribute to a version, have an empty `version_id` in
+//! CSV and `null` in JSON. Several crates can be written to the same output:
+//!
+//! ```rust
+//! use crates_io_api::{export::DownloadsCsv, CrateDownloads};
+//!
+//! # fn run(serde: &CrateDownloads, tokio: &CrateDownloads) -> std::io::Result<()> {
+//! let mut csv = DownloadsCsv::new(Vec::new());
+//! csv.write_crate_downloads("serde", serde)?;
+//! csv.write_crate_downloads("tokio", tokio)?;
+//! let output = String::from_utf8(csv.into_inner()).unwrap();
+//! assert!(output.starts_with("crate,date,version_id,downloads\n"));
+//! # Ok(())
+//! # }
+//! ```
+
+use serde_derive::*;
+use std::io::{self, Write};
+
+use crate::{CrateDownloads, Date, VersionDownloads};
+
+/// The columns of the exported records, in order.
+pub const COLUMNS: [&str; 4] = ["crate", "date", "version_id", "downloads"];
+
+/// A single exported download count.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DownloadRecord {
+    /// The crate name.
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    /// The day of the downloads.
+    pub date: Date,
+    /// The version id, or `None` for extra downloads.
+    pub version_id: Option<u64>,
+    /// The number of downloads.
+    pub downloads: u64,
+}
+
+impl DownloadRecord {
+    /// Get the records of the downloads of a crate, extra downloads last.
+    pub fn from_crate_downloads(crate_name: &str, downloads: &CrateDownloads) -> Vec<Self> {
+        let extra = downloads.meta.extra_downloads.iter().map(|d| Self {
+            crate_name: crate_name.to_string(),
+            date: d.date,
+            version_id: None,
+            downloads: d.downloads,
+        });
+        Self::from_version_downloads(crate_name, &downloads.version_downloads)
+            .into_iter()
+            .chain(extra)
+            .collect()
+    }
+
+    /// Get the records of the downloads of versions of a crate.
+    pub fn from_version_downloads(crate_name: &str, downloads: &[VersionDownloads]) -> Vec<Self> {
+        downloads
+            .iter()
+            .map(|d| Self {
+                crate_name: crate_name.to_string(),
+                date: d.date,
+                version_id: Some(d.version),
+                downloads: d.downloads,
+            })
+            .collect()
+    }
+}
+
+/// Writes download records as CSV, with a header row before the first
+/// record.
+#[derive(Debug)]
+pub struct DownloadsCsv<W> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write> DownloadsCsv<W> {
+    /// Construct a CSV exporter writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+        }
+    }
+
+    /// Write the downloads of a crate, including its extra downloads.
+    pub fn write_crate_downloads(
+        &mut self,
+        crate_name: &str,
+        downloads: &CrateDownloads,
+    ) -> io::Result<()> {
+        self.write_records(&DownloadRecord::from_crate_downloads(crate_name, downloads))
+    }
+
+    /// Write the downloads of versions of a crate.
+    pub fn write_version_downloads(
+        &mut self,
+        crate_name: &str,
+        downloads: &[VersionDownloads],
+    ) -> io::Result<()> {
+        self.write_records(&DownloadRecord::from_version_downloads(
+            crate_name, downloads,
+        ))
+    }
+
+    /// Write records.
+    pub fn write_records(&mut self, records: &[DownloadRecord]) -> io::Result<()> {
+        if !self.header_written {
+            writeln!(self.writer, "{}", COLUMNS.join(","))?;
+            self.header_written = true;
+        }
+        for record in records {
+            let version_id = record.version_id.map(|id| id.to_string());
+            writeln!(
+                self.writer,
+                "{},{},{},{}",
+                csv_field(&record.crate_name),
+                record.date,
+                version_id.as_deref().unwrap_or(""),
+                record.downloads
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Get the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Writes download records as newline delimited JSON, one object per line.
+#[derive(Debug)]
+pub struct DownloadsNdjson<W> {
+    writer: W,
+}
+
+impl<W: Write> DownloadsNdjson<W> {
+    /// Construct an ndjson exporter writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Write the downloads of a crate, including its extra downloads.
+    pub fn write_crate_downloads(
+        &mut self,
+        crate_name: &str,
+        downloads: &CrateDownloads,
+    ) -> io::Result<()> {
+        self.write_records(&DownloadRecord::from_crate_downloads(crate_name, downloads))
+    }
+
+    /// Write the downloads of versions of a crate.
+    pub fn write_version_downloads(
+        &mut self,
+        crate_name: &str,
+        downloads: &[VersionDownloads],
+    ) -> io::Result<()> {
+        self.write_records(&DownloadRecord::from_version_downloads(
+            crate_name, downloads,
+        ))
+    }
+
+    /// Write records.
+    pub fn write_records(&mut self, records: &[DownloadRecord]) -> io::Result<()> {
+        for record in records {
+            serde_json::to_writer(&mut self.writer, record)?;
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Get the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CrateDownloadsMeta, ExtraDownloads};
+
+    #[test]
+    fn test_export_downloads() {
+        let date = Date::from_ymd(2022, 1, 28).unwrap();
+        let downloads = CrateDownloads {
+            version_downloads: vec![VersionDownloads {
+                date,
+                downloads: 1,
+                version: 7,
+            }],
+            meta: CrateDownloadsMeta {
+                extra_downloads: vec![ExtraDownloads {
+                    date,
+                    downloads: 16,
+                }],
+            },
+        };
+
+        let mut csv = DownloadsCsv::new(Vec::new());
+        csv.write_crate_downloads("serde", &downloads).unwrap();
+        csv.write_version_downloads("a,\"b\"", &downloads.version_downloads)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv.into_inner()).unwrap(),
+            "crate,date,version_id,downloads\n\
+             serde,2022-01-28,7,1\n\
+             serde,2022-01-28,,16\n\
+             \"a,\"\"b\"\"\",2022-01-28,7,1\n"
+        );
+        let mut empty = DownloadsCsv::new(Vec::new());
+        empty.write_records(&[]).unwrap();
+        assert_eq!(empty.into_inner(), b"crate,date,version_id,downloads\n");
+
+        let mut ndjson = DownloadsNdjson::new(Vec::new());
+        ndjson.write_crate_downloads("serde", &downloads).unwrap();
+        let output = String::from_utf8(ndjson.into_inner()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"crate":"serde","date":"2022-01-28","version_id":7,"downloads":1}"#,
+                r#"{"crate":"serde","date":"2022-01-28","version_id":null,"downloads":16}"#,
+            ]
+        );
+        let record: DownloadRecord = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(record.version_id, None);
+    }
+}
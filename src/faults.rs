@@ -0,0 +1,229 @@
+//! Fault injection for resilience testing.
+//!
+//! Requires the `faults` feature.
+//!
+//! Attach [`Faults`] to a client with `with_faults()` to make a share of its
+//! requests slow or fail like an overloaded crates.io would, eg to test the
+//! retry and caching logic of an application:
+//!
+//! ```rust
+//! use crates_io_api::{faults::Faults, AsyncClient};
+//! use std::{sync::Arc, time::Duration};
+//!
+//! let faults = Faults::new(42)
+//!     .latency(0.5, Duration::from_millis(200))
+//!     .too_many_requests(0.1)
+//!     .malformed_json(0.05);
+//! let client = AsyncClient::new("my_bot (help@my_bot.com)", Duration::from_secs(1))
+//!     .unwrap()
+//!     .with_faults(Arc::new(faults));
+//! ```
+//!
+//! Faults are drawn from a pseudo-random generator with the given seed, so a
+//! sequence of requests fails the same way on every run.
+
+use reqwest::{StatusCode, Url};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::Error;
+
+/// Rates of injected faults, each between 0 and 1.
+///
+/// Failed requests are not sent. Corrupted bodies are corrupted after the
+/// real response was received.
+#[derive(Debug)]
+pub struct Faults {
+    latency: f64,
+    delay: Duration,
+    too_many_requests: f64,
+    server_errors: f64,
+    truncated_bodies: f64,
+    malformed_json: f64,
+    state: Mutex<u64>,
+}
+
+/// The faults injected into a single request.
+#[derive(Debug, Default)]
+pub(crate) struct Injected {
+    /// Extra latency before the request.
+    pub delay: Option<Duration>,
+    /// An error to fail the request with.
+    pub error: Option<StatusCode>,
+}
+
+impl Faults {
+    /// Construct a layer without faults, with the given random seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            latency: 0.0,
+            delay: Duration::ZERO,
+            too_many_requests: 0.0,
+            server_errors: 0.0,
+            truncated_bodies: 0.0,
+            malformed_json: 0.0,
+            // Xorshift gets stuck at zero.
+            state: Mutex::new(seed.max(1)),
+        }
+    }
+
+    /// Delay a share of the requests by `delay`.
+    #[must_use]
+    pub fn latency(mut self, rate: f64, delay: Duration) -> Self {
+        self.latency = rate;
+        self.delay = delay;
+        self
+    }
+
+    /// Fail a share of the requests with `429 Too Many Requests`.
+    #[must_use]
+    pub fn too_many_requests(mut self, rate: f64) -> Self {
+        self.too_many_requests = rate;
+        self
+    }
+
+    /// Fail a share of the requests with `500 Internal Server Error`,
+    /// `502 Bad Gateway` or `503 Service Unavailable`.
+    #[must_use]
+    pub fn server_errors(mut self, rate: f64) -> Self {
+        self.server_errors = rate;
+        self
+    }
+
+    /// Cut off a share of the response bodies in the middle.
+    #[must_use]
+    pub fn truncated_bodies(mut self, rate: f64) -> Self {
+        self.truncated_bodies = rate;
+        self
+    }
+
+    /// Replace a share of the response bodies with invalid JSON.
+    #[must_use]
+    pub fn malformed_json(mut self, rate: f64) -> Self {
+        self.malformed_json = rate;
+        self
+    }
+
+    /// Get the next pseudo-random number in `[0, 1)`.
+    fn next(&self) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Decide the faults of the next request.
+    pub(crate) fn inject(&self) -> Injected {
+        let delay = (self.next() < self.latency).then_some(self.delay);
+        let roll = self.next();
+        let error = if roll < self.too_many_requests {
+            Some(StatusCode::TOO_MANY_REQUESTS)
+        } else if roll < self.too_many_requests + self.server_errors {
+            let errors = [
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+            ];
+            Some(errors[(self.next() * errors.len() as f64) as usize])
+        } else {
+            None
+        };
+        Injected { delay, error }
+    }
+
+    /// Corrupt a response body, maybe.
+    pub(crate) fn corrupt(&self, content: String) -> String {
+        let roll = self.next();
+        if roll < self.truncated_bodies {
+            let mut end = content.len() / 2;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content[..end].to_string()
+        } else if roll < self.truncated_bodies + self.malformed_json {
+            "{\"injected\": fault".to_string()
+        } else {
+            content
+        }
+    }
+}
+
+/// Construct the error a client returns for a response with the given status.
+pub(crate) fn status_error(url: &Url, status: StatusCode) -> Error {
+    let res = http::Response::builder()
+        .status(status)
+        .body(String::new())
+        .unwrap();
+    let err = reqwest::Response::from(res).error_for_status().unwrap_err();
+    log::debug!("Injected {} for {}", status, url);
+    Error::Http(err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_no_faults() {
+        let faults = Faults::new(1);
+        for _ in 0..100 {
+            let injected = faults.inject();
+            assert!(injected.delay.is_none() && injected.error.is_none());
+            assert_eq!(faults.corrupt("{}".to_string()), "{}");
+        }
+    }
+
+    #[test]
+    fn test_fault_rates() {
+        let faults = Faults::new(7)
+            .latency(1.0, Duration::from_millis(5))
+            .too_many_requests(0.25)
+            .server_errors(0.25)
+            .malformed_json(0.5);
+        let injected: Vec<_> = (0..1000).map(|_| faults.inject()).collect();
+        assert!(injected
+            .iter()
+            .all(|i| i.delay == Some(Duration::from_millis(5))));
+        let rate_limited = injected
+            .iter()
+            .filter(|i| i.error == Some(StatusCode::TOO_MANY_REQUESTS))
+            .count();
+        let failed = injected.iter().filter(|i| i.error.is_some()).count();
+        assert!((150..350).contains(&rate_limited), "{}", rate_limited);
+        assert!((400..600).contains(&failed), "{}", failed);
+
+        let malformed = (0..1000)
+            .filter(|_| faults.corrupt("{}".to_string()) != "{}")
+            .count();
+        assert!((400..600).contains(&malformed), "{}", malformed);
+
+        // The same seed injects the same faults.
+        let a = Faults::new(3).server_errors(0.5);
+        let b = Faults::new(3).server_errors(0.5);
+        for _ in 0..100 {
+            assert_eq!(a.inject().error, b.inject().error);
+        }
+    }
+
+    #[test]
+    fn test_status_error() {
+        let url = Url::parse("https://crates.io/api/v1/summary").unwrap();
+        match status_error(&url, StatusCode::TOO_MANY_REQUESTS) {
+            Error::Http(err) => assert_eq!(err.status(), Some(StatusCode::TOO_MANY_REQUESTS)),
+            err => panic!("unexpected error: {}", err),
+        }
+
+        // Failed requests are not sent.
+        let client = crate::SyncClient::new("test", Duration::ZERO)
+            .unwrap()
+            .with_faults(std::sync::Arc::new(Faults::new(1).server_errors(1.0)));
+        match client.summary() {
+            Err(Error::Http(err)) => assert!(err.status().unwrap().is_server_error()),
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+
+        let faults = Faults::new(1).truncated_bodies(1.0);
+        assert_eq!(faults.corrupt(r#"{"a":"äöü"}"#.to_string()), r#"{"a":""#);
+    }
+}
@@ -0,0 +1,195 @@
+//! Record API responses to a fixture file and replay them later.
+//!
+//! Requires the `fixtures` feature.
+//!
+//! Tests of tools built on this crate can record real responses once, check
+//! the fixture file in, and then replay them deterministically without
+//! network access or rate limits:
+//!
+//! ```rust,no_run
+//! use crates_io_api::{fixtures::Fixtures, SyncClient};
+//! use std::sync::Arc;
+//!
+//! let fixtures = if std::env::var_os("RECORD").is_some() {
+//!     Fixtures::record("tests/fixtures/serde.json")
+//! } else {
+//!     Fixtures::replay("tests/fixtures/serde.json").unwrap()
+//! };
+//! let client = SyncClient::new("my_bot (help@my_bot.com)", std::time::Duration::from_secs(1))
+//!     .unwrap()
+//!     .with_fixtures(Arc::new(fixtures));
+//! let krate = client.get_crate("serde").unwrap();
+//! ```
+//!
+//! Responses are identified by URL, including the query string. Successful
+//! responses as well as `404 Not Found` and `403 Forbidden` errors are
+//! recorded; other errors are not.
+
+use reqwest::{StatusCode, Url};
+use serde_derive::*;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::{MissingFixtureError, NotFoundError, PermissionDeniedError};
+use crate::Error;
+
+/// A recorded response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Fixture {
+    status: u16,
+    body: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+/// A fixture file, either being recorded or replayed.
+///
+/// Attach it to a client with `with_fixtures()`.
+#[derive(Debug)]
+pub struct Fixtures {
+    path: PathBuf,
+    mode: Mode,
+    entries: Mutex<BTreeMap<String, Fixture>>,
+}
+
+impl Fixtures {
+    /// Record responses to the file at `path`.
+    ///
+    /// Requests are sent as usual. The file is overwritten with the recorded
+    /// responses by [`Self::save`], or when the fixtures are dropped.
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: Mode::Record,
+            entries: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Replay the responses recorded in the file at `path`.
+    ///
+    /// No requests are sent. Requests without a recorded response fail with
+    /// [`Error::MissingFixture`].
+    pub fn replay(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let content = std::fs::read_to_string(&path)?;
+        let entries = serde_json::from_str(&content)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Get the path of the fixture file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check whether responses are replayed instead of recorded.
+    pub fn is_replay(&self) -> bool {
+        self.mode == Mode::Replay
+    }
+
+    /// Write the recorded responses to the fixture file.
+    ///
+    /// Does nothing when replaying.
+    pub fn save(&self) -> io::Result<()> {
+        if self.is_replay() {
+            return Ok(());
+        }
+        let content = serde_json::to_string_pretty(&*self.entries.lock().unwrap())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        std::fs::write(&self.path, content)
+    }
+
+    /// Record a response, if recording.
+    pub(crate) fn record_response(&self, url: &Url, status: StatusCode, body: &str) {
+        if self.mode == Mode::Record {
+            self.entries.lock().unwrap().insert(
+                url.to_string(),
+                Fixture {
+                    status: status.as_u16(),
+                    body: body.to_string(),
+                },
+            );
+        }
+    }
+
+    /// Get the recorded response body for a URL, or the recorded error.
+    pub(crate) fn replay_response(&self, url: &Url) -> Result<String, Error> {
+        let entries = self.entries.lock().unwrap();
+        let fixture = entries.get(url.as_str()).ok_or_else(|| {
+            Error::MissingFixture(MissingFixtureError {
+                url: url.to_string(),
+            })
+        })?;
+        match StatusCode::from_u16(fixture.status) {
+            Ok(StatusCode::NOT_FOUND) => Err(Error::NotFound(NotFoundError {
+                url: url.to_string(),
+            })),
+            Ok(StatusCode::FORBIDDEN) => Err(Error::PermissionDenied(PermissionDeniedError {
+                reason: fixture.body.clone(),
+            })),
+            _ => Ok(fixture.body.clone()),
+        }
+    }
+}
+
+impl Drop for Fixtures {
+    fn drop(&mut self) {
+        if let Err(err) = self.save() {
+            log::warn!(
+                "Could not save fixtures to {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!(
+            "crates_io_api_fixtures_{}.json",
+            std::process::id()
+        ));
+        let url = Url::parse("https://crates.io/api/v1/crates/serde?include=").unwrap();
+        let missing = Url::parse("https://crates.io/api/v1/crates/nope?include=").unwrap();
+
+        let fixtures = Fixtures::record(&path);
+        fixtures.record_response(&url, StatusCode::OK, r#"{"crate":{}}"#);
+        fixtures.record_response(&missing, StatusCode::NOT_FOUND, "");
+        drop(fixtures);
+
+        let fixtures = Fixtures::replay(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(fixtures.is_replay());
+        assert_eq!(fixtures.replay_response(&url).unwrap(), r#"{"crate":{}}"#);
+        assert!(matches!(
+            fixtures.replay_response(&missing),
+            Err(Error::NotFound(_))
+        ));
+        let other = Url::parse("https://crates.io/api/v1/summary").unwrap();
+        assert!(matches!(
+            fixtures.replay_response(&other),
+            Err(Error::MissingFixture(_))
+        ));
+        // Replaying clients don't touch the network.
+        let client = crate::SyncClient::new("test", std::time::Duration::from_secs(60))
+            .unwrap()
+            .with_fixtures(std::sync::Arc::new(fixtures));
+        assert!(client.get_raw("crates/serde", &[("include", "")]).is_ok());
+        assert!(!client.crate_exists("nope").unwrap());
+    }
+}
@@ -0,0 +1,862 @@
+//! Dependency graphs spanning multiple crates.
+
+use serde_derive::*;
+#[cfg(any(feature = "petgraph", feature = "semver"))]
+use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+#[cfg(feature = "semver")]
+use crate::semver::{Version as SemVersion, VersionReq};
+#[cfg(feature = "semver")]
+use crate::types::Version;
+use crate::types::{Dependency, DependencyKind, ReverseDependencies};
+#[cfg(feature = "petgraph")]
+use crate::{CrateName, InvalidCrateNameError};
+
+/// A directed graph of dependencies between crates.
+///
+/// Edges point from the dependent crate to its dependency.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DependencyGraph {
+    /// The crate the graph was built from.
+    pub root: String,
+    /// All crates in the graph, with their distance from the root.
+    pub crates: BTreeMap<String, usize>,
+    /// All dependency edges.
+    pub edges: Vec<DependencyEdge>,
+}
+
+/// A dependency of one crate on another.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DependencyEdge {
+    /// The crate that has the dependency.
+    pub dependent: String,
+    /// The crate that is depended on.
+    pub dependency: String,
+    /// The version requirement of the dependency.
+    pub req: String,
+    /// The dependency kind.
+    pub kind: DependencyKind,
+    /// Whether the dependency is optional.
+    pub optional: bool,
+}
+
+impl DependencyGraph {
+    pub(crate) fn new(root: &str) -> Self {
+        let mut crates = BTreeMap::new();
+        crates.insert(root.to_string(), 0);
+        Self {
+            root: root.to_string(),
+            crates,
+            edges: Vec::new(),
+        }
+    }
+
+    /// Get the edges of all crates that directly depend on the given crate.
+    pub fn dependents_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a DependencyEdge> {
+        self.edges.iter().filter(move |e| e.dependency == name)
+    }
+
+    /// Get the edges of all direct dependencies of the given crate.
+    pub fn dependencies_of<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a DependencyEdge> {
+        self.edges.iter().filter(move |e| e.dependent == name)
+    }
+
+    /// Render the graph in the Graphviz DOT format.
+    ///
+    /// Edges are labelled with the version requirement, and optional
+    /// dependencies are drawn dashed.
+    ///
+    /// Requires the `dot` feature.
+    #[cfg(feature = "dot")]
+    pub fn to_dot(&self) -> String {
+        let mut dot = DotWriter::new(&self.root);
+        for name in self.crates.keys() {
+            dot.node(name, name);
+        }
+        for edge in &self.edges {
+            dot.edge(
+                &edge.dependent,
+                &edge.dependency,
+                &edge.req,
+                &edge.kind,
+                edge.optional,
+            );
+        }
+        dot.finish()
+    }
+
+    /// Convert the graph into a [`petgraph::Graph`].
+    ///
+    /// Every crate is a node, and every edge points from the dependent crate
+    /// to its dependency. Fails if the graph contains an invalid crate name.
+    ///
+    /// Requires the `petgraph` feature.
+    #[cfg(feature = "petgraph")]
+    pub fn to_petgraph(
+        &self,
+    ) -> Result<petgraph::Graph<CrateName, DependencyKind>, InvalidCrateNameError> {
+        let mut graph = petgraph::Graph::new();
+        let mut nodes = HashMap::new();
+        let names = self.crates.keys().chain(
+            self.edges
+                .iter()
+                .flat_map(|e| [&e.dependent, &e.dependency]),
+        );
+        for name in names {
+            if !nodes.contains_key(name.as_str()) {
+                let node = graph.add_node(CrateName::new(name.as_str())?);
+                nodes.insert(name.as_str(), node);
+            }
+        }
+        for edge in &self.edges {
+            graph.add_edge(
+                nodes[edge.dependent.as_str()],
+                nodes[edge.dependency.as_str()],
+                edge.kind.clone(),
+            );
+        }
+        Ok(graph)
+    }
+}
+
+/// Incrementally builds a DOT digraph.
+#[cfg(feature = "dot")]
+struct DotWriter {
+    out: String,
+}
+
+#[cfg(feature = "dot")]
+impl DotWriter {
+    fn new(name: &str) -> Self {
+        Self {
+            out: format!("digraph {} {{\n", quote(name)),
+        }
+    }
+
+    fn node(&mut self, id: &str, label: &str) {
+        self.out += &format!("    {} [label={}];\n", quote(id), quote(label));
+    }
+
+    fn edge(&mut self, from: &str, to: &str, req: &str, kind: &DependencyKind, optional: bool) {
+        let label = if *kind == DependencyKind::Normal {
+            req.to_string()
+        } else {
+            format!("{req} ({kind})")
+        };
+        let style = if optional { ", style=dashed" } else { "" };
+        self.out += &format!(
+            "    {} -> {} [label={}{}];\n",
+            quote(from),
+            quote(to),
+            quote(&label),
+            style
+        );
+    }
+
+    fn finish(mut self) -> String {
+        self.out += "}\n";
+        self.out
+    }
+}
+
+/// Quote a DOT identifier.
+#[cfg(feature = "dot")]
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Limits for building a [`DependencyGraph`].
+///
+/// Every crate in the graph requires at least one request, so graphs for
+/// popular crates can get very expensive. By default, only direct
+/// dependencies are included.
+#[derive(Debug, Clone)]
+pub struct GraphLimits {
+    pub(crate) max_depth: usize,
+    pub(crate) max_crates: Option<usize>,
+}
+
+impl GraphLimits {
+    /// Construct the default limits.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_depth: 1,
+            max_crates: None,
+        }
+    }
+
+    /// Set the maximum distance from the root crate.
+    #[must_use]
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Set the maximum number of crates in the graph, including the root.
+    #[must_use]
+    pub fn max_crates(mut self, max: Option<usize>) -> Self {
+        self.max_crates = max;
+        self
+    }
+}
+
+impl Default for GraphLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// State of a breadth-first walk over reverse dependencies.
+///
+/// Shared by the async and sync clients, which perform the requests.
+pub(crate) struct ReverseDependencyWalk {
+    graph: DependencyGraph,
+    limits: GraphLimits,
+    queue: VecDeque<(String, usize)>,
+    edges: HashSet<(String, String)>,
+}
+
+impl ReverseDependencyWalk {
+    pub(crate) fn new(root: &str, limits: GraphLimits) -> Self {
+        Self {
+            graph: DependencyGraph::new(root),
+            limits,
+            queue: vec![(root.to_string(), 0)].into(),
+            edges: HashSet::new(),
+        }
+    }
+
+    /// Get the next crate whose reverse dependencies should be fetched.
+    pub(crate) fn next_crate(&mut self) -> Option<String> {
+        while let Some((name, depth)) = self.queue.pop_front() {
+            if depth < self.limits.max_depth {
+                return Some(name);
+            }
+        }
+        None
+    }
+
+    /// Add the reverse dependencies of a crate returned by [`Self::next_crate`].
+    pub(crate) fn add(&mut self, name: &str, deps: ReverseDependencies) {
+        let depth = self.graph.crates[name] + 1;
+        for rdep in deps.dependencies {
+            let dependent = rdep.crate_version.crate_name;
+            if !self.graph.crates.contains_key(&dependent) {
+                if let Some(max) = self.limits.max_crates {
+                    if self.graph.crates.len() >= max {
+                        continue;
+                    }
+                }
+                self.graph.crates.insert(dependent.clone(), depth);
+                self.queue.push_back((dependent.clone(), depth));
+            }
+
+            if self.edges.insert((dependent.clone(), name.to_string())) {
+                self.graph.edges.push(DependencyEdge {
+                    dependent,
+                    dependency: name.to_string(),
+                    req: rdep.dependency.req,
+                    kind: rdep.dependency.kind,
+                    optional: rdep.dependency.optional,
+                });
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> DependencyGraph {
+        self.graph
+    }
+}
+
+/// A tree of the resolved dependencies of a crate version.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DependencyTree {
+    /// The root crate.
+    pub name: String,
+    /// The version of the root crate.
+    pub version: String,
+    /// The direct dependencies of the root crate.
+    pub dependencies: Vec<ResolvedDependency>,
+}
+
+impl DependencyTree {
+    /// Render the tree in the Graphviz DOT format.
+    ///
+    /// Every resolved crate version is a single node, so crate versions that
+    /// appear multiple times in the tree are merged. Dependencies that could
+    /// not be resolved are omitted.
+    ///
+    /// Requires the `dot` feature.
+    #[cfg(feature = "dot")]
+    pub fn to_dot(&self) -> String {
+        let root = format!("{} {}", self.name, self.version);
+        let mut dot = DotWriter::new(&root);
+        dot.node(&root, &root);
+
+        let mut nodes = HashSet::new();
+        let mut edges = HashSet::new();
+        let mut stack: Vec<_> = self
+            .dependencies
+            .iter()
+            .map(|dep| (root.clone(), dep))
+            .collect();
+        while let Some((parent, dep)) = stack.pop() {
+            let version = match &dep.version {
+                Some(version) => version,
+                None => continue,
+            };
+            let id = format!("{} {}", dep.name, version);
+            if nodes.insert(id.clone()) && id != root {
+                dot.node(&id, &id);
+            }
+            if edges.insert((parent.clone(), id.clone(), dep.kind.clone())) {
+                dot.edge(&parent, &id, &dep.req, &dep.kind, dep.optional);
+            }
+            stack.extend(dep.dependencies.iter().map(|child| (id.clone(), child)));
+        }
+        dot.finish()
+    }
+}
+
+/// A dependency in a [`DependencyTree`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResolvedDependency {
+    /// The name of the depended on crate.
+    pub name: String,
+    /// The version requirement of the dependency.
+    pub req: String,
+    /// The dependency kind.
+    pub kind: DependencyKind,
+    /// Whether the dependency is optional.
+    pub optional: bool,
+    /// The target the dependency is restricted to, if any.
+    pub target: Option<String>,
+    /// The newest non-yanked version matching `req`.
+    ///
+    /// `None` if no published version matches the requirement.
+    pub version: Option<String>,
+    /// Whether the dependencies of this crate version are already listed
+    /// elsewhere in the tree, and have been omitted here.
+    pub duplicate: bool,
+    /// The dependencies of the resolved version.
+    ///
+    /// Empty if the maximum depth was reached.
+    pub dependencies: Vec<ResolvedDependency>,
+}
+
+/// Selects which dependencies are followed when building a [`DependencyTree`].
+///
+/// By default, normal and build dependencies are followed, including
+/// target-specific ones, while dev and optional dependencies are skipped.
+#[derive(Debug, Clone)]
+pub struct DependencyFilter {
+    pub(crate) normal: bool,
+    pub(crate) build: bool,
+    pub(crate) dev: bool,
+    pub(crate) optional: bool,
+    pub(crate) target_specific: bool,
+}
+
+impl DependencyFilter {
+    /// Construct the default filter.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            normal: true,
+            build: true,
+            dev: false,
+            optional: false,
+            target_specific: true,
+        }
+    }
+
+    /// Include normal dependencies.
+    #[must_use]
+    pub fn normal(mut self, include: bool) -> Self {
+        self.normal = include;
+        self
+    }
+
+    /// Include build dependencies.
+    #[must_use]
+    pub fn build(mut self, include: bool) -> Self {
+        self.build = include;
+        self
+    }
+
+    /// Include dev dependencies.
+    #[must_use]
+    pub fn dev(mut self, include: bool) -> Self {
+        self.dev = include;
+        self
+    }
+
+    /// Include optional dependencies.
+    #[must_use]
+    pub fn optional(mut self, include: bool) -> Self {
+        self.optional = include;
+        self
+    }
+
+    /// Include dependencies that only apply to specific targets.
+    #[must_use]
+    pub fn target_specific(mut self, include: bool) -> Self {
+        self.target_specific = include;
+        self
+    }
+
+    /// Check whether a dependency passes the filter.
+    pub fn matches(&self, dep: &Dependency) -> bool {
+        let kind = match dep.kind {
+            DependencyKind::Build => self.build,
+            DependencyKind::Dev => self.dev,
+            _ => self.normal,
+        };
+        kind && (self.optional || !dep.optional) && (self.target_specific || dep.target.is_none())
+    }
+}
+
+impl Default for DependencyFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A request needed to continue a [`DependencyTreeWalk`].
+#[cfg(feature = "semver")]
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum TreeRequest {
+    /// Fetch the dependencies of a crate version.
+    Dependencies { name: String, version: String },
+    /// Fetch the published versions of a crate.
+    Versions(String),
+}
+
+#[cfg(feature = "semver")]
+type VersionKey = (String, String);
+
+/// State of a breadth-first dependency resolution.
+///
+/// Shared by the async and sync clients, which perform the requests.
+#[cfg(feature = "semver")]
+pub(crate) struct DependencyTreeWalk {
+    root: VersionKey,
+    max_depth: usize,
+    filter: DependencyFilter,
+    /// Depth of every crate version that was reached.
+    depths: HashMap<VersionKey, usize>,
+    /// Filtered dependencies of every expanded crate version.
+    children: HashMap<VersionKey, Vec<ResolvedDependency>>,
+    /// Available versions of every crate, newest first.
+    versions: HashMap<String, Vec<SemVersion>>,
+    requested: HashSet<String>,
+    version_queue: VecDeque<String>,
+    expand_queue: VecDeque<VersionKey>,
+    /// Dependencies waiting for the versions of their crate.
+    unresolved: Vec<(VersionKey, usize)>,
+}
+
+#[cfg(feature = "semver")]
+impl DependencyTreeWalk {
+    pub(crate) fn new(
+        name: &str,
+        version: &str,
+        max_depth: usize,
+        filter: DependencyFilter,
+    ) -> Self {
+        let root = (name.to_string(), version.to_string());
+        let mut depths = HashMap::new();
+        depths.insert(root.clone(), 0);
+        let mut expand_queue = VecDeque::new();
+        if max_depth > 0 {
+            expand_queue.push_back(root.clone());
+        }
+        Self {
+            root,
+            max_depth,
+            filter,
+            depths,
+            children: HashMap::new(),
+            versions: HashMap::new(),
+            requested: HashSet::new(),
+            version_queue: VecDeque::new(),
+            expand_queue,
+            unresolved: Vec::new(),
+        }
+    }
+
+    /// Get the next request to perform, or `None` if the tree is complete.
+    pub(crate) fn next_request(&mut self) -> Option<TreeRequest> {
+        if let Some(name) = self.version_queue.pop_front() {
+            return Some(TreeRequest::Versions(name));
+        }
+        self.expand_queue
+            .pop_front()
+            .map(|(name, version)| TreeRequest::Dependencies { name, version })
+    }
+
+    /// Add the response to a [`TreeRequest::Dependencies`] request.
+    pub(crate) fn add_dependencies(&mut self, name: &str, version: &str, deps: Vec<Dependency>) {
+        let key = (name.to_string(), version.to_string());
+        let children: Vec<_> = deps
+            .into_iter()
+            .filter(|dep| self.filter.matches(dep))
+            .map(|dep| ResolvedDependency {
+                name: dep.crate_id,
+                req: dep.req,
+                kind: dep.kind,
+                optional: dep.optional,
+                target: dep.target,
+                version: None,
+                duplicate: false,
+                dependencies: Vec::new(),
+            })
+            .collect();
+
+        let mut ready = Vec::new();
+        for (index, child) in children.iter().enumerate() {
+            if self.versions.contains_key(&child.name) {
+                ready.push(index);
+            } else {
+                self.unresolved.push((key.clone(), index));
+                if self.requested.insert(child.name.clone()) {
+                    self.version_queue.push_back(child.name.clone());
+                }
+            }
+        }
+        self.children.insert(key.clone(), children);
+        for index in ready {
+            self.resolve(&key, index);
+        }
+    }
+
+    /// Add the response to a [`TreeRequest::Versions`] request.
+    ///
+    /// Yanked and unparsable versions are ignored.
+    pub(crate) fn add_versions(&mut self, name: &str, versions: &[Version]) {
+        let mut available: Vec<_> = versions
+            .iter()
+            .filter(|v| !v.yanked)
+            .filter_map(|v| SemVersion::parse(&v.num).ok())
+            .collect();
+        available.sort_by(|a, b| b.cmp(a));
+        self.versions.insert(name.to_string(), available);
+
+        let (ready, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.unresolved)
+            .into_iter()
+            .partition(|(key, index)| self.children[key][*index].name == name);
+        self.unresolved = waiting;
+        for (key, index) in ready {
+            self.resolve(&key, index);
+        }
+    }
+
+    /// Pick a version for a dependency, and queue it for expansion.
+    fn resolve(&mut self, key: &VersionKey, index: usize) {
+        let child = &self.children[key][index];
+        let resolved = VersionReq::parse(&child.req).ok().and_then(|req| {
+            self.versions[&child.name]
+                .iter()
+                .find(|v| req.matches(v))
+                .map(|v| v.to_string())
+        });
+        let child_key = resolved.clone().map(|v| (child.name.clone(), v));
+        self.children.get_mut(key).unwrap()[index].version = resolved;
+
+        let depth = self.depths[key] + 1;
+        if let Some(child_key) = child_key {
+            if !self.depths.contains_key(&child_key) {
+                self.depths.insert(child_key.clone(), depth);
+                if depth < self.max_depth {
+                    self.expand_queue.push_back(child_key);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> DependencyTree {
+        let mut visited = HashSet::new();
+        visited.insert(self.root.clone());
+        DependencyTree {
+            dependencies: self.build_children(&self.root, &mut visited),
+            name: self.root.0.clone(),
+            version: self.root.1.clone(),
+        }
+    }
+
+    fn build_children(
+        &self,
+        key: &VersionKey,
+        visited: &mut HashSet<VersionKey>,
+    ) -> Vec<ResolvedDependency> {
+        let children = match self.children.get(key) {
+            Some(children) => children,
+            None => return Vec::new(),
+        };
+        children
+            .iter()
+            .map(|child| {
+                let mut node = child.clone();
+                if let Some(version) = &child.version {
+                    let child_key = (child.name.clone(), version.clone());
+                    if self.children.contains_key(&child_key) {
+                        if visited.insert(child_key.clone()) {
+                            node.dependencies = self.build_children(&child_key, visited);
+                        } else {
+                            node.duplicate = true;
+                        }
+                    }
+                }
+                node
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{ReverseDependency, Version};
+
+    fn rdeps(dependency: &str, dependents: &[&str]) -> ReverseDependencies {
+        let dependencies = dependents
+            .iter()
+            .enumerate()
+            .map(|(id, dependent)| {
+                let mut crate_version = Version::new(dependent, "1.0.0");
+                crate_version.id = id as u64;
+                let mut dependency = Dependency::new(dependency, "^1");
+                dependency.id = id as u64;
+                dependency.version_id = id as u64;
+                ReverseDependency {
+                    crate_version,
+                    dependency,
+                }
+            })
+            .collect();
+        ReverseDependencies {
+            dependencies,
+            meta: crate::Meta::new(dependents.len() as u64),
+        }
+    }
+
+    fn dep(name: &str, req: &str, kind: &str, optional: bool) -> Dependency {
+        let mut dep = Dependency::new(name, req);
+        dep.kind = kind.into();
+        dep.optional = optional;
+        dep
+    }
+
+    #[cfg(feature = "semver")]
+    fn versions(name: &str, nums: &[(&str, bool)]) -> Vec<Version> {
+        nums.iter()
+            .enumerate()
+            .map(|(id, (num, yanked))| {
+                let mut version = Version::new(name, num);
+                version.id = id as u64;
+                version.yanked = *yanked;
+                version
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "semver")]
+    fn deps_request(name: &str, version: &str) -> Option<TreeRequest> {
+        Some(TreeRequest::Dependencies {
+            name: name.to_string(),
+            version: version.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_dependency_filter() {
+        let filter = DependencyFilter::new();
+        assert!(filter.matches(&dep("a", "1", "normal", false)));
+        assert!(filter.matches(&dep("a", "1", "build", false)));
+        assert!(!filter.matches(&dep("a", "1", "dev", false)));
+        assert!(!filter.matches(&dep("a", "1", "normal", true)));
+
+        let mut target_dep = dep("a", "1", "normal", false);
+        target_dep.target = Some("cfg(windows)".to_string());
+        assert!(filter.matches(&target_dep));
+        assert!(!filter.target_specific(false).matches(&target_dep));
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_dependency_tree_walk() {
+        let mut walk = DependencyTreeWalk::new("a", "1.0.0", 3, DependencyFilter::new());
+
+        assert_eq!(walk.next_request(), deps_request("a", "1.0.0"));
+        walk.add_dependencies(
+            "a",
+            "1.0.0",
+            vec![
+                dep("b", "^1.1", "normal", false),
+                dep("c", "0.2", "build", false),
+                dep("d", "1", "dev", false),
+            ],
+        );
+        assert_eq!(walk.next_request(), Some(TreeRequest::Versions("b".into())));
+        walk.add_versions(
+            "b",
+            &versions("b", &[("1.3.0", true), ("1.2.0", false), ("2.0.0", false)]),
+        );
+        assert_eq!(walk.next_request(), Some(TreeRequest::Versions("c".into())));
+        walk.add_versions("c", &versions("c", &[("0.3.0", false)]));
+
+        assert_eq!(walk.next_request(), deps_request("b", "1.2.0"));
+        walk.add_dependencies("b", "1.2.0", vec![dep("b", "=1.2.0", "normal", false)]);
+        assert_eq!(walk.next_request(), None);
+
+        let tree = walk.finish();
+        assert_eq!(tree.dependencies.len(), 2);
+        let b = &tree.dependencies[0];
+        assert_eq!(b.version.as_deref(), Some("1.2.0"));
+        assert!(!b.duplicate);
+        // A crate version that depends on itself is only expanded once.
+        assert_eq!(b.dependencies.len(), 1);
+        assert!(b.dependencies[0].duplicate);
+        let c = &tree.dependencies[1];
+        assert_eq!(c.version, None);
+        assert!(c.dependencies.is_empty());
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_dependency_tree_walk_max_depth() {
+        let mut walk = DependencyTreeWalk::new("a", "1.0.0", 1, DependencyFilter::new());
+        walk.next_request();
+        walk.add_dependencies("a", "1.0.0", vec![dep("b", "1", "normal", false)]);
+        assert_eq!(walk.next_request(), Some(TreeRequest::Versions("b".into())));
+        walk.add_versions("b", &versions("b", &[("1.0.0", false)]));
+        assert_eq!(walk.next_request(), None);
+
+        let tree = walk.finish();
+        assert_eq!(tree.dependencies[0].version.as_deref(), Some("1.0.0"));
+
+        let mut walk = DependencyTreeWalk::new("a", "1.0.0", 0, DependencyFilter::new());
+        assert_eq!(walk.next_request(), None);
+        assert!(walk.finish().dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_reverse_dependency_walk() {
+        let mut walk = ReverseDependencyWalk::new("a", GraphLimits::new().max_depth(2));
+
+        assert_eq!(walk.next_crate().as_deref(), Some("a"));
+        walk.add("a", rdeps("a", &["b", "c"]));
+        assert_eq!(walk.next_crate().as_deref(), Some("b"));
+        walk.add("b", rdeps("b", &["c", "d"]));
+        assert_eq!(walk.next_crate().as_deref(), Some("c"));
+        walk.add("c", rdeps("c", &["e", "e"]));
+        // "d" and "e" are at the maximum depth.
+        assert_eq!(walk.next_crate(), None);
+
+        let graph = walk.finish();
+        let depths: Vec<_> = graph.crates.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(depths, [("a", 0), ("b", 1), ("c", 1), ("d", 2), ("e", 2)]);
+        assert_eq!(graph.edges.len(), 5);
+        let dependents: Vec<_> = graph.dependents_of("c").map(|e| &e.dependent).collect();
+        assert_eq!(dependents, ["e"]);
+        let dependencies: Vec<_> = graph.dependencies_of("c").map(|e| &e.dependency).collect();
+        assert_eq!(dependencies, ["a", "b"]);
+    }
+
+    #[cfg(feature = "dot")]
+    #[test]
+    fn test_graph_to_dot() {
+        let mut graph = DependencyGraph::new("a");
+        graph.crates.insert("b".into(), 1);
+        graph.crates.insert("c\"d".into(), 1);
+        graph.edges.push(DependencyEdge {
+            dependent: "b".into(),
+            dependency: "a".into(),
+            req: "^1".into(),
+            kind: DependencyKind::Normal,
+            optional: false,
+        });
+        graph.edges.push(DependencyEdge {
+            dependent: "c\"d".into(),
+            dependency: "a".into(),
+            req: "0.2".into(),
+            kind: DependencyKind::Dev,
+            optional: true,
+        });
+
+        let expected = r#"digraph "a" {
+    "a" [label="a"];
+    "b" [label="b"];
+    "c\"d" [label="c\"d"];
+    "b" -> "a" [label="^1"];
+    "c\"d" -> "a" [label="0.2 (dev)", style=dashed];
+}
+"#;
+        assert_eq!(graph.to_dot(), expected);
+    }
+
+    #[cfg(all(feature = "dot", feature = "semver"))]
+    #[test]
+    fn test_tree_to_dot() {
+        let mut walk = DependencyTreeWalk::new("a", "1.0.0", 2, DependencyFilter::new());
+        walk.next_request();
+        walk.add_dependencies("a", "1.0.0", vec![dep("b", "1", "normal", false)]);
+        walk.next_request();
+        walk.add_versions("b", &versions("b", &[("1.0.0", false)]));
+        walk.next_request();
+        walk.add_dependencies("b", "1.0.0", vec![dep("a", "1", "build", false)]);
+        walk.next_request();
+        walk.add_versions("a", &versions("a", &[("1.0.0", false)]));
+        assert_eq!(walk.next_request(), None);
+
+        let expected = r#"digraph "a 1.0.0" {
+    "a 1.0.0" [label="a 1.0.0"];
+    "b 1.0.0" [label="b 1.0.0"];
+    "a 1.0.0" -> "b 1.0.0" [label="1"];
+    "b 1.0.0" -> "a 1.0.0" [label="1 (build)"];
+}
+"#;
+        assert_eq!(walk.finish().to_dot(), expected);
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_graph_to_petgraph() {
+        let mut graph = DependencyGraph::new("a");
+        graph.crates.insert("b".into(), 1);
+        graph.edges.push(DependencyEdge {
+            dependent: "b".into(),
+            dependency: "a".into(),
+            req: "^1".into(),
+            kind: DependencyKind::Dev,
+            optional: false,
+        });
+
+        let petgraph = graph.to_petgraph().unwrap();
+        assert_eq!(petgraph.node_count(), 2);
+        let edge = petgraph.raw_edges()[0].clone();
+        assert_eq!(petgraph[edge.source()].as_str(), "b");
+        assert_eq!(petgraph[edge.target()].as_str(), "a");
+        assert_eq!(edge.weight, DependencyKind::Dev);
+
+        graph.crates.insert("not a name".into(), 1);
+        assert!(graph.to_petgraph().is_err());
+    }
+
+    #[test]
+    fn test_reverse_dependency_walk_max_crates() {
+        let limits = GraphLimits::new().max_depth(5).max_crates(Some(2));
+        let mut walk = ReverseDependencyWalk::new("a", limits);
+        walk.next_crate();
+        walk.add("a", rdeps("a", &["b", "c"]));
+
+        let graph = walk.finish();
+        assert_eq!(graph.crates.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+    }
+}
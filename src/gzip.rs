@@ -0,0 +1,109 @@
+//! Decompressing gzip files, like `.crate` files.
+//!
+//! Decoding is done by flate2, which verifies the CRC32 and size trailers of
+//! every gzip member.
+
+use flate2::read::MultiGzDecoder;
+use std::io::{self, Read};
+
+/// The default limit of the decompressed size, which is the limit crates.io
+/// enforces when unpacking uploaded `.crate` files.
+pub(crate) const DEFAULT_MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Decompress a gzip file, concatenating all of its members.
+///
+/// Fails without decompressing further if the result would be larger than
+/// `max_size` bytes.
+pub(crate) fn decompress(data: &[u8], max_size: u64) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    MultiGzDecoder::new(data)
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut out)?;
+    if out.len() as u64 > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("decompressed size exceeds {} bytes", max_size),
+        ));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decompress() {
+        // `printf 'hello hello hello world\n' | gzip -9`, with fixed codes.
+        let fixed = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9,
+            0xc9, 0x57, 0xc8, 0x40, 0x22, 0xcb, 0xf3, 0x8b, 0x72, 0x52, 0xb8, 0x00, 0x88, 0xd9,
+            0x5b, 0xe0, 0x18, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            decompress(&fixed, DEFAULT_MAX_SIZE).unwrap(),
+            b"hello hello hello world\n"
+        );
+
+        // The same text twice, the second time in a stored block.
+        let mut stored = fixed.to_vec();
+        stored.extend_from_slice(&[0x1f, 0x8b, 0x08, 0x08, 0, 0, 0, 0, 0, 0x03, b'a', 0]);
+        stored.extend_from_slice(&[0x01, 24, 0, !24, 0xff]);
+        stored.extend_from_slice(b"hello hello hello world\n");
+        stored.extend_from_slice(&[0x88, 0xd9, 0x5b, 0xe0, 24, 0, 0, 0]);
+        assert_eq!(
+            decompress(&stored, DEFAULT_MAX_SIZE).unwrap(),
+            b"hello hello hello world\nhello hello hello world\n"
+        );
+
+        // A longer text with dynamic codes.
+        let dynamic = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xed, 0xd3, 0x31, 0x0e,
+            0x82, 0x40, 0x14, 0x84, 0xe1, 0x9e, 0x53, 0xcc, 0x01, 0x28, 0x7c, 0x22, 0x88, 0xde,
+            0x06, 0xcd, 0x43, 0x36, 0x59, 0x77, 0x0d, 0xbe, 0x04, 0x8e, 0x6f, 0x8c, 0x37, 0x98,
+            0xca, 0x62, 0x92, 0x69, 0xff, 0x6e, 0xbe, 0x9c, 0x8a, 0xe3, 0x80, 0x3a, 0x63, 0x42,
+            0xae, 0xe5, 0xe1, 0x2b, 0xc2, 0xf7, 0x68, 0xb1, 0xa5, 0x58, 0xf0, 0xae, 0x4f, 0xc7,
+            0xea, 0x2f, 0x8f, 0x14, 0xa9, 0x96, 0x2b, 0xa6, 0xdb, 0xfd, 0xb7, 0x26, 0x7f, 0x43,
+            0x63, 0xc3, 0x23, 0x1b, 0x76, 0x6c, 0x78, 0x62, 0xc3, 0x9e, 0x0d, 0x07, 0x36, 0x3c,
+            0xb3, 0xe1, 0xc8, 0x86, 0x17, 0xfa, 0x00, 0xfc, 0x75, 0xe8, 0xef, 0x18, 0x7d, 0x1e,
+            0xa3, 0xdf, 0x63, 0xf4, 0x7d, 0x8c, 0xfe, 0x8f, 0xd1, 0x07, 0x92, 0x67, 0x79, 0x96,
+            0x67, 0x79, 0x96, 0x67, 0x79, 0x96, 0x67, 0x79, 0x96, 0xe7, 0xff, 0xf1, 0xfc, 0x01,
+            0x95, 0x44, 0x99, 0x17, 0x71, 0x0d, 0x00, 0x00,
+        ];
+        let text: String = (0..60)
+            .map(|i| {
+                format!(
+                    "line {} of a longer text, with some repetition: abcabcabc\n",
+                    i % 17
+                )
+            })
+            .collect();
+        assert_eq!(
+            decompress(&dynamic, DEFAULT_MAX_SIZE).unwrap(),
+            text.as_bytes()
+        );
+
+        assert!(decompress(b"not gzip", DEFAULT_MAX_SIZE).is_err());
+        assert!(decompress(&fixed[..20], DEFAULT_MAX_SIZE).is_err());
+
+        // A wrong CRC32 or size in the trailer.
+        for pos in [fixed.len() - 8, fixed.len() - 4] {
+            let mut corrupted = fixed;
+            corrupted[pos] ^= 1;
+            assert!(decompress(&corrupted, DEFAULT_MAX_SIZE).is_err());
+        }
+    }
+
+    #[test]
+    fn test_decompress_max_size() {
+        let fixed = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9,
+            0xc9, 0x57, 0xc8, 0x40, 0x22, 0xcb, 0xf3, 0x8b, 0x72, 0x52, 0xb8, 0x00, 0x88, 0xd9,
+            0x5b, 0xe0, 0x18, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(decompress(&fixed, 24).unwrap().len(), 24);
+        let err = decompress(&fixed, 23).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "decompressed size exceeds 23 bytes");
+    }
+}
@@ -44,14 +44,92 @@
 #![recursion_limit = "128"]
 #![deny(missing_docs)]
 
+mod api;
 mod async_client;
+mod autocomplete;
+mod budget;
+mod cache;
+mod cached_summary;
+mod config;
+mod cost;
+mod crate_name;
+mod datetime;
+#[cfg(feature = "db-dump")]
+pub mod db_dump;
+#[cfg(feature = "docsrs")]
+pub mod docsrs;
+pub mod endpoints;
 mod error;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "faults")]
+pub mod faults;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+mod graph;
+#[cfg(feature = "source")]
+mod gzip;
+mod limiter_state;
+#[cfg(feature = "lockfile")]
+pub mod lockfile;
+pub mod mirror;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod query;
+#[cfg(feature = "redis")]
+pub mod redis;
+mod report;
+mod request_lock;
+#[cfg(feature = "semver")]
+pub mod semver;
+#[cfg(feature = "tower")]
+pub mod service;
+mod smart_client;
+mod snapshot;
+#[cfg(feature = "source")]
+pub mod source;
+pub mod sparse_index;
+#[cfg(feature = "spdx")]
+pub mod spdx;
+#[cfg(feature = "stream-decode")]
+mod stream_decode;
 mod sync_client;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod types;
+pub mod watcher;
 
+#[cfg(feature = "source")]
+pub use crate::error::InvalidArchiveError;
+#[cfg(feature = "spdx")]
+pub use crate::error::InvalidLicenseError;
+#[cfg(feature = "semver")]
+pub use crate::error::InvalidVersionError;
+#[cfg(feature = "fixtures")]
+pub use crate::error::MissingFixtureError;
 pub use crate::{
-    async_client::Client as AsyncClient,
-    error::{Error, NotFoundError, PermissionDeniedError},
-    sync_client::SyncClient,
+    api::{CratesIoApi, SyncCratesIoApi},
+    async_client::{Client as AsyncClient, CrateStream},
+    budget::Budget,
+    cache::{CacheStore, MemoryCache},
+    cached_summary::CachedSummary,
+    config::ClientConfig,
+    cost::{CostEstimate, Operation},
+    crate_name::CrateName,
+    datetime::{Date, Timestamp},
+    error::{
+        BudgetExhaustedError, CancelledError, Error, InvalidConfigError, InvalidCrateNameError,
+        InvalidDateError, InvalidQueryError, JsonDecodeError, NotFoundError, PermissionDeniedError,
+    },
+    graph::{
+        DependencyEdge, DependencyFilter, DependencyGraph, DependencyTree, GraphLimits,
+        ResolvedDependency,
+    },
+    limiter_state::LimiterState,
+    query::Query,
+    report::{CrateReport, MaintenanceMetrics},
+    smart_client::{SmartClient, VersionInfo},
+    snapshot::{CategorySnapshot, SnapshotEntry, SnapshotOptions},
+    sync_client::{CratesIter, PrefetchHandle, ReverseDependenciesIter, SyncClient, VersionsIter},
     types::*,
 };
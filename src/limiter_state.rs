@@ -0,0 +1,254 @@
+use serde_derive::*;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::Budget;
+
+/// Rate limiter and budget state saved to disk.
+///
+/// Every client starts with a clean slate, so short-lived processes that are
+/// started often would together exceed the rate limit. Attach the same state
+/// file to their clients with `with_limiter_state()` or
+/// [`ClientConfig::limiter_state`] to make them wait for the requests of
+/// previous runs, and continue their [`Budget`]:
+///
+/// ```rust,no_run
+/// use crates_io_api::{LimiterState, SyncClient};
+/// use std::sync::Arc;
+///
+/// let state = Arc::new(LimiterState::open("/tmp/my_bot_limiter.json").unwrap());
+/// let client = SyncClient::new("my_bot (help@my_bot.com)", std::time::Duration::from_secs(1))
+///     .unwrap()
+///     .with_limiter_state(state);
+/// ```
+///
+/// The file is read once when opened, and written at most once per
+/// [`save_interval`](Self::save_interval) and when the state is dropped. It
+/// doesn't coordinate processes running at the same time.
+///
+/// [`ClientConfig::limiter_state`]: crate::ClientConfig::limiter_state
+#[derive(Debug)]
+pub struct LimiterState {
+    path: PathBuf,
+    save_interval: Duration,
+    stored: Mutex<Stored>,
+    saving: Mutex<Saving>,
+}
+
+/// When the state was last written, and whether it changed since.
+#[derive(Debug, Default)]
+struct Saving {
+    saved_at: Option<Instant>,
+    dirty: bool,
+}
+
+/// The file contents, with times in milliseconds since the Unix epoch.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+struct Stored {
+    last_request: Option<u64>,
+    budget_used: u64,
+    budget_recent: Vec<u64>,
+}
+
+impl LimiterState {
+    /// Open the state file at `path`.
+    ///
+    /// A missing file is treated as a clean slate, and created on the first
+    /// request.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let stored = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Stored::default(),
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            path,
+            save_interval: Self::DEFAULT_SAVE_INTERVAL,
+            stored: Mutex::new(stored),
+            saving: Mutex::new(Saving::default()),
+        })
+    }
+
+    /// The default of [`save_interval`](Self::save_interval).
+    pub const DEFAULT_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Write the state at most once per `interval`.
+    ///
+    /// Requests in between are saved with the next write, or when the state
+    /// is dropped, so a process that is killed can lose them.
+    #[must_use]
+    pub fn save_interval(mut self, interval: Duration) -> Self {
+        self.save_interval = interval;
+        self
+    }
+
+    /// Get the path of the state file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the state to the file.
+    pub fn save(&self) -> io::Result<()> {
+        *self.saving.lock().unwrap() = Saving {
+            saved_at: Some(Instant::now()),
+            dirty: false,
+        };
+        let stored = self.stored.lock().unwrap().clone();
+        let content = serde_json::to_string(&stored)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        std::fs::write(&self.path, content)
+    }
+
+    /// Save the state, logging failures.
+    pub(crate) fn save_logged(&self) {
+        if let Err(err) = self.save() {
+            log::warn!(
+                "Could not save limiter state to {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+
+    /// Get how long to wait until the rate limit allows the next request.
+    pub(crate) fn wait_time(&self, rate_limit: Duration) -> Duration {
+        let last_request = match self.stored.lock().unwrap().last_request {
+            Some(last_request) => UNIX_EPOCH + Duration::from_millis(last_request),
+            None => return Duration::ZERO,
+        };
+        let elapsed = SystemTime::now()
+            .duration_since(last_request)
+            .unwrap_or_default();
+        rate_limit.saturating_sub(elapsed)
+    }
+
+    /// Record a request, with the state of the budget after it.
+    ///
+    /// Returns whether the state should be saved, which the caller does with
+    /// [`save_logged`](Self::save_logged) off the request path.
+    pub(crate) fn record_request(&self, budget: Option<&Budget>) -> bool {
+        {
+            let mut stored = self.stored.lock().unwrap();
+            stored.last_request = Some(to_millis(SystemTime::now()));
+            if let Some(budget) = budget {
+                let (used, recent) = budget.snapshot();
+                stored.budget_used = used;
+                stored.budget_recent = recent
+                    .into_iter()
+                    .map(to_system_time)
+                    .map(to_millis)
+                    .collect();
+            }
+        }
+        let mut saving = self.saving.lock().unwrap();
+        let due = saving
+            .saved_at
+            .map_or(true, |saved_at| saved_at.elapsed() >= self.save_interval);
+        if due {
+            // Taken by the caller, so that other requests don't save as well.
+            saving.saved_at = Some(Instant::now());
+        } else {
+            saving.dirty = true;
+        }
+        due
+    }
+
+    /// Continue a budget from the saved counters.
+    pub(crate) fn restore_budget(&self, budget: &Budget) {
+        let stored = self.stored.lock().unwrap();
+        let recent = stored
+            .budget_recent
+            .iter()
+            .filter_map(|&millis| to_instant(UNIX_EPOCH + Duration::from_millis(millis)))
+            .collect();
+        budget.restore(stored.budget_used, recent);
+    }
+}
+
+impl Drop for LimiterState {
+    fn drop(&mut self) {
+        if self.saving.get_mut().unwrap().dirty {
+            self.save_logged();
+        }
+    }
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn to_system_time(instant: Instant) -> SystemTime {
+    SystemTime::now() - instant.elapsed()
+}
+
+/// Convert a time to an instant, unless it's too far in the past to be
+/// represented.
+fn to_instant(time: SystemTime) -> Option<Instant> {
+    let ago = SystemTime::now().duration_since(time).unwrap_or_default();
+    Instant::now().checked_sub(ago)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_limiter_state() {
+        let path =
+            std::env::temp_dir().join(format!("crates_io_api_limiter_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let state = LimiterState::open(&path).unwrap();
+        assert_eq!(state.wait_time(Duration::from_secs(60)), Duration::ZERO);
+        let budget = Budget::new().max_requests(2).max_requests_per_hour(5);
+        budget.acquire().unwrap();
+        assert!(state.record_request(Some(&budget)));
+        state.save_logged();
+
+        // A new process continues where the previous one stopped.
+        let state = LimiterState::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let wait = state.wait_time(Duration::from_secs(60));
+        assert!(wait > Duration::from_secs(50), "{:?}", wait);
+        let budget = Budget::new().max_requests(2).max_requests_per_hour(5);
+        state.restore_budget(&budget);
+        assert_eq!(budget.used(), 1);
+        assert!(budget.acquire().is_ok());
+        assert!(budget.acquire().is_err());
+        assert!(LimiterState::open(std::env::temp_dir()).is_err());
+    }
+
+    #[test]
+    fn test_limiter_state_save_interval() {
+        let path = std::env::temp_dir().join(format!(
+            "crates_io_api_limiter_interval_{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let state = LimiterState::open(&path)
+            .unwrap()
+            .save_interval(Duration::from_secs(3600));
+        let budget = Budget::new();
+        budget.acquire().unwrap();
+        assert!(state.record_request(Some(&budget)));
+        state.save_logged();
+        budget.acquire().unwrap();
+        assert!(!state.record_request(Some(&budget)));
+        let saved = LimiterState::open(&path).unwrap();
+        assert_eq!(saved.stored.lock().unwrap().budget_used, 1);
+        drop(saved);
+
+        // The pending request is saved on drop.
+        drop(state);
+        let saved = LimiterState::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.stored.lock().unwrap().budget_used, 2);
+    }
+}
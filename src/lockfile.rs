@@ -0,0 +1,436 @@
+//! Checking `Cargo.lock` files against crates.io.
+//!
+//! Requires the `lockfile` feature.
+//!
+//! ```rust
+//! use crates_io_api::lockfile::Lockfile;
+//!
+//! let lockfile = Lockfile::parse(r#"
+//! [[package]]
+//! name = "serde"
+//! version = "1.0.136"
+//! source = "registry+https://github.com/rust-lang/crates.io-index"
+//! "#);
+//! assert_eq!(lockfile.packages[0].name, "serde");
+//! ```
+//!
+//! The output of `cargo metadata --format-version 1` can be read with
+//! [`Lockfile::from_cargo_metadata`] instead, eg for workspaces without a
+//! lockfile in version control.
+
+use serde_derive::*;
+use std::collections::BTreeMap;
+
+use crate::{CrateResponse, Error, User};
+
+const CRATES_IO_SOURCES: [&str; 2] = [
+    "registry+https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+/// The packages of a `Cargo.lock` file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Lockfile {
+    /// All locked packages, in file order.
+    pub packages: Vec<LockedPackage>,
+}
+
+/// A package locked to an exact version.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LockedPackage {
+    /// The package name.
+    pub name: String,
+    /// The locked version.
+    pub version: String,
+    /// The package source.
+    ///
+    /// `None` for path dependencies, including the workspace members.
+    pub source: Option<String>,
+}
+
+impl LockedPackage {
+    /// Check whether the package is published on crates.io.
+    pub fn is_crates_io(&self) -> bool {
+        self.source
+            .as_deref()
+            .is_some_and(|source| CRATES_IO_SOURCES.contains(&source))
+    }
+}
+
+impl Lockfile {
+    /// Parse the contents of a `Cargo.lock` file.
+    ///
+    /// Only the `[[package]]` tables are read. Packages without a name or a
+    /// version are skipped.
+    pub fn parse(contents: &str) -> Self {
+        let mut packages = Vec::new();
+        let mut current: Option<(Option<String>, Option<String>, Option<String>)> = None;
+
+        let mut flush = |current: &mut Option<_>| {
+            if let Some((Some(name), Some(version), source)) = current.take() {
+                packages.push(LockedPackage {
+                    name,
+                    version,
+                    source,
+                });
+            }
+        };
+
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') && !line.ends_with(',') {
+                flush(&mut current);
+                if line == "[[package]]" {
+                    current = Some((None, None, None));
+                }
+                continue;
+            }
+            let (package, (key, value)) = match (current.as_mut(), line.split_once('=')) {
+                (Some(package), Some(pair)) => (package, pair),
+                _ => continue,
+            };
+            let value = match value
+                .trim()
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+            {
+                Some(value) => value.to_string(),
+                None => continue,
+            };
+            match key.trim() {
+                "name" => package.0 = Some(value),
+                "version" => package.1 = Some(value),
+                "source" => package.2 = Some(value),
+                _ => {}
+            }
+        }
+        flush(&mut current);
+
+        Self { packages }
+    }
+
+    /// Read the packages from the JSON output of
+    /// `cargo metadata --format-version 1`.
+    ///
+    /// All other fields of the output are ignored.
+    pub fn from_cargo_metadata(json: &str) -> Result<Self, serde_json::Error> {
+        #[derive(Deserialize)]
+        struct Metadata {
+            packages: Vec<LockedPackage>,
+        }
+
+        let metadata: Metadata = serde_json::from_str(json)?;
+        Ok(Self {
+            packages: metadata.packages,
+        })
+    }
+
+    /// Group the crates.io packages by crate name.
+    pub(crate) fn crates_io_packages(&self) -> BTreeMap<&str, Vec<&LockedPackage>> {
+        let mut crates = BTreeMap::<_, Vec<_>>::new();
+        for package in self.packages.iter().filter(|p| p.is_crates_io()) {
+            crates
+                .entry(package.name.as_str())
+                .or_default()
+                .push(package);
+        }
+        crates
+    }
+}
+
+/// The result of checking a [`Lockfile`] for yanked versions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LockfileAudit {
+    /// Packages locked to a yanked version.
+    pub yanked: Vec<LockedPackage>,
+    /// Packages whose crate or version does not exist on crates.io.
+    pub not_found: Vec<LockedPackage>,
+}
+
+impl LockfileAudit {
+    /// Check whether no problems were found.
+    pub fn is_clean(&self) -> bool {
+        self.yanked.is_empty() && self.not_found.is_empty()
+    }
+
+    /// Check the locked versions of a crate against the crate response.
+    pub(crate) fn add(
+        &mut self,
+        packages: &[&LockedPackage],
+        res: Result<CrateResponse, Error>,
+    ) -> Result<(), Error> {
+        let krate = match res {
+            Ok(krate) => krate,
+            Err(Error::NotFound(_)) => {
+                self.not_found.extend(packages.iter().map(|p| (*p).clone()));
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        for package in packages {
+            match krate.versions.iter().find(|v| v.num == package.version) {
+                Some(version) if version.yanked => self.yanked.push((*package).clone()),
+                Some(_) => {}
+                None => self.not_found.push((*package).clone()),
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> Self {
+        self.yanked.sort();
+        self.not_found.sort();
+        self
+    }
+}
+
+/// Live crates.io metadata of the packages of a [`Lockfile`], see
+/// `enrich_lockfile()` on the clients.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LockfileReport {
+    /// The packages found on crates.io, sorted by name and version.
+    pub packages: Vec<EnrichedPackage>,
+    /// Packages whose crate or version does not exist on crates.io.
+    pub not_found: Vec<LockedPackage>,
+}
+
+/// A locked package with the metadata of its crate.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct EnrichedPackage {
+    /// The locked package.
+    pub package: LockedPackage,
+    /// Whether the locked version is yanked.
+    pub yanked: bool,
+    /// The highest stable version of the crate, or the highest version if
+    /// there is no stable one.
+    pub latest_version: String,
+    /// Downloads of the crate of all time.
+    pub downloads: u64,
+    /// Downloads of the crate in the last 90 days.
+    pub recent_downloads: Option<u64>,
+    /// The owners of the crate.
+    pub owners: Vec<User>,
+}
+
+impl EnrichedPackage {
+    /// Check whether the locked version is older than the latest version.
+    ///
+    /// Versions that are not valid semver are never outdated.
+    pub fn is_outdated(&self) -> bool {
+        use crate::semver::parse_version;
+        match (
+            parse_version(&self.package.version),
+            parse_version(&self.latest_version),
+        ) {
+            (Ok(locked), Ok(latest)) => locked < latest,
+            _ => false,
+        }
+    }
+}
+
+impl LockfileReport {
+    /// Add the locked versions of a crate with the crate response and the
+    /// owners of the crate.
+    ///
+    /// `owners` is `None` if they weren't fetched, because the crate doesn't
+    /// exist.
+    pub(crate) fn add(
+        &mut self,
+        packages: &[&LockedPackage],
+        res: Result<CrateResponse, Error>,
+        owners: Option<Result<Vec<User>, Error>>,
+    ) -> Result<(), Error> {
+        let krate = match res {
+            Ok(krate) => krate,
+            Err(Error::NotFound(_)) => {
+                self.not_found.extend(packages.iter().map(|p| (*p).clone()));
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        let owners = owners.transpose()?.unwrap_or_default();
+        let data = &krate.crate_data;
+        for package in packages {
+            match krate.versions.iter().find(|v| v.num == package.version) {
+                Some(version) => self.packages.push(EnrichedPackage {
+                    package: (*package).clone(),
+                    yanked: version.yanked,
+                    latest_version: data
+                        .max_stable_version
+                        .clone()
+                        .unwrap_or_else(|| data.max_version.clone()),
+                    downloads: data.downloads,
+                    recent_downloads: data.recent_downloads,
+                    owners: owners.clone(),
+                }),
+                None => self.not_found.push((*package).clone()),
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> Self {
+        self.packages.sort_by(|a, b| a.package.cmp(&b.package));
+        self.not_found.sort();
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Crate, NotFoundError, Version};
+
+    const LOCKFILE: &str = r#"
+# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "log",
+ "serde 1.0.136",
+]
+
+[[package]]
+name = "log"
+version = "0.4.14"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "51b9bbe6c47d51fc3e1a9b945965946b4c44142ab8792c50835a980d362c2710"
+
+[[package]]
+name = "serde"
+version = "1.0.136"
+source = "sparse+https://index.crates.io/"
+
+[[package]]
+name = "serde"
+version = "0.9.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "forked"
+version = "1.0.0"
+source = "git+https://github.com/example/forked#abcdef"
+
+[metadata]
+"checksum log 0.4.14" = "51b9"
+"#;
+
+    fn crate_response(name: &str, versions: &[(&str, bool)]) -> CrateResponse {
+        let versions = versions
+            .iter()
+            .enumerate()
+            .map(|(id, (num, yanked))| {
+                let mut version = Version::new(name, num);
+                version.id = id as u64;
+                version.yanked = *yanked;
+                version
+            })
+            .collect();
+        CrateResponse::new(Crate::new(name, "1.0.0"), versions)
+    }
+
+    #[test]
+    fn test_parse_lockfile() {
+        let lockfile = Lockfile::parse(LOCKFILE);
+        let packages: Vec<_> = lockfile
+            .packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str(), p.is_crates_io()))
+            .collect();
+        assert_eq!(
+            packages,
+            [
+                ("app", "0.1.0", false),
+                ("log", "0.4.14", true),
+                ("serde", "1.0.136", true),
+                ("serde", "0.9.0", true),
+                ("forked", "1.0.0", false),
+            ]
+        );
+
+        let crates = lockfile.crates_io_packages();
+        assert_eq!(crates.keys().copied().collect::<Vec<_>>(), ["log", "serde"]);
+        assert_eq!(crates["serde"].len(), 2);
+    }
+
+    #[test]
+    fn test_lockfile_from_cargo_metadata() {
+        let json = serde_json::json!({
+            "packages": [
+                {"name": "app", "version": "0.1.0", "source": null, "id": "app 0.1.0"},
+                {
+                    "name": "log",
+                    "version": "0.4.14",
+                    "source": "registry+https://github.com/rust-lang/crates.io-index",
+                    "dependencies": [],
+                },
+            ],
+            "workspace_members": ["app 0.1.0"],
+            "version": 1,
+        });
+        let lockfile = Lockfile::from_cargo_metadata(&json.to_string()).unwrap();
+        let crates = lockfile.crates_io_packages();
+        assert_eq!(crates.keys().copied().collect::<Vec<_>>(), ["log"]);
+        assert!(Lockfile::from_cargo_metadata("{}").is_err());
+    }
+
+    #[test]
+    fn test_lockfile_report() {
+        let lockfile = Lockfile::parse(LOCKFILE);
+        let crates = lockfile.crates_io_packages();
+        let mut report = LockfileReport::default();
+
+        let not_found = Error::NotFound(NotFoundError {
+            url: "https://crates.io/api/v1/crates/log".into(),
+        });
+        report.add(&crates["log"], Err(not_found), None).unwrap();
+        let serde = crate_response("serde", &[("1.0.136", true), ("0.9.0", false)]);
+        report
+            .add(
+                &crates["serde"],
+                Ok(serde),
+                Some(Ok(vec![User::new(1, "dtolnay")])),
+            )
+            .unwrap();
+
+        let report = report.finish();
+        assert_eq!(report.not_found.len(), 1);
+        let packages: Vec<_> = report
+            .packages
+            .iter()
+            .map(|p| (p.package.version.as_str(), p.yanked, p.is_outdated()))
+            .collect();
+        assert_eq!(packages, [("0.9.0", false, true), ("1.0.136", true, false)]);
+        assert_eq!(report.packages[0].latest_version, "1.0.0");
+        assert_eq!(report.packages[0].owners[0].login, "dtolnay");
+    }
+
+    #[test]
+    fn test_lockfile_audit() {
+        let lockfile = Lockfile::parse(LOCKFILE);
+        let crates = lockfile.crates_io_packages();
+        let mut audit = LockfileAudit::default();
+
+        let not_found = Error::NotFound(NotFoundError {
+            url: "https://crates.io/api/v1/crates/log".into(),
+        });
+        audit.add(&crates["log"], Err(not_found)).unwrap();
+        let serde = crate_response("serde", &[("1.0.136", true), ("1.0.0", false)]);
+        audit.add(&crates["serde"], Ok(serde)).unwrap();
+
+        let audit = audit.finish();
+        assert!(!audit.is_clean());
+        let names = |packages: &[LockedPackage]| -> Vec<String> {
+            packages
+                .iter()
+                .map(|p| format!("{} {}", p.name, p.version))
+                .collect()
+        };
+        assert_eq!(names(&audit.yanked), ["serde 1.0.136"]);
+        assert_eq!(names(&audit.not_found), ["log 0.4.14", "serde 0.9.0"]);
+    }
+}
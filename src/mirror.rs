@@ -0,0 +1,748 @@
+//! A local copy of crate metadata, kept up to date incrementally.
+//!
+//! A [`Mirror`] stores the [`CrateResponse`] of every crate in its
+//! [`MirrorScope`] in a [`CacheStore`]. The first sync fetches all of them,
+//! later syncs only fetch the crates that were updated since, by walking the
+//! recently updated crates until one that is already known:
+//!
+//! ```rust,no_run
+//! # use crates_io_api::{AsyncClient, Error, MemoryCache};
+//! # use crates_io_api::mirror::{Mirror, MirrorScope};
+//! # use futures::prelude::*;
+//! # use std::sync::Arc;
+//! # async fn run(client: AsyncClient) -> Result<(), Error> {
+//! let store = Arc::new(MemoryCache::new());
+//! let mut mirror = Mirror::new(client, MirrorScope::Category("parsing".to_string()), store);
+//! mirror.sync().await?;
+//! let serde = mirror.get("nom");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The state of the mirror is kept in the store as well, so a mirror with a
+//! persistent store resumes with an incremental sync after a restart.
+//!
+//! Crates that are deleted or renamed, or that leave a category or keyword,
+//! are dropped from the mirror. To notice the latter, every sync also lists
+//! the crates of the scope, with one request per 100 crates.
+//!
+//! Every sync compares the fetched crates with their stored copies, and
+//! reports the differences as [`ChangeEvent`]s. The stream of
+//! [`Mirror::into_change_stream`] only contains those:
+//!
+//! ```rust,no_run
+//! # use crates_io_api::{AsyncClient, CacheStore, Error};
+//! # use crates_io_api::mirror::{Mirror, MirrorScope};
+//! # use futures::prelude::*;
+//! # use std::sync::Arc;
+//! # async fn run(client: AsyncClient, store: Arc<dyn CacheStore>) -> Result<(), Error> {
+//! let scope = MirrorScope::Keyword("cli".to_string());
+//! let mut changes = Mirror::new(client, scope, store)
+//!     .owners(true)
+//!     .into_change_stream(std::time::Duration::from_secs(600));
+//! while let Some(change) = changes.next().await {
+//!     println!("{:?}", change?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::prelude::*;
+use futures::stream::BoxStream;
+use serde_derive::*;
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::watcher::{CrateState, WatchEvent};
+use crate::{AsyncClient, CacheStore, CrateResponse, CratesQuery, Error, Sort, Timestamp};
+
+/// The crates kept by a [`Mirror`].
+#[derive(Debug, Clone)]
+pub enum MirrorScope {
+    /// A fixed list of crates.
+    Crates(Vec<String>),
+    /// All crates in a category, identified by its slug.
+    Category(String),
+    /// All crates with a keyword.
+    Keyword(String),
+}
+
+impl MirrorScope {
+    /// Build a query for all crates in the scope.
+    fn query(&self, sort: Sort) -> CratesQuery {
+        let builder = CratesQuery::builder()
+            .sort(sort)
+            .page_size(CratesQuery::MAX_PAGE_SIZE);
+        match self {
+            Self::Crates(names) => names.iter().fold(builder, |b, name| b.id(name)),
+            Self::Category(slug) => builder.category(slug),
+            Self::Keyword(keyword) => builder.keyword(keyword),
+        }
+        .build()
+    }
+}
+
+/// A difference between the stored and the fetched copy of a crate.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ChangeEvent {
+    /// A crate entered the scope after the first sync.
+    Added {
+        /// The crate name.
+        crate_name: String,
+    },
+    /// A crate left the scope, or was deleted or renamed, and was dropped
+    /// from the mirror.
+    Removed {
+        /// The crate name.
+        crate_name: String,
+    },
+    /// A new version, a yank or an owner change, like a
+    /// [`Watcher`](crate::watcher::Watcher) reports it.
+    Release(WatchEvent),
+    /// The description of a crate changed.
+    DescriptionChanged {
+        /// The crate name.
+        crate_name: String,
+        /// The previous description.
+        old: Option<String>,
+        /// The new description.
+        new: Option<String>,
+    },
+}
+
+/// The outcome of a [`Mirror::sync`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SyncReport {
+    /// The names of the crates that were fetched.
+    ///
+    /// Crates that were not found are left out, see [`ChangeEvent::Removed`].
+    pub updated: Vec<String>,
+    /// Whether all crates were fetched, instead of only the updated ones.
+    pub backfill: bool,
+    /// The differences to the stored copies of the fetched crates, and the
+    /// crates that were dropped.
+    ///
+    /// Always empty for the first sync.
+    pub changes: Vec<ChangeEvent>,
+}
+
+/// The state of a mirror, stored next to the crates.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct MirrorState {
+    crates: BTreeSet<String>,
+    /// The latest update time of all mirrored crates.
+    synced_until: Option<Timestamp>,
+}
+
+/// The stored copy of a crate and of its owners, if any.
+type StoredCrate = (Option<CrateResponse>, Option<BTreeSet<String>>);
+
+/// A local copy of the metadata of a set of crates.
+///
+/// See the [module documentation](self).
+pub struct Mirror {
+    client: AsyncClient,
+    scope: MirrorScope,
+    store: Arc<dyn CacheStore>,
+    prefix: String,
+    owners: bool,
+    state: MirrorState,
+    /// Whether `state` was loaded from the store.
+    loaded: bool,
+}
+
+impl Mirror {
+    /// Construct a mirror, resuming from the state in `store` if there is
+    /// one.
+    ///
+    /// The state is loaded by the first [`Self::sync`], so the store isn't
+    /// called here.
+    pub fn new(client: AsyncClient, scope: MirrorScope, store: Arc<dyn CacheStore>) -> Self {
+        Self {
+            client,
+            scope,
+            store,
+            prefix: "mirror".to_string(),
+            owners: false,
+            state: MirrorState::default(),
+            loaded: false,
+        }
+    }
+
+    /// Set the prefix of the keys in the store, so that multiple mirrors can
+    /// share one store.
+    ///
+    /// Defaults to `mirror`.
+    #[must_use]
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self.state = MirrorState::default();
+        self.loaded = false;
+        self
+    }
+
+    /// Mirror the owners of every crate as well, so that owner changes are
+    /// reported.
+    ///
+    /// This takes one more request per fetched crate. Owner changes don't
+    /// count as updates on crates.io, so they are only noticed once a crate
+    /// is fetched for another reason.
+    #[must_use]
+    pub fn owners(mut self, mirror: bool) -> Self {
+        self.owners = mirror;
+        self
+    }
+
+    /// Load the state from the store, unless it was loaded before.
+    async fn load_state(&mut self) {
+        if self.loaded {
+            return;
+        }
+        let key = self.state_key();
+        self.state = crate::cache::call(&self.store, move |store| store.get(&key))
+            .await
+            .and_then(|state| serde_json::from_str(&state).ok())
+            .unwrap_or_default();
+        self.loaded = true;
+    }
+
+    fn state_key(&self) -> String {
+        format!("{}/state", self.prefix)
+    }
+
+    fn crate_key(&self, crate_name: &str) -> String {
+        format!("{}/crates/{}", self.prefix, crate_name)
+    }
+
+    fn owners_key(&self, crate_name: &str) -> String {
+        format!("{}/owners/{}", self.prefix, crate_name)
+    }
+
+    /// Get the names of all mirrored crates.
+    ///
+    /// Empty until the first [`Self::sync`] loaded the state of the mirror.
+    pub fn crate_names(&self) -> impl Iterator<Item = &str> {
+        self.state.crates.iter().map(String::as_str)
+    }
+
+    /// Get the latest update time of all mirrored crates, or `None` before
+    /// the first sync of this mirror.
+    pub fn synced_until(&self) -> Option<Timestamp> {
+        self.state.synced_until
+    }
+
+    /// Get the mirrored copy of a crate.
+    ///
+    /// Returns `None` for crates that were dropped from the mirror, even if
+    /// the store still has their copy, and before the first [`Self::sync`].
+    ///
+    /// The store is called directly, so with a blocking store, like
+    /// `RedisStore`, this blocks on I/O. Call it off the async runtime then,
+    /// eg with `tokio::task::block_in_place` on a multi-threaded runtime.
+    pub fn get(&self, crate_name: &str) -> Option<CrateResponse> {
+        if !self.state.crates.contains(crate_name) {
+            return None;
+        }
+        let content = self.store.get(&self.crate_key(crate_name))?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Get the logins of the mirrored owners of a crate, if owners are
+    /// mirrored.
+    ///
+    /// Like [`Self::get`], this blocks on I/O with a blocking store.
+    pub fn get_owners(&self, crate_name: &str) -> Option<BTreeSet<String>> {
+        if !self.state.crates.contains(crate_name) {
+            return None;
+        }
+        let content = self.store.get(&self.owners_key(crate_name))?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Fetch all crates in the scope on the first sync, and afterwards only
+    /// the crates that were updated since the previous sync.
+    ///
+    /// Nothing is stored unless all crates were fetched, so a failed sync is
+    /// repeated by the next one, which reports the same changes.
+    pub async fn sync(&mut self) -> Result<SyncReport, Error> {
+        self.load_state().await;
+        let backfill = self.state.synced_until.is_none();
+        let mut state = self.state.clone();
+        let mut changes = Vec::new();
+        let names = match self.state.synced_until {
+            None => self.all_names().await?,
+            Some(synced_until) => {
+                let listed: BTreeSet<_> = self.all_names().await?.into_iter().collect();
+                for name in state.crates.difference(&listed) {
+                    changes.push(ChangeEvent::Removed {
+                        crate_name: name.clone(),
+                    });
+                }
+                state.crates.retain(|name| listed.contains(name));
+                self.updated_names(synced_until).await?
+            }
+        };
+
+        let mut stored = if backfill {
+            Vec::new()
+        } else {
+            self.stored(&names).await
+        }
+        .into_iter();
+
+        let mut updated = Vec::new();
+        let mut writes = Vec::new();
+        for name in names {
+            let old = stored.next();
+            let (krate, owners) = match self.fetch(&name).await {
+                Ok(fetched) => fetched,
+                // Deleted or renamed since it was listed, like a crawl skips
+                // it.
+                Err(Error::NotFound(_)) => {
+                    if state.crates.remove(&name) {
+                        changes.push(ChangeEvent::Removed { crate_name: name });
+                    }
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            if let Some((old, old_owners)) = old {
+                // A crate that re-enters the scope is added again.
+                let old = old.filter(|_| self.state.crates.contains(&name));
+                changes.extend(Self::changes(
+                    &name,
+                    (old, old_owners),
+                    &krate,
+                    owners.clone(),
+                ));
+            }
+
+            state.synced_until = state.synced_until.max(Some(krate.crate_data.updated_at));
+            writes.push((
+                self.crate_key(&name),
+                serde_json::to_string(&krate).unwrap(),
+            ));
+            if let Some(owners) = owners {
+                writes.push((
+                    self.owners_key(&name),
+                    serde_json::to_string(&owners).unwrap(),
+                ));
+            }
+            state.crates.insert(name.clone());
+            updated.push(name);
+        }
+        // An empty scope is synced as well.
+        state.synced_until = state.synced_until.or(Some(Timestamp::UNIX_EPOCH));
+
+        // The state is written last, so that an interrupted write is repeated
+        // by the next sync.
+        writes.push((self.state_key(), serde_json::to_string(&state).unwrap()));
+        crate::cache::call(&self.store, move |store| {
+            for (key, value) in writes {
+                store.put(&key, value, Duration::MAX);
+            }
+        })
+        .await;
+        self.state = state;
+        Ok(SyncReport {
+            updated,
+            backfill,
+            changes,
+        })
+    }
+
+    /// Fetch a crate and, if owners are mirrored, its owners.
+    async fn fetch(
+        &self,
+        crate_name: &str,
+    ) -> Result<(CrateResponse, Option<BTreeSet<String>>), Error> {
+        let krate = self.client.get_crate(crate_name).await?;
+        let owners = if self.owners {
+            let owners = self.client.crate_owners(crate_name).await?;
+            Some(owners.into_iter().map(|user| user.login).collect())
+        } else {
+            None
+        };
+        Ok((krate, owners))
+    }
+
+    /// Read the stored copies of crates and, if owners are mirrored, of
+    /// their owners.
+    ///
+    /// The store is called once for all of them, as it may block.
+    async fn stored(&self, names: &[String]) -> Vec<StoredCrate> {
+        let keys: Vec<_> = names
+            .iter()
+            .map(|name| {
+                let owners_key = self.owners.then(|| self.owners_key(name));
+                (self.crate_key(name), owners_key)
+            })
+            .collect();
+        crate::cache::call(&self.store, move |store| {
+            keys.into_iter()
+                .map(|(crate_key, owners_key)| {
+                    let krate = store
+                        .get(&crate_key)
+                        .and_then(|content| serde_json::from_str(&content).ok());
+                    let owners = owners_key
+                        .and_then(|key| store.get(&key))
+                        .and_then(|content| serde_json::from_str(&content).ok());
+                    (krate, owners)
+                })
+                .collect()
+        })
+        .await
+    }
+
+    /// Compare a fetched crate with its stored copy.
+    fn changes(
+        crate_name: &str,
+        (old, old_owners): StoredCrate,
+        krate: &CrateResponse,
+        owners: Option<BTreeSet<String>>,
+    ) -> Vec<ChangeEvent> {
+        let old = match old {
+            Some(old) => old,
+            None => {
+                return vec![ChangeEvent::Added {
+                    crate_name: crate_name.to_string(),
+                }]
+            }
+        };
+        let mut changes: Vec<_> = CrateState::new(&old, old_owners)
+            .diff(crate_name, &CrateState::new(krate, owners))
+            .into_iter()
+            .map(ChangeEvent::Release)
+            .collect();
+        if old.crate_data.description != krate.crate_data.description {
+            changes.push(ChangeEvent::DescriptionChanged {
+                crate_name: crate_name.to_string(),
+                old: old.crate_data.description,
+                new: krate.crate_data.description.clone(),
+            });
+        }
+        changes
+    }
+
+    async fn all_names(&self) -> Result<Vec<String>, Error> {
+        match &self.scope {
+            MirrorScope::Crates(names) => Ok(names.clone()),
+            scope => {
+                self.client
+                    .crates_stream(scope.query(Sort::Alphabetical))
+                    .map_ok(|krate| krate.name)
+                    .try_collect()
+                    .await
+            }
+        }
+    }
+
+    /// Walk the recently updated crates of the scope until one that wasn't
+    /// updated since `synced_until`.
+    async fn updated_names(&self, synced_until: Timestamp) -> Result<Vec<String>, Error> {
+        let mut seen = BTreeSet::new();
+        self.client
+            .crates_stream(self.scope.query(Sort::RecentUpdates))
+            .try_take_while(|krate| future::ready(Ok(krate.updated_at > synced_until)))
+            .try_filter_map(|krate| {
+                future::ready(Ok(seen.insert(krate.name.clone()).then_some(krate.name)))
+            })
+            .try_collect()
+            .await
+    }
+
+    /// Convert the mirror into an endless stream of sync reports, with a
+    /// pause of `interval` between syncs.
+    ///
+    /// The first sync happens right away.
+    pub fn into_stream(self, interval: Duration) -> BoxStream<'static, Result<SyncReport, Error>> {
+        stream::unfold((self, true), move |(mut mirror, first)| async move {
+            if !first {
+                tokio::time::sleep(interval).await;
+            }
+            let report = mirror.sync().await;
+            Some((report, (mirror, false)))
+        })
+        .boxed()
+    }
+
+    /// Convert the mirror into an endless stream of changes, with a pause of
+    /// `interval` between syncs.
+    ///
+    /// The first sync happens right away, and only establishes a baseline if
+    /// nothing was mirrored yet.
+    pub fn into_change_stream(
+        self,
+        interval: Duration,
+    ) -> BoxStream<'static, Result<ChangeEvent, Error>> {
+        stream::unfold(
+            (self.into_stream(interval), VecDeque::new()),
+            |(mut reports, mut pending)| async move {
+                while pending.is_empty() {
+                    match reports.next().await? {
+                        Ok(report) => pending.extend(report.changes.into_iter().map(Ok)),
+                        Err(err) => pending.push_back(Err(err)),
+                    }
+                }
+                let change = pending.pop_front()?;
+                Some((change, (reports, pending)))
+            },
+        )
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockServer;
+    use crate::{Category, CategoryResponse, Crate, CratesPage, Version};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_mirror_scope_query() {
+        let query = MirrorScope::Crates(vec!["a".into(), "b".into()]).query(Sort::RecentUpdates);
+        assert_eq!(query.ids(), ["a", "b"]);
+        let query = MirrorScope::Keyword("cli".into()).query(Sort::Alphabetical);
+        assert_eq!(query.keywords(), ["cli"]);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_mirror() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["1.0.0"])
+            .with_crate("bar", &["1.0.0"]);
+        let store = Arc::new(crate::MemoryCache::new());
+        let scope = MirrorScope::Crates(vec!["foo".to_string(), "bar".to_string()]);
+        let mut mirror = Mirror::new(server.async_client(), scope.clone(), store.clone());
+        let report = mirror.sync().await.unwrap();
+        assert!(report.backfill);
+        assert_eq!(report.updated, ["foo", "bar"]);
+        assert_eq!(mirror.get("bar").unwrap().crate_data.max_version, "1.0.0");
+
+        // Only foo was updated since.
+        let mut foo = Crate::new("foo", "1.1.0");
+        foo.updated_at = crate::types::timestamp("2023-01-01T00:00:00Z");
+        foo.description = Some("Foo".to_string());
+        let server = server
+            .with_json(
+                "crates/foo",
+                &CrateResponse::new(
+                    foo.clone(),
+                    vec![Version::new("foo", "1.1.0"), Version::new("foo", "1.0.0")],
+                ),
+            )
+            .with_json(
+                "crates",
+                &CratesPage::new(vec![foo, Crate::new("bar", "1.0.0")], 2),
+            );
+
+        // The state is resumed from the store.
+        let mut mirror = Mirror::new(server.async_client(), scope, store);
+        let report = mirror.sync().await.unwrap();
+        assert!(!report.backfill);
+        assert_eq!(report.updated, ["foo"]);
+        assert_eq!(
+            report.changes,
+            [
+                ChangeEvent::Release(WatchEvent::NewVersion {
+                    crate_name: "foo".to_string(),
+                    version: "1.1.0".to_string(),
+                }),
+                ChangeEvent::DescriptionChanged {
+                    crate_name: "foo".to_string(),
+                    old: None,
+                    new: Some("Foo".to_string()),
+                },
+            ]
+        );
+        assert_eq!(mirror.get("foo").unwrap().crate_data.max_version, "1.1.0");
+        assert_eq!(mirror.crate_names().collect::<Vec<_>>(), ["bar", "foo"]);
+        assert_eq!(
+            mirror.synced_until(),
+            Some("2023-01-01T00:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_mirror_removed() {
+        let listing = "crates?page=1&per_page=100&sort=alpha&category=parsing";
+        let crates = |names: &[&str]| {
+            let crates = names.iter().map(|name| Crate::new(name, "1.0.0"));
+            CratesPage::new(crates.collect(), names.len() as u64)
+        };
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("a", &["1.0.0"])
+            .with_crate("b", &["1.0.0"])
+            .with_crate("c", &["1.0.0"])
+            .with_json(listing, &crates(&["a", "b", "c"]))
+            .with_json(
+                "crates?page=2&per_page=100&sort=alpha&category=parsing",
+                &crates(&[]),
+            );
+        let scope = MirrorScope::Category("parsing".to_string());
+        let store = Arc::new(crate::MemoryCache::new());
+        let mut mirror = Mirror::new(server.async_client(), scope, store);
+        mirror.sync().await.unwrap();
+
+        // b left the category, and the updated c was deleted.
+        let mut c = Crate::new("c", "1.0.0");
+        c.updated_at = crate::types::timestamp("2099-01-01T00:00:00Z");
+        let _server = server
+            .with_json(listing, &crates(&["a", "c"]))
+            .with_json(
+                "crates",
+                &CratesPage::new(vec![c, Crate::new("a", "1.0.0")], 2),
+            )
+            .with_error("crates/c", 404, "Not Found");
+        let report = mirror.sync().await.unwrap();
+        assert!(report.updated.is_empty());
+        assert_eq!(
+            report.changes,
+            [
+                ChangeEvent::Removed {
+                    crate_name: "b".to_string()
+                },
+                ChangeEvent::Removed {
+                    crate_name: "c".to_string()
+                },
+            ]
+        );
+        assert_eq!(mirror.crate_names().collect::<Vec<_>>(), ["a"]);
+        assert!(mirror.get("b").is_none());
+    }
+
+    /// A blocking store that fails if it is called on the thread of the
+    /// test, ie directly on the async runtime, once it is armed.
+    struct OffRuntimeStore {
+        inner: crate::MemoryCache,
+        runtime_thread: std::thread::ThreadId,
+        armed: AtomicBool,
+    }
+
+    impl OffRuntimeStore {
+        fn check(&self) {
+            if self.armed.load(Ordering::SeqCst) {
+                assert_ne!(std::thread::current().id(), self.runtime_thread);
+            }
+        }
+    }
+
+    impl CacheStore for OffRuntimeStore {
+        fn get(&self, key: &str) -> Option<String> {
+            self.check();
+            self.inner.get(key)
+        }
+
+        fn put(&self, key: &str, value: String, ttl: std::time::Duration) {
+            self.check();
+            self.inner.put(key, value, ttl)
+        }
+
+        fn blocking(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_blocking_store() {
+        use crate::SnapshotOptions;
+
+        let mut updated = Crate::new("foo", "1.0.0");
+        updated.updated_at = crate::types::timestamp("2099-01-01T00:00:00Z");
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["1.0.0"])
+            .with_json(
+                "crates?page=1&per_page=100&sort=alpha&category=parsing",
+                &CratesPage::new(vec![Crate::new("foo", "1.0.0")], 1),
+            )
+            .with_json(
+                "crates?page=2&per_page=100&sort=alpha&category=parsing",
+                &CratesPage::new(Vec::new(), 1),
+            )
+            .with_json(
+                "crates",
+                &CratesPage::new(vec![updated, Crate::new("bar", "1.0.0")], 2),
+            )
+            .with_json(
+                "categories/parsing",
+                &CategoryResponse {
+                    category: Category::new("parsing", "Parsing"),
+                },
+            );
+        let store = Arc::new(OffRuntimeStore {
+            inner: crate::MemoryCache::new(),
+            runtime_thread: std::thread::current().id(),
+            armed: AtomicBool::new(false),
+        });
+        let client = server.async_client();
+        store.armed.store(true, Ordering::SeqCst);
+        let scope = MirrorScope::Crates(vec!["foo".to_string()]);
+        let mut mirror = Mirror::new(client.clone(), scope, store.clone());
+
+        // The second sync compares with the stored copies.
+        mirror.sync().await.unwrap();
+        let report = mirror.sync().await.unwrap();
+        assert_eq!(report.updated, ["foo"]);
+        assert!(report.changes.is_empty());
+
+        let options = SnapshotOptions::new().resumable(store.clone());
+        let snapshot = client.category_snapshot("parsing", &options).await.unwrap();
+        assert_eq!(snapshot.crates.len(), 1);
+        assert!(store.inner.len() > 2);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_mirror_failed_sync() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["1.0.0"])
+            .with_crate("bar", &["1.0.0"]);
+        let scope = MirrorScope::Crates(vec!["foo".to_string(), "bar".to_string()]);
+        let store = Arc::new(crate::MemoryCache::new());
+        let mut mirror = Mirror::new(server.async_client(), scope, store);
+        mirror.sync().await.unwrap();
+
+        // Both crates were updated since, but bar fails to load after foo.
+        let mut foo = Crate::new("foo", "1.1.0");
+        foo.updated_at = crate::types::timestamp("2023-01-02T00:00:00Z");
+        let mut bar = Crate::new("bar", "1.0.0");
+        bar.updated_at = crate::types::timestamp("2023-01-01T00:00:00Z");
+        let server = server
+            .with_json(
+                "crates/foo",
+                &CrateResponse::new(
+                    foo.clone(),
+                    vec![Version::new("foo", "1.1.0"), Version::new("foo", "1.0.0")],
+                ),
+            )
+            .with_json(
+                "crates",
+                &CratesPage::new(vec![foo, bar.clone(), Crate::new("baz", "1.0.0")], 3),
+            )
+            .with_error("crates/bar", 500, "Internal Server Error");
+        assert!(mirror.sync().await.is_err());
+        assert_eq!(mirror.get("foo").unwrap().crate_data.max_version, "1.0.0");
+
+        // The release of foo is reported by the next sync.
+        let _server = server.with_json(
+            "crates/bar",
+            &CrateResponse::new(bar, vec![Version::new("bar", "1.0.0")]),
+        );
+        let report = mirror.sync().await.unwrap();
+        assert_eq!(report.updated, ["foo", "bar"]);
+        assert_eq!(
+            report.changes,
+            [ChangeEvent::Release(WatchEvent::NewVersion {
+                crate_name: "foo".to_string(),
+                version: "1.1.0".to_string(),
+            })]
+        );
+        assert_eq!(mirror.get("foo").unwrap().crate_data.max_version, "1.1.0");
+    }
+}
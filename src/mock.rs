@@ -0,0 +1,484 @@
+//! A programmable in-memory client for tests.
+//!
+//! Requires the `mock` feature.
+//!
+//! [`MockClient`] implements [`CratesIoApi`] and [`SyncCratesIoApi`], and
+//! answers from the data registered on it, without network access. Anything
+//! that isn't registered is reported as [`Error::NotFound`].
+//!
+//! ```rust
+//! use crates_io_api::{mock::MockClient, Crate, CrateResponse, SyncCratesIoApi, Version};
+//!
+//! let mock = MockClient::new().with_crate(CrateResponse::new(
+//!     Crate::new("foo", "1.0.0"),
+//!     vec![Version::new("foo", "1.0.0")],
+//! ));
+//! assert!(mock.version_exists("foo", "1.0.0").unwrap());
+//! assert!(!mock.crate_exists("bar").unwrap());
+//! ```
+//!
+//! [`CratesIoApi`]: crate::CratesIoApi
+//! [`SyncCratesIoApi`]: crate::SyncCratesIoApi
+
+use futures::future::{self, BoxFuture};
+use futures::FutureExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::NotFoundError;
+use crate::types::*;
+use crate::{CratesIoApi, Error, SyncCratesIoApi};
+
+type ErrorFactory = Arc<dyn Fn() -> Error + Send + Sync>;
+
+/// An in-memory client with canned responses, see the [module docs](self).
+#[derive(Clone, Default)]
+pub struct MockClient {
+    summary: Option<Summary>,
+    crates: HashMap<String, CrateResponse>,
+    downloads: HashMap<String, CrateDownloads>,
+    owners: HashMap<String, Vec<User>>,
+    reverse_dependencies: HashMap<String, Vec<ReverseDependency>>,
+    authors: HashMap<(String, String), Vec<String>>,
+    dependencies: HashMap<(String, String), Vec<Dependency>>,
+    users: HashMap<String, User>,
+    teams: HashMap<String, Team>,
+    errors: HashMap<String, ErrorFactory>,
+}
+
+impl std::fmt::Debug for MockClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut crates: Vec<_> = self.crates.keys().collect();
+        crates.sort();
+        let mut errors: Vec<_> = self.errors.keys().collect();
+        errors.sort();
+        f.debug_struct("MockClient")
+            .field("crates", &crates)
+            .field("errors", &errors)
+            .finish_non_exhaustive()
+    }
+}
+
+fn key(name: &str) -> String {
+    name.to_lowercase()
+}
+
+fn not_found(path: &str) -> Error {
+    Error::NotFound(NotFoundError {
+        url: format!("mock://{}", path),
+    })
+}
+
+impl MockClient {
+    /// Construct a client without any data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the response of `summary()`.
+    #[must_use]
+    pub fn with_summary(mut self, summary: Summary) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Register a crate with its versions.
+    ///
+    /// The crate is returned by `get_crate()` and `crate_version()`, and is
+    /// listed by `crates()` if it matches the query. Names are
+    /// case-insensitive.
+    #[must_use]
+    pub fn with_crate(mut self, krate: CrateResponse) -> Self {
+        self.crates.insert(key(&krate.crate_data.name), krate);
+        self
+    }
+
+    /// Set the download stats of a crate.
+    #[must_use]
+    pub fn with_downloads(mut self, crate_name: &str, downloads: CrateDownloads) -> Self {
+        self.downloads.insert(key(crate_name), downloads);
+        self
+    }
+
+    /// Set the owners of a crate.
+    #[must_use]
+    pub fn with_owners(mut self, crate_name: &str, owners: Vec<User>) -> Self {
+        self.owners.insert(key(crate_name), owners);
+        self
+    }
+
+    /// Set the reverse dependencies of a crate.
+    ///
+    /// They are paged by 100, like the API does.
+    #[must_use]
+    pub fn with_reverse_dependencies(
+        mut self,
+        crate_name: &str,
+        dependencies: Vec<ReverseDependency>,
+    ) -> Self {
+        self.reverse_dependencies
+            .insert(key(crate_name), dependencies);
+        self
+    }
+
+    /// Set the author names of a crate version.
+    ///
+    /// Versions without registered authors are reported as
+    /// [`Authors::unavailable`].
+    #[must_use]
+    pub fn with_authors(mut self, crate_name: &str, version: &str, names: Vec<String>) -> Self {
+        self.authors
+            .insert((key(crate_name), version.to_string()), names);
+        self
+    }
+
+    /// Set the dependencies of a crate version.
+    ///
+    /// Versions without registered dependencies have none.
+    #[must_use]
+    pub fn with_dependencies(
+        mut self,
+        crate_name: &str,
+        version: &str,
+        dependencies: Vec<Dependency>,
+    ) -> Self {
+        self.dependencies
+            .insert((key(crate_name), version.to_string()), dependencies);
+        self
+    }
+
+    /// Register a user.
+    #[must_use]
+    pub fn with_user(mut self, user: User) -> Self {
+        self.users.insert(key(&user.login), user);
+        self
+    }
+
+    /// Register a team.
+    #[must_use]
+    pub fn with_team(mut self, team: Team) -> Self {
+        self.teams.insert(key(&team.login), team);
+        self
+    }
+
+    /// Fail every request about the crate, user or team with the given name
+    /// with an error created by `error`.
+    ///
+    /// ```rust
+    /// use crates_io_api::{mock::MockClient, ApiErrors, Error, SyncCratesIoApi};
+    ///
+    /// let mock = MockClient::new().with_error("foo", || Error::Api(ApiErrors::new("injected")));
+    /// assert!(mock.get_crate("foo").is_err());
+    /// ```
+    #[must_use]
+    pub fn with_error<F>(mut self, name: &str, error: F) -> Self
+    where
+        F: Fn() -> Error + Send + Sync + 'static,
+    {
+        self.errors.insert(key(name), Arc::new(error));
+        self
+    }
+
+    fn check(&self, name: &str) -> Result<(), Error> {
+        match self.errors.get(&key(name)) {
+            Some(error) => Err(error()),
+            None => Ok(()),
+        }
+    }
+
+    fn find_crate(&self, crate_name: &str) -> Result<&CrateResponse, Error> {
+        self.check(crate_name)?;
+        self.crates
+            .get(&key(crate_name))
+            .ok_or_else(|| not_found(&format!("crates/{}", crate_name)))
+    }
+
+    fn find_version(&self, crate_name: &str, version: &str) -> Result<&Version, Error> {
+        self.find_crate(crate_name)?
+            .versions
+            .iter()
+            .find(|v| v.num == version)
+            .ok_or_else(|| not_found(&format!("crates/{}/{}", crate_name, version)))
+    }
+
+    fn matches(query: &CratesQuery, krate: &CrateResponse) -> bool {
+        let name = key(&krate.crate_data.name);
+        query
+            .search
+            .as_ref()
+            .map_or(true, |search| name.contains(&key(search)))
+            && (query.ids.is_empty() || query.ids.iter().any(|id| key(id) == name))
+            && query
+                .keywords
+                .iter()
+                .all(|k| krate.keywords.iter().any(|kw| &kw.keyword == k))
+            && query
+                .category
+                .as_ref()
+                .map_or(true, |c| krate.categories.iter().any(|cat| &cat.slug == c))
+    }
+}
+
+impl SyncCratesIoApi for MockClient {
+    fn summary(&self) -> Result<Summary, Error> {
+        self.summary.clone().ok_or_else(|| not_found("summary"))
+    }
+
+    fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, Error> {
+        self.find_crate(crate_name).cloned()
+    }
+
+    fn crate_exists(&self, crate_name: &str) -> Result<bool, Error> {
+        crate::async_client::exists(self.find_crate(crate_name))
+    }
+
+    fn version_exists(&self, crate_name: &str, version: &str) -> Result<bool, Error> {
+        crate::async_client::exists(self.find_version(crate_name, version))
+    }
+
+    fn crate_version(&self, crate_name: &str, version: &str) -> Result<Version, Error> {
+        self.find_version(crate_name, version).cloned()
+    }
+
+    fn crate_downloads(&self, crate_name: &str) -> Result<CrateDownloads, Error> {
+        self.check(crate_name)?;
+        self.downloads
+            .get(&key(crate_name))
+            .cloned()
+            .ok_or_else(|| not_found(&format!("crates/{}/downloads", crate_name)))
+    }
+
+    fn crate_owners(&self, crate_name: &str) -> Result<Vec<User>, Error> {
+        self.find_crate(crate_name)?;
+        Ok(self
+            .owners
+            .get(&key(crate_name))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn crate_reverse_dependencies_page(
+        &self,
+        crate_name: &str,
+        page: u64,
+    ) -> Result<ReverseDependencies, Error> {
+        self.find_crate(crate_name)?;
+        let all = self
+            .reverse_dependencies
+            .get(&key(crate_name))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let dependencies = all
+            .iter()
+            .skip((page.max(1) as usize - 1) * 100)
+            .take(100)
+            .cloned()
+            .collect();
+        Ok(ReverseDependencies {
+            dependencies,
+            meta: Meta::new(all.len() as u64),
+        })
+    }
+
+    fn crate_authors(&self, crate_name: &str, version: &str) -> Result<Authors, Error> {
+        self.find_version(crate_name, version)?;
+        Authors::from_response(
+            self.authors
+                .get(&(key(crate_name), version.to_string()))
+                .cloned()
+                .ok_or_else(|| not_found(&format!("crates/{}/{}/authors", crate_name, version))),
+        )
+    }
+
+    fn crate_dependencies(
+        &self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<Vec<Dependency>, Error> {
+        self.find_version(crate_name, version)?;
+        Ok(self
+            .dependencies
+            .get(&(key(crate_name), version.to_string()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn crates(&self, query: CratesQuery) -> Result<CratesPage, Error> {
+        query.validate()?;
+        let mut found: Vec<_> = self
+            .crates
+            .values()
+            .filter(|krate| Self::matches(&query, krate))
+            .map(|krate| krate.crate_data.clone())
+            .collect();
+        // The order of the registered crates is unspecified.
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        let total = found.len() as u64;
+        let crates = found
+            .into_iter()
+            .skip(((query.page - 1) * query.per_page) as usize)
+            .take(query.per_page as usize)
+            .collect();
+        Ok(CratesPage::new(crates, total))
+    }
+
+    fn user(&self, username: &str) -> Result<User, Error> {
+        self.check(username)?;
+        self.users
+            .get(&key(username))
+            .cloned()
+            .ok_or_else(|| not_found(&format!("users/{}", username)))
+    }
+
+    fn team(&self, login: &str) -> Result<Team, Error> {
+        self.check(login)?;
+        self.teams
+            .get(&key(login))
+            .cloned()
+            .ok_or_else(|| not_found(&format!("teams/{}", login)))
+    }
+}
+
+impl CratesIoApi for MockClient {
+    fn summary<'a>(&'a self) -> BoxFuture<'a, Result<Summary, Error>> {
+        future::ready(SyncCratesIoApi::summary(self)).boxed()
+    }
+
+    fn get_crate<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<CrateResponse, Error>> {
+        future::ready(SyncCratesIoApi::get_crate(self, crate_name)).boxed()
+    }
+
+    fn crate_exists<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<bool, Error>> {
+        future::ready(SyncCratesIoApi::crate_exists(self, crate_name)).boxed()
+    }
+
+    fn version_exists<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        future::ready(SyncCratesIoApi::version_exists(self, crate_name, version)).boxed()
+    }
+
+    fn crate_version<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Version, Error>> {
+        future::ready(SyncCratesIoApi::crate_version(self, crate_name, version)).boxed()
+    }
+
+    fn crate_downloads<'a>(
+        &'a self,
+        crate_name: &'a str,
+    ) -> BoxFuture<'a, Result<CrateDownloads, Error>> {
+        future::ready(SyncCratesIoApi::crate_downloads(self, crate_name)).boxed()
+    }
+
+    fn crate_owners<'a>(&'a self, crate_name: &'a str) -> BoxFuture<'a, Result<Vec<User>, Error>> {
+        future::ready(SyncCratesIoApi::crate_owners(self, crate_name)).boxed()
+    }
+
+    fn crate_reverse_dependencies_page<'a>(
+        &'a self,
+        crate_name: &'a str,
+        page: u64,
+    ) -> BoxFuture<'a, Result<ReverseDependencies, Error>> {
+        future::ready(SyncCratesIoApi::crate_reverse_dependencies_page(
+            self, crate_name, page,
+        ))
+        .boxed()
+    }
+
+    fn crate_authors<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Authors, Error>> {
+        future::ready(SyncCratesIoApi::crate_authors(self, crate_name, version)).boxed()
+    }
+
+    fn crate_dependencies<'a>(
+        &'a self,
+        crate_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Dependency>, Error>> {
+        future::ready(SyncCratesIoApi::crate_dependencies(
+            self, crate_name, version,
+        ))
+        .boxed()
+    }
+
+    fn crates<'a>(&'a self, query: CratesQuery) -> BoxFuture<'a, Result<CratesPage, Error>> {
+        future::ready(SyncCratesIoApi::crates(self, query)).boxed()
+    }
+
+    fn user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<User, Error>> {
+        future::ready(SyncCratesIoApi::user(self, username)).boxed()
+    }
+
+    fn team<'a>(&'a self, login: &'a str) -> BoxFuture<'a, Result<Team, Error>> {
+        future::ready(SyncCratesIoApi::team(self, login)).boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // Only the sync trait, to avoid ambiguous method calls.
+    use super::{MockClient, SyncCratesIoApi};
+    use crate::types::*;
+    use crate::Error;
+
+    fn mock() -> MockClient {
+        MockClient::new()
+            .with_crate(CrateResponse::new(
+                Crate::new("foo", "1.1.0"),
+                vec![Version::new("foo", "1.1.0"), Version::new("foo", "1.0.0")],
+            ))
+            .with_crate(CrateResponse::new(
+                Crate::new("foo-derive", "1.0.0"),
+                vec![Version::new("foo-derive", "1.0.0")],
+            ))
+            .with_dependencies("foo", "1.1.0", vec![Dependency::new("foo-derive", "^1")])
+            .with_user(User::new(1, "alice"))
+    }
+
+    #[test]
+    fn test_mock_lookups() {
+        let mock = mock();
+        assert_eq!(mock.get_crate("Foo").unwrap().versions.len(), 2);
+        assert!(mock.version_exists("foo", "1.0.0").unwrap());
+        assert!(!mock.version_exists("foo", "2.0.0").unwrap());
+        assert!(matches!(mock.get_crate("bar"), Err(Error::NotFound(_))));
+
+        let deps = mock.crate_dependencies("foo", "1.1.0").unwrap();
+        assert_eq!(deps[0].crate_id, "foo-derive");
+        assert!(mock.crate_dependencies("foo", "1.0.0").unwrap().is_empty());
+        assert!(mock.crate_authors("foo", "1.0.0").unwrap().unavailable);
+        assert_eq!(mock.user("alice").unwrap().id, 1);
+    }
+
+    #[test]
+    fn test_mock_crates_query() {
+        let mock = mock();
+        let query = CratesQuery::builder().search("foo").page_size(1).build();
+        let page = SyncCratesIoApi::crates(&mock, query).unwrap();
+        assert_eq!(page.meta.total, 2);
+        assert_eq!(page.crates[0].name, "foo");
+
+        let query = CratesQuery::builder().search("derive").build();
+        let page = SyncCratesIoApi::crates(&mock, query).unwrap();
+        assert_eq!(page.crates.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_error_injection() {
+        use crate::CratesIoApi;
+
+        let mock = mock().with_error("foo", || Error::Api(ApiErrors::new("injected")));
+        let api: &dyn CratesIoApi = &mock;
+        assert!(matches!(api.get_crate("foo").await, Err(Error::Api(_))));
+        assert!(api.crate_exists("foo").await.is_err());
+        assert!(api.crate_exists("foo-derive").await.unwrap());
+    }
+}
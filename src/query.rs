@@ -0,0 +1,211 @@
+use serde_derive::*;
+use std::convert::TryFrom;
+
+use crate::error::InvalidQueryError;
+use crate::{CratesQuery, Sort};
+
+/// A composable description of a crate search, compiled to a [`CratesQuery`].
+///
+/// Unlike a [`CratesQuery`], a `Query` has no pagination, is checked for
+/// combinations that the API would ignore or reject, and can be stored with
+/// serde, eg in the configuration of a discovery tool:
+///
+/// ```rust
+/// use crates_io_api::{Query, Sort};
+///
+/// let query = Query::text("http")
+///     .category("web-programming")
+///     .keyword("async")
+///     .sort(Sort::RecentDownloads);
+/// let crates_query = query.compile().unwrap();
+/// assert_eq!(crates_query.keywords(), ["async"]);
+///
+/// let json = serde_json::to_string(&query).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"{"text":"http","category":"web-programming","keywords":["async"],"sort":"recent-downloads"}"#
+/// );
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    crates: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    user_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    team_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sort: Option<Sort>,
+}
+
+impl Query {
+    /// Construct a query matching all crates.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Construct a query for crates matching a search text.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Only match crates in a category, identified by its slug.
+    #[must_use]
+    pub fn category(mut self, slug: impl Into<String>) -> Self {
+        self.category = Some(slug.into());
+        self
+    }
+
+    /// Only match crates with a keyword.
+    ///
+    /// Can be called multiple times: crates must have all of the keywords.
+    #[must_use]
+    pub fn keyword(mut self, keyword: impl Into<String>) -> Self {
+        self.keywords.push(keyword.into());
+        self
+    }
+
+    /// Only match the crate with the given name.
+    ///
+    /// Can be called multiple times to match any of several crates.
+    #[must_use]
+    pub fn crate_name(mut self, crate_name: impl Into<String>) -> Self {
+        self.crates.push(crate_name.into());
+        self
+    }
+
+    /// Only match crates owned by a user.
+    #[must_use]
+    pub fn user_id(mut self, user_id: u64) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    /// Only match crates owned by a team.
+    #[must_use]
+    pub fn team_id(mut self, team_id: u64) -> Self {
+        self.team_id = Some(team_id);
+        self
+    }
+
+    /// Set the sort order.
+    ///
+    /// Defaults to [`Sort::Relevance`] for queries with a search text, and to
+    /// [`Sort::RecentUpdates`] otherwise.
+    #[must_use]
+    pub fn sort(mut self, sort: Sort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Check that the query is meaningful.
+    ///
+    /// Texts, categories and keywords can't be empty, crates can't be owned
+    /// by both a user and a team, and sorting by relevance requires a search
+    /// text.
+    pub fn validate(&self) -> Result<(), InvalidQueryError> {
+        if self
+            .text
+            .as_deref()
+            .is_some_and(|text| text.trim().is_empty())
+        {
+            return Err(InvalidQueryError::new("search text can't be empty"));
+        }
+        if self.category.as_deref() == Some("") {
+            return Err(InvalidQueryError::new("category can't be empty"));
+        }
+        if self.keywords.iter().any(|keyword| keyword.is_empty()) {
+            return Err(InvalidQueryError::new("keywords can't be empty"));
+        }
+        if self.user_id.is_some() && self.team_id.is_some() {
+            return Err(InvalidQueryError::new(
+                "can't filter by both user_id and team_id",
+            ));
+        }
+        if self.sort == Some(Sort::Relevance) && self.text.is_none() {
+            return Err(InvalidQueryError::new(
+                "sorting by relevance requires a search text",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate the query and compile it to the first page of a
+    /// [`CratesQuery`].
+    pub fn compile(&self) -> Result<CratesQuery, InvalidQueryError> {
+        self.validate()?;
+        let sort = self.sort.unwrap_or(match self.text {
+            Some(_) => Sort::Relevance,
+            None => Sort::RecentUpdates,
+        });
+        let query = CratesQuery {
+            sort,
+            user_id: self.user_id,
+            team_id: self.team_id,
+            category: self.category.clone(),
+            search: self.text.clone(),
+            keywords: self.keywords.clone(),
+            ids: self.crates.clone(),
+            sort_set: self.sort.is_some() || self.text.is_some(),
+            ..CratesQuery::default()
+        };
+        query.validate()?;
+        Ok(query)
+    }
+}
+
+impl TryFrom<&Query> for CratesQuery {
+    type Error = InvalidQueryError;
+
+    fn try_from(query: &Query) -> Result<Self, Self::Error> {
+        query.compile()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_query_compile() {
+        let query = Query::text("http").keyword("async").keyword("client");
+        let compiled = query.compile().unwrap();
+        assert_eq!(compiled.search().map(String::as_str), Some("http"));
+        assert_eq!(compiled.keywords(), ["async", "client"]);
+        assert_eq!(*compiled.sort(), Sort::Relevance);
+
+        let compiled = CratesQuery::try_from(&Query::all().crate_name("serde")).unwrap();
+        assert_eq!(compiled.ids(), ["serde"]);
+        assert_eq!(*compiled.sort(), Sort::RecentUpdates);
+
+        for invalid in [
+            Query::text(" "),
+            Query::all().category(""),
+            Query::all().keyword(""),
+            Query::all().user_id(1).team_id(2),
+            Query::all().sort(Sort::Relevance),
+        ] {
+            assert!(invalid.compile().is_err(), "{:?}", invalid);
+        }
+
+        let json = r#"{"category":"parsing","user_id":3,"sort":"alpha"}"#;
+        let query: Query = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            query,
+            Query::all()
+                .category("parsing")
+                .user_id(3)
+                .sort(Sort::Alphabetical)
+        );
+        assert_eq!(serde_json::to_string(&query).unwrap(), json);
+    }
+}
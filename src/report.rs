@@ -0,0 +1,302 @@
+use serde_derive::*;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::{FullCrate, Timestamp, Version};
+
+/// A machine-readable report of a crate, combining a [`FullCrate`] with
+/// metrics computed from it.
+///
+/// The report serializes to the same structure for every crate, so audit
+/// tools can emit and compare reports without deriving the metrics
+/// themselves:
+///
+/// ```rust,no_run
+/// # async fn run() -> Result<(), crates_io_api::Error> {
+/// use crates_io_api::{AsyncClient, CrateReport};
+///
+/// let client = AsyncClient::new("my_bot (help@my_bot.com)", std::time::Duration::from_secs(1))
+///     .unwrap();
+/// let report = CrateReport::new(client.full_crate("serde", false).await?);
+/// println!("{}", serde_json::to_string_pretty(&report).unwrap());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct CrateReport {
+    /// The crate the report is about.
+    #[serde(rename = "crate")]
+    pub crate_data: FullCrate,
+    /// Downloads in the last 90 days.
+    pub recent_downloads: u64,
+    /// The number of crates depending on the crate.
+    pub dependents: u64,
+    /// The number of versions that are not yanked.
+    pub releases: usize,
+    /// The mean number of days between two releases that are not yanked, or
+    /// `None` for fewer than two releases.
+    pub days_between_releases: Option<f64>,
+    /// The minimum supported Rust version declared by the newest release.
+    pub msrv: Option<String>,
+}
+
+impl CrateReport {
+    /// Compute the report of a crate.
+    ///
+    /// The metrics only cover the versions present in the crate, so
+    /// retrieving it with a [`VersionFilter`](crate::VersionFilter) other
+    /// than `All` affects them.
+    pub fn new(crate_data: FullCrate) -> Self {
+        let mut releases: Vec<Timestamp> = crate_data
+            .versions
+            .iter()
+            .filter(|v| !v.yanked)
+            .map(|v| v.created_at)
+            .collect();
+        releases.sort();
+        let days_between_releases = match (releases.first(), releases.last()) {
+            (Some(first), Some(last)) if releases.len() > 1 => {
+                let span = last.duration_since(*first).unwrap_or_default();
+                let span = span.as_secs_f64() / 86_400.0;
+                Some(span / (releases.len() - 1) as f64)
+            }
+            _ => None,
+        };
+        let msrv = crate_data
+            .versions
+            .iter()
+            .filter(|v| !v.yanked)
+            .max_by_key(|v| v.created_at)
+            .and_then(|v| v.rust_version.clone());
+
+        Self {
+            recent_downloads: crate_data
+                .recent_downloads
+                .unwrap_or_else(|| crate_data.downloads.total()),
+            dependents: crate_data.reverse_dependencies.meta.total,
+            releases: releases.len(),
+            days_between_releases,
+            msrv,
+            crate_data,
+        }
+    }
+}
+
+impl From<FullCrate> for CrateReport {
+    fn from(crate_data: FullCrate) -> Self {
+        Self::new(crate_data)
+    }
+}
+
+/// Signals of whether a crate is maintained, computed from its versions.
+///
+/// See [`FullCrate::maintenance`] and [`MaintenanceMetrics::from_versions`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MaintenanceMetrics {
+    /// The number of versions, including yanked ones.
+    pub releases: usize,
+    /// The number of yanked versions.
+    pub yanked: usize,
+    /// When the first version was published.
+    pub first_release: Option<Timestamp>,
+    /// When the latest version was published.
+    pub last_release: Option<Timestamp>,
+    /// The number of distinct users who published versions.
+    ///
+    /// Old versions don't record their publisher and aren't counted.
+    pub publishers: usize,
+}
+
+impl MaintenanceMetrics {
+    /// Compute the metrics of a list of versions, eg from
+    /// `crate_versions()` on the clients.
+    pub fn from_versions(versions: &[Version]) -> Self {
+        Self::compute(versions.iter().map(|v| {
+            (
+                v.created_at,
+                v.yanked,
+                v.published_by.as_ref().map(|u| u.id),
+            )
+        }))
+    }
+
+    fn compute(versions: impl Iterator<Item = (Timestamp, bool, Option<u64>)>) -> Self {
+        let mut metrics = Self {
+            releases: 0,
+            yanked: 0,
+            first_release: None,
+            last_release: None,
+            publishers: 0,
+        };
+        let mut publishers = HashSet::new();
+        for (created_at, yanked, publisher) in versions {
+            metrics.releases += 1;
+            metrics.yanked += usize::from(yanked);
+            metrics.first_release = Some(
+                metrics
+                    .first_release
+                    .map_or(created_at, |t| t.min(created_at)),
+            );
+            metrics.last_release = Some(
+                metrics
+                    .last_release
+                    .map_or(created_at, |t| t.max(created_at)),
+            );
+            publishers.extend(publisher);
+        }
+        metrics.publishers = publishers.len();
+        metrics
+    }
+
+    /// The mean time between two releases, or `None` for fewer than two
+    /// releases.
+    pub fn mean_release_interval(&self) -> Option<Duration> {
+        match (self.first_release, self.last_release) {
+            (Some(first), Some(last)) if self.releases > 1 => {
+                Some(last.duration_since(first)? / (self.releases - 1) as u32)
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of whole days from the latest release to `now`, negative
+    /// if `now` is earlier.
+    pub fn days_since_last_release(&self, now: Timestamp) -> Option<i64> {
+        self.last_release
+            .map(|last| (now.unix_timestamp() - last.unix_timestamp()) / 86_400)
+    }
+
+    /// The fraction of versions that are yanked, or `None` without versions.
+    pub fn yank_ratio(&self) -> Option<f64> {
+        if self.releases == 0 {
+            None
+        } else {
+            Some(self.yanked as f64 / self.releases as f64)
+        }
+    }
+}
+
+impl FullCrate {
+    /// Compute the maintenance metrics of the versions of the crate.
+    ///
+    /// Like [`CrateReport`], this only covers the versions present in the
+    /// crate.
+    pub fn maintenance(&self) -> MaintenanceMetrics {
+        MaintenanceMetrics::compute(self.versions.iter().map(|v| {
+            (
+                v.created_at,
+                v.yanked,
+                v.published_by.as_ref().map(|u| u.id),
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        Crate, CrateDownloads, CrateDownloadsMeta, CrateResponse, ReverseDependencies,
+        VersionDownloads,
+    };
+
+    /// Build a crate from `(num, created_at, yanked, rust_version)` tuples,
+    /// newest first.
+    fn full_crate(versions: &[(&str, &str, bool, Option<&str>)]) -> FullCrate {
+        let versions = versions
+            .iter()
+            .enumerate()
+            .map(|(id, (num, created_at, yanked, rust_version))| {
+                let mut version = Version::new("a", num);
+                version.id = id as u64;
+                version.created_at = crate::types::timestamp(created_at);
+                version.updated_at = version.created_at;
+                version.yanked = *yanked;
+                version.rust_version = rust_version.map(str::to_string);
+                crate::FullVersion::new(version, None, Vec::new())
+            })
+            .collect();
+        let mut krate = Crate::new("a", "1.0.0");
+        krate.downloads = 100;
+        let downloads = CrateDownloads {
+            version_downloads: vec![VersionDownloads {
+                date: "2020-01-01".parse().unwrap(),
+                downloads: 7,
+                version: 0,
+            }],
+            meta: CrateDownloadsMeta {
+                extra_downloads: Vec::new(),
+            },
+        };
+        let reverse_dependencies = ReverseDependencies {
+            dependencies: Vec::new(),
+            meta: crate::Meta::new(3),
+        };
+        FullCrate::new(
+            CrateResponse::new(krate, Vec::new()),
+            None,
+            downloads,
+            Vec::new(),
+            Some(reverse_dependencies),
+            versions,
+        )
+    }
+
+    #[test]
+    fn test_crate_report() {
+        let report = CrateReport::new(full_crate(&[
+            ("1.1.0", "2020-01-21T00:00:00Z", true, Some("1.60")),
+            ("1.0.0", "2020-01-11T00:00:00Z", false, Some("1.56")),
+            ("0.1.0", "2020-01-01T00:00:00Z", false, None),
+        ]));
+        assert_eq!(report.recent_downloads, 7);
+        assert_eq!(report.dependents, 3);
+        assert_eq!(report.releases, 2);
+        assert_eq!(report.days_between_releases, Some(10.0));
+        assert_eq!(report.msrv.as_deref(), Some("1.56"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["crate"]["name"], "a");
+        assert_eq!(json["msrv"], "1.56");
+
+        let report = CrateReport::from(full_crate(&[(
+            "1.0.0",
+            "2020-01-01T00:00:00Z",
+            false,
+            None,
+        )]));
+        assert_eq!(report.days_between_releases, None);
+    }
+
+    #[test]
+    fn test_maintenance_metrics() {
+        let mut krate = full_crate(&[
+            ("1.1.0", "2020-01-21T00:00:00Z", true, None),
+            ("1.0.0", "2020-01-11T00:00:00Z", false, None),
+            ("0.1.0", "2020-01-01T00:00:00Z", false, None),
+            ("0.0.1", "2020-01-01T00:00:00Z", true, None),
+        ]);
+        let publisher = |id| Some(crate::User::new(id, "u"));
+        krate.versions[0].published_by = publisher(1);
+        krate.versions[1].published_by = publisher(2);
+        krate.versions[2].published_by = publisher(1);
+
+        let metrics = krate.maintenance();
+        assert_eq!(metrics.releases, 4);
+        assert_eq!(metrics.publishers, 2);
+        assert_eq!(metrics.yank_ratio(), Some(0.5));
+        assert_eq!(
+            metrics.mean_release_interval(),
+            Some(Duration::from_secs(160 * 3600))
+        );
+        let now = "2020-02-01T12:00:00Z".parse().unwrap();
+        assert_eq!(metrics.days_since_last_release(now), Some(11));
+
+        let empty = MaintenanceMetrics::from_versions(&[]);
+        assert_eq!(empty.mean_release_interval(), None);
+        assert_eq!(empty.yank_ratio(), None);
+        assert_eq!(empty.days_since_last_release(now), None);
+    }
+}
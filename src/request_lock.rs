@@ -0,0 +1,169 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, Notify, OwnedMutexGuard};
+use tokio::time::Instant;
+
+/// The lock that serializes the requests of a client and its clones.
+///
+/// Low priority requests, like those of `prefetch()`, have a separate lane:
+/// they only take the lock while no normal request waits for or holds it,
+/// and never hold it while waiting for the rate limit, so normal requests
+/// are delayed by at most one low priority request that is already sent.
+#[derive(Debug, Default)]
+pub(crate) struct RequestLock {
+    last_request_time: Arc<Mutex<Option<Instant>>>,
+    /// Serializes the low priority requests among themselves.
+    low_priority: Arc<Mutex<()>>,
+    /// The number of normal requests waiting for or holding the lock.
+    normal: AtomicUsize,
+    /// Notified when `normal` drops to zero.
+    idle: Notify,
+}
+
+/// Exclusive access to the time of the last request.
+///
+/// Dropping the guard lets the next request through.
+pub(crate) struct RequestGuard {
+    last_request_time: OwnedMutexGuard<Option<Instant>>,
+    _low_priority: Option<OwnedMutexGuard<()>>,
+    _normal: Option<Normal>,
+}
+
+/// Counts a normal request in [`RequestLock::normal`] while it lives.
+struct Normal(Arc<RequestLock>);
+
+impl RequestLock {
+    /// Wait for the lock.
+    ///
+    /// Low priority requests also wait until the rate limit allows them to
+    /// be sent, without holding the lock meanwhile.
+    pub(crate) async fn lock(
+        self: &Arc<Self>,
+        rate_limit: Duration,
+        low_priority: bool,
+    ) -> RequestGuard {
+        if !low_priority {
+            self.normal.fetch_add(1, Ordering::SeqCst);
+            let normal = Normal(self.clone());
+            return RequestGuard {
+                last_request_time: self.last_request_time.clone().lock_owned().await,
+                _low_priority: None,
+                _normal: Some(normal),
+            };
+        }
+
+        let low_priority = self.low_priority.clone().lock_owned().await;
+        loop {
+            self.wait_idle().await;
+            // Only fails if a normal request came in since.
+            let last_request_time = match self.last_request_time.clone().try_lock_owned() {
+                Ok(guard) => guard,
+                Err(_) => continue,
+            };
+            let wait = last_request_time
+                .map(|at| rate_limit.saturating_sub(at.elapsed()))
+                .unwrap_or_default();
+            if wait.is_zero() {
+                return RequestGuard {
+                    last_request_time,
+                    _low_priority: Some(low_priority),
+                    _normal: None,
+                };
+            }
+            drop(last_request_time);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Wait until no normal request waits for or holds the lock.
+    async fn wait_idle(&self) {
+        loop {
+            // Created before the check, so that no notification is missed.
+            let idle = self.idle.notified();
+            if self.normal.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+impl Drop for Normal {
+    fn drop(&mut self) {
+        if self.0.normal.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+impl Deref for RequestGuard {
+    type Target = Option<Instant>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.last_request_time
+    }
+}
+
+impl DerefMut for RequestGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.last_request_time
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_lock_priority() {
+        let lock = Arc::new(RequestLock::default());
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let guard = lock.lock(Duration::ZERO, false).await;
+        let low = tokio::spawn({
+            let (lock, order) = (lock.clone(), order.clone());
+            async move {
+                let _guard = lock.lock(Duration::ZERO, true).await;
+                order.lock().unwrap().push("low");
+            }
+        });
+        tokio::task::yield_now().await;
+        let normal = tokio::spawn({
+            let (lock, order) = (lock.clone(), order.clone());
+            async move {
+                let _guard = lock.lock(Duration::ZERO, false).await;
+                order.lock().unwrap().push("normal");
+            }
+        });
+        tokio::task::yield_now().await;
+
+        // The normal request came in later, but goes first.
+        drop(guard);
+        normal.await.unwrap();
+        low.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), ["normal", "low"]);
+    }
+
+    #[tokio::test]
+    async fn test_request_lock_low_priority_rate_limit() {
+        let lock = Arc::new(RequestLock::default());
+        let rate_limit = Duration::from_millis(50);
+        let start = Instant::now();
+        *lock.lock(rate_limit, false).await = Some(start);
+
+        let low = tokio::spawn({
+            let lock = lock.clone();
+            async move {
+                let _guard = lock.lock(rate_limit, true).await;
+                start.elapsed()
+            }
+        });
+        tokio::task::yield_now().await;
+        // Waiting for the rate limit doesn't hold the lock.
+        assert!(lock.last_request_time.try_lock().is_ok());
+        assert!(low.await.unwrap() >= rate_limit);
+    }
+}
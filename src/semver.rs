@@ -0,0 +1,91 @@
+//! Semantic versions and version requirements.
+//!
+//! crates.io reports versions and dependency requirements as plain strings.
+//! This module re-exports the types of the [`semver`](https://docs.rs/semver)
+//! crate, which implements the rules Cargo uses, with helpers that report
+//! parse errors as [`InvalidVersionError`].
+//!
+//! Requires the `semver` feature.
+//!
+//! ```rust
+//! use crates_io_api::semver::{parse_req, parse_version};
+//!
+//! let req = parse_req("^1.2").unwrap();
+//! assert!(req.matches(&parse_version("1.9.0").unwrap()));
+//! assert!(!req.matches(&parse_version("2.0.0").unwrap()));
+//! ```
+
+pub use ::semver::{BuildMetadata, Comparator, Op, Prerelease, Version, VersionReq};
+
+use crate::error::InvalidVersionError;
+
+/// Parse a version string, ignoring surrounding whitespace.
+pub fn parse_version(input: &str) -> Result<Version, InvalidVersionError> {
+    Version::parse(input.trim()).map_err(|err| InvalidVersionError::new(input, err.to_string()))
+}
+
+/// Parse a version requirement, ignoring surrounding whitespace.
+pub fn parse_req(input: &str) -> Result<VersionReq, InvalidVersionError> {
+    VersionReq::parse(input.trim()).map_err(|err| InvalidVersionError::new(input, err.to_string()))
+}
+
+/// Check if a version is a pre-release.
+pub fn is_prerelease(version: &Version) -> bool {
+    !version.pre.is_empty()
+}
+
+/// Check if `other` is compatible with `version` according to the caret
+/// rules of Cargo, ie if `^version` would allow it apart from its precedence.
+///
+/// The leftmost non-zero component must be equal, so `1.2.3` is compatible
+/// with `1.9.0`, `0.2.3` with `0.2.9` and `0.0.3` only with `0.0.3`.
+pub fn is_compatible(version: &Version, other: &Version) -> bool {
+    if version.major != other.major {
+        false
+    } else if version.major > 0 {
+        true
+    } else if version.minor != other.minor {
+        false
+    } else {
+        version.minor > 0 || version.patch == other.patch
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        parse_version(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(v(" 1.2.3 ").to_string(), "1.2.3");
+        let err = parse_version("1.2").unwrap_err();
+        assert!(err.to_string().starts_with("Invalid version '1.2': "));
+    }
+
+    #[test]
+    fn test_parse_req() {
+        assert!(parse_req(" ^1.2 ").unwrap().matches(&v("1.9.0")));
+        let err = parse_req("latest").unwrap_err();
+        assert!(err.to_string().starts_with("Invalid version 'latest': "));
+    }
+
+    #[test]
+    fn test_is_prerelease() {
+        assert!(is_prerelease(&v("1.0.0-alpha.1")));
+        assert!(!is_prerelease(&v("1.0.0+build.5")));
+    }
+
+    #[test]
+    fn test_version_compatible() {
+        assert!(is_compatible(&v("1.2.3"), &v("1.9.0")));
+        assert!(!is_compatible(&v("1.2.3"), &v("2.0.0")));
+        assert!(is_compatible(&v("0.2.3"), &v("0.2.9")));
+        assert!(!is_compatible(&v("0.2.3"), &v("0.3.0")));
+        assert!(is_compatible(&v("0.0.3"), &v("0.0.3-rc.1")));
+        assert!(!is_compatible(&v("0.0.3"), &v("0.0.4")));
+    }
+}
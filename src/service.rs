@@ -0,0 +1,121 @@
+//! A [`tower_service::Service`] implementation for [`AsyncClient`].
+//!
+//! Requires the `tower` feature.
+//!
+//! This allows wrapping the client with tower middleware, eg for retries,
+//! timeouts or load shedding. The rate limit of the client still applies to
+//! every request; use a zero rate limit if a middleware takes care of it.
+//!
+//! ```rust,no_run
+//! # async fn run() -> Result<(), crates_io_api::Error> {
+//! use crates_io_api::{service::ApiRequest, AsyncClient};
+//! use tower_service::Service;
+//!
+//! let mut client =
+//!     AsyncClient::new("my_bot (help@my_bot.com)", std::time::Duration::from_secs(1)).unwrap();
+//! let summary = client.call(ApiRequest::new("summary")).await?;
+//! println!("{}", summary["num_crates"]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`AsyncClient`]: crate::AsyncClient
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::task::{Context, Poll};
+
+use crate::{AsyncClient, Error, RequestOptions};
+
+/// A GET request against an API path, relative to the API root.
+///
+/// The response is the raw JSON, like [`AsyncClient::get_raw`].
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+    path: String,
+    options: RequestOptions,
+}
+
+impl ApiRequest {
+    /// Construct a request for a path, eg `"crates/serde/owners"`.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            options: RequestOptions::new(),
+        }
+    }
+
+    /// Get the path of the request.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Append a query parameter.
+    #[must_use]
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options = self.options.query(key, value);
+        self
+    }
+
+    /// Add a header, overriding the default header of the same name.
+    #[must_use]
+    pub fn header(
+        mut self,
+        name: reqwest::header::HeaderName,
+        value: reqwest::header::HeaderValue,
+    ) -> Self {
+        self.options = self.options.header(name, value);
+        self
+    }
+}
+
+impl tower_service::Service<ApiRequest> for AsyncClient {
+    type Response = serde_json::Value;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<serde_json::Value, Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        // Requests wait for the rate limit when they are executed.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: ApiRequest) -> Self::Future {
+        let client = self.clone();
+        async move { client.get_raw_with(&request.path, &request.options).await }.boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_api_request() {
+        let request = ApiRequest::new("crates/serde/versions")
+            .query("sort", "date")
+            .header(
+                reqwest::header::HeaderName::from_static("traceparent"),
+                reqwest::header::HeaderValue::from_static("00-1-2-01"),
+            );
+        assert_eq!(request.path(), "crates/serde/versions");
+        assert_eq!(request.options.query, [("sort".into(), "date".into())]);
+        assert_eq!(request.options.headers.len(), 1);
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    #[tokio::test]
+    async fn test_service_async() {
+        use tower_service::Service;
+
+        let server = crate::testing::MockServer::start()
+            .unwrap()
+            .with_crate("serde", &["1.0.0"]);
+        let mut client = server.async_client();
+        let krate = client
+            .call(ApiRequest::new("crates/serde").query("include", ""))
+            .await
+            .unwrap();
+        assert_eq!(krate["crate"]["name"], "serde");
+        assert_eq!(server.requests(), ["crates/serde?include="]);
+    }
+}
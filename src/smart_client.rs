@@ -0,0 +1,217 @@
+use serde_derive::*;
+
+use crate::error::NotFoundError;
+use crate::sparse_index::{IndexDependency, IndexEntry, SparseIndex};
+use crate::{AsyncClient, CrateDownloads, CrateResponse, Dependency, Error, User};
+
+/// A published version of a crate, as known to both the index and the API.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VersionInfo {
+    /// The version.
+    pub num: String,
+    /// Whether the version is yanked.
+    pub yanked: bool,
+    /// The minimum supported Rust version.
+    pub rust_version: Option<String>,
+}
+
+impl From<&IndexEntry> for VersionInfo {
+    fn from(entry: &IndexEntry) -> Self {
+        Self {
+            num: entry.version.clone(),
+            yanked: entry.yanked,
+            rust_version: entry.rust_version.clone(),
+        }
+    }
+}
+
+impl From<Dependency> for IndexDependency {
+    fn from(dep: Dependency) -> Self {
+        Self {
+            name: dep.crate_id,
+            req: dep.req,
+            features: dep.features,
+            optional: dep.optional,
+            default_features: dep.default_features,
+            target: dep.target,
+            kind: Some(dep.kind),
+            registry: None,
+            package: None,
+        }
+    }
+}
+
+/// A client that answers questions about versions and dependencies from the
+/// sparse index, and only uses the API for the rest.
+///
+/// The index is served from a CDN and is not rate limited, so lookups that
+/// don't need rich metadata like descriptions, downloads or owners are much
+/// faster and don't count against the crawler policy of the API.
+///
+/// If the index can't be reached or returns garbage, the lookup falls back
+/// to the API. A crate that is missing from the index is reported as missing,
+/// without asking the API.
+#[derive(Clone)]
+pub struct SmartClient {
+    api: AsyncClient,
+    index: SparseIndex,
+}
+
+impl SmartClient {
+    /// Combine an API client and an index client.
+    pub fn new(api: AsyncClient, index: SparseIndex) -> Self {
+        Self { api, index }
+    }
+
+    /// Get the API client.
+    pub fn api(&self) -> &AsyncClient {
+        &self.api
+    }
+
+    /// Get the index client.
+    pub fn index(&self) -> &SparseIndex {
+        &self.index
+    }
+
+    /// Get the index entries of a crate, or `None` if the index is unavailable.
+    async fn entries(&self, crate_name: &str) -> Option<Result<Vec<IndexEntry>, Error>> {
+        match self.index.crate_entries(crate_name).await {
+            Err(err @ Error::NotFound(_)) => Some(Err(err)),
+            Err(err) => {
+                log::warn!(
+                    "Could not read {} from the index, falling back to the API: {}",
+                    crate_name,
+                    err
+                );
+                None
+            }
+            Ok(entries) => Some(Ok(entries)),
+        }
+    }
+
+    /// Check if a crate exists.
+    pub async fn crate_exists(&self, crate_name: &str) -> Result<bool, Error> {
+        match self.entries(crate_name).await {
+            Some(Ok(_)) => Ok(true),
+            Some(Err(Error::NotFound(_))) => Ok(false),
+            Some(Err(err)) => Err(err),
+            None => self.api.crate_exists(crate_name).await,
+        }
+    }
+
+    /// Check if a specific version of a crate exists.
+    ///
+    /// Yanked versions exist.
+    pub async fn version_exists(&self, crate_name: &str, version: &str) -> Result<bool, Error> {
+        match self.entries(crate_name).await {
+            Some(Ok(entries)) => Ok(entries.iter().any(|e| e.version == version)),
+            Some(Err(Error::NotFound(_))) => Ok(false),
+            Some(Err(err)) => Err(err),
+            None => self.api.version_exists(crate_name, version).await,
+        }
+    }
+
+    /// Retrieve all published versions of a crate, oldest first.
+    pub async fn versions(&self, crate_name: &str) -> Result<Vec<VersionInfo>, Error> {
+        match self.entries(crate_name).await {
+            Some(entries) => Ok(entries?.iter().map(VersionInfo::from).collect()),
+            None => {
+                let krate = self.api.get_crate(crate_name).await?;
+                // The API lists the newest version first.
+                Ok(krate
+                    .versions
+                    .into_iter()
+                    .rev()
+                    .map(|v| VersionInfo {
+                        num: v.num,
+                        yanked: v.yanked,
+                        rust_version: v.rust_version,
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Retrieve the dependencies of a crate version.
+    pub async fn dependencies(
+        &self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<Vec<IndexDependency>, Error> {
+        match self.entries(crate_name).await {
+            Some(entries) => entries?
+                .into_iter()
+                .find(|e| e.version == version)
+                .map(|e| e.deps)
+                .ok_or_else(|| {
+                    Error::NotFound(NotFoundError {
+                        url: format!(
+                            "{}#{}",
+                            self.index
+                                .entries_url(crate_name)
+                                .map(|url| url.to_string())
+                                .unwrap_or_default(),
+                            version
+                        ),
+                    })
+                }),
+            None => Ok(self
+                .api
+                .crate_dependencies(crate_name, version)
+                .await?
+                .into_iter()
+                .map(IndexDependency::from)
+                .collect()),
+        }
+    }
+
+    /// Retrieve information of a crate from the API.
+    pub async fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, Error> {
+        self.api.get_crate(crate_name).await
+    }
+
+    /// Retrieve download stats for a crate from the API.
+    pub async fn crate_downloads(&self, crate_name: &str) -> Result<CrateDownloads, Error> {
+        self.api.crate_downloads(crate_name).await
+    }
+
+    /// Retrieve the owners of a crate from the API.
+    pub async fn crate_owners(&self, crate_name: &str) -> Result<Vec<User>, Error> {
+        self.api.crate_owners(crate_name).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::DependencyKind;
+
+    #[test]
+    fn test_index_dependency_from_api() {
+        let mut dep = Dependency::new("serde", "^1.0");
+        dep.default_features = false;
+        dep.features = vec!["derive".to_string()];
+        dep.kind = DependencyKind::Dev;
+        let dep = IndexDependency::from(dep);
+        assert_eq!(dep.crate_name(), "serde");
+        assert_eq!(dep.kind, Some(DependencyKind::Dev));
+        assert_eq!(dep.features, ["derive"]);
+        assert!(!dep.default_features);
+    }
+
+    #[tokio::test]
+    async fn test_smart_client_async() {
+        let user_agent = "crates-io-api-continuous-integration (github.com/theduke/crates-io-api)";
+        let client = SmartClient::new(
+            AsyncClient::new(user_agent, std::time::Duration::from_millis(1000)).unwrap(),
+            SparseIndex::new(user_agent).unwrap(),
+        );
+        assert!(client.version_exists("serde", "1.0.0").await.unwrap());
+        assert!(!client
+            .crate_exists("this-crate-does-not-exist-hopefully")
+            .await
+            .unwrap());
+        let deps = client.dependencies("serde", "1.0.0").await.unwrap();
+        assert!(deps.iter().any(|d| d.crate_name() == "serde_derive"));
+    }
+}
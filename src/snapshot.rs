@@ -0,0 +1,218 @@
+use serde_derive::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{CacheStore, Crate, CrateResponse, CratesQuery, Sort, Timestamp};
+
+/// Options for `category_snapshot()` on the clients.
+#[derive(Clone, Default)]
+pub struct SnapshotOptions {
+    store: Option<Arc<dyn CacheStore>>,
+    max_crates: Option<usize>,
+}
+
+impl std::fmt::Debug for SnapshotOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SnapshotOptions")
+            .field("store", &self.store.is_some())
+            .field("max_crates", &self.max_crates)
+            .finish()
+    }
+}
+
+impl SnapshotOptions {
+    /// Construct the default options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the progress of the snapshot in `store`, so that a snapshot
+    /// that failed, eg on a network error, resumes where it stopped when it
+    /// is taken again.
+    ///
+    /// Once a snapshot completes, the next one starts from scratch.
+    #[must_use]
+    pub fn resumable(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Only include the first `max` crates of the category, alphabetically.
+    #[must_use]
+    pub fn max_crates(mut self, max: usize) -> Self {
+        self.max_crates = Some(max);
+        self
+    }
+}
+
+/// A report of all crates in a category, see `category_snapshot()` on the
+/// clients.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct CategorySnapshot {
+    /// The slug of the category.
+    pub category: String,
+    /// The crates of the category, alphabetically.
+    pub crates: Vec<SnapshotEntry>,
+}
+
+/// A crate in a [`CategorySnapshot`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SnapshotEntry {
+    /// The crate name.
+    pub name: String,
+    /// The highest version.
+    pub max_version: String,
+    /// Downloads of all time.
+    pub downloads: u64,
+    /// Downloads in the last 90 days.
+    pub recent_downloads: Option<u64>,
+    /// The number of crates depending on the crate.
+    pub dependents: u64,
+    /// When the latest version was published.
+    pub last_release: Option<Timestamp>,
+}
+
+impl SnapshotEntry {
+    pub(crate) fn new(krate: Crate, response: &CrateResponse, dependents: u64) -> Self {
+        Self {
+            last_release: response.versions.iter().map(|v| v.created_at).max(),
+            name: krate.name,
+            max_version: krate.max_version,
+            downloads: krate.downloads,
+            recent_downloads: krate.recent_downloads,
+            dependents,
+        }
+    }
+}
+
+/// The stored progress of a snapshot.
+///
+/// The included crates are stored under their own keys, so that including
+/// one doesn't rewrite all others.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct StoredProgress {
+    crates: usize,
+    complete: bool,
+}
+
+/// The progress of a snapshot while it is taken.
+pub(crate) struct SnapshotProgress<'a> {
+    options: &'a SnapshotOptions,
+    slug: String,
+    crates: Vec<SnapshotEntry>,
+    done: HashSet<String>,
+}
+
+impl<'a> SnapshotProgress<'a> {
+    /// Start a snapshot, resuming an incomplete one from the store.
+    pub(crate) async fn load(slug: &str, options: &'a SnapshotOptions) -> SnapshotProgress<'a> {
+        let crates = match &options.store {
+            Some(store) => {
+                let key = Self::key(slug);
+                crate::cache::call(store, move |store| Self::load_crates(store, &key)).await
+            }
+            None => Vec::new(),
+        };
+        let done = crates.iter().map(|c| c.name.clone()).collect();
+        Self {
+            options,
+            slug: slug.to_string(),
+            crates,
+            done,
+        }
+    }
+
+    /// Read the crates of an incomplete snapshot.
+    fn load_crates(store: &dyn CacheStore, key: &str) -> Vec<SnapshotEntry> {
+        let progress = store
+            .get(key)
+            .and_then(|progress| serde_json::from_str::<StoredProgress>(&progress).ok())
+            .filter(|progress| !progress.complete)
+            .unwrap_or_default();
+        // Crates after a missing one are fetched again.
+        (0..progress.crates)
+            .map_while(|index| {
+                let entry = store.get(&Self::crate_key(key, index))?;
+                serde_json::from_str(&entry).ok()
+            })
+            .collect()
+    }
+
+    fn key(slug: &str) -> String {
+        format!("snapshot/{}", slug)
+    }
+
+    fn crate_key(key: &str, index: usize) -> String {
+        format!("{}/{}", key, index)
+    }
+
+    /// Build the query for the crates of the category.
+    ///
+    /// The crates are sorted alphabetically, so that the pages don't shift
+    /// while the snapshot is taken.
+    pub(crate) fn query(&self) -> CratesQuery {
+        CratesQuery::builder()
+            .category(self.slug.as_str())
+            .sort(Sort::Alphabetical)
+            .page_size(CratesQuery::MAX_PAGE_SIZE)
+            .build()
+    }
+
+    /// Check if enough crates are included.
+    pub(crate) fn is_full(&self) -> bool {
+        self.options
+            .max_crates
+            .is_some_and(|max| self.crates.len() >= max)
+    }
+
+    /// Check if a crate is already included, from a previous attempt.
+    pub(crate) fn contains(&self, crate_name: &str) -> bool {
+        self.done.contains(crate_name)
+    }
+
+    /// Include a crate and store the progress.
+    pub(crate) async fn push(&mut self, entry: SnapshotEntry) {
+        self.done.insert(entry.name.clone());
+        let index = self.crates.len();
+        let content = serde_json::to_string(&entry).unwrap();
+        self.crates.push(entry);
+        self.save(Some((index, content)), false).await;
+    }
+
+    /// Complete the snapshot.
+    pub(crate) async fn finish(self) -> CategorySnapshot {
+        self.save(None, true).await;
+        let mut crates = self.crates;
+        crates.sort_by(|a, b| a.name.cmp(&b.name));
+        crates.truncate(self.options.max_crates.unwrap_or(usize::MAX));
+        CategorySnapshot {
+            category: self.slug,
+            crates,
+        }
+    }
+
+    /// Store the progress, after the crate at `index` if there is a new one.
+    async fn save(&self, entry: Option<(usize, String)>, complete: bool) {
+        let store = match &self.options.store {
+            Some(store) => store,
+            None => return,
+        };
+        let key = Self::key(&self.slug);
+        let progress = StoredProgress {
+            crates: self.crates.len(),
+            complete,
+        };
+        let progress = serde_json::to_string(&progress).unwrap();
+        crate::cache::call(store, move |store| {
+            if let Some((index, content)) = entry {
+                store.put(&Self::crate_key(&key, index), content, Duration::MAX);
+            }
+            store.put(&key, progress, Duration::MAX);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,445 @@
+//! Downloading the source of published crate versions and comparing them.
+//!
+//! Requires the `source` feature.
+//!
+//! Every published version is stored on a CDN as a `.crate` file, a gzip
+//! compressed tar archive of the packaged sources. [`diff_versions`]
+//! downloads two versions and reports which files were added, removed or
+//! modified between them, so reviewers can inspect what actually changed in
+//! a release rather than what the repository claims:
+//!
+//! ```rust,no_run
+//! # fn run() -> Result<(), crates_io_api::Error> {
+//! use crates_io_api::source;
+//!
+//! let diff = source::diff_versions("my_bot (help@my_bot.com)", "serde", "1.0.100", "1.0.101", Some(3))?;
+//! for path in &diff.modified {
+//!     print!("{}", diff.unified[path]);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Like [`db_dump::download`](crate::db_dump), the downloads don't go
+//! through the API or the rate limit of a client.
+
+use serde_derive::*;
+use similar::TextDiff;
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::time::Duration;
+use url::Url;
+
+use crate::error::{InvalidArchiveError, NotFoundError};
+use crate::{CrateName, Error, Version};
+
+/// Location of the `.crate` files of all published versions.
+pub const CRATES_CDN_URL: &str = "https://static.crates.io/crates/";
+
+/// After this long, the diff of a file falls back to a coarser one.
+const DIFF_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The files of a published crate version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrateSource {
+    /// The contents of the files, by path relative to the crate root.
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+impl CrateSource {
+    /// Read the contents of a `.crate` file.
+    ///
+    /// The `{name}-{version}/` directory that contains all files of the
+    /// archive is stripped from their paths. Archives that decompress to more
+    /// than 512 MiB, the limit of crates.io, are rejected.
+    pub fn from_crate_file(data: &[u8]) -> io::Result<Self> {
+        Self::from_crate_file_with_limit(data, crate::gzip::DEFAULT_MAX_SIZE)
+    }
+
+    /// Read the contents of a `.crate` file that decompresses to at most
+    /// `max_size` bytes.
+    pub fn from_crate_file_with_limit(data: &[u8], max_size: u64) -> io::Result<Self> {
+        let tar = crate::gzip::decompress(data, max_size)?;
+        let mut archive = tar::Archive::new(tar.as_slice());
+        let mut files = BTreeMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            // Also the path of GNU long name and PAX headers.
+            let path = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+            let size = entry.size();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            if (contents.len() as u64) < size {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let path = match path.split_once('/') {
+                Some((_, path)) => path.to_string(),
+                None => path,
+            };
+            files.insert(path, contents);
+        }
+        Ok(Self { files })
+    }
+
+    /// The total size of the files, in bytes.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.files
+            .values()
+            .map(|contents| contents.len() as u64)
+            .sum()
+    }
+
+    /// Compare the files with those of a `new` version.
+    ///
+    /// With `context`, unified diffs with that many lines of context are
+    /// included for every text file that changed. Binary files are only
+    /// listed.
+    pub fn diff(&self, new: &CrateSource, context: Option<usize>) -> SourceDiff {
+        let mut diff = SourceDiff::default();
+        for (path, old_contents) in &self.files {
+            match new.files.get(path) {
+                None => diff.removed.push(path.clone()),
+                Some(new_contents) if new_contents != old_contents => {
+                    diff.modified.push(path.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        diff.added = new
+            .files
+            .keys()
+            .filter(|path| !self.files.contains_key(*path))
+            .cloned()
+            .collect();
+
+        if let Some(context) = context {
+            let changed = diff.added.iter().chain(&diff.removed).chain(&diff.modified);
+            for path in changed {
+                let old = self.files.get(path).map(Vec::as_slice);
+                let new = new.files.get(path).map(Vec::as_slice);
+                if let Some(unified) = unified_diff(path, old, new, context) {
+                    diff.unified.insert(path.clone(), unified);
+                }
+            }
+        }
+        diff
+    }
+}
+
+/// The changes of the files between two versions of a crate.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SourceDiff {
+    /// The paths of files only in the new version.
+    pub added: Vec<String>,
+    /// The paths of files only in the old version.
+    pub removed: Vec<String>,
+    /// The paths of files whose contents changed.
+    pub modified: Vec<String>,
+    /// Unified diffs of the changed text files, by path, if requested.
+    pub unified: BTreeMap<String, String>,
+}
+
+impl SourceDiff {
+    /// Check if no file changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// The size of a published version, see [`package_size`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PackageSize {
+    /// The size of the `.crate` file, in bytes.
+    pub crate_size: u64,
+    /// The total size of the packaged files, in bytes, or `None` if the
+    /// `.crate` file wasn't inspected.
+    pub uncompressed_size: Option<u64>,
+    /// The number of packaged files, or `None` if the `.crate` file wasn't
+    /// inspected.
+    pub files: Option<usize>,
+}
+
+impl PackageSize {
+    /// Get the size of the `.crate` file recorded by crates.io, without
+    /// downloading it.
+    ///
+    /// Returns `None` for old versions, for which crates.io didn't record
+    /// the size.
+    pub fn from_version(version: &Version) -> Option<Self> {
+        version.crate_size.map(|crate_size| Self {
+            crate_size,
+            uncompressed_size: None,
+            files: None,
+        })
+    }
+
+    /// Get the sizes of the contents of a `.crate` file.
+    pub fn from_crate_file(data: &[u8]) -> io::Result<Self> {
+        let source = CrateSource::from_crate_file(data)?;
+        Ok(Self {
+            crate_size: data.len() as u64,
+            uncompressed_size: Some(source.uncompressed_size()),
+            files: Some(source.files.len()),
+        })
+    }
+}
+
+/// Download the `.crate` file of a version and report its sizes.
+///
+/// To track the size of many versions over time, prefer
+/// [`PackageSize::from_version`] where the uncompressed size and the number
+/// of files aren't needed, as it doesn't download anything.
+pub fn package_size(user_agent: &str, version: &Version) -> Result<PackageSize, Error> {
+    let data = download(user_agent, &version.crate_name, &version.num)?;
+    let mut size = PackageSize::from_crate_file(&data)
+        .map_err(|err| invalid_archive(&version.crate_name, &version.num, err))?;
+    if let Some(crate_size) = version.crate_size {
+        size.crate_size = crate_size;
+    }
+    Ok(size)
+}
+
+/// Build the url of the `.crate` file of a version.
+pub fn crate_file_url(crate_name: &str, version: &str) -> Result<Url, Error> {
+    let mut url = Url::parse(CRATES_CDN_URL)?;
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .push(crate_name)
+        .push(&format!("{}-{}.crate", crate_name, version));
+    if CrateName::validate(crate_name).is_err() {
+        return Err(Error::NotFound(NotFoundError {
+            url: url.to_string(),
+        }));
+    }
+    crate::semver::parse_version(version)?;
+    Ok(url)
+}
+
+/// Download the `.crate` file of a version.
+pub fn download(user_agent: &str, crate_name: &str, version: &str) -> Result<Vec<u8>, Error> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(user_agent)
+        .build()?;
+    download_with(&client, crate_name, version)
+}
+
+/// Download and read the files of a version.
+pub fn fetch(user_agent: &str, crate_name: &str, version: &str) -> Result<CrateSource, Error> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(user_agent)
+        .build()?;
+    fetch_with(&client, crate_name, version)
+}
+
+/// Download two versions of a crate and compare their files.
+///
+/// See [`CrateSource::diff`] for the `context` of unified diffs.
+pub fn diff_versions(
+    user_agent: &str,
+    crate_name: &str,
+    old_version: &str,
+    new_version: &str,
+    context: Option<usize>,
+) -> Result<SourceDiff, Error> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(user_agent)
+        .build()?;
+    let old = fetch_with(&client, crate_name, old_version)?;
+    let new = fetch_with(&client, crate_name, new_version)?;
+    Ok(old.diff(&new, context))
+}
+
+fn download_with(
+    client: &reqwest::blocking::Client,
+    crate_name: &str,
+    version: &str,
+) -> Result<Vec<u8>, Error> {
+    let url = crate_file_url(crate_name, version)?;
+    let res = client.get(url.clone()).send()?;
+    // The CDN answers 403 rather than 404 for files that don't exist.
+    if matches!(res.status().as_u16(), 403 | 404) {
+        return Err(Error::NotFound(NotFoundError {
+            url: url.to_string(),
+        }));
+    }
+    Ok(res.error_for_status()?.bytes()?.to_vec())
+}
+
+fn fetch_with(
+    client: &reqwest::blocking::Client,
+    crate_name: &str,
+    version: &str,
+) -> Result<CrateSource, Error> {
+    let data = download_with(client, crate_name, version)?;
+    CrateSource::from_crate_file(&data).map_err(|err| invalid_archive(crate_name, version, err))
+}
+
+fn invalid_archive(crate_name: &str, version: &str, err: io::Error) -> Error {
+    Error::InvalidArchive(InvalidArchiveError {
+        url: crate_file_url(crate_name, version)
+            .map(|url| url.to_string())
+            .unwrap_or_default(),
+        reason: err.to_string(),
+    })
+}
+
+/// Build the unified diff of a file, or `None` for binary files.
+fn unified_diff(
+    path: &str,
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+    context: usize,
+) -> Option<String> {
+    fn text(contents: Option<&[u8]>) -> Option<&str> {
+        match contents {
+            Some(contents) if contents.contains(&0) => None,
+            Some(contents) => std::str::from_utf8(contents).ok(),
+            None => Some(""),
+        }
+    }
+    let (old_text, new_text) = (text(old)?, text(new)?);
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(old_text, new_text);
+    let old_header = old.map_or("/dev/null".to_string(), |_| format!("a/{}", path));
+    let new_header = new.map_or("/dev/null".to_string(), |_| format!("b/{}", path));
+    Some(
+        diff.unified_diff()
+            .context_radius(context)
+            .header(&old_header, &new_header)
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Build a `.crate` file.
+    fn crate_file(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(Vec::new());
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            let path = format!("a-1.0.0/{}", path);
+            tar.append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        let tar = tar.into_inner().unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        io::Write::write_all(&mut gz, &tar).unwrap();
+        gz.finish().unwrap()
+    }
+
+    #[test]
+    fn test_crate_source_diff() {
+        let old = CrateSource::from_crate_file(&crate_file(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"a\"\nversion = \"1.0.0\"\n",
+            ),
+            ("src/lib.rs", "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n"),
+            ("README.md", "a"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            old.files.keys().collect::<Vec<_>>(),
+            ["Cargo.toml", "README.md", "src/lib.rs"]
+        );
+        let small = crate_file(&[("README.md", "a")]);
+        assert!(CrateSource::from_crate_file_with_limit(&small, 4 * 512).is_ok());
+        assert!(CrateSource::from_crate_file_with_limit(&small, 4 * 512 - 1).is_err());
+        let new = CrateSource::from_crate_file(&crate_file(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"a\"\nversion = \"1.0.0\"\n",
+            ),
+            ("src/lib.rs", "1\n2\nthree\n4\n5\n6\n7\n8\n10\n11\n"),
+            ("src/bin.rs", "fn main() {}"),
+        ]))
+        .unwrap();
+
+        let diff = old.diff(&new, None);
+        assert_eq!(diff.added, ["src/bin.rs"]);
+        assert_eq!(diff.removed, ["README.md"]);
+        assert_eq!(diff.modified, ["src/lib.rs"]);
+        assert!(diff.unified.is_empty());
+        assert!(old.diff(&old, Some(3)).is_empty());
+
+        let diff = old.diff(&new, Some(1));
+        assert_eq!(
+            diff.unified["src/lib.rs"],
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n\
+             @@ -2,3 +2,3 @@\n 2\n-3\n+three\n 4\n\
+             @@ -8,3 +8,3 @@\n 8\n-9\n 10\n+11\n"
+        );
+        assert_eq!(
+            diff.unified["README.md"],
+            "--- a/README.md\n+++ /dev/null\n@@ -1 +0,0 @@\n-a\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            diff.unified["src/bin.rs"],
+            "--- /dev/null\n+++ b/src/bin.rs\n@@ -0,0 +1 @@\n+fn main() {}\n\\ No newline at end of file\n"
+        );
+
+        let diff = old.diff(&new, Some(3));
+        assert_eq!(diff.unified["src/lib.rs"].matches("@@ -").count(), 1);
+    }
+
+    #[test]
+    fn test_package_size() {
+        let data = crate_file(&[("Cargo.toml", "[package]\n"), ("src/lib.rs", "")]);
+        let size = PackageSize::from_crate_file(&data).unwrap();
+        assert_eq!(size.crate_size, data.len() as u64);
+        assert_eq!(size.uncompressed_size, Some(10));
+        assert_eq!(size.files, Some(2));
+        assert!(PackageSize::from_crate_file(&data[..20]).is_err());
+    }
+
+    #[test]
+    fn test_long_paths() {
+        let long = format!("a-1.0.0/{}.rs", "a".repeat(200));
+        // Long paths are stored in a GNU long name header.
+        let gnu = crate_file(&[(&long[8..], "gnu")]);
+        let source = CrateSource::from_crate_file(&gnu).unwrap();
+        assert_eq!(source.files[&long[8..]], b"gnu");
+
+        // Or in a PAX extended header.
+        let mut tar = tar::Builder::new(Vec::new());
+        tar.append_pax_extensions([("path", long.as_bytes())])
+            .unwrap();
+        let mut header = tar::Header::new_ustar();
+        header.set_size(3);
+        header.set_mode(0o644);
+        header.as_mut_bytes()[..7].copy_from_slice(b"a-1.0.0");
+        header.set_cksum();
+        tar.append(&header, &b"pax"[..]).unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        io::Write::write_all(&mut gz, &tar.into_inner().unwrap()).unwrap();
+        let source = CrateSource::from_crate_file(&gz.finish().unwrap()).unwrap();
+        assert_eq!(source.files[&long[8..]], b"pax");
+    }
+
+    #[test]
+    fn test_crate_file_url() {
+        assert_eq!(
+            crate_file_url("serde", "1.0.0").unwrap().as_str(),
+            "https://static.crates.io/crates/serde/serde-1.0.0.crate"
+        );
+        assert!(matches!(
+            crate_file_url("../a", "1.0.0"),
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            crate_file_url("serde", "../1"),
+            Err(Error::InvalidVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,411 @@
+//! A client for the sparse HTTP index at <https://index.crates.io>.
+//!
+//! The index is what cargo uses to resolve dependencies. It contains the
+//! versions, dependencies, features and checksums of every crate, and is
+//! served from a CDN, so it is much cheaper to query than the API.
+//!
+//! ```rust,no_run
+//! # async fn run() -> Result<(), crates_io_api::Error> {
+//! use crates_io_api::sparse_index::SparseIndex;
+//!
+//! let index = SparseIndex::new("my_bot (help@my_bot.com)").unwrap();
+//! for entry in index.crate_entries("serde").await? {
+//!     println!("{} {}", entry.version, entry.yanked);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use reqwest::{header, Client as HttpClient, StatusCode, Url};
+use serde_derive::*;
+use std::collections::HashMap;
+
+use crate::error::{decode_json, NotFoundError};
+use crate::{CrateName, DependencyKind, Error, InvalidCrateNameError};
+
+/// The URL of the crates.io sparse index.
+pub const SPARSE_INDEX_URL: &str = "https://index.crates.io/";
+
+/// The `config.json` of an index.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexConfig {
+    /// The download URL template for crate files.
+    pub dl: String,
+    /// The base URL of the web API.
+    pub api: Option<String>,
+    /// Whether the index requires authentication.
+    #[serde(rename = "auth-required", default)]
+    pub auth_required: bool,
+}
+
+impl IndexConfig {
+    /// Get the URL of the `.crate` file of a version.
+    ///
+    /// The `{crate}`, `{version}`, `{prefix}`, `{lowerprefix}` and
+    /// `{sha256-checksum}` markers of the template are replaced. If the
+    /// template has no markers, `/{crate}/{version}/download` is appended.
+    ///
+    /// Entries come from any index, so their name isn't validated with the
+    /// rules of crates.io, but an empty name is an error.
+    pub fn download_url(&self, entry: &IndexEntry) -> Result<String, InvalidCrateNameError> {
+        if entry.name.is_empty() {
+            return Err(InvalidCrateNameError::new("", "name is empty"));
+        }
+        const MARKERS: [&str; 5] = [
+            "{crate}",
+            "{version}",
+            "{prefix}",
+            "{lowerprefix}",
+            "{sha256-checksum}",
+        ];
+        if !MARKERS.iter().any(|marker| self.dl.contains(marker)) {
+            return Ok(format!(
+                "{}/{}/{}/download",
+                self.dl.trim_end_matches('/'),
+                entry.name,
+                entry.version
+            ));
+        }
+        Ok(self
+            .dl
+            .replace("{crate}", &entry.name)
+            .replace("{version}", &entry.version)
+            .replace("{prefix}", &prefix(&entry.name))
+            .replace("{lowerprefix}", &prefix(&entry.name.to_lowercase()))
+            .replace("{sha256-checksum}", &entry.checksum))
+    }
+}
+
+/// A single published version, as listed in the index.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexEntry {
+    /// The crate name.
+    pub name: String,
+    /// The version.
+    #[serde(rename = "vers")]
+    pub version: String,
+    /// The dependencies of the version.
+    pub deps: Vec<IndexDependency>,
+    /// The SHA256 checksum of the `.crate` file.
+    #[serde(rename = "cksum")]
+    pub checksum: String,
+    /// The features of the version.
+    pub features: HashMap<String, Vec<String>>,
+    /// Features using the newer `dep:` or `pkg?/feat` syntax.
+    ///
+    /// These are stored separately so that older versions of cargo ignore
+    /// them. Use [`Self::all_features`] to get both.
+    #[serde(default)]
+    pub features2: Option<HashMap<String, Vec<String>>>,
+    /// Whether the version is yanked.
+    pub yanked: bool,
+    /// The value of the `links` manifest key.
+    #[serde(default)]
+    pub links: Option<String>,
+    /// The minimum supported Rust version.
+    #[serde(default)]
+    pub rust_version: Option<String>,
+    /// The version of the entry format.
+    #[serde(default)]
+    pub v: Option<u32>,
+}
+
+impl IndexEntry {
+    /// Get the features and the `features2` of the version, merged.
+    pub fn all_features(&self) -> HashMap<String, Vec<String>> {
+        let mut features = self.features.clone();
+        for (name, values) in self.features2.iter().flatten() {
+            features
+                .entry(name.clone())
+                .or_default()
+                .extend(values.iter().cloned());
+        }
+        features
+    }
+}
+
+/// A dependency of an [`IndexEntry`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexDependency {
+    /// The name of the dependency.
+    ///
+    /// If the dependency is renamed, this is the new name, and the original
+    /// crate name is in [`Self::package`].
+    pub name: String,
+    /// The version requirement.
+    pub req: String,
+    /// The enabled features.
+    pub features: Vec<String>,
+    /// Whether the dependency is optional.
+    pub optional: bool,
+    /// Whether the default features are enabled.
+    pub default_features: bool,
+    /// The target platform, eg `cfg(windows)`.
+    pub target: Option<String>,
+    /// The dependency kind.
+    ///
+    /// `None` is the same as [`DependencyKind::Normal`].
+    pub kind: Option<DependencyKind>,
+    /// The index URL of the registry, if it isn't the same index.
+    #[serde(default)]
+    pub registry: Option<String>,
+    /// The original crate name, if the dependency is renamed.
+    #[serde(default)]
+    pub package: Option<String>,
+}
+
+impl IndexDependency {
+    /// Get the name of the crate this dependency refers to, taking renames
+    /// into account.
+    pub fn crate_name(&self) -> &str {
+        self.package.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Get the directory of a crate in the index, eg `se/rd` for `serde`.
+///
+/// Names are split by characters, since names from other indexes are not
+/// necessarily ASCII. Names must not be empty.
+fn prefix(name: &str) -> String {
+    let chars: Vec<char> = name.chars().take(4).collect();
+    let part = |range: std::ops::Range<usize>| chars[range].iter().collect::<String>();
+    match chars.len() {
+        1 => "1".to_string(),
+        2 => "2".to_string(),
+        3 => format!("3/{}", part(0..1)),
+        _ => format!("{}/{}", part(0..2), part(2..4)),
+    }
+}
+
+/// Get the path of the index file of a crate, relative to the index root.
+pub(crate) fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    format!("{}/{}", prefix(&name), name)
+}
+
+/// Parse an index file, which contains one JSON entry per line.
+pub(crate) fn parse_entries(contents: &str) -> Result<Vec<IndexEntry>, Error> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            decode_json(line).map_err(|mut err| {
+                err.message = format!(
+                    "invalid index entry on line {}: {}",
+                    number + 1,
+                    err.message
+                );
+                Error::JsonDecode(err)
+            })
+        })
+        .collect()
+}
+
+/// A client for a sparse index.
+///
+/// Unlike the API, the index is served from a CDN and has no crawler
+/// policy, so requests are not rate limited.
+#[derive(Clone)]
+pub struct SparseIndex {
+    client: HttpClient,
+    base_url: Url,
+}
+
+impl SparseIndex {
+    /// Instantiate a client for the crates.io index.
+    ///
+    /// Returns an [`Error`] if the given user agent is invalid.
+    pub fn new(user_agent: &str) -> Result<Self, reqwest::header::InvalidHeaderValue> {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::USER_AGENT,
+            header::HeaderValue::from_str(user_agent)?,
+        );
+
+        let client = HttpClient::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
+
+        Ok(Self::with_http_client(client))
+    }
+
+    /// Instantiate a client for the crates.io index.
+    pub fn with_http_client(client: HttpClient) -> Self {
+        Self {
+            client,
+            base_url: Url::parse(SPARSE_INDEX_URL).unwrap(),
+        }
+    }
+
+    /// Use a different index, eg a mirror.
+    ///
+    /// Returns an error if the URL is invalid.
+    pub fn with_base_url(mut self, base_url: &str) -> Result<Self, Error> {
+        let mut base_url = Url::parse(base_url)?;
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        self.base_url = base_url;
+        Ok(self)
+    }
+
+    async fn get(&self, url: &Url) -> Result<String, Error> {
+        let res = self.client.get(url.clone()).send().await?;
+        match res.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Err(Error::NotFound(NotFoundError {
+                url: url.to_string(),
+            })),
+            _ => crate::async_client::body_text(res.error_for_status()?).await,
+        }
+    }
+
+    /// Retrieve the `config.json` of the index.
+    pub async fn config(&self) -> Result<IndexConfig, Error> {
+        let url = self.base_url.join("config.json")?;
+        let content = self.get(&url).await?;
+        Ok(decode_json(&content)?)
+    }
+
+    /// Get the URL of the index file of a crate.
+    ///
+    /// The crate name is validated with [`CrateName`] rules: an invalid name
+    /// can never be in the index, so a [`Error::NotFound`] is returned
+    /// without a request.
+    pub(crate) fn entries_url(&self, crate_name: &str) -> Result<Url, Error> {
+        if CrateName::validate(crate_name).is_err() {
+            let mut url = self.base_url.clone();
+            url.path_segments_mut()
+                .unwrap()
+                .pop_if_empty()
+                .push(crate_name);
+            return Err(Error::NotFound(NotFoundError {
+                url: url.to_string(),
+            }));
+        }
+        Ok(self.base_url.join(&index_path(crate_name))?)
+    }
+
+    /// Retrieve all published versions of a crate, oldest first.
+    ///
+    /// Crate names are case-insensitive.
+    pub async fn crate_entries(&self, crate_name: &str) -> Result<Vec<IndexEntry>, Error> {
+        let url = self.entries_url(crate_name)?;
+        parse_entries(&self.get(&url).await?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ENTRIES: &str = r#"{"name":"foo","vers":"0.1.0","deps":[],"cksum":"aa","features":{},"yanked":true}
+{"name":"foo","vers":"0.2.0","deps":[{"name":"bar","req":"^1","features":["std"],"optional":true,"default_features":false,"target":"cfg(unix)","kind":"normal","package":"bar-rs"}],"cksum":"bb","features":{"std":["bar/std"]},"features2":{"bar":["dep:bar"]},"yanked":false,"rust_version":"1.56","v":2}
+"#;
+
+    #[test]
+    fn test_index_path() {
+        assert_eq!(index_path("a"), "1/a");
+        assert_eq!(index_path("ab"), "2/ab");
+        assert_eq!(index_path("abc"), "3/a/abc");
+        assert_eq!(index_path("Serde"), "se/rd/serde");
+        assert_eq!(index_path("cargo-edit"), "ca/rg/cargo-edit");
+    }
+
+    #[test]
+    fn test_entries_url_invalid_name() {
+        let index = SparseIndex::new("test").unwrap();
+        assert_eq!(
+            index.entries_url("Serde").unwrap().as_str(),
+            "https://index.crates.io/se/rd/serde"
+        );
+        for name in ["", "ñandú", "../../config.json", "se/rde"] {
+            assert!(
+                matches!(index.entries_url(name), Err(Error::NotFound(_))),
+                "{:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let entries = parse_entries(ENTRIES).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].yanked);
+
+        let entry = &entries[1];
+        assert_eq!(entry.version, "0.2.0");
+        assert_eq!(entry.checksum, "bb");
+        assert_eq!(entry.rust_version.as_deref(), Some("1.56"));
+        assert_eq!(entry.deps[0].crate_name(), "bar-rs");
+        assert_eq!(entry.deps[0].target.as_deref(), Some("cfg(unix)"));
+
+        let features = entry.all_features();
+        assert_eq!(features["std"], ["bar/std"]);
+        assert_eq!(features["bar"], ["dep:bar"]);
+
+        let err = parse_entries("{}\n{").unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn test_download_url() {
+        let entry = &parse_entries(ENTRIES).unwrap()[1];
+        let config = |dl: &str| IndexConfig {
+            dl: dl.to_string(),
+            api: None,
+            auth_required: false,
+        };
+        assert_eq!(
+            config("https://static.crates.io/crates")
+                .download_url(entry)
+                .unwrap(),
+            "https://static.crates.io/crates/foo/0.2.0/download"
+        );
+        let mirror = config(
+            "https://mirror/{prefix}/{lowerprefix}/{crate}-{version}.crate?{sha256-checksum}",
+        );
+        assert_eq!(
+            mirror.download_url(entry).unwrap(),
+            "https://mirror/3/f/3/f/foo-0.2.0.crate?bb"
+        );
+
+        let mut entry = entry.clone();
+        entry.name = "ÑandúS".to_string();
+        assert_eq!(
+            mirror.download_url(&entry).unwrap(),
+            "https://mirror/Ña/nd/ña/nd/ÑandúS-0.2.0.crate?bb"
+        );
+        entry.name = String::new();
+        assert!(mirror.download_url(&entry).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_crate_entries_async() {
+        let server = crate::testing::MockServer::start()
+            .unwrap()
+            .with_response("3/f/foo", 200, ENTRIES)
+            .with_response(
+                "config.json",
+                200,
+                r#"{"dl": "https://static.crates.io/crates", "api": "https://crates.io"}"#,
+            );
+        let index = SparseIndex::new("test")
+            .unwrap()
+            .with_base_url(&server.base_url())
+            .unwrap();
+
+        let entries = index.crate_entries("Foo").await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].version, "0.2.0");
+        assert!(matches!(
+            index.crate_entries("bar").await,
+            Err(Error::NotFound(_))
+        ));
+        let config = index.config().await.unwrap();
+        assert_eq!(config.api.as_deref(), Some("https://crates.io"));
+        assert_eq!(server.requests(), ["3/f/foo", "3/b/bar", "config.json"]);
+    }
+}
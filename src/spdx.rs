@@ -0,0 +1,438 @@
+//! SPDX license expressions.
+//!
+//! crates.io reports the license of a version as an SPDX expression like
+//! `MIT OR Apache-2.0`. This module parses them and answers common
+//! compliance questions.
+//!
+//! Requires the `spdx` feature.
+//!
+//! ```rust
+//! use crates_io_api::spdx::LicenseExpr;
+//!
+//! let expr = LicenseExpr::parse("MIT OR GPL-3.0-only").unwrap();
+//! assert!(expr.is_osi_approved());
+//! assert!(!expr.is_copyleft());
+//! ```
+
+use serde_derive::*;
+
+use crate::error::InvalidLicenseError;
+
+/// OSI approved licenses, lowercase and without `-only`/`-or-later`.
+const OSI_APPROVED: &[&str] = &[
+    "0bsd",
+    "afl-3.0",
+    "agpl-3.0",
+    "apache-2.0",
+    "artistic-2.0",
+    "blueoak-1.0.0",
+    "bsd-2-clause",
+    "bsd-2-clause-patent",
+    "bsd-3-clause",
+    "bsl-1.0",
+    "cddl-1.0",
+    "cecill-2.1",
+    "ecl-2.0",
+    "epl-1.0",
+    "epl-2.0",
+    "eupl-1.1",
+    "eupl-1.2",
+    "gpl-2.0",
+    "gpl-3.0",
+    "isc",
+    "lgpl-2.1",
+    "lgpl-3.0",
+    "mit",
+    "mit-0",
+    "mpl-1.1",
+    "mpl-2.0",
+    "ms-pl",
+    "ms-rl",
+    "ncsa",
+    "ofl-1.1",
+    "osl-3.0",
+    "postgresql",
+    "python-2.0",
+    "unicode-dfs-2016",
+    "unlicense",
+    "upl-1.0",
+    "zlib",
+];
+
+/// Copyleft licenses, lowercase and without `-only`/`-or-later`.
+///
+/// Includes weak copyleft licenses like the LGPL and MPL.
+const COPYLEFT: &[&str] = &[
+    "agpl-1.0",
+    "agpl-3.0",
+    "cddl-1.0",
+    "cddl-1.1",
+    "cecill-2.1",
+    "epl-1.0",
+    "epl-2.0",
+    "eupl-1.1",
+    "eupl-1.2",
+    "gpl-1.0",
+    "gpl-2.0",
+    "gpl-3.0",
+    "lgpl-2.0",
+    "lgpl-2.1",
+    "lgpl-3.0",
+    "mpl-1.0",
+    "mpl-1.1",
+    "mpl-2.0",
+    "ms-rl",
+    "osl-3.0",
+];
+
+/// A parsed SPDX license expression.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+    /// A single license.
+    License(LicenseReq),
+    /// All of the expressions apply.
+    And(Vec<LicenseExpr>),
+    /// Any one of the expressions can be chosen.
+    Or(Vec<LicenseExpr>),
+}
+
+/// A single license in a [`LicenseExpr`], like `GPL-2.0+ WITH
+/// Classpath-exception-2.0`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LicenseReq {
+    /// The license identifier, eg `MIT`.
+    pub id: String,
+    /// Whether later versions of the license are also allowed (`+`).
+    pub or_later: bool,
+    /// The license exception, eg `LLVM-exception`.
+    pub exception: Option<String>,
+}
+
+impl LicenseReq {
+    /// Get the identifier in lowercase, without the `-only` or `-or-later`
+    /// suffix.
+    fn base_id(&self) -> String {
+        let id = self.id.to_lowercase();
+        let id = id.strip_suffix("-only").unwrap_or(&id);
+        id.strip_suffix("-or-later").unwrap_or(id).to_string()
+    }
+
+    /// Check if the license is approved by the Open Source Initiative.
+    pub fn is_osi_approved(&self) -> bool {
+        OSI_APPROVED.contains(&self.base_id().as_str())
+    }
+
+    /// Check if the license is a copyleft license, including weak copyleft
+    /// licenses like the LGPL and MPL.
+    pub fn is_copyleft(&self) -> bool {
+        COPYLEFT.contains(&self.base_id().as_str())
+    }
+}
+
+impl LicenseExpr {
+    /// Parse a license expression.
+    ///
+    /// Operators are case-insensitive, and the `/` separator used by old
+    /// crates is treated like `OR`.
+    pub fn parse(input: &str) -> Result<Self, InvalidLicenseError> {
+        let tokens = tokenize(input);
+        let mut parser = Parser {
+            input,
+            tokens,
+            pos: 0,
+        };
+        let expr = parser.or_expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(parser.error("unexpected token"));
+        }
+        Ok(expr)
+    }
+
+    /// Get all licenses mentioned in the expression.
+    pub fn licenses(&self) -> Vec<&LicenseReq> {
+        match self {
+            Self::License(req) => vec![req],
+            Self::And(exprs) | Self::Or(exprs) => exprs.iter().flat_map(Self::licenses).collect(),
+        }
+    }
+
+    /// Check if the expression can be satisfied using only licenses that are
+    /// accepted by `accept`.
+    pub fn evaluate<F: Fn(&LicenseReq) -> bool>(&self, accept: &F) -> bool {
+        match self {
+            Self::License(req) => accept(req),
+            Self::And(exprs) => exprs.iter().all(|expr| expr.evaluate(accept)),
+            Self::Or(exprs) => exprs.iter().any(|expr| expr.evaluate(accept)),
+        }
+    }
+
+    /// Check if the expression can be satisfied using only OSI approved
+    /// licenses.
+    pub fn is_osi_approved(&self) -> bool {
+        self.evaluate(&LicenseReq::is_osi_approved)
+    }
+
+    /// Check if every way of satisfying the expression involves a copyleft
+    /// license.
+    pub fn is_copyleft(&self) -> bool {
+        !self.evaluate(&|req: &LicenseReq| !req.is_copyleft())
+    }
+}
+
+impl std::fmt::Display for LicenseReq {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.id)?;
+        if self.or_later {
+            f.write_str("+")?;
+        }
+        if let Some(exception) = &self.exception {
+            write!(f, " WITH {}", exception)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for LicenseExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (exprs, op) = match self {
+            Self::License(req) => return req.fmt(f),
+            Self::And(exprs) => (exprs, " AND "),
+            Self::Or(exprs) => (exprs, " OR "),
+        };
+        for (index, expr) in exprs.iter().enumerate() {
+            if index > 0 {
+                f.write_str(op)?;
+            }
+            match expr {
+                Self::Or(_) if op == " AND " => write!(f, "({})", expr)?,
+                _ => write!(f, "{}", expr)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for LicenseExpr {
+    type Err = InvalidLicenseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Slash,
+    Word(&'a str),
+}
+
+impl Token<'_> {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in input.char_indices() {
+        let token = match c {
+            '(' => Some(Token::Open),
+            ')' => Some(Token::Close),
+            '/' => Some(Token::Slash),
+            c if c.is_whitespace() => None,
+            _ => {
+                start.get_or_insert(index);
+                continue;
+            }
+        };
+        if let Some(start) = start.take() {
+            tokens.push(Token::Word(&input[start..index]));
+        }
+        tokens.extend(token);
+    }
+    if let Some(start) = start {
+        tokens.push(Token::Word(&input[start..]));
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &str) -> InvalidLicenseError {
+        InvalidLicenseError::new(self.input, reason)
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn or_expr(&mut self) -> Result<LicenseExpr, InvalidLicenseError> {
+        let mut exprs = vec![self.and_expr()?];
+        while let Some(token) = self.peek() {
+            if token != Token::Slash && !token.is_keyword("OR") {
+                break;
+            }
+            self.pos += 1;
+            exprs.push(self.and_expr()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            LicenseExpr::Or(exprs)
+        })
+    }
+
+    fn and_expr(&mut self) -> Result<LicenseExpr, InvalidLicenseError> {
+        let mut exprs = vec![self.with_expr()?];
+        while self.peek().is_some_and(|token| token.is_keyword("AND")) {
+            self.pos += 1;
+            exprs.push(self.with_expr()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            LicenseExpr::And(exprs)
+        })
+    }
+
+    fn with_expr(&mut self) -> Result<LicenseExpr, InvalidLicenseError> {
+        let mut expr = self.primary()?;
+        if self.peek().is_some_and(|token| token.is_keyword("WITH")) {
+            self.pos += 1;
+            let exception = match self.next() {
+                Some(Token::Word(word)) if is_identifier(word) => word.to_string(),
+                _ => return Err(self.error("expected a license exception after WITH")),
+            };
+            match &mut expr {
+                LicenseExpr::License(req) if req.exception.is_none() => {
+                    req.exception = Some(exception)
+                }
+                _ => return Err(self.error("WITH must follow a single license")),
+            }
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<LicenseExpr, InvalidLicenseError> {
+        match self.next() {
+            Some(Token::Open) => {
+                let expr = self.or_expr()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(self.error("unclosed parenthesis")),
+                }
+            }
+            Some(Token::Word(word))
+                if !["AND", "OR", "WITH"]
+                    .iter()
+                    .any(|keyword| word.eq_ignore_ascii_case(keyword)) =>
+            {
+                let (id, or_later) = match word.strip_suffix('+') {
+                    Some(id) => (id, true),
+                    None => (word, false),
+                };
+                if !is_identifier(id) {
+                    return Err(self.error(&format!("invalid license identifier '{}'", word)));
+                }
+                Ok(LicenseExpr::License(LicenseReq {
+                    id: id.to_string(),
+                    or_later,
+                    exception: None,
+                }))
+            }
+            _ => Err(self.error("expected a license")),
+        }
+    }
+}
+
+fn is_identifier(word: &str) -> bool {
+    !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == ':')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn license(id: &str) -> LicenseExpr {
+        LicenseExpr::License(LicenseReq {
+            id: id.to_string(),
+            or_later: false,
+            exception: None,
+        })
+    }
+
+    #[test]
+    fn test_parse_license_expr() {
+        assert_eq!(
+            LicenseExpr::parse("MIT OR Apache-2.0").unwrap(),
+            LicenseExpr::Or(vec![license("MIT"), license("Apache-2.0")])
+        );
+        assert_eq!(
+            LicenseExpr::parse("MIT/Apache-2.0").unwrap(),
+            LicenseExpr::parse("MIT OR Apache-2.0").unwrap()
+        );
+
+        let expr =
+            LicenseExpr::parse("(MIT or Apache-2.0) AND GPL-2.0+ WITH Classpath-exception-2.0")
+                .unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "(MIT OR Apache-2.0) AND GPL-2.0+ WITH Classpath-exception-2.0"
+        );
+        let gpl = expr.licenses()[2];
+        assert!(gpl.or_later);
+        assert_eq!(gpl.exception.as_deref(), Some("Classpath-exception-2.0"));
+
+        // AND binds tighter than OR.
+        assert_eq!(
+            LicenseExpr::parse("MIT AND ISC OR Zlib").unwrap(),
+            LicenseExpr::Or(vec![
+                LicenseExpr::And(vec![license("MIT"), license("ISC")]),
+                license("Zlib"),
+            ])
+        );
+
+        for invalid in [
+            "",
+            "MIT OR",
+            "(MIT",
+            "MIT)",
+            "MIT WITH",
+            "(MIT OR ISC) WITH x",
+            "M!T",
+        ] {
+            assert!(LicenseExpr::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_license_expr_helpers() {
+        let parse = |s: &str| LicenseExpr::parse(s).unwrap();
+        assert!(parse("MIT OR Apache-2.0").is_osi_approved());
+        assert!(parse("CC0-1.0 OR MIT").is_osi_approved());
+        assert!(!parse("CC0-1.0 AND MIT").is_osi_approved());
+        assert!(!parse("WTFPL").is_osi_approved());
+
+        assert!(parse("GPL-3.0-or-later").is_copyleft());
+        assert!(parse("MIT AND LGPL-2.1-only").is_copyleft());
+        assert!(!parse("MIT OR GPL-3.0").is_copyleft());
+        assert!(!parse("Apache-2.0 WITH LLVM-exception").is_copyleft());
+    }
+}
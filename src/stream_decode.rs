@@ -0,0 +1,204 @@
+//! Decoding response bodies while they are received.
+//!
+//! Requires the `stream-decode` feature. Without it, a body is read into
+//! memory completely before it is decoded, so large pages are held in memory
+//! twice: once as JSON and once decoded. With it, the body is decoded on the
+//! blocking thread pool as its chunks arrive, and only the first few
+//! kilobytes are kept, to report API errors and decoding errors. Decoding
+//! from a reader is somewhat slower than from a buffer, so this trades speed
+//! for memory.
+
+use futures::prelude::*;
+use serde::de::DeserializeOwned;
+use std::io::{self, BufReader, Read};
+use tokio::sync::mpsc;
+
+use crate::error::{CancelledError, JsonDecodeError};
+use crate::{ApiErrors, Error};
+
+/// The number of bytes kept from the start of a body.
+///
+/// API errors are much shorter, so they are always kept completely.
+const PREFIX_LEN: usize = 4096;
+
+/// The number of chunks buffered between the body and the decoder.
+const CHUNKS: usize = 8;
+
+/// Decode the body of a successful response while it is received.
+///
+/// Like [`decode_response`](crate::async_client::decode_response), bodies
+/// that are API errors are returned as [`Error::Api`].
+pub(crate) async fn decode_body<T>(res: reqwest::Response) -> Result<T, Error>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(CHUNKS);
+    let decoder = tokio::task::spawn_blocking(move || decode(&mut ChannelReader::new(rx)));
+
+    let mut body = res.bytes_stream();
+    let mut body_error = None;
+    while let Some(chunk) = body.next().await {
+        match chunk {
+            Ok(chunk) => {
+                // Sending fails if the decoder already gave up.
+                if tx.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+            Err(err) => {
+                body_error = Some(err);
+                break;
+            }
+        }
+    }
+    drop(tx);
+
+    let decoded = match decoder.await {
+        Ok(decoded) => decoded,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(_) => Err(Error::Cancelled(CancelledError::new(
+            "the runtime is shutting down",
+        ))),
+    };
+    // A truncated body fails to decode, so report why it was truncated.
+    match body_error {
+        Some(err) => Err(err.into()),
+        None => decoded,
+    }
+}
+
+/// Decode the chunks received by `reader`.
+fn decode<T, B>(reader: &mut ChannelReader<B>) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    B: AsRef<[u8]>,
+{
+    let mut de = serde_json::Deserializer::from_reader(BufReader::new(&mut *reader));
+    let value = serde_path_to_error::deserialize(&mut de);
+    let end = de.end();
+    drop(de);
+    let prefix = reader.prefix_text();
+
+    // Only bodies with an `errors` key can be errors, and they are short.
+    if reader.len <= PREFIX_LEN && prefix.contains("\"errors\"") {
+        if let Ok(errors) = serde_json::from_str::<ApiErrors>(&prefix) {
+            return Err(Error::Api(errors));
+        }
+    }
+    let value = value.map_err(|err| JsonDecodeError::new(err, &prefix))?;
+    end.map_err(|err| JsonDecodeError::from_json(err, &prefix))?;
+    Ok(value)
+}
+
+/// A reader of the chunks of a body, sent by the task receiving it.
+struct ChannelReader<B> {
+    rx: mpsc::Receiver<B>,
+    chunk: Option<B>,
+    /// The position in `chunk`.
+    pos: usize,
+    /// The first [`PREFIX_LEN`] bytes of the body.
+    prefix: Vec<u8>,
+    /// The number of bytes read so far.
+    len: usize,
+}
+
+impl<B: AsRef<[u8]>> ChannelReader<B> {
+    fn new(rx: mpsc::Receiver<B>) -> Self {
+        Self {
+            rx,
+            chunk: None,
+            pos: 0,
+            prefix: Vec::new(),
+            len: 0,
+        }
+    }
+
+    fn prefix_text(&self) -> String {
+        String::from_utf8_lossy(&self.prefix).into_owned()
+    }
+}
+
+impl<B: AsRef<[u8]>> Read for ChannelReader<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(chunk) = &self.chunk {
+                let rest = &chunk.as_ref()[self.pos..];
+                if !rest.is_empty() {
+                    let n = rest.len().min(buf.len());
+                    buf[..n].copy_from_slice(&rest[..n]);
+                    let keep = n.min(PREFIX_LEN.saturating_sub(self.prefix.len()));
+                    self.prefix.extend_from_slice(&rest[..keep]);
+                    self.pos += n;
+                    self.len += n;
+                    return Ok(n);
+                }
+            }
+            match self.rx.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = Some(chunk);
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Summary;
+
+    fn decode_chunks<T: DeserializeOwned>(chunks: &[&'static str]) -> Result<T, Error> {
+        let (tx, rx) = mpsc::channel(chunks.len().max(1));
+        for chunk in chunks {
+            tx.try_send(chunk.as_bytes()).unwrap();
+        }
+        drop(tx);
+        decode(&mut ChannelReader::new(rx))
+    }
+
+    #[test]
+    fn test_decode_chunks() {
+        let value: serde_json::Value =
+            decode_chunks(&[r#"{"keywords": ["#, r#""errors"]"#, "}\n"]).unwrap();
+        assert_eq!(value["keywords"][0], "errors");
+
+        let errors = [r#"{"errors": [{"detail": "#, r#""Not Found"}]}"#];
+        assert!(matches!(
+            decode_chunks::<serde_json::Value>(&errors),
+            Err(Error::Api(_))
+        ));
+
+        match decode_chunks::<Summary>(&[r#"{"num_crates":"#, r#" "many"}"#]) {
+            Err(Error::JsonDecode(err)) => {
+                assert_eq!(err.path(), Some("num_crates"));
+                assert!(err.snippet().unwrap().contains("many"));
+            }
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+        assert!(matches!(
+            decode_chunks::<serde_json::Value>(&["{}", " {}"]),
+            Err(Error::JsonDecode(_))
+        ));
+        assert!(matches!(
+            decode_chunks::<serde_json::Value>(&[r#"{"a": "#]),
+            Err(Error::JsonDecode(_))
+        ));
+    }
+
+    #[test]
+    fn test_prefix() {
+        let (tx, rx) = mpsc::channel(2);
+        let long = vec![b'a'; PREFIX_LEN];
+        tx.try_send(long.clone()).unwrap();
+        tx.try_send(b"bc".to_vec()).unwrap();
+        drop(tx);
+        let mut reader = ChannelReader::new(rx);
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body).unwrap();
+        assert_eq!(body.len(), PREFIX_LEN + 2);
+        assert_eq!(reader.len, PREFIX_LEN + 2);
+        assert_eq!(reader.prefix, long);
+    }
+}
@@ -1,18 +1,24 @@
 use super::*;
-use std::iter::Extend;
+use std::collections::VecDeque;
+use std::future::Future;
 
-use log::trace;
-use reqwest::{blocking::Client as HttpClient, header, StatusCode, Url};
-use serde::de::DeserializeOwned;
+use futures::stream::{Stream, StreamExt};
+use reqwest::{header, Client as HttpClient};
 
-use crate::{error::JsonDecodeError, types::*};
+use crate::endpoints::{Endpoint, Paginated};
+use crate::types::*;
+use crate::{AsyncClient, CrateStream, Date};
 
 /// A synchronous client for the crates.io API.
+///
+/// Requests are sent by an internal [`AsyncClient`] on a runtime with a
+/// single worker thread, so both clients support the same endpoints and
+/// behave the same.
+/// Like other blocking clients, it must not be used from within an async
+/// runtime.
 pub struct SyncClient {
-    client: HttpClient,
-    base_url: Url,
-    rate_limit: std::time::Duration,
-    last_request_time: std::sync::Mutex<Option<std::time::Instant>>,
+    inner: AsyncClient,
+    runtime: tokio::runtime::Runtime,
 }
 
 impl SyncClient {
@@ -30,7 +36,7 @@ impl SyncClient {
     ///
     /// ```rust
     /// # fn f() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = crates_io_api::AsyncClient::new(
+    /// let client = crates_io_api::SyncClient::new(
     ///   "my_bot (help@my_bot.com)",
     ///   std::time::Duration::from_millis(1000),
     /// ).unwrap();
@@ -47,90 +53,407 @@ impl SyncClient {
             header::HeaderValue::from_str(user_agent)?,
         );
 
-        Ok(Self {
-            client: HttpClient::builder()
-                .default_headers(headers)
-                .build()
-                .unwrap(),
-            base_url: Url::parse("https://crates.io/api/v1/").unwrap(),
-            rate_limit,
-            last_request_time: std::sync::Mutex::new(None),
-        })
-    }
-
-    fn get<T: DeserializeOwned>(&self, url: Url) -> Result<T, Error> {
-        trace!("GET {}", url);
-
-        let mut lock = self.last_request_time.lock().unwrap();
-        if let Some(last_request_time) = lock.take() {
-            let now = std::time::Instant::now();
-            if last_request_time.elapsed() < self.rate_limit {
-                std::thread::sleep((last_request_time + self.rate_limit) - now);
-            }
-        }
+        let client = HttpClient::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
 
-        let time = std::time::Instant::now();
+        Ok(Self::with_http_client(client, rate_limit))
+    }
 
-        let res = self.client.get(url.clone()).send()?;
+    /// Instantiate a new client with a custom HTTP client.
+    ///
+    /// The HTTP client is an async `reqwest::Client`, which is driven by the
+    /// runtime of this client.
+    ///
+    /// To respect the offical [Crawler Policy](https://crates.io/policies#crawlers),
+    /// you must specify both a descriptive user agent and a rate limit interval.
+    ///
+    /// At most one request will be executed in the specified duration.
+    /// The guidelines suggest 1 per second or less.
+    pub fn with_http_client(client: HttpClient, rate_limit: std::time::Duration) -> Self {
+        Self::from_async(AsyncClient::with_http_client(client, rate_limit))
+    }
 
-        if !res.status().is_success() {
-            let err = match res.status() {
-                StatusCode::NOT_FOUND => Error::NotFound(super::error::NotFoundError {
-                    url: url.to_string(),
-                }),
-                StatusCode::FORBIDDEN => {
-                    let reason = res.text().unwrap_or_default();
-                    Error::PermissionDenied(super::error::PermissionDeniedError { reason })
-                }
-                _ => Error::from(res.error_for_status().unwrap_err()),
-            };
+    /// Instantiate a new client from a configuration.
+    ///
+    /// Returns an error if the user agent, token or base URL is invalid.
+    pub fn from_config(config: &crate::ClientConfig) -> Result<Self, Error> {
+        AsyncClient::from_config(config).map(Self::from_async)
+    }
 
-            return Err(err);
-        }
+    fn from_async(inner: AsyncClient) -> Self {
+        // The worker drives background tasks like prefetching, and the
+        // connections they open, between blocking calls.
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("could not start the runtime of the client");
+        Self { inner, runtime }
+    }
+
+    /// Apply a builder method of the inner client.
+    fn map_inner(mut self, f: impl FnOnce(AsyncClient) -> AsyncClient) -> Self {
+        self.inner = f(self.inner);
+        self
+    }
 
-        *lock = Some(time);
+    /// Wait for a future of the inner client.
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
 
-        let content = res.text()?;
+    /// Use a different API root, eg a mirror or a mock server.
+    ///
+    /// Returns an error if the URL is invalid.
+    pub fn with_base_url(mut self, base_url: &str) -> Result<Self, Error> {
+        self.inner = self.inner.with_base_url(base_url)?;
+        Ok(self)
+    }
 
-        // First, check for api errors.
+    /// Limit the number of requests, see [`Budget`](crate::Budget).
+    #[must_use]
+    pub fn with_budget(self, budget: std::sync::Arc<crate::Budget>) -> Self {
+        self.map_inner(|inner| inner.with_budget(budget))
+    }
 
-        if let Ok(errors) = serde_json::from_str::<ApiErrors>(&content) {
-            return Err(Error::Api(errors));
-        }
+    /// Answer requests from a cache, see [`CacheStore`](crate::CacheStore).
+    ///
+    /// Responses are cached for `ttl`.
+    #[must_use]
+    pub fn with_cache(
+        self,
+        cache: std::sync::Arc<dyn crate::CacheStore>,
+        ttl: std::time::Duration,
+    ) -> Self {
+        self.map_inner(|inner| inner.with_cache(cache, ttl))
+    }
 
-        let jd = &mut serde_json::Deserializer::from_str(&content);
-        serde_path_to_error::deserialize::<_, T>(jd).map_err(|err| {
-            Error::JsonDecode(JsonDecodeError {
-                message: format!("Could not decode JSON: {err} (path: {})", err.path()),
-            })
-        })
+    /// Share the rate limit with all clients using the same store, eg
+    /// replicas of a bot sharing a `RedisStore` (requires the `redis` feature).
+    ///
+    /// Every request claims a key in the store for the duration of the rate
+    /// limit, see [`CacheStore::claim`](crate::CacheStore::claim).
+    #[must_use]
+    pub fn with_shared_rate_limit(self, store: std::sync::Arc<dyn crate::CacheStore>) -> Self {
+        self.map_inner(|inner| inner.with_shared_rate_limit(store))
+    }
+
+    /// Sort the crate queries passed to this client that don't choose a sort
+    /// with `sort`, rather than [`Sort::RecentUpdates`].
+    ///
+    /// A sort set on the query, eg with [`CratesQueryBuilder::sort`], takes
+    /// precedence. The default doesn't apply to endpoints called with
+    /// [`Self::send`].
+    #[must_use]
+    pub fn with_default_sort(self, sort: Sort) -> Self {
+        self.map_inner(|inner| inner.with_default_sort(sort))
+    }
+
+    /// Fetch the crate queries passed to this client that don't choose a
+    /// page size in pages of `page_size`, rather than 30.
+    ///
+    /// Crawls with the maximum of [`CratesQuery::MAX_PAGE_SIZE`] need the
+    /// fewest requests. A page size set on the query, eg with
+    /// [`CratesQueryBuilder::page_size`], takes precedence. The default
+    /// doesn't apply to endpoints called with [`Self::send`].
+    #[must_use]
+    pub fn with_default_page_size(self, page_size: u64) -> Self {
+        self.map_inner(|inner| inner.with_default_page_size(page_size))
+    }
+
+    /// Cache the results of [`Self::autocomplete`] for `ttl`, separately from
+    /// the cache of other requests.
+    ///
+    /// A short TTL of a few minutes keeps typeahead snappy when users delete
+    /// and retype characters.
+    #[must_use]
+    pub fn with_autocomplete_cache(self, ttl: std::time::Duration) -> Self {
+        self.map_inner(|inner| inner.with_autocomplete_cache(ttl))
+    }
+
+    /// Save the rate limiter and budget state to disk, see
+    /// [`LimiterState`](crate::LimiterState).
+    ///
+    /// The counters of an attached budget are restored from the state.
+    #[must_use]
+    pub fn with_limiter_state(self, state: std::sync::Arc<crate::LimiterState>) -> Self {
+        self.map_inner(|inner| inner.with_limiter_state(state))
+    }
+
+    /// Inject faults into a share of the requests.
+    ///
+    /// Requires the `faults` feature. See the [`faults`](crate::faults)
+    /// module.
+    #[cfg(feature = "faults")]
+    #[must_use]
+    pub fn with_faults(self, faults: std::sync::Arc<crate::faults::Faults>) -> Self {
+        self.map_inner(|inner| inner.with_faults(faults))
+    }
+
+    /// Record responses to, or replay them from, a fixture file.
+    ///
+    /// Requires the `fixtures` feature. See the [`fixtures`](crate::fixtures)
+    /// module.
+    #[cfg(feature = "fixtures")]
+    #[must_use]
+    pub fn with_fixtures(self, fixtures: std::sync::Arc<crate::fixtures::Fixtures>) -> Self {
+        self.map_inner(|inner| inner.with_fixtures(fixtures))
+    }
+
+    /// Set how fields in API responses that are not modelled by this crate
+    /// are treated.
+    ///
+    /// By default, they are ignored.
+    #[must_use]
+    pub fn unknown_fields(self, mode: UnknownFields) -> Self {
+        self.map_inner(|inner| inner.unknown_fields(mode))
+    }
+
+    /// Call a typed endpoint, see the [`endpoints`](crate::endpoints) module.
+    pub fn send<E: Endpoint>(&self, endpoint: &E) -> Result<E::Output, Error> {
+        self.block_on(self.inner.send(endpoint))
+    }
+
+    /// Call a typed endpoint with extra headers and query parameters.
+    pub fn send_with<E: Endpoint>(
+        &self,
+        endpoint: &E,
+        options: &RequestOptions,
+    ) -> Result<E::Output, Error> {
+        self.block_on(self.inner.send_with(endpoint, options))
+    }
+
+    /// Fetch `n` pages of a paginated endpoint, starting at its page.
+    ///
+    /// Stops early at an empty page, which ends the results. The returned
+    /// `next_page` can be set on the endpoint to collect the following pages
+    /// later.
+    pub fn collect_pages<E: Paginated>(
+        &self,
+        endpoint: &E,
+        n: u64,
+    ) -> Result<CollectedPages<E::Item>, Error> {
+        self.block_on(self.inner.collect_pages(endpoint, n))
+    }
+
+    /// Send a GET request to an arbitrary API path and return the raw JSON.
+    ///
+    /// This is an escape hatch for endpoints that don't have a typed method
+    /// yet. The path is relative to the API root, eg
+    /// `"crates/serde/reverse_dependencies"`, and its segments are escaped.
+    /// The request is rate limited like all others.
+    pub fn get_raw(&self, path: &str, query: &[(&str, &str)]) -> Result<serde_json::Value, Error> {
+        self.block_on(self.inner.get_raw(path, query))
     }
 
     /// Retrieve a summary containing crates.io wide information.
     pub fn summary(&self) -> Result<Summary, Error> {
-        let url = self.base_url.join("summary").unwrap();
-        self.get(url)
+        self.block_on(self.inner.summary())
     }
 
     /// Retrieve information of a crate.
     ///
     /// If you require detailed information, consider using [full_crate]().
     pub fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, Error> {
-        let url = super::async_client::build_crate_url(&self.base_url, crate_name)?;
-        self.get(url)
+        self.block_on(self.inner.get_crate(crate_name))
+    }
+
+    /// Retrieve information of a crate, tolerating `-` vs `_` mismatches.
+    ///
+    /// If the crate can't be found under the given name, the lookup is retried
+    /// with all separators replaced by `_` and then by `-`.
+    /// Use [`CrateResponse::crate_data`] to get the canonical name.
+    pub fn get_crate_normalized(&self, crate_name: &str) -> Result<CrateResponse, Error> {
+        self.block_on(self.inner.get_crate_normalized(crate_name))
+    }
+
+    /// Get the canonical name of a crate, as reported by the API.
+    ///
+    /// See [`Self::get_crate_normalized`].
+    pub fn canonical_crate_name(&self, crate_name: &str) -> Result<String, Error> {
+        self.block_on(self.inner.canonical_crate_name(crate_name))
+    }
+
+    /// Retrieve information for multiple crates.
+    ///
+    /// Returns a lazy iterator that yields each crate name together with its
+    /// result, in the given order.
+    /// Requests are performed one by one when the iterator is advanced.
+    pub fn get_many_crates<'a, I, S>(
+        &'a self,
+        names: I,
+    ) -> impl Iterator<Item = (String, Result<CrateResponse, Error>)> + 'a
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Blocking::new(&self.runtime, self.inner.get_many_crates(names, 1))
+    }
+
+    /// Check for updates of a list of dependencies.
+    ///
+    /// Takes pairs of crate names and version requirements, and returns the
+    /// newest matching and newest stable version for each, in the same order.
+    /// Crate names are looked up with [`Self::get_crate_normalized`].
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn check_updates<N, R>(&self, deps: &[(N, R)]) -> Result<Vec<UpdateCheck>, Error>
+    where
+        N: AsRef<str>,
+        R: AsRef<str>,
+    {
+        self.block_on(self.inner.check_updates(deps))
+    }
+
+    /// Suggest updates for a list of dependencies.
+    ///
+    /// Takes pairs of crate names and current versions, and returns the
+    /// newest compatible and newest incompatible version for each, in the
+    /// same order. Crate names are looked up with
+    /// [`Self::get_crate_normalized`].
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn suggest_updates<N, V>(&self, deps: &[(N, V)]) -> Result<Vec<UpdateSuggestion>, Error>
+    where
+        N: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.block_on(self.inner.suggest_updates(deps))
+    }
+
+    /// Check the crates.io packages of a `Cargo.lock` for yanked versions.
+    ///
+    /// Requires the `lockfile` feature.
+    /// Every crate is only requested once, even if multiple versions of it
+    /// are locked.
+    #[cfg(feature = "lockfile")]
+    pub fn audit_yanked(
+        &self,
+        lockfile: &crate::lockfile::Lockfile,
+    ) -> Result<crate::lockfile::LockfileAudit, Error> {
+        self.block_on(self.inner.audit_yanked(lockfile))
+    }
+
+    /// Enrich the crates.io packages of a `Cargo.lock` with the live metadata
+    /// of their crates: the latest version, yanked status, downloads and
+    /// owners.
+    ///
+    /// Every crate takes two requests, even if multiple versions of it are
+    /// locked.
+    ///
+    /// Requires the `lockfile` feature.
+    #[cfg(feature = "lockfile")]
+    pub fn enrich_lockfile(
+        &self,
+        lockfile: &crate::lockfile::Lockfile,
+    ) -> Result<crate::lockfile::LockfileReport, Error> {
+        self.block_on(self.inner.enrich_lockfile(lockfile))
+    }
+
+    /// Fetch crates into the cache in the background, so that later calls of
+    /// [`Self::get_crate`] are answered without waiting.
+    ///
+    /// Returns right away, with a handle to wait until all crates are
+    /// fetched. The crates are fetched on the runtime of the client, and
+    /// stop being fetched when the client is dropped. Prefetching has low
+    /// priority: its requests are only sent while no other request of the
+    /// client waits for the rate limit, so they delay those by at most one
+    /// request. Errors are logged and otherwise ignored. Without a cache,
+    /// nothing is fetched.
+    pub fn prefetch<I, S>(&self, crate_names: I) -> PrefetchHandle
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        PrefetchHandle {
+            runtime: self.runtime.handle().clone(),
+            task: self.runtime.spawn(self.inner.prefetch(crate_names)),
+        }
+    }
+
+    /// Check if a crate exists.
+    ///
+    /// Only fetches the bare crate record, without versions, keywords or
+    /// categories.
+    pub fn crate_exists(&self, crate_name: &str) -> Result<bool, Error> {
+        self.block_on(self.inner.crate_exists(crate_name))
+    }
+
+    /// Check if a specific version of a crate exists.
+    ///
+    /// Yanked versions exist.
+    pub fn version_exists(&self, crate_name: &str, version: &str) -> Result<bool, Error> {
+        self.block_on(self.inner.version_exists(crate_name, version))
+    }
+
+    /// Retrieve a single version of a crate.
+    pub fn crate_version(&self, crate_name: &str, version: &str) -> Result<Version, Error> {
+        self.block_on(self.inner.crate_version(crate_name, version))
+    }
+
+    /// Compare the key metrics of multiple crates.
+    ///
+    /// The crates themselves are fetched in batches of up to 100, while the
+    /// release and reverse dependency data requires two more requests for
+    /// every crate. Crates that are named more than once are compared once.
+    pub fn compare_crates<S: AsRef<str>>(
+        &self,
+        crate_names: &[S],
+    ) -> Result<CrateComparison, Error> {
+        self.block_on(self.inner.compare_crates(crate_names))
     }
 
     /// Retrieve download stats for a crate.
     pub fn crate_downloads(&self, crate_name: &str) -> Result<CrateDownloads, Error> {
-        let url = super::async_client::build_crate_downloads_url(&self.base_url, crate_name)?;
-        self.get(url)
+        self.block_on(self.inner.crate_downloads(crate_name))
+    }
+
+    /// Retrieve download stats for a crate, restricted to the days from `from`
+    /// to `to`, inclusive.
+    ///
+    /// Note: crates.io only provides the downloads of the last 90 days.
+    pub fn downloads_between(
+        &self,
+        crate_name: &str,
+        from: Date,
+        to: Date,
+    ) -> Result<CrateDownloads, Error> {
+        self.block_on(self.inner.downloads_between(crate_name, from, to))
+    }
+
+    /// Retrieve download stats for a single crate version.
+    ///
+    /// Unlike [`Self::crate_downloads`], this also works for versions whose
+    /// downloads are only included in the `extra_downloads`.
+    pub fn crate_version_downloads(
+        &self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<Vec<VersionDownloads>, Error> {
+        self.block_on(self.inner.crate_version_downloads(crate_name, version))
     }
 
     /// Retrieve the owners of a crate.
     pub fn crate_owners(&self, crate_name: &str) -> Result<Vec<User>, Error> {
-        let url = super::async_client::build_crate_owners_url(&self.base_url, crate_name)?;
-        let resp: Owners = self.get(url)?;
-        Ok(resp.users)
+        self.block_on(self.inner.crate_owners(crate_name))
+    }
+
+    /// Retrieve the owners of a crate, with teams told apart from users.
+    pub fn crate_owners_typed(&self, crate_name: &str) -> Result<Vec<Owner>, Error> {
+        self.block_on(self.inner.crate_owners_typed(crate_name))
+    }
+
+    /// Retrieve the owners of multiple crates, to find the users and teams
+    /// they have in common.
+    pub fn shared_owners<S: AsRef<str>>(
+        &self,
+        crate_names: &[S],
+    ) -> Result<OwnershipOverlap, Error> {
+        self.block_on(self.inner.shared_owners(crate_names))
     }
 
     /// Get a single page of reverse dependencies.
@@ -141,17 +464,7 @@ impl SyncClient {
         crate_name: &str,
         page: u64,
     ) -> Result<ReverseDependencies, Error> {
-        let url =
-            super::async_client::build_crate_reverse_deps_url(&self.base_url, crate_name, page)?;
-        let page = self.get::<ReverseDependenciesAsReceived>(url)?;
-
-        let mut deps = ReverseDependencies {
-            dependencies: Vec::new(),
-            meta: Meta { total: 0 },
-        };
-        deps.meta.total = page.meta.total;
-        deps.extend(page);
-        Ok(deps)
+        self.block_on(self.inner.crate_reverse_dependencies_page(crate_name, page))
     }
 
     /// Load all reverse dependencies of a crate.
@@ -163,37 +476,83 @@ impl SyncClient {
         &self,
         crate_name: &str,
     ) -> Result<ReverseDependencies, Error> {
-        let mut deps = ReverseDependencies {
-            dependencies: Vec::new(),
-            meta: Meta { total: 0 },
-        };
-
-        for page_number in 1.. {
-            let page = self.crate_reverse_dependencies_page(crate_name, page_number)?;
-            if page.dependencies.is_empty() {
-                break;
-            }
-
-            deps.dependencies.extend(page.dependencies);
-            deps.meta.total = page.meta.total;
-        }
-        Ok(deps)
+        self.block_on(self.inner.crate_reverse_dependencies(crate_name))
     }
 
     /// Get the total count of reverse dependencies for a given crate.
+    ///
+    /// Only a single reverse dependency is fetched.
     pub fn crate_reverse_dependency_count(&self, crate_name: &str) -> Result<u64, Error> {
-        let page = self.crate_reverse_dependencies_page(crate_name, 1)?;
-        Ok(page.meta.total)
+        self.block_on(self.inner.crate_reverse_dependency_count(crate_name))
+    }
+
+    /// Get the total count of reverse dependencies for multiple crates.
+    ///
+    /// Only a single reverse dependency is fetched for each crate.
+    pub fn reverse_dependency_counts<S: AsRef<str>>(
+        &self,
+        crate_names: &[S],
+    ) -> Result<std::collections::HashMap<String, u64>, Error> {
+        self.block_on(self.inner.reverse_dependency_counts(crate_names))
+    }
+
+    /// Build a graph of all crates that transitively depend on the given
+    /// crate, ie all crates that would be affected if it broke.
+    ///
+    /// The graph is walked breadth-first within the given [`GraphLimits`].
+    /// Note: every crate in the graph requires at least one request, since
+    /// all of its reverse dependencies are fetched.
+    pub fn reverse_dependency_graph(
+        &self,
+        crate_name: &str,
+        limits: GraphLimits,
+    ) -> Result<DependencyGraph, Error> {
+        self.block_on(self.inner.reverse_dependency_graph(crate_name, limits))
+    }
+
+    /// Compare the dependencies of two versions of a crate.
+    pub fn diff_dependencies(
+        &self,
+        crate_name: &str,
+        old_version: &str,
+        new_version: &str,
+    ) -> Result<DependencyDiff, Error> {
+        self.block_on(
+            self.inner
+                .diff_dependencies(crate_name, old_version, new_version),
+        )
+    }
+
+    /// Resolve the dependency tree of a crate version.
+    ///
+    /// Every dependency that passes the [`DependencyFilter`] is resolved to
+    /// the newest non-yanked version matching its requirement, and expanded
+    /// until `max_depth` levels of the tree are resolved.
+    /// Crate versions that appear multiple times are only expanded once.
+    ///
+    /// Note: every crate and crate version in the tree requires a request.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn dependency_tree(
+        &self,
+        crate_name: &str,
+        version: &str,
+        max_depth: usize,
+        filter: &DependencyFilter,
+    ) -> Result<DependencyTree, Error> {
+        self.block_on(
+            self.inner
+                .dependency_tree(crate_name, version, max_depth, filter),
+        )
     }
 
     /// Retrieve the authors for a crate version.
+    ///
+    /// If crates.io no longer provides the authors, an empty list is
+    /// returned, see [`Authors::unavailable`].
     pub fn crate_authors(&self, crate_name: &str, version: &str) -> Result<Authors, Error> {
-        let url =
-            super::async_client::build_crate_authors_url(&self.base_url, crate_name, version)?;
-        let res: AuthorsResponse = self.get(url)?;
-        Ok(Authors {
-            names: res.meta.names,
-        })
+        self.block_on(self.inner.crate_authors(crate_name, version))
     }
 
     /// Retrieve the dependencies of a crate version.
@@ -202,33 +561,7 @@ impl SyncClient {
         crate_name: &str,
         version: &str,
     ) -> Result<Vec<Dependency>, Error> {
-        let url =
-            super::async_client::build_crate_dependencies_url(&self.base_url, crate_name, version)?;
-        let resp: Dependencies = self.get(url)?;
-        Ok(resp.dependencies)
-    }
-
-    fn full_version(&self, version: Version) -> Result<FullVersion, Error> {
-        let authors = self.crate_authors(&version.crate_name, &version.num)?;
-        let deps = self.crate_dependencies(&version.crate_name, &version.num)?;
-
-        let v = FullVersion {
-            created_at: version.created_at,
-            updated_at: version.updated_at,
-            dl_path: version.dl_path,
-            downloads: version.downloads,
-            features: version.features,
-            id: version.id,
-            num: version.num,
-            yanked: version.yanked,
-            license: version.license,
-            links: version.links,
-            readme_path: version.readme_path,
-
-            author_names: authors.names,
-            dependencies: deps,
-        };
-        Ok(v)
+        self.block_on(self.inner.crate_dependencies(crate_name, version))
     }
 
     /// Retrieve all available information for a crate, including download
@@ -240,50 +573,67 @@ impl SyncClient {
     /// detailed information for all versions will be available.
     ///
     /// Note: Each version requires two extra requests.
+    ///
+    /// See [`Self::full_crate_with_options`] for more control over the
+    /// requests that are made.
     pub fn full_crate(&self, name: &str, all_versions: bool) -> Result<FullCrate, Error> {
-        let resp = self.get_crate(name)?;
-        let data = resp.crate_data;
-
-        let dls = self.crate_downloads(name)?;
-        let owners = self.crate_owners(name)?;
-        let reverse_dependencies = self.crate_reverse_dependencies(name)?;
-
-        let versions = if resp.versions.is_empty() {
-            vec![]
-        } else if all_versions {
-            //let versions_res: Result<Vec<FullVersion>> = resp.versions
-            resp.versions
-                .into_iter()
-                .map(|v| self.full_version(v))
-                .collect::<Result<Vec<FullVersion>, Error>>()?
-        } else {
-            let v = self.full_version(resp.versions[0].clone())?;
-            vec![v]
-        };
+        self.block_on(self.inner.full_crate(name, all_versions))
+    }
 
-        let full = FullCrate {
-            id: data.id,
-            name: data.name,
-            description: data.description,
-            license: versions[0].license.clone(),
-            documentation: data.documentation,
-            homepage: data.homepage,
-            repository: data.repository,
-            total_downloads: data.downloads,
-            recent_downloads: data.recent_downloads,
-            max_version: data.max_version,
-            max_stable_version: data.max_stable_version,
-            created_at: data.created_at,
-            updated_at: data.updated_at,
-
-            categories: resp.categories,
-            keywords: resp.keywords,
-            downloads: dls,
-            owners,
-            reverse_dependencies,
-            versions,
-        };
-        Ok(full)
+    /// Retrieve all available information for a crate, with version details
+    /// only for the versions selected by the filter.
+    pub fn full_crate_versions(
+        &self,
+        name: &str,
+        filter: VersionFilter,
+    ) -> Result<FullCrate, Error> {
+        self.block_on(self.inner.full_crate_versions(name, filter))
+    }
+
+    /// Retrieve information for a crate, including only the parts selected
+    /// by the given [`FullCrateOptions`].
+    ///
+    /// Parts that are skipped are left empty in the returned [`FullCrate`].
+    pub fn full_crate_with_options(
+        &self,
+        name: &str,
+        options: &FullCrateOptions,
+    ) -> Result<FullCrate, Error> {
+        self.block_on(self.inner.full_crate_with_options(name, options))
+    }
+
+    /// Fetch a crate and plan the requests needed to retrieve the rest of
+    /// the information selected by the options.
+    pub fn full_crate_plan(
+        &self,
+        name: &str,
+        options: &FullCrateOptions,
+    ) -> Result<FullCratePlan, Error> {
+        self.block_on(self.inner.full_crate_plan(name, options))
+    }
+
+    /// Retrieve the rest of the information for a planned crate, see
+    /// [`Self::full_crate_plan`].
+    ///
+    /// The options should be the ones the plan was made with.
+    pub fn full_crate_from_plan(
+        &self,
+        plan: FullCratePlan,
+        options: &FullCrateOptions,
+    ) -> Result<FullCrate, Error> {
+        self.block_on(self.inner.full_crate_from_plan(plan, options))
+    }
+
+    /// Estimate how many requests an operation sends, so that callers can
+    /// warn users or pick cheaper options before committing to it.
+    ///
+    /// Estimating sends requests itself: one for reverse dependencies and
+    /// crawls, and two for full crates. They are not part of the estimate.
+    pub fn estimate_cost(
+        &self,
+        operation: &crate::Operation<'_>,
+    ) -> Result<crate::CostEstimate, Error> {
+        self.block_on(self.inner.estimate_cost(operation))
     }
 
     /// Retrieve a page of crates, optionally constrained by a query.
@@ -291,6 +641,9 @@ impl SyncClient {
     /// If you want to get all results without worrying about paging,
     /// use [`all_crates`].
     ///
+    /// Returns [`Error::InvalidQuery`] without performing a request if the
+    /// query is invalid (see [`CratesQuery::validate`]).
+    ///
     /// # Examples
     ///
     /// Retrieve the first page of results for the query "api", with 100 items
@@ -314,22 +667,433 @@ impl SyncClient {
     /// # }
     /// ```
     pub fn crates(&self, query: CratesQuery) -> Result<CratesPage, Error> {
-        let mut url = self.base_url.join("crates")?;
-        query.build(url.query_pairs_mut());
+        self.block_on(self.inner.crates(query))
+    }
 
-        self.get(url)
+    /// Get the total count of crates matching a query.
+    ///
+    /// Only a single crate is fetched, the page and page size of the query
+    /// are ignored.
+    pub fn crates_count(&self, query: &CratesQuery) -> Result<u64, Error> {
+        self.block_on(self.inner.crates_count(query))
+    }
+
+    /// Get the `limit` most relevant crates matching the beginning of a
+    /// name, for typeahead.
+    ///
+    /// At most [`CratesQuery::MAX_PAGE_SIZE`] crates are returned, and an
+    /// empty prefix is answered without a request. Blocking requests can't be
+    /// cancelled, so typeahead in an event loop should use the
+    /// [`AsyncClient`](crate::AsyncClient) instead, which cancels stale
+    /// requests.
+    pub fn autocomplete(&self, prefix: &str, limit: u64) -> Result<Vec<Crate>, Error> {
+        self.block_on(self.inner.autocomplete(prefix, limit))
+    }
+
+    /// Search for a crate with exactly the given name.
+    ///
+    /// Relies on the exact match detection of the server, which ignores case
+    /// and `-` vs `_` differences. Returns `None` if there is no exact match.
+    pub fn find_crate_exact(&self, name: &str) -> Result<Option<Crate>, Error> {
+        self.block_on(self.inner.find_crate_exact(name))
     }
 
     /// Retrieves a user by username.
     pub fn user(&self, username: &str) -> Result<User, Error> {
-        let url = self.base_url.join(&format!("users/{}", username))?;
-        self.get::<UserResponse>(url).map(|response| response.user)
+        self.block_on(self.inner.user(username))
+    }
+
+    /// Retrieves a team by login.
+    ///
+    /// Team logins have the form `github:<org>:<team>`.
+    pub fn team(&self, login: &str) -> Result<Team, Error> {
+        self.block_on(self.inner.team(login))
+    }
+
+    /// Iterate over all crates owned by the user with the given login,
+    /// alphabetically.
+    ///
+    /// The user is resolved first, so an unknown login fails with
+    /// [`Error::NotFound`] instead of yielding no crates.
+    pub fn crates_by_user(&self, login: &str) -> Result<CratesIter<'_>, Error> {
+        let crates = self.block_on(self.inner.crates_by_user(login))?;
+        Ok(CratesIter::new(self, crates))
+    }
+
+    /// Retrieve all crates owned by the team with the given login.
+    ///
+    /// Note: this first resolves the team, and then pages through the crates
+    /// list, which will result in multiple requests.
+    pub fn crates_owned_by_team(&self, team_login: &str) -> Result<Vec<Crate>, Error> {
+        self.block_on(self.inner.crates_owned_by_team(team_login))
+    }
+
+    /// Retrieves a keyword by its id.
+    pub fn keyword(&self, id: &str) -> Result<Keyword, Error> {
+        self.block_on(self.inner.keyword(id))
+    }
+
+    /// Retrieves a category by its slug.
+    pub fn category(&self, slug: &str) -> Result<Category, Error> {
+        self.block_on(self.inner.category(slug))
+    }
+
+    /// Iterate over all crates with a keyword, by recent downloads.
+    ///
+    /// The keyword is checked first, so an unknown keyword fails with
+    /// [`Error::NotFound`] instead of yielding no crates.
+    pub fn crates_with_keyword(&self, keyword: &str) -> Result<CratesIter<'_>, Error> {
+        let crates = self.block_on(self.inner.crates_with_keyword(keyword))?;
+        Ok(CratesIter::new(self, crates))
+    }
+
+    /// Iterate over all crates in a category, by recent downloads.
+    ///
+    /// The category is checked first, so an unknown slug fails with
+    /// [`Error::NotFound`] instead of yielding no crates.
+    pub fn crates_in_category(&self, slug: &str) -> Result<CratesIter<'_>, Error> {
+        let crates = self.block_on(self.inner.crates_in_category(slug))?;
+        Ok(CratesIter::new(self, crates))
+    }
+
+    /// Retrieve the `n` crates with the most downloads of all time.
+    ///
+    /// Unlike [`Summary::most_downloaded`], `n` isn't limited to 10.
+    pub fn most_downloaded(&self, n: usize) -> Result<Vec<Crate>, Error> {
+        self.block_on(self.inner.most_downloaded(n))
+    }
+
+    /// Retrieve the `n` crates with the most downloads in the last 90 days.
+    ///
+    /// Unlike [`Summary::most_recently_downloaded`], `n` isn't limited to 10.
+    pub fn most_recently_downloaded(&self, n: usize) -> Result<Vec<Crate>, Error> {
+        self.block_on(self.inner.most_recently_downloaded(n))
+    }
+
+    /// Retrieve the `n` most recently published new crates.
+    ///
+    /// Unlike [`Summary::new_crates`], `n` isn't limited to 10.
+    pub fn newest_crates(&self, n: usize) -> Result<Vec<Crate>, Error> {
+        self.block_on(self.inner.newest_crates(n))
+    }
+
+    /// Take a snapshot of all crates in a category, with their downloads,
+    /// dependents and latest release.
+    ///
+    /// Takes two requests per crate on top of the pages of the category, all
+    /// under the rate limit. Crates that are deleted while the snapshot is
+    /// taken are left out. See [`SnapshotOptions::resumable`] to continue a
+    /// failed snapshot instead of starting over.
+    pub fn category_snapshot(
+        &self,
+        slug: &str,
+        options: &SnapshotOptions,
+    ) -> Result<CategorySnapshot, Error> {
+        self.block_on(self.inner.category_snapshot(slug, options))
+    }
+
+    /// Retrieve the top `n` crates in a category, ordered by `sort`.
+    ///
+    /// Fetches as many pages as needed. Returns fewer than `n` crates only if
+    /// the category doesn't contain enough crates.
+    pub fn top_crates_in_category(
+        &self,
+        category_slug: &str,
+        n: usize,
+        sort: Sort,
+    ) -> Result<Vec<Crate>, Error> {
+        self.block_on(self.inner.top_crates_in_category(category_slug, n, sort))
+    }
+
+    /// Iterate over all crates matching a query, starting at its page.
+    ///
+    /// Pages are fetched lazily, when the crates of the previous page are
+    /// used up. This is the blocking equivalent of
+    /// [`AsyncClient::crates_stream`](crate::AsyncClient::crates_stream).
+    pub fn crates_iter(&self, query: CratesQuery) -> CratesIter<'_> {
+        CratesIter::new(self, self.inner.crates_stream(query))
+    }
+
+    /// Enumerate the names of all crates on crates.io, alphabetically.
+    ///
+    /// Crawls pages of [`CratesQuery::MAX_PAGE_SIZE`] crates in a stable
+    /// order (see [`CrateStream::stable_order`](crate::CrateStream::stable_order)),
+    /// so crates published or deleted during the crawl don't shift the
+    /// others. At one request per second, the crawl takes about half an
+    /// hour. Analyses that don't need live data can read the names from the
+    /// database dump (requires the `db-dump` feature) with a single download
+    /// instead.
+    pub fn all_crate_names(&self) -> impl Iterator<Item = Result<String, Error>> + '_ {
+        Blocking::new(&self.runtime, self.inner.all_crate_names())
+    }
+
+    /// Iterate over all crates, newest first.
+    ///
+    /// This is the blocking equivalent of
+    /// [`AsyncClient::new_crates_stream`](crate::AsyncClient::new_crates_stream).
+    pub fn new_crates_iter(&self) -> impl Iterator<Item = Result<Crate, Error>> + '_ {
+        Blocking::new(&self.runtime, self.inner.new_crates_stream())
+    }
+
+    /// Iterate over all crates, most recently updated first.
+    ///
+    /// This is the blocking equivalent of
+    /// [`AsyncClient::just_updated_stream`](crate::AsyncClient::just_updated_stream).
+    pub fn just_updated_iter(&self) -> impl Iterator<Item = Result<Crate, Error>> + '_ {
+        Blocking::new(&self.runtime, self.inner.just_updated_stream())
+    }
+
+    /// Iterate over the [`FullCrate`]s of all crates matching a query.
+    ///
+    /// This is the blocking equivalent of
+    /// [`AsyncClient::full_crates_stream`](crate::AsyncClient::full_crates_stream).
+    /// See [`Self::full_crates_events`] to skip crates that disappear during
+    /// the crawl.
+    pub fn full_crates_iter(
+        &self,
+        query: CratesQuery,
+        options: FullCrateOptions,
+    ) -> impl Iterator<Item = Result<FullCrate, Error>> + '_ {
+        Blocking::new(&self.runtime, self.inner.full_crates_stream(query, options))
+    }
+
+    /// Iterate over the [`FullCrate`]s of all crates matching a query.
+    ///
+    /// Crates that are listed and then not found when their details are
+    /// fetched are reported as [`CrawlEvent::Skipped`] instead of ending the
+    /// iteration with [`Error::NotFound`]. Stops after the first other
+    /// error.
+    pub fn full_crates_events(
+        &self,
+        query: CratesQuery,
+        options: FullCrateOptions,
+    ) -> impl Iterator<Item = Result<CrawlEvent<FullCrate>, Error>> + '_ {
+        let mut failed = false;
+        Blocking::new(&self.runtime, self.inner.full_crates_events(query, options))
+            .take_while(move |event| !std::mem::replace(&mut failed, event.is_err()))
+    }
+
+    /// Iterate over all reverse dependencies of a crate.
+    ///
+    /// Pages of 100 reverse dependencies are fetched lazily.
+    pub fn reverse_dependencies_iter<'a>(
+        &'a self,
+        crate_name: &'a str,
+    ) -> ReverseDependenciesIter<'a> {
+        let mut page = 1;
+        ReverseDependenciesIter(Pager::new(move || {
+            let deps = self.crate_reverse_dependencies_page(crate_name, page)?;
+            page += 1;
+            Ok((deps.dependencies, deps.meta.total))
+        }))
+    }
+
+    /// Iterate over all versions of a crate, newest first.
+    ///
+    /// crates.io returns all versions of a crate at once, so a single request
+    /// is made when the first version is requested.
+    pub fn versions_iter<'a>(&'a self, crate_name: &'a str) -> VersionsIter<'a> {
+        let mut fetched = false;
+        VersionsIter(Pager::new(move || {
+            if std::mem::replace(&mut fetched, true) {
+                return Ok((Vec::new(), 0));
+            }
+            let versions = self.get_crate(crate_name)?.versions;
+            let total = versions.len() as u64;
+            Ok((versions, total))
+        }))
+    }
+}
+
+/// A handle to the crates prefetched by [`SyncClient::prefetch`].
+///
+/// Dropping the handle doesn't stop prefetching.
+#[derive(Debug)]
+pub struct PrefetchHandle {
+    runtime: tokio::runtime::Handle,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl PrefetchHandle {
+    /// Check whether all crates were fetched.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait until all crates are fetched, or the client was dropped.
+    ///
+    /// Like [`SyncClient`], this must not be called from within an async
+    /// runtime.
+    pub fn join(self) {
+        if let Err(err) = self.runtime.block_on(self.task) {
+            if err.is_panic() {
+                std::panic::resume_unwind(err.into_panic());
+            }
+        }
+    }
+}
+
+/// Iterates over a stream of the inner client, on the runtime of the
+/// [`SyncClient`].
+struct Blocking<'a, S> {
+    runtime: &'a tokio::runtime::Runtime,
+    stream: S,
+}
+
+impl<'a, S> Blocking<'a, S> {
+    fn new(runtime: &'a tokio::runtime::Runtime, stream: S) -> Self {
+        Self { runtime, stream }
+    }
+}
+
+impl<S: Stream + Unpin> Iterator for Blocking<'_, S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+/// A page of items, and the total count of items on all pages.
+type Page<T> = (Vec<T>, u64);
+
+/// Lazily fetches pages until an empty page or an error is returned.
+struct Pager<'a, T> {
+    fetch: Box<dyn FnMut() -> Result<Page<T>, Error> + 'a>,
+    items: VecDeque<T>,
+    total: Option<u64>,
+    closed: bool,
+}
+
+impl<'a, T> Pager<'a, T> {
+    fn new(fetch: impl FnMut() -> Result<Page<T>, Error> + 'a) -> Self {
+        Self {
+            fetch: Box::new(fetch),
+            items: VecDeque::new(),
+            total: None,
+            closed: false,
+        }
+    }
+}
+
+impl<T> Iterator for Pager<'_, T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.items.pop_front() {
+            return Some(Ok(item));
+        }
+        if self.closed {
+            return None;
+        }
+        match (self.fetch)() {
+            Ok((page, total)) => {
+                // The first page is the most accurate, since it was fetched
+                // before the others.
+                self.total.get_or_insert(total);
+                self.items.extend(page);
+                self.closed = self.items.is_empty();
+                self.items.pop_front().map(Ok)
+            }
+            Err(err) => {
+                self.closed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// An iterator over the crates matching a query.
+///
+/// Returned by [`SyncClient::crates_iter`]. Stops after the first error.
+pub struct CratesIter<'a>(Blocking<'a, CrateStream>);
+
+impl<'a> CratesIter<'a> {
+    fn new(client: &'a SyncClient, crates: CrateStream) -> Self {
+        Self(Blocking::new(&client.runtime, crates))
+    }
+
+    /// Get the total count of crates matching the query.
+    ///
+    /// Returns `None` until the first page was fetched.
+    pub fn total(&self) -> Option<u64> {
+        self.0.stream.total()
+    }
+
+    /// Crawl in a stable order, for crawls of many pages.
+    ///
+    /// See [`CrateStream::stable_order`](crate::CrateStream::stable_order).
+    /// Must be called before the first crate is retrieved.
+    #[must_use]
+    pub fn stable_order(mut self) -> Self {
+        self.0.stream = self.0.stream.stable_order();
+        self
+    }
+
+    /// Get the names of the crates that were skipped because they were
+    /// already yielded, see [`Self::stable_order`].
+    pub fn duplicates(&self) -> &[String] {
+        self.0.stream.duplicates()
+    }
+}
+
+impl Iterator for CratesIter<'_> {
+    type Item = Result<Crate, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// An iterator over the reverse dependencies of a crate.
+///
+/// Returned by [`SyncClient::reverse_dependencies_iter`]. Stops after the
+/// first error.
+pub struct ReverseDependenciesIter<'a>(Pager<'a, ReverseDependency>);
+
+impl ReverseDependenciesIter<'_> {
+    /// Get the total count of reverse dependencies.
+    ///
+    /// Returns `None` until the first page was fetched.
+    pub fn total(&self) -> Option<u64> {
+        self.0.total
+    }
+}
+
+impl Iterator for ReverseDependenciesIter<'_> {
+    type Item = Result<ReverseDependency, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// An iterator over the versions of a crate.
+///
+/// Returned by [`SyncClient::versions_iter`]. Stops after the first error.
+pub struct VersionsIter<'a>(Pager<'a, Version>);
+
+impl VersionsIter<'_> {
+    /// Get the total count of versions.
+    ///
+    /// Returns `None` until the first page was fetched.
+    pub fn total(&self) -> Option<u64> {
+        self.0.total
+    }
+}
+
+impl Iterator for VersionsIter<'_> {
+    type Item = Result<Version, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::testing::MockServer;
 
     fn build_test_client() -> SyncClient {
         SyncClient::new(
@@ -339,18 +1103,42 @@ mod test {
         .unwrap()
     }
 
+    #[test]
+    fn test_pager() {
+        let mut pages = vec![Ok((vec![], 3)), Ok((vec![3], 3)), Ok((vec![1, 2], 3))];
+        let mut pager = Pager::new(|| pages.pop().unwrap());
+        assert_eq!(pager.total, None);
+        assert_eq!(pager.next().unwrap().unwrap(), 1);
+        assert_eq!(pager.total, Some(3));
+        let items: Vec<_> = pager.map(Result::unwrap).collect();
+        assert_eq!(items, [2, 3]);
+
+        // Errors end the iteration.
+        let mut calls = 0;
+        let mut pager = Pager::<u64>::new(|| {
+            calls += 1;
+            Err(Error::NotFound(crate::error::NotFoundError {
+                url: "foo".to_string(),
+            }))
+        });
+        assert!(matches!(pager.next(), Some(Err(Error::NotFound(_)))));
+        assert!(pager.next().is_none());
+        drop(pager);
+        assert_eq!(calls, 1);
+    }
+
     #[test]
     fn test_summary() -> Result<(), Error> {
         let client = build_test_client();
         let summary = client.summary()?;
-        assert!(summary.most_downloaded.len() > 0);
-        assert!(summary.just_updated.len() > 0);
-        assert!(summary.new_crates.len() > 0);
-        assert!(summary.most_recently_downloaded.len() > 0);
+        assert!(!summary.most_downloaded.is_empty());
+        assert!(!summary.just_updated.is_empty());
+        assert!(!summary.new_crates.is_empty());
+        assert!(!summary.most_recently_downloaded.is_empty());
         assert!(summary.num_crates > 0);
         assert!(summary.num_downloads > 0);
-        assert!(summary.popular_categories.len() > 0);
-        assert!(summary.popular_keywords.len() > 0);
+        assert!(!summary.popular_categories.is_empty());
+        assert!(!summary.popular_keywords.is_empty());
         Ok(())
     }
 
@@ -382,11 +1170,7 @@ mod test {
 
         let user = client.user("theduke")?;
 
-        let res = client.crates(CratesQuery {
-            user_id: Some(user.id),
-            per_page: 5,
-            ..Default::default()
-        })?;
+        let res = client.crates(CratesQuery::builder().user_id(user.id).page_size(5).build())?;
 
         assert!(!res.crates.is_empty());
         // Ensure all found have the searched user as owner.
@@ -406,4 +1190,459 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_mock_server_sync() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["0.1.0", "0.2.0"])
+            .with_reverse_dependencies("foo", &[("bar", "1.0.0"), ("baz", "2.0.0")])
+            .with_error("crates/locked", 403, "must be logged in");
+        let client = server.sync_client();
+
+        let krate = client.get_crate("foo").unwrap();
+        assert_eq!(krate.versions[0].num, "0.2.0");
+        assert!(client.version_exists("foo", "0.1.0").unwrap());
+        assert!(client
+            .crate_dependencies("foo", "0.2.0")
+            .unwrap()
+            .is_empty());
+
+        let deps = client.crate_reverse_dependencies("foo").unwrap();
+        assert_eq!(deps.dependencies[1].crate_name(), "baz");
+        let mut deps = client.reverse_dependencies_iter("foo");
+        assert_eq!(deps.next().unwrap().unwrap().crate_name(), "bar");
+        assert_eq!(deps.total(), Some(2));
+        assert_eq!(deps.next().unwrap().unwrap().crate_name(), "baz");
+        assert!(deps.next().is_none());
+        assert_eq!(client.crate_reverse_dependency_count("foo").unwrap(), 2);
+
+        let full = client.full_crate("foo", true).unwrap();
+        assert_eq!(full.max_version, "0.2.0");
+        assert_eq!(full.versions[1].num, "0.1.0");
+        assert_eq!(full.reverse_dependencies.meta.total, 2);
+        assert!(full.reverse_dependencies_fetched);
+
+        assert!(matches!(client.get_crate("nope"), Err(Error::NotFound(_))));
+        assert!(matches!(
+            client.get_crate("locked"),
+            Err(Error::PermissionDenied(_))
+        ));
+        assert_eq!(server.requests()[0], "crates/foo");
+    }
+
+    #[test]
+    fn test_mock_server_crates_by_user() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_json(
+                "users/alice",
+                &UserResponse {
+                    user: User::new(7, "alice"),
+                },
+            )
+            .with_json(
+                "crates?page=1&per_page=100&sort=alpha&user_id=7",
+                &CratesPage::new(vec![Crate::new("a", "1.0.0"), Crate::new("b", "1.0.0")], 2),
+            )
+            .with_json("crates", &CratesPage::new(Vec::new(), 2));
+        let client = server.sync_client();
+
+        let names: Vec<_> = client
+            .crates_by_user("alice")
+            .unwrap()
+            .map(|krate| krate.unwrap().name)
+            .collect();
+        assert_eq!(names, ["a", "b"]);
+        assert!(matches!(
+            client.crates_by_user("bob").map(|_| ()),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_mock_server_keyword_and_category() {
+        let mut keyword = Keyword::new("parser");
+        keyword.crates_cnt = 1;
+        let mut category = Category::new("parsing", "Parsing");
+        category.crates_cnt = 1;
+        let server = MockServer::start()
+            .unwrap()
+            .with_json("keywords/parser", &KeywordResponse { keyword })
+            .with_json("categories/parsing", &CategoryResponse { category })
+            .with_json(
+                "crates?page=1&per_page=100&sort=recent-downloads&all_keywords=parser",
+                &CratesPage::new(vec![Crate::new("nom", "7.0.0")], 1),
+            )
+            .with_json(
+                "crates?page=1&per_page=100&sort=recent-downloads&category=parsing",
+                &CratesPage::new(vec![Crate::new("nom", "7.0.0")], 1),
+            )
+            .with_json("crates", &CratesPage::new(Vec::new(), 1));
+        let client = server.sync_client();
+
+        assert_eq!(client.keyword("parser").unwrap().crates_cnt, 1);
+        let names = |crates: crate::CratesIter<'_>| -> Vec<_> {
+            crates.map(|krate| krate.unwrap().name).collect()
+        };
+        assert_eq!(
+            names(client.crates_with_keyword("parser").unwrap()),
+            ["nom"]
+        );
+        assert_eq!(
+            names(client.crates_in_category("parsing").unwrap()),
+            ["nom"]
+        );
+        assert!(matches!(
+            client.crates_with_keyword("nope").map(|_| ()),
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            client.crates_in_category("nope").map(|_| ()),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_mock_server_category_snapshot() {
+        use crate::{MemoryCache, SnapshotOptions};
+
+        let mut category = Category::new("parsing", "Parsing");
+        category.crates_cnt = 3;
+        let server = MockServer::start()
+            .unwrap()
+            .with_json("categories/parsing", &CategoryResponse { category })
+            .with_json(
+                "crates?page=1&per_page=100&sort=alpha&category=parsing",
+                &CratesPage::new(
+                    vec![
+                        Crate::new("a", "1.0.0"),
+                        Crate::new("b", "1.0.0"),
+                        Crate::new("c", "1.0.0"),
+                    ],
+                    3,
+                ),
+            )
+            .with_json("crates", &CratesPage::new(Vec::new(), 3))
+            .with_crate("a", &["0.1.0", "1.0.0"])
+            .with_reverse_dependencies("a", &[("x", "1.0.0"), ("y", "1.0.0")])
+            .with_error("crates/c", 500, "boom");
+        let client = server.sync_client();
+        let store = std::sync::Arc::new(MemoryCache::new());
+        let options = SnapshotOptions::new().resumable(store);
+
+        assert!(client.category_snapshot("parsing", &options).is_err());
+        let fetched = server.requests().len();
+
+        // The snapshot resumes after `a`, and the deleted `b` is left out.
+        let server = server.with_crate("c", &["2.0.0"]);
+        let snapshot = client.category_snapshot("parsing", &options).unwrap();
+        let names: Vec<_> = snapshot.crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["a", "c"]);
+        assert_eq!(snapshot.crates[0].dependents, 2);
+        assert!(snapshot.crates[0].last_release.is_some());
+        let fetched_a = |requests: &[String]| requests.iter().any(|r| r.starts_with("crates/a"));
+        let requests = server.requests();
+        assert!(fetched_a(&requests[..fetched]));
+        assert!(!fetched_a(&requests[fetched..]), "{:?}", requests);
+
+        // A completed snapshot isn't resumed.
+        let fetched = requests.len();
+        let options = options.max_crates(1);
+        let snapshot = client.category_snapshot("parsing", &options).unwrap();
+        assert_eq!(snapshot.crates.len(), 1);
+        assert!(fetched_a(&server.requests()[fetched..]));
+        assert!(matches!(
+            client.category_snapshot("nope", &options),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_mock_server_collect_pages() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_json(
+                "crates?page=1&per_page=2&sort=alpha",
+                &CratesPage::new(vec![Crate::new("a", "1.0.0"), Crate::new("b", "1.0.0")], 3),
+            )
+            .with_json(
+                "crates?page=2&per_page=2&sort=alpha",
+                &CratesPage::new(vec![Crate::new("c", "1.0.0")], 3),
+            )
+            .with_json("crates", &CratesPage::new(Vec::new(), 3));
+        let client = server.sync_client();
+        let query = CratesQuery::builder()
+            .sort(Sort::Alphabetical)
+            .page_size(2)
+            .build();
+        let endpoint = crate::endpoints::ListCrates { query: &query };
+
+        let pages = client.collect_pages(&endpoint, 1).unwrap();
+        let names: Vec<_> = pages.items.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(pages.next_page, Some(2));
+
+        let pages = client.collect_pages(&endpoint, 5).unwrap();
+        assert_eq!(pages.items.len(), 3);
+        assert_eq!(pages.next_page, None);
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[test]
+    fn test_mock_server_query_defaults() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_json(
+                "crates?page=1&per_page=100&sort=alpha",
+                &CratesPage::new(vec![Crate::new("a", "1.0.0")], 1),
+            )
+            .with_json(
+                "crates?page=1&per_page=5&sort=downloads",
+                &CratesPage::new(vec![Crate::new("b", "1.0.0")], 1),
+            );
+        let client = server
+            .sync_client()
+            .with_default_page_size(100)
+            .with_default_sort(Sort::Alphabetical);
+
+        let page = client.crates(CratesQuery::default()).unwrap();
+        assert_eq!(page.crates[0].name, "a");
+        let query = CratesQuery::builder()
+            .page_size(5)
+            .sort(Sort::Downloads)
+            .build();
+        let page = client.crates(query).unwrap();
+        assert_eq!(page.crates[0].name, "b");
+        let query: CratesQuery = "per_page=5&sort=downloads".parse().unwrap();
+        assert_eq!(client.crates(query).unwrap().crates[0].name, "b");
+    }
+
+    #[test]
+    fn test_mock_server_stable_order() {
+        let server = crate::testing::stable_order_server();
+        let client = server.sync_client();
+        let query = CratesQuery::builder()
+            .sort(Sort::Downloads)
+            .page_size(2)
+            .page(7)
+            .build();
+
+        let mut crates = client.crates_iter(query).stable_order();
+        let names: Vec<_> = crates.by_ref().map(|krate| krate.unwrap().name).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(crates.duplicates(), ["b"]);
+        assert_eq!(server.requests().len(), 2);
+
+        // Without any pagination links, the crawl continues by page number.
+        let server = MockServer::start()
+            .unwrap()
+            .with_json(
+                "crates?per_page=2&sort=alpha",
+                &CratesPage::new(vec![Crate::new("a", "1.0.0"), Crate::new("b", "1.0.0")], 3),
+            )
+            .with_json(
+                "crates?page=2&per_page=2&sort=alpha",
+                &CratesPage::new(vec![Crate::new("c", "1.0.0")], 3),
+            )
+            .with_json("crates", &CratesPage::new(Vec::new(), 3));
+        let query = CratesQuery::builder().page_size(2).build();
+        let names: Result<Vec<_>, _> = server
+            .sync_client()
+            .crates_iter(query)
+            .stable_order()
+            .map(|krate| krate.map(|krate| krate.name))
+            .collect();
+        assert_eq!(names.unwrap(), ["a", "b", "c"]);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_mock_server_all_crate_names() {
+        let mut first = CratesPage::new(vec![Crate::new("a", "1.0.0")], 2);
+        first.meta.next_page = Some("?seek=YQ&per_page=100&sort=alpha".to_string());
+        let server = MockServer::start()
+            .unwrap()
+            .with_json("crates?per_page=100&sort=alpha", &first)
+            .with_json(
+                "crates?seek=YQ&per_page=100&sort=alpha",
+                &CratesPage::new(vec![Crate::new("b", "1.0.0")], 2),
+            );
+        let client = server.sync_client().with_default_sort(Sort::Downloads);
+
+        let names: Result<Vec<_>, _> = client.all_crate_names().collect();
+        assert_eq!(names.unwrap(), ["a", "b"]);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_mock_server_sync_feeds() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["1.0.0"])
+            .with_crate("bar", &["1.0.0"])
+            .with_json(
+                "crates",
+                &CratesPage::new(
+                    vec![Crate::new("foo", "1.0.0"), Crate::new("bar", "1.0.0")],
+                    2,
+                ),
+            );
+        let client = server.sync_client();
+        let names = |crates: Result<Vec<Crate>, Error>| -> Vec<String> {
+            crates.unwrap().into_iter().map(|c| c.name).collect()
+        };
+
+        assert_eq!(
+            names(client.new_crates_iter().take(2).collect()),
+            ["foo", "bar"]
+        );
+        assert_eq!(
+            names(client.just_updated_iter().take(2).collect()),
+            ["foo", "bar"]
+        );
+
+        let options = FullCrateOptions::new()
+            .authors(false)
+            .reverse_dependencies(false);
+        let query = CratesQuery::builder().page_size(2).build();
+        let crates: Result<Vec<_>, _> = client.full_crates_iter(query, options).take(2).collect();
+        let names: Vec<_> = crates.unwrap().into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["foo", "bar"]);
+    }
+
+    #[test]
+    fn test_mock_server_full_crates_events() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["1.0.0"])
+            .with_json(
+                "crates?page=1&per_page=30&sort=recent-updates",
+                &CratesPage::new(
+                    vec![Crate::new("gone", "1.0.0"), Crate::new("foo", "1.0.0")],
+                    2,
+                ),
+            )
+            .with_json("crates", &CratesPage::new(Vec::new(), 2));
+        let client = server.sync_client();
+        let options = FullCrateOptions::new()
+            .authors(false)
+            .reverse_dependencies(false);
+
+        let events: Vec<_> = client
+            .full_crates_events(CratesQuery::default(), options)
+            .map(|event| event.unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], CrawlEvent::Skipped { crate_name } if crate_name == "gone"));
+        let full = events.into_iter().nth(1).unwrap().into_item().unwrap();
+        assert_eq!(full.name, "foo");
+    }
+
+    #[test]
+    fn test_mock_server_full_crate_plan() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["0.1.0", "0.2.0", "0.3.0"]);
+        let client = server.sync_client();
+        let options = FullCrateOptions::new().authors(false);
+
+        let plan = client.full_crate_plan("foo", &options).unwrap();
+        assert_eq!(plan.crate_name(), "foo");
+        assert_eq!(
+            plan.versions().collect::<Vec<_>>(),
+            ["0.3.0", "0.2.0", "0.1.0"]
+        );
+        assert_eq!(plan.requests(), 6);
+        assert_eq!(plan.eta(), std::time::Duration::ZERO);
+        let estimate = client
+            .estimate_cost(&crate::Operation::FullCrate {
+                crate_name: "foo",
+                options: &options,
+            })
+            .unwrap();
+        // The crate, its downloads and owners, 3 versions and an empty page of
+        // reverse dependencies.
+        assert_eq!(estimate.requests, 7);
+        let estimate = client
+            .estimate_cost(&crate::Operation::ReverseDependencies { crate_name: "foo" })
+            .unwrap();
+        assert_eq!(estimate.requests, 1);
+        let before = server.requests().len();
+        let full = client.full_crate_from_plan(plan, &options).unwrap();
+        assert_eq!(full.versions.len(), 3);
+        assert_eq!(server.requests().len() - before, 6);
+    }
+
+    #[test]
+    fn test_mock_server_cache() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_crate("foo", &["1.0.0"])
+            .with_crate("bar", &["2.0.0"]);
+        let cache = std::sync::Arc::new(crate::MemoryCache::new());
+        let config = server
+            .config()
+            .cache(cache.clone(), std::time::Duration::from_secs(60));
+        let client = SyncClient::from_config(&config).unwrap();
+
+        client.prefetch(["foo", "nope"]).join();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(client.get_crate("foo").unwrap().crate_data.name, "foo");
+        assert_eq!(client.get_crate("bar").unwrap().crate_data.name, "bar");
+        assert_eq!(client.get_crate("bar").unwrap().crate_data.name, "bar");
+        assert_eq!(
+            server.requests(),
+            ["crates/foo", "crates/nope", "crates/bar"]
+        );
+    }
+
+    #[test]
+    fn test_mock_server_cache_hits_skip_limits() {
+        let server = MockServer::start().unwrap().with_crate("foo", &["1.0.0"]);
+        let budget = std::sync::Arc::new(crate::Budget::new().max_requests(1));
+        let config = server
+            .config()
+            .rate_limit(std::time::Duration::from_secs(10))
+            .budget(budget.clone())
+            .cache(
+                std::sync::Arc::new(crate::MemoryCache::new()),
+                std::time::Duration::from_secs(60),
+            );
+        let client = SyncClient::from_config(&config).unwrap();
+
+        let start = std::time::Instant::now();
+        for _ in 0..3 {
+            assert_eq!(client.get_crate("foo").unwrap().crate_data.name, "foo");
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(budget.used(), 1);
+        assert!(matches!(
+            client.get_crate("bar"),
+            Err(Error::BudgetExhausted(_))
+        ));
+    }
+
+    #[test]
+    fn test_mock_server_deadline() {
+        use std::time::{Duration, Instant};
+
+        let server = MockServer::start().unwrap().with_crate("foo", &["1.0.0"]);
+        let config = server.config().rate_limit(Duration::from_millis(300));
+        let client = SyncClient::from_config(&config).unwrap();
+        let endpoint = crate::endpoints::GetCrate { crate_name: "foo" };
+
+        // Failed requests count against the rate limit as well.
+        assert!(client.crate_exists("nope").is_ok());
+        let options =
+            crate::RequestOptions::new().deadline(Instant::now() + Duration::from_millis(50));
+        assert!(matches!(
+            client.send_with(&endpoint, &options),
+            Err(Error::Cancelled(_))
+        ));
+        let options =
+            crate::RequestOptions::new().deadline(Instant::now() + Duration::from_secs(5));
+        assert!(client.send_with(&endpoint, &options).is_ok());
+        assert_eq!(server.requests().len(), 2);
+    }
 }
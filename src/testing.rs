@@ -0,0 +1,305 @@
+//! A local mock server for integration tests.
+//!
+//! Requires the `testing` feature.
+//!
+//! [`MockServer`] serves canned responses over HTTP on localhost, in the same
+//! shape as crates.io, and hands out clients pointed at it. Unlike
+//! [`MockClient`](crate::mock::MockClient), the whole request pipeline of the
+//! real clients is exercised, including URL building, error handling and
+//! decoding.
+//!
+//! ```rust
+//! use crates_io_api::testing::MockServer;
+//!
+//! let server = MockServer::start()
+//!     .unwrap()
+//!     .with_crate("foo", &["1.0.0", "1.1.0"])
+//!     .with_reverse_dependencies("foo", &[("bar", "0.1.0")]);
+//! let client = server.sync_client();
+//! assert_eq!(client.get_crate("foo").unwrap().crate_data.max_version, "1.1.0");
+//! assert_eq!(client.crate_reverse_dependency_count("foo").unwrap(), 1);
+//! assert!(!client.crate_exists("baz").unwrap());
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use crate::types::*;
+use crate::{AsyncClient, ClientConfig, SyncClient};
+
+/// A canned response.
+#[derive(Debug, Clone)]
+struct Route {
+    status: u16,
+    body: String,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    routes: HashMap<String, Route>,
+    requests: Vec<String>,
+}
+
+/// A local HTTP server mimicking the crates.io API.
+///
+/// Routes are paths relative to the API root, optionally with a query string,
+/// eg `"crates/foo"` or `"crates?page=1&per_page=10"`. A route without a
+/// query matches requests with any query. Requests without a route get a
+/// `404 Not Found` like crates.io sends it.
+///
+/// The server stops when it is dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Start a server on a free port on localhost.
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Err(err) = stream.and_then(|stream| serve(stream, &state)) {
+                        log::warn!("Mock server connection failed: {}", err);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// Get the API root URL of the server.
+    pub fn base_url(&self) -> String {
+        format!("http://{}/api/v1/", self.addr)
+    }
+
+    /// Get a client configuration for the server, without rate limit.
+    pub fn config(&self) -> ClientConfig {
+        ClientConfig::new("crates-io-api-mock-server")
+            .rate_limit(std::time::Duration::ZERO)
+            .base_url(self.base_url())
+    }
+
+    /// Get an async client for the server, without rate limit.
+    pub fn async_client(&self) -> AsyncClient {
+        AsyncClient::from_config(&self.config()).unwrap()
+    }
+
+    /// Get a sync client for the server, without rate limit.
+    pub fn sync_client(&self) -> SyncClient {
+        SyncClient::from_config(&self.config()).unwrap()
+    }
+
+    /// Get the paths of all received requests, with query strings, relative
+    /// to the API root.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Respond to a route with the given status and body.
+    #[must_use]
+    pub fn with_response(self, route: &str, status: u16, body: impl Into<String>) -> Self {
+        self.state.lock().unwrap().routes.insert(
+            route.trim_start_matches('/').to_string(),
+            Route {
+                status,
+                body: body.into(),
+            },
+        );
+        self
+    }
+
+    /// Respond to a route with `200 OK` and the given value as JSON.
+    #[must_use]
+    pub fn with_json<T: serde::Serialize>(self, route: &str, value: &T) -> Self {
+        let body = serde_json::to_string(value).unwrap();
+        self.with_response(route, 200, body)
+    }
+
+    /// Respond to a route with an error in the format of crates.io.
+    #[must_use]
+    pub fn with_error(self, route: &str, status: u16, detail: &str) -> Self {
+        let body = serde_json::to_string(&ApiErrors::new(detail)).unwrap();
+        self.with_response(route, status, body)
+    }
+
+    /// Serve a crate with the given versions, oldest first.
+    ///
+    /// The crate, its versions and their (empty) dependencies, downloads and
+    /// owners are served. The last version is the highest one.
+    #[must_use]
+    pub fn with_crate(self, crate_name: &str, versions: &[&str]) -> Self {
+        let max_version = versions.last().copied().unwrap_or("0.0.0");
+        let mut krate = Crate::new(crate_name, max_version);
+        krate.num_versions = Some(versions.len() as u64);
+        let versions: Vec<_> = versions
+            .iter()
+            .rev()
+            .map(|num| Version::new(crate_name, num))
+            .collect();
+
+        let mut server = self
+            .with_json(
+                &format!("crates/{}", crate_name),
+                &CrateResponse::new(krate, versions.clone()),
+            )
+            .with_json(
+                &format!("crates/{}/downloads", crate_name),
+                &CrateDownloads {
+                    version_downloads: Vec::new(),
+                    meta: CrateDownloadsMeta {
+                        extra_downloads: Vec::new(),
+                    },
+                },
+            )
+            .with_json(
+                &format!("crates/{}/owners", crate_name),
+                &Owners { users: Vec::new() },
+            )
+            .with_reverse_dependencies(crate_name, &[]);
+        for version in versions {
+            let path = format!("crates/{}/{}", crate_name, version.num);
+            server = server
+                .with_json(
+                    &format!("{}/dependencies", path),
+                    &Dependencies {
+                        dependencies: Vec::new(),
+                    },
+                )
+                .with_json(&path, &VersionResponse { version });
+        }
+        server
+    }
+
+    /// Serve the reverse dependencies of a crate, as a list of dependent
+    /// crates and versions.
+    ///
+    /// They are paged by 100, like crates.io does.
+    #[must_use]
+    pub fn with_reverse_dependencies(self, crate_name: &str, dependents: &[(&str, &str)]) -> Self {
+        let path = format!("crates/{}/reverse_dependencies", crate_name);
+        let total = dependents.len() as u64;
+        let page = |dependents: &[(&str, &str)], offset: usize| {
+            let mut versions = Vec::new();
+            let mut dependencies = Vec::new();
+            for (id, (dependent, num)) in dependents.iter().enumerate() {
+                let mut version = Version::new(dependent, num);
+                version.id = (offset + id) as u64 + 1;
+                let mut dependency = Dependency::new(crate_name, "*");
+                dependency.version_id = version.id;
+                versions.push(version);
+                dependencies.push(dependency);
+            }
+            ReverseDependenciesAsReceived {
+                meta: Meta::new(total),
+                dependencies,
+                versions,
+            }
+        };
+
+        // Pages past the end are empty.
+        let mut server = self.with_json(&path, &page(&[], 0));
+        for (index, chunk) in dependents.chunks(100).enumerate() {
+            server = server.with_json(
+                &format!("{}?per_page=100&page={}", path, index + 1),
+                &page(chunk, index * 100),
+            );
+        }
+        server
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the accept loop.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Answer a single request.
+fn serve(stream: TcpStream, state: &Mutex<State>) -> io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let target = request_line.split(' ').nth(1).unwrap_or_default();
+    let path = target
+        .strip_prefix("/api/v1/")
+        .unwrap_or_else(|| target.trim_start_matches('/'))
+        .to_string();
+    let route = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(path.clone());
+        let without_query = path.split('?').next().unwrap_or_default();
+        state
+            .routes
+            .get(&path)
+            .or_else(|| state.routes.get(without_query))
+            .cloned()
+    };
+    let route = route.unwrap_or_else(|| Route {
+        status: 404,
+        body: serde_json::to_string(&ApiErrors::new("Not Found")).unwrap(),
+    });
+
+    write!(
+        &stream,
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        route.status,
+        route.body.len(),
+        route.body
+    )?;
+    (&stream).flush()
+}
+
+/// Serve a crawl in a stable order: the second page repeats a crate and
+/// has no seek cursor, so the crawl ends after it.
+///
+/// Shared by the tests of the sync and async clients.
+#[cfg(test)]
+pub(crate) fn stable_order_server() -> MockServer {
+    let mut first = CratesPage::new(vec![Crate::new("a", "1.0.0"), Crate::new("b", "1.0.0")], 3);
+    first.meta.next_page = Some("?seek=Yg&per_page=2&sort=alpha".to_string());
+    let mut second = CratesPage::new(vec![Crate::new("b", "1.0.0"), Crate::new("c", "1.0.0")], 3);
+    second.meta.prev_page = Some("?seek=-Yg&per_page=2&sort=alpha".to_string());
+    MockServer::start()
+        .unwrap()
+        .with_json("crates?per_page=2&sort=alpha", &first)
+        .with_json("crates?seek=Yg&per_page=2&sort=alpha", &second)
+        .with_json(
+            "crates",
+            &CratesPage::new(vec![Crate::new("z", "1.0.0")], 3),
+        )
+}
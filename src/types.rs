@@ -1,18 +1,42 @@
 //! Types for the data that is available via the API.
 
-use chrono::{DateTime, NaiveDate, Utc};
+use crate::error::InvalidQueryError;
+use crate::{Date, Error, Timestamp};
 use serde_derive::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Parse a timestamp in the format of the API.
+#[cfg(test)]
+pub(crate) fn timestamp(value: &str) -> Timestamp {
+    value.parse().unwrap()
+}
 
 /// Used to specify the sort behaviour of the `Client::crates()` method.
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct ApiErrors {
     /// Individual errors.
     pub errors: Vec<ApiError>,
 }
 
+impl ApiErrors {
+    /// Construct an error response with a single message.
+    ///
+    /// Useful for injecting errors into test doubles.
+    pub fn new(detail: impl Into<String>) -> Self {
+        Self {
+            errors: vec![ApiError {
+                detail: Some(detail.into()),
+            }],
+        }
+    }
+}
+
 /// Used to specify the sort behaviour of the `Client::crates()` method.
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct ApiError {
     /// Error message.
     pub detail: Option<String>,
@@ -29,27 +53,47 @@ impl std::fmt::Display for ApiError {
 }
 
 /// Used to specify the sort behaviour of the `Client::crates()` method.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Covers every sort mode supported by the crates.io search endpoint.
+///
+/// Serialized with the names the API uses, eg `"recent-downloads"`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Sort {
     /// Sort alphabetically.
+    #[serde(rename = "alpha")]
     Alphabetical,
     /// Sort by relevance (meaningless if used without a query).
+    #[serde(rename = "relevance")]
     Relevance,
-    /// Sort by downloads.
+    /// Sort by all-time downloads.
+    #[serde(rename = "downloads")]
     Downloads,
-    /// Sort by recent downloads
+    /// Sort by downloads in the last 90 days.
+    #[serde(rename = "recent-downloads")]
     RecentDownloads,
     /// Sort by recent updates
+    #[serde(rename = "recent-updates")]
     RecentUpdates,
-    /// Sort by new
+    /// Sort by creation date, newest first.
+    #[serde(rename = "new")]
     NewlyAdded,
 }
 
 impl Sort {
-    pub(crate) fn to_str(&self) -> &str {
+    /// All available sort modes.
+    pub const ALL: [Sort; 6] = [
+        Self::Alphabetical,
+        Self::Relevance,
+        Self::Downloads,
+        Self::RecentDownloads,
+        Self::RecentUpdates,
+        Self::NewlyAdded,
+    ];
+
+    pub(crate) fn to_str(self) -> &'static str {
         match self {
             Self::Alphabetical => "alpha",
-            Self::Relevance => "",
+            Self::Relevance => "relevance",
             Self::Downloads => "downloads",
             Self::RecentDownloads => "recent-downloads",
             Self::RecentUpdates => "recent-updates",
@@ -58,6 +102,85 @@ impl Sort {
     }
 }
 
+/// How a client treats fields in API responses that are not modelled by this
+/// crate.
+///
+/// Checking for unknown fields requires decoding every response twice, so
+/// it's meant for detecting API changes early, eg in CI or while debugging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownFields {
+    /// Silently drop unknown fields.
+    #[default]
+    Ignore,
+    /// Log unknown fields as warnings.
+    Warn,
+    /// Fail with [`Error::JsonDecode`] if there are unknown fields.
+    Deny,
+}
+
+/// Extra headers and query parameters for a single request, see
+/// `send_with()` on the clients.
+///
+/// Useful for trying out new API parameters, or for passing tracing headers
+/// through to crates.io. Requests with extra headers bypass the cache of the
+/// client, since the headers can change the response.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    pub(crate) headers: reqwest::header::HeaderMap,
+    pub(crate) query: Vec<(String, String)>,
+    pub(crate) deadline: Option<std::time::Instant>,
+}
+
+impl RequestOptions {
+    /// Construct options without any extras.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header, overriding the default header of the same name.
+    ///
+    /// The request bypasses the cache of the client.
+    #[must_use]
+    pub fn header(
+        mut self,
+        name: reqwest::header::HeaderName,
+        value: reqwest::header::HeaderValue,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Append a query parameter.
+    ///
+    /// Parameters already set by the endpoint are not removed, so adding one
+    /// of them again sends it twice.
+    #[must_use]
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((key.into(), value.into()));
+        self
+    }
+
+    /// Give up on the request at `deadline`, including the time spent
+    /// waiting for the rate limit.
+    ///
+    /// Requests past their deadline fail with [`Error::Cancelled`].
+    #[must_use]
+    pub fn deadline(mut self, deadline: std::time::Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Get the URL with the extra query parameters appended.
+    pub(crate) fn apply_query(&self, url: &reqwest::Url) -> reqwest::Url {
+        let mut url = url.clone();
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+        url
+    }
+}
+
 /// Options for the [crates]() method of the client.
 ///
 /// Used to specify pagination, sorting and a query.
@@ -70,6 +193,7 @@ pub struct CratesQuery {
     /// The page to fetch.
     pub(crate) page: u64,
     pub(crate) user_id: Option<u64>,
+    pub(crate) team_id: Option<u64>,
     /// Crates.io category name.
     /// See https://crates.io/categories
     /// NOTE: requires lower-case dash-separated categories, not the pretty
@@ -77,26 +201,151 @@ pub struct CratesQuery {
     pub(crate) category: Option<String>,
     /// Search query string.
     pub(crate) search: Option<String>,
+    /// Only return crates that have all of the given keywords.
+    pub(crate) keywords: Vec<String>,
+    /// Only return crates with the given names.
+    pub(crate) ids: Vec<String>,
+    /// Whether the sort was chosen, rather than left at its default.
+    pub(crate) sort_set: bool,
+    /// Whether the page size was chosen, rather than left at its default.
+    pub(crate) page_size_set: bool,
+    /// The seek cursor, sent instead of the page. An empty cursor requests
+    /// the first page of a seek-based crawl.
+    pub(crate) seek: Option<String>,
 }
 
 impl CratesQuery {
+    /// The maximum page size accepted by the API.
+    pub const MAX_PAGE_SIZE: u64 = 100;
+
+    /// Check that the query will be accepted by the API.
+    ///
+    /// The page size must be between 1 and [`Self::MAX_PAGE_SIZE`], the page
+    /// must be at least 1, and the query can't filter by both a user and a
+    /// team.
+    pub fn validate(&self) -> Result<(), InvalidQueryError> {
+        if self.per_page == 0 || self.per_page > Self::MAX_PAGE_SIZE {
+            return Err(InvalidQueryError::new(format!(
+                "page size must be between 1 and {}, got {}",
+                Self::MAX_PAGE_SIZE,
+                self.per_page
+            )));
+        }
+        if self.page == 0 {
+            return Err(InvalidQueryError::new("page must be at least 1"));
+        }
+        if self.user_id.is_some() && self.team_id.is_some() {
+            return Err(InvalidQueryError::new(
+                "can't filter by both user_id and team_id",
+            ));
+        }
+        Ok(())
+    }
+
     pub(crate) fn build(&self, mut q: url::form_urlencoded::Serializer<'_, url::UrlQuery<'_>>) {
-        q.append_pair("page", &self.page.to_string());
+        match self.seek.as_deref() {
+            None => {
+                q.append_pair("page", &self.page.to_string());
+            }
+            Some("") => {}
+            Some(seek) => {
+                q.append_pair("seek", seek);
+            }
+        }
         q.append_pair("per_page", &self.per_page.to_string());
         q.append_pair("sort", self.sort.to_str());
         if let Some(id) = self.user_id {
             q.append_pair("user_id", &id.to_string());
         }
+        if let Some(id) = self.team_id {
+            q.append_pair("team_id", &id.to_string());
+        }
         if let Some(search) = &self.search {
             q.append_pair("q", search);
         }
         if let Some(cat) = &self.category {
             q.append_pair("category", cat);
         }
+        if !self.keywords.is_empty() {
+            q.append_pair("all_keywords", &self.keywords.join(" "));
+        }
+        for id in &self.ids {
+            q.append_pair("ids[]", id);
+        }
+    }
+}
+
+/// Formats the query string sent to the API, eg
+/// `page=1&per_page=30&sort=recent-updates`.
+impl std::fmt::Display for CratesQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut url = reqwest::Url::parse("https://crates.io/api/v1/crates").unwrap();
+        self.build(url.query_pairs_mut());
+        f.write_str(url.query().unwrap_or_default())
+    }
+}
+
+/// Parses a query string, with or without a leading `?`, or a whole URL.
+///
+/// Missing parameters keep their defaults. Unknown parameters are rejected,
+/// and so are queries that fail [`CratesQuery::validate`].
+impl std::str::FromStr for CratesQuery {
+    type Err = InvalidQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let query_string = s.split_once('?').map_or(s, |(_, query)| query);
+        let mut query = CratesQuery::default();
+        for (key, value) in url::form_urlencoded::parse(query_string.as_bytes()) {
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| InvalidQueryError::new(format!("invalid {}: {:?}", key, value)))
+            };
+            match &*key {
+                "page" => query.page = number()?,
+                "per_page" => query.set_page_size(number()?),
+                "sort" => {
+                    query.sort_set = true;
+                    query.sort = Sort::ALL
+                        .iter()
+                        .copied()
+                        .find(|sort| sort.to_str() == value)
+                        .ok_or_else(|| {
+                            InvalidQueryError::new(format!("invalid sort: {:?}", value))
+                        })?
+                }
+                "user_id" => query.user_id = Some(number()?),
+                "team_id" => query.team_id = Some(number()?),
+                "q" => query.search = Some(value.into_owned()),
+                "category" => query.category = Some(value.into_owned()),
+                "all_keywords" => {
+                    query.keywords = value.split_whitespace().map(str::to_string).collect()
+                }
+                "ids[]" => query.ids.push(value.into_owned()),
+                "seek" => query.seek = Some(value.into_owned()),
+                _ => {
+                    return Err(InvalidQueryError::new(format!(
+                        "unknown parameter: {:?}",
+                        key
+                    )))
+                }
+            }
+        }
+        query.validate()?;
+        Ok(query)
     }
 }
 
 impl CratesQuery {
+    /// Get the URL of the query on crates.io.
+    ///
+    /// Use [`ListCrates`](crate::endpoints::ListCrates) for other servers.
+    pub fn to_url(&self) -> reqwest::Url {
+        let mut url = reqwest::Url::parse("https://crates.io/api/v1/crates").unwrap();
+        self.build(url.query_pairs_mut());
+        url
+    }
+
     /// Construct a new [`CratesQueryBuilder`].
     pub fn builder() -> CratesQueryBuilder {
         CratesQueryBuilder::new()
@@ -110,6 +359,7 @@ impl CratesQuery {
     /// Set the crate query's sort.
     pub fn set_sort(&mut self, sort: Sort) {
         self.sort = sort;
+        self.sort_set = true;
     }
 
     /// Get the crate query's per page.
@@ -120,6 +370,7 @@ impl CratesQuery {
     /// Set the crate query's per page.
     pub fn set_page_size(&mut self, per_page: u64) {
         self.per_page = per_page;
+        self.page_size_set = true;
     }
 
     /// Get the crate query's page.
@@ -142,6 +393,16 @@ impl CratesQuery {
         self.user_id = user_id;
     }
 
+    /// Get the crate query's team id.
+    pub fn team_id(&self) -> Option<u64> {
+        self.team_id
+    }
+
+    /// Set the crate query's team id.
+    pub fn set_team_id(&mut self, team_id: Option<u64>) {
+        self.team_id = team_id;
+    }
+
     /// Get a reference to the crate query's category.
     pub fn category(&self) -> Option<&String> {
         self.category.as_ref()
@@ -161,6 +422,26 @@ impl CratesQuery {
     pub fn set_search(&mut self, search: Option<String>) {
         self.search = search;
     }
+
+    /// Get a reference to the crate query's keywords.
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+
+    /// Set the crate query's keywords.
+    pub fn set_keywords(&mut self, keywords: Vec<String>) {
+        self.keywords = keywords;
+    }
+
+    /// Get a reference to the crate names the query is restricted to.
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// Set the crate names the query is restricted to.
+    pub fn set_ids(&mut self, ids: Vec<String>) {
+        self.ids = ids;
+    }
 }
 
 impl Default for CratesQuery {
@@ -170,9 +451,36 @@ impl Default for CratesQuery {
             per_page: 30,
             page: 1,
             user_id: None,
+            team_id: None,
             category: None,
             search: None,
+            keywords: Vec::new(),
+            ids: Vec::new(),
+            sort_set: false,
+            page_size_set: false,
+            seek: None,
+        }
+    }
+}
+
+/// The sort and page size a client uses for queries that don't choose
+/// them.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct QueryDefaults {
+    pub(crate) sort: Option<Sort>,
+    pub(crate) page_size: Option<u64>,
+}
+
+impl QueryDefaults {
+    /// Fill in the sort and page size the query left at their defaults.
+    pub(crate) fn apply(&self, mut query: CratesQuery) -> CratesQuery {
+        if let (Some(sort), false) = (self.sort, query.sort_set) {
+            query.sort = sort;
+        }
+        if let (Some(page_size), false) = (self.page_size, query.page_size_set) {
+            query.per_page = page_size;
         }
+        query
     }
 }
 
@@ -193,14 +501,21 @@ impl CratesQueryBuilder {
     /// Set the sorting method.
     #[must_use]
     pub fn sort(mut self, sort: Sort) -> Self {
-        self.query.sort = sort;
+        self.query.set_sort(sort);
         self
     }
 
     /// Set the page size.
     #[must_use]
     pub fn page_size(mut self, size: u64) -> Self {
-        self.query.per_page = size;
+        self.query.set_page_size(size);
+        self
+    }
+
+    /// Set the page to fetch.
+    #[must_use]
+    pub fn page(mut self, page: u64) -> Self {
+        self.query.page = page;
         self
     }
 
@@ -211,6 +526,13 @@ impl CratesQueryBuilder {
         self
     }
 
+    /// Filter by a team id.
+    #[must_use]
+    pub fn team_id(mut self, team_id: u64) -> Self {
+        self.query.team_id = Some(team_id);
+        self
+    }
+
     /// Crates.io category name.
     /// See https://crates.io/categories
     /// NOTE: requires lower-case dash-separated categories, not the pretty
@@ -228,11 +550,41 @@ impl CratesQueryBuilder {
         self
     }
 
+    /// Only return crates that have the given keyword.
+    ///
+    /// Can be called multiple times: crates must have all of the keywords.
+    #[must_use]
+    pub fn keyword(mut self, keyword: impl Into<String>) -> Self {
+        self.query.keywords.push(keyword.into());
+        self
+    }
+
+    /// Only return the crate with the given name.
+    ///
+    /// Can be called multiple times to fetch several crates with a single
+    /// request. Names that don't exist are silently skipped by the API.
+    #[must_use]
+    pub fn id(mut self, crate_name: impl Into<String>) -> Self {
+        self.query.ids.push(crate_name.into());
+        self
+    }
+
     /// Finalize the builder into a usable [`CratesQuery`].
+    ///
+    /// The query is validated when it is sent. Use [`Self::try_build`] to
+    /// validate it right away.
     #[must_use]
     pub fn build(self) -> CratesQuery {
         self.query
     }
+
+    /// Finalize the builder into a validated [`CratesQuery`].
+    ///
+    /// See [`CratesQuery::validate`].
+    pub fn try_build(self) -> Result<CratesQuery, InvalidQueryError> {
+        self.query.validate()?;
+        Ok(self.query)
+    }
 }
 
 impl Default for CratesQueryBuilder {
@@ -242,15 +594,93 @@ impl Default for CratesQueryBuilder {
 }
 
 /// Pagination information.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+///
+/// The links are query strings relative to the requested endpoint, eg
+/// `?page=2&per_page=10`, or `?seek=...` for endpoints using seek-based
+/// pagination.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Meta {
     /// The total amount of results.
     pub total: u64,
+    /// The query string of the next page, if there is one.
+    #[serde(default)]
+    pub next_page: Option<String>,
+    /// The query string of the previous page, if there is one.
+    #[serde(default)]
+    pub prev_page: Option<String>,
+}
+
+impl Meta {
+    /// Construct pagination information.
+    pub fn new(total: u64) -> Self {
+        Self {
+            total,
+            ..Self::default()
+        }
+    }
+
+    /// Check whether the server announced a next page.
+    ///
+    /// Endpoints that don't send links never have a next page according to
+    /// this, use [`Self::total`] for them.
+    pub fn has_next_page(&self) -> bool {
+        self.next_page.is_some()
+    }
+
+    /// Get the number of the next page, for page-based pagination.
+    pub fn next_page_number(&self) -> Option<u64> {
+        self.next_page_param("page")?.parse().ok()
+    }
+
+    /// Get the seek cursor of the next page, for seek-based pagination.
+    pub fn next_seek(&self) -> Option<String> {
+        self.next_page_param("seek")
+    }
+
+    fn next_page_param(&self, name: &str) -> Option<String> {
+        let query = self.next_page.as_deref()?.trim_start_matches('?');
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+}
+
+/// The results of several pages of a paginated endpoint, see
+/// `collect_pages()` on the clients.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CollectedPages<T> {
+    /// The results of all pages, in order.
+    pub items: Vec<T>,
+    /// The page to continue with, or `None` once an empty page ended the
+    /// results.
+    pub next_page: Option<u64>,
+}
+
+impl<T> CollectedPages<T> {
+    pub(crate) fn new(page: u64) -> Self {
+        Self {
+            items: Vec::new(),
+            next_page: Some(page),
+        }
+    }
+
+    /// Add the results of the `next_page`.
+    pub(crate) fn push(&mut self, items: Vec<T>) {
+        if items.is_empty() {
+            self.next_page = None;
+        } else {
+            self.items.extend(items);
+            self.next_page = self.next_page.map(|page| page + 1);
+        }
+    }
 }
 
 /// Links to individual API endpoints that provide crate details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct CrateLinks {
     pub owner_team: String,
     pub owner_user: String,
@@ -263,6 +693,7 @@ pub struct CrateLinks {
 /// A Rust crate published to crates.io.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Crate {
     pub id: String,
     pub name: String,
@@ -287,15 +718,111 @@ pub struct Crate {
     pub versions: Option<Vec<u64>>,
     pub max_version: String,
     pub max_stable_version: Option<String>,
+    /// The most recently published version, including pre-releases.
+    #[serde(default)]
+    pub newest_version: Option<String>,
+    /// The version shown by default on crates.io.
+    ///
+    /// This is the highest stable version that is not yanked, if any.
+    #[serde(default)]
+    pub default_version: Option<String>,
+    /// Whether the default version is yanked, which means all versions are.
+    #[serde(default)]
+    pub yanked: Option<bool>,
+    /// The number of published versions, including yanked ones.
+    #[serde(default)]
+    pub num_versions: Option<u64>,
     pub links: CrateLinks,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    /// NOTE: only set if the crate was loaded via a search query.
     pub exact_match: Option<bool>,
+    /// Fields returned by the API that this crate doesn't model yet.
+    ///
+    /// Requires the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Crate {
+    /// Construct a crate with the given name and highest version.
+    ///
+    /// All other fields are empty or zero, and can be set afterwards. Mostly
+    /// useful for tests and mocks.
+    pub fn new(name: &str, max_version: &str) -> Self {
+        #[allow(deprecated)]
+        Self {
+            id: name.to_string(),
+            name: name.to_string(),
+            description: None,
+            license: None,
+            documentation: None,
+            homepage: None,
+            repository: None,
+            downloads: 0,
+            recent_downloads: None,
+            categories: None,
+            keywords: None,
+            versions: None,
+            max_version: max_version.to_string(),
+            max_stable_version: None,
+            newest_version: None,
+            default_version: None,
+            yanked: None,
+            num_versions: None,
+            links: CrateLinks::default(),
+            created_at: Timestamp::UNIX_EPOCH,
+            updated_at: Timestamp::UNIX_EPOCH,
+            exact_match: None,
+            #[cfg(feature = "extra-fields")]
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Check if the server reported this crate as an exact match for the
+    /// search query it was loaded with.
+    pub fn is_exact_match(&self) -> bool {
+        self.exact_match.unwrap_or(false)
+    }
+
+    /// Parse the highest version as semver.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn max_semver(&self) -> Result<crate::semver::Version, crate::InvalidVersionError> {
+        crate::semver::parse_version(&self.max_version)
+    }
+
+    /// Parse the highest stable version as semver, if there is one.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn max_stable_semver(
+        &self,
+    ) -> Option<Result<crate::semver::Version, crate::InvalidVersionError>> {
+        self.max_stable_version
+            .as_deref()
+            .map(crate::semver::parse_version)
+    }
+
+    /// Parse the most recently published version as semver, if known.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn newest_semver(
+        &self,
+    ) -> Option<Result<crate::semver::Version, crate::InvalidVersionError>> {
+        self.newest_version
+            .as_deref()
+            .map(crate::semver::parse_version)
+    }
 }
 
 /// Full data for a crate listing.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct CratesPage {
     pub crates: Vec<Crate>,
     #[serde(default)]
@@ -307,9 +834,24 @@ pub struct CratesPage {
     pub meta: Meta,
 }
 
+impl CratesPage {
+    /// Construct a page with the given crates and the total amount of
+    /// results.
+    pub fn new(crates: Vec<Crate>, total: u64) -> Self {
+        Self {
+            crates,
+            versions: Vec::new(),
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            meta: Meta::new(total),
+        }
+    }
+}
+
 /// Links to API endpoints providing extra data for a crate version.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct VersionLinks {
     #[deprecated(
         since = "0.7.1",
@@ -324,11 +866,12 @@ pub struct VersionLinks {
 /// A [`Crate`] version.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Version {
     #[serde(rename = "crate")]
     pub crate_name: String,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     pub dl_path: String,
     pub downloads: u64,
     pub features: HashMap<String, Vec<String>>,
@@ -340,33 +883,174 @@ pub struct Version {
     pub links: VersionLinks,
     pub crate_size: Option<u64>,
     pub published_by: Option<User>,
+    /// The minimum supported Rust version, if declared.
+    pub rust_version: Option<String>,
+    /// The Rust edition, if declared.
+    #[serde(default)]
+    pub edition: Option<String>,
+    /// The SHA256 checksum of the `.crate` file.
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// Whether the version has a library target.
+    ///
+    /// Not known for versions published before crates.io tracked it.
+    #[serde(default)]
+    pub has_lib: Option<bool>,
+    /// The names of the binary targets.
+    ///
+    /// Not known for versions published before crates.io tracked it.
+    #[serde(default)]
+    pub bin_names: Option<Vec<String>>,
+    /// Fields returned by the API that this crate doesn't model yet.
+    ///
+    /// Requires the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// API response for a single crate version.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub struct VersionResponse {
+    pub version: Version,
+}
+
+impl Version {
+    /// Check whether the version is known to have binary targets, eg to be
+    /// installed with `cargo install`.
+    pub fn has_bins(&self) -> bool {
+        self.bin_names
+            .as_ref()
+            .is_some_and(|names| !names.is_empty())
+    }
+
+    /// Construct a version of a crate.
+    ///
+    /// All other fields are empty or zero, and can be set afterwards. Mostly
+    /// useful for tests and mocks.
+    pub fn new(crate_name: &str, num: &str) -> Self {
+        Self {
+            crate_name: crate_name.to_string(),
+            created_at: Timestamp::UNIX_EPOCH,
+            updated_at: Timestamp::UNIX_EPOCH,
+            dl_path: format!("/api/v1/crates/{}/{}/download", crate_name, num),
+            downloads: 0,
+            features: HashMap::new(),
+            id: 0,
+            num: num.to_string(),
+            yanked: false,
+            license: None,
+            readme_path: None,
+            links: VersionLinks::default(),
+            crate_size: None,
+            published_by: None,
+            rust_version: None,
+            edition: None,
+            checksum: None,
+            has_lib: None,
+            bin_names: None,
+            #[cfg(feature = "extra-fields")]
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Parse the license as an SPDX expression, if there is one.
+    ///
+    /// Requires the `spdx` feature.
+    #[cfg(feature = "spdx")]
+    pub fn license_expr(
+        &self,
+    ) -> Option<Result<crate::spdx::LicenseExpr, crate::InvalidLicenseError>> {
+        self.license.as_deref().map(crate::spdx::LicenseExpr::parse)
+    }
+
+    /// Parse the version number as semver.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn semver(&self) -> Result<crate::semver::Version, crate::InvalidVersionError> {
+        crate::semver::parse_version(&self.num)
+    }
 }
 
 /// A crate category.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Category {
     pub category: String,
     pub crates_cnt: u64,
-    pub created_at: DateTime<Utc>,
+    pub created_at: Timestamp,
     pub description: String,
     pub id: String,
     pub slug: String,
+    /// Fields returned by the API that this crate doesn't model yet.
+    ///
+    /// Requires the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Category {
+    /// Construct a category with the given slug and name.
+    ///
+    /// All other fields are empty or zero, and can be set afterwards. Mostly
+    /// useful for tests and mocks.
+    pub fn new(slug: &str, category: &str) -> Self {
+        Self {
+            category: category.to_string(),
+            crates_cnt: 0,
+            created_at: Timestamp::UNIX_EPOCH,
+            description: String::new(),
+            id: slug.to_string(),
+            slug: slug.to_string(),
+            #[cfg(feature = "extra-fields")]
+            extra: HashMap::new(),
+        }
+    }
 }
 
 /// A keyword available on crates.io.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Keyword {
     pub id: String,
     pub keyword: String,
     pub crates_cnt: u64,
-    pub created_at: DateTime<Utc>,
+    pub created_at: Timestamp,
+    /// Fields returned by the API that this crate doesn't model yet.
+    ///
+    /// Requires the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Keyword {
+    /// Construct a keyword.
+    ///
+    /// All other fields are empty or zero, and can be set afterwards. Mostly
+    /// useful for tests and mocks.
+    pub fn new(keyword: &str) -> Self {
+        Self {
+            id: keyword.to_string(),
+            keyword: keyword.to_string(),
+            crates_cnt: 0,
+            created_at: Timestamp::UNIX_EPOCH,
+            #[cfg(feature = "extra-fields")]
+            extra: HashMap::new(),
+        }
+    }
 }
 
 /// Full data for a crate.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct CrateResponse {
     pub categories: Vec<Category>,
     #[serde(rename = "crate")]
@@ -375,9 +1059,314 @@ pub struct CrateResponse {
     pub versions: Vec<Version>,
 }
 
-/// Summary for crates.io.
+impl CrateResponse {
+    /// Get the version shown by default on crates.io, if it's known.
+    pub fn default_version(&self) -> Option<&Version> {
+        let num = self.crate_data.default_version.as_deref()?;
+        self.versions.iter().find(|v| v.num == num)
+    }
+
+    /// Construct a response for a crate and its versions, without categories
+    /// or keywords.
+    pub fn new(crate_data: Crate, versions: Vec<Version>) -> Self {
+        Self {
+            categories: Vec::new(),
+            crate_data,
+            keywords: Vec::new(),
+            versions,
+        }
+    }
+
+    /// Get all non-yanked versions that match a requirement, newest first.
+    ///
+    /// Following Cargo, pre-releases only match requirements that mention a
+    /// pre-release of the same version. Versions that are not valid semver
+    /// never match.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn versions_matching(&self, req: &crate::semver::VersionReq) -> Vec<&Version> {
+        self.sorted_versions(|v| req.matches(v))
+    }
+
+    /// Get the newest non-yanked version that matches a requirement.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn latest_matching(&self, req: &crate::semver::VersionReq) -> Option<&Version> {
+        self.versions_matching(req).into_iter().next()
+    }
+
+    /// Get the newest non-yanked version that is not a pre-release.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn latest_stable(&self) -> Option<&Version> {
+        self.sorted_versions(|v| !crate::semver::is_prerelease(v))
+            .into_iter()
+            .next()
+    }
+
+    #[cfg(feature = "semver")]
+    fn sorted_versions<F>(&self, filter: F) -> Vec<&Version>
+    where
+        F: Fn(&crate::semver::Version) -> bool,
+    {
+        let mut versions: Vec<_> = self
+            .versions
+            .iter()
+            .filter(|v| !v.yanked)
+            .filter_map(|v| v.semver().ok().map(|num| (num, v)))
+            .filter(|(num, _)| filter(num))
+            .collect();
+        versions.sort_by(|a, b| b.0.cmp(&a.0));
+        versions.into_iter().map(|(_, v)| v).collect()
+    }
+}
+
+/// Available updates for a dependency, see `Client::check_updates`.
+#[cfg(feature = "semver")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct UpdateCheck {
+    /// The canonical crate name.
+    pub name: String,
+    /// The checked version requirement.
+    pub req: String,
+    /// The newest non-yanked version matching the requirement.
+    pub latest_matching: Option<String>,
+    /// The newest non-yanked stable version.
+    pub latest: Option<String>,
+}
+
+#[cfg(feature = "semver")]
+impl UpdateCheck {
+    pub(crate) fn new(
+        req: &str,
+        parsed: &crate::semver::VersionReq,
+        krate: &CrateResponse,
+    ) -> Self {
+        Self {
+            name: krate.crate_data.name.clone(),
+            req: req.to_string(),
+            latest_matching: krate.latest_matching(parsed).map(|v| v.num.clone()),
+            latest: krate.latest_stable().map(|v| v.num.clone()),
+        }
+    }
+
+    /// Check whether a newer stable version exists outside of the requirement.
+    pub fn is_outdated(&self) -> bool {
+        match (&self.latest_matching, &self.latest) {
+            (Some(matching), Some(latest)) => {
+                match (
+                    crate::semver::parse_version(matching),
+                    crate::semver::parse_version(latest),
+                ) {
+                    (Ok(matching), Ok(latest)) => latest > matching,
+                    _ => false,
+                }
+            }
+            (None, Some(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The size of a version jump, by the leftmost component that changed.
+#[cfg(feature = "semver")]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateKind {
+    /// Only the patch version or the pre-release changed.
+    Patch,
+    /// The minor version changed.
+    Minor,
+    /// The major version changed.
+    Major,
+}
+
+/// A version to update to, see [`UpdateSuggestion`].
+#[cfg(feature = "semver")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SuggestedVersion {
+    /// The version number.
+    pub num: String,
+    /// The size of the jump from the current version.
+    pub kind: UpdateKind,
+    /// Whether the version is a pre-release.
+    pub prerelease: bool,
+}
+
+/// Newer versions of a dependency, see `suggest_updates()` on the clients.
+///
+/// Only versions that are not yanked and newer than the current one are
+/// suggested. Stable versions are preferred, a pre-release is only suggested
+/// if there is no newer stable version.
+#[cfg(feature = "semver")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct UpdateSuggestion {
+    /// The canonical crate name.
+    pub name: String,
+    /// The current version.
+    pub current: String,
+    /// The newest version that is semver compatible with the current one,
+    /// so that `cargo update` can pick it up.
+    pub compatible: Option<SuggestedVersion>,
+    /// The newest version that is not semver compatible with the current
+    /// one, which requires changing the version requirement.
+    pub incompatible: Option<SuggestedVersion>,
+}
+
+#[cfg(feature = "semver")]
+impl UpdateSuggestion {
+    pub(crate) fn new(current: &crate::semver::Version, krate: &CrateResponse) -> Self {
+        let newer: Vec<_> = krate
+            .versions
+            .iter()
+            .filter(|v| !v.yanked)
+            .filter_map(|v| v.semver().ok())
+            .filter(|num| num > current)
+            .collect();
+        let suggest = |compatible: bool| {
+            let candidates = newer
+                .iter()
+                .filter(|num| crate::semver::is_compatible(current, num) == compatible);
+            let stable = candidates
+                .clone()
+                .filter(|num| !crate::semver::is_prerelease(num))
+                .max();
+            stable
+                .or_else(|| candidates.max())
+                .map(|num| SuggestedVersion {
+                    num: num.to_string(),
+                    kind: if num.major != current.major {
+                        UpdateKind::Major
+                    } else if num.minor != current.minor {
+                        UpdateKind::Minor
+                    } else {
+                        UpdateKind::Patch
+                    },
+                    prerelease: crate::semver::is_prerelease(num),
+                })
+        };
+        Self {
+            name: krate.crate_data.name.clone(),
+            current: current.to_string(),
+            compatible: suggest(true),
+            incompatible: suggest(false),
+        }
+    }
+}
+
+/// Metrics of multiple crates, see `Client::compare_crates`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct CrateComparison {
+    /// Metrics of every crate that was found, in the requested order.
+    pub crates: Vec<CrateMetrics>,
+    /// Requested crates that don't exist.
+    pub missing: Vec<String>,
+}
+
+/// Metrics of a single crate in a [`CrateComparison`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct CrateMetrics {
+    /// The canonical crate name.
+    pub name: String,
+    /// The newest stable version, or the newest version if there is no
+    /// stable release.
+    pub version: String,
+    /// Total downloads.
+    pub downloads: u64,
+    /// Downloads within the last 90 days.
+    pub recent_downloads: Option<u64>,
+    /// The release date of `version`.
+    pub last_release: Timestamp,
+    /// Number of crates depending on this crate.
+    pub reverse_dependencies: u64,
+    /// The minimum supported Rust version of `version`, if declared.
+    pub rust_version: Option<String>,
+}
+
+impl CrateComparison {
+    /// Get the metrics of a crate by name.
+    pub fn get(&self, name: &str) -> Option<&CrateMetrics> {
+        let name = crate::crate_name::normalize(name);
+        self.crates
+            .iter()
+            .find(|m| crate::crate_name::normalize(&m.name) == name)
+    }
+
+    /// Match the crates returned by a query with the requested names.
+    ///
+    /// Returns the found crates in the requested order, and the missing names.
+    /// Names that are requested more than once are only matched the first
+    /// time.
+    pub(crate) fn match_crates<S: AsRef<str>>(
+        names: &[S],
+        mut found: Vec<Crate>,
+    ) -> (Vec<Crate>, Vec<String>) {
+        let mut crates = Vec::new();
+        let mut missing = Vec::new();
+        let mut requested = HashSet::new();
+        for name in names {
+            let name = name.as_ref();
+            let normalized = crate::crate_name::normalize(name);
+            if !requested.insert(normalized.clone()) {
+                continue;
+            }
+            match found
+                .iter()
+                .position(|c| crate::crate_name::normalize(&c.name) == normalized)
+            {
+                Some(index) => crates.push(found.swap_remove(index)),
+                None => missing.push(name.to_string()),
+            }
+        }
+        (crates, missing)
+    }
+
+    /// Build the query that fetches the given crates with a single request.
+    pub(crate) fn query<S: AsRef<str>>(names: &[S]) -> CratesQuery {
+        names
+            .iter()
+            .fold(CratesQuery::builder(), |builder, name| {
+                builder.id(name.as_ref())
+            })
+            .page_size(CratesQuery::MAX_PAGE_SIZE)
+            .build()
+    }
+
+    /// The version to compare for a crate.
+    pub(crate) fn compared_version(krate: &Crate) -> &str {
+        krate
+            .max_stable_version
+            .as_deref()
+            .unwrap_or(&krate.max_version)
+    }
+}
+
+impl CrateMetrics {
+    pub(crate) fn new(krate: Crate, version: Version, reverse_dependencies: u64) -> Self {
+        Self {
+            name: krate.name,
+            version: version.num,
+            downloads: krate.downloads,
+            recent_downloads: krate.recent_downloads,
+            last_release: version.created_at,
+            reverse_dependencies,
+            rust_version: version.rust_version,
+        }
+    }
+}
+
+/// Summary for crates.io.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Summary {
     pub just_updated: Vec<Crate>,
     pub most_downloaded: Vec<Crate>,
@@ -392,8 +1381,9 @@ pub struct Summary {
 /// Download data for a single crate version.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct VersionDownloads {
-    pub date: NaiveDate,
+    pub date: Date,
     pub downloads: u64,
     pub version: u64,
 }
@@ -402,29 +1392,247 @@ pub struct VersionDownloads {
 /// Only required for old download data.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct ExtraDownloads {
-    pub date: NaiveDate,
+    pub date: Date,
     pub downloads: u64,
 }
 
 /// Additional data for crate downloads.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct CrateDownloadsMeta {
     pub extra_downloads: Vec<ExtraDownloads>,
 }
 
-/// Download data for all versions of a [`Crate`].
+/// Download data for a single crate version.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
+pub struct VersionDownloadsResponse {
+    pub version_downloads: Vec<VersionDownloads>,
+}
+
+/// Download data for all versions of a [`Crate`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(missing_docs)]
+#[non_exhaustive]
 pub struct CrateDownloads {
     pub version_downloads: Vec<VersionDownloads>,
     pub meta: CrateDownloadsMeta,
 }
 
+/// The downloads of a crate on one day, see [`CrateDownloads::total_by_date`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DateDownloads {
+    /// The day.
+    pub date: Date,
+    /// Downloads of the versions listed in
+    /// [`CrateDownloads::version_downloads`].
+    pub versions: u64,
+    /// Downloads from [`CrateDownloadsMeta::extra_downloads`], ie of versions
+    /// that are not listed individually.
+    pub extra: u64,
+}
+
+impl DateDownloads {
+    /// Total downloads of the day.
+    pub fn total(&self) -> u64 {
+        self.versions + self.extra
+    }
+}
+
+/// Downloads within a period, see [`CrateDownloads::weekly`] and friends.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DownloadPeriod {
+    /// The first day of the period.
+    pub start: Date,
+    /// Total downloads within the period.
+    pub downloads: u64,
+}
+
+impl DownloadPeriod {
+    /// Relative change of every period compared to the previous one.
+    ///
+    /// The first entry is always `None`, as is every entry following a period
+    /// without downloads. Note that the first and last periods returned by
+    /// [`CrateDownloads::weekly`] and [`CrateDownloads::monthly`] are usually
+    /// incomplete.
+    pub fn growth_rates(periods: &[DownloadPeriod]) -> Vec<Option<f64>> {
+        std::iter::once(None)
+            .chain(
+                periods
+                    .windows(2)
+                    .map(|w| growth(w[0].downloads, w[1].downloads)),
+            )
+            .take(periods.len())
+            .collect()
+    }
+}
+
+fn growth(previous: u64, current: u64) -> Option<f64> {
+    if previous == 0 {
+        None
+    } else {
+        Some((current as f64 - previous as f64) / previous as f64)
+    }
+}
+
+impl CrateDownloads {
+    /// Total downloads per day, across all versions.
+    ///
+    /// Includes the `extra_downloads` of versions that are not listed
+    /// individually. Days without downloads between the first and last day
+    /// are filled with zeros.
+    pub fn daily(&self) -> Vec<DownloadPeriod> {
+        let totals = self.total_by_date();
+        let (first, last) = match (totals.first(), totals.last()) {
+            (Some(first), Some(last)) => (first.date, last.date),
+            _ => return Vec::new(),
+        };
+        let mut totals = totals.iter().peekable();
+        let mut days = Vec::new();
+        let mut date = first;
+        while date <= last {
+            let downloads = match totals.next_if(|d| d.date == date) {
+                Some(d) => d.total(),
+                None => 0,
+            };
+            days.push(DownloadPeriod {
+                start: date,
+                downloads,
+            });
+            date = date.add_days(1);
+        }
+        days
+    }
+
+    /// Downloads per day with data, in date order, merging the downloads of
+    /// all versions with the `extra_downloads` of the same day.
+    ///
+    /// Unlike [`CrateDownloads::daily`], days without data are left out and
+    /// the version and extra downloads are kept apart.
+    pub fn total_by_date(&self) -> Vec<DateDownloads> {
+        let mut days = std::collections::BTreeMap::<Date, DateDownloads>::new();
+        let version_downloads = self
+            .version_downloads
+            .iter()
+            .map(|d| (d.date, d.downloads, 0));
+        let extra_downloads = self
+            .meta
+            .extra_downloads
+            .iter()
+            .map(|d| (d.date, 0, d.downloads));
+        for (date, versions, extra) in version_downloads.chain(extra_downloads) {
+            let day = days.entry(date).or_insert(DateDownloads {
+                date,
+                versions: 0,
+                extra: 0,
+            });
+            day.versions += versions;
+            day.extra += extra;
+        }
+        days.into_values().collect()
+    }
+
+    /// Total downloads per week, with weeks starting on Monday.
+    pub fn weekly(&self) -> Vec<DownloadPeriod> {
+        self.grouped(|date| date.add_days(-(date.days_from_monday() as i32)))
+    }
+
+    /// Total downloads per calendar month.
+    pub fn monthly(&self) -> Vec<DownloadPeriod> {
+        self.grouped(|date| date.add_days(1 - date.day() as i32))
+    }
+
+    /// Total downloads of the last `days` days, up to and including the
+    /// latest day with data.
+    ///
+    /// crates.io only returns the downloads of the last 90 days.
+    pub fn trailing_total(&self, days: u32) -> u64 {
+        let daily = self.daily();
+        daily
+            .iter()
+            .rev()
+            .take(days as usize)
+            .map(|d| d.downloads)
+            .sum()
+    }
+
+    /// Total downloads across all versions and days.
+    pub fn total(&self) -> u64 {
+        self.daily().iter().map(|d| d.downloads).sum()
+    }
+
+    /// Restrict the downloads to the days from `from` to `to`, inclusive.
+    pub fn between(&self, from: Date, to: Date) -> CrateDownloads {
+        CrateDownloads {
+            version_downloads: self
+                .version_downloads
+                .iter()
+                .filter(|d| d.date >= from && d.date <= to)
+                .cloned()
+                .collect(),
+            meta: CrateDownloadsMeta {
+                extra_downloads: self
+                    .meta
+                    .extra_downloads
+                    .iter()
+                    .filter(|d| d.date >= from && d.date <= to)
+                    .cloned()
+                    .collect(),
+            },
+        }
+    }
+
+    /// Relative change of the downloads of the last `days` days compared to
+    /// the `days` days before.
+    ///
+    /// `0.5` means 50% more downloads. Returns `None` if there is not
+    /// enough data, or if there were no downloads in the earlier period.
+    pub fn period_over_period(&self, days: usize) -> Option<f64> {
+        let daily = self.daily();
+        if days == 0 || daily.len() < 2 * days {
+            return None;
+        }
+        let total = |days: &[DownloadPeriod]| days.iter().map(|d| d.downloads).sum::<u64>();
+        let current = total(&daily[daily.len() - days..]);
+        let previous = total(&daily[daily.len() - 2 * days..daily.len() - days]);
+        growth(previous, current)
+    }
+
+    /// Relative change of the downloads of the last 7 days compared to the
+    /// week before, see [`Self::period_over_period`].
+    pub fn week_over_week(&self) -> Option<f64> {
+        self.period_over_period(7)
+    }
+
+    fn grouped<F>(&self, period_start: F) -> Vec<DownloadPeriod>
+    where
+        F: Fn(Date) -> Date,
+    {
+        let mut periods: Vec<DownloadPeriod> = Vec::new();
+        for day in self.daily() {
+            let start = period_start(day.start);
+            match periods.last_mut() {
+                Some(period) if period.start == start => period.downloads += day.downloads,
+                _ => periods.push(DownloadPeriod {
+                    start,
+                    downloads: day.downloads,
+                }),
+            }
+        }
+        periods
+    }
+}
+
 /// A crates.io user.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct User {
     pub avatar: Option<String>,
     pub email: Option<String>,
@@ -433,11 +1641,290 @@ pub struct User {
     pub login: String,
     pub name: Option<String>,
     pub url: String,
+    /// Fields returned by the API that this crate doesn't model yet.
+    ///
+    /// Requires the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl User {
+    /// Construct a user with the given id and login.
+    pub fn new(id: u64, login: &str) -> Self {
+        Self {
+            avatar: None,
+            email: None,
+            id,
+            kind: None,
+            login: login.to_string(),
+            name: None,
+            url: format!("https://github.com/{}", login),
+            #[cfg(feature = "extra-fields")]
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Check if this is a team, which the owners endpoint reports as a
+    /// user with the `team` kind.
+    pub fn is_team(&self) -> bool {
+        self.kind.as_deref() == Some("team")
+    }
+}
+
+/// An owner of a crate, which is either a user or a team.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Owner {
+    /// An individual user.
+    User(User),
+    /// A GitHub team, with a login like `github:rust-lang:libs`.
+    Team(Team),
+}
+
+impl Owner {
+    /// Get the login of the user or team.
+    pub fn login(&self) -> &str {
+        match self {
+            Self::User(user) => &user.login,
+            Self::Team(team) => &team.login,
+        }
+    }
+}
+
+impl From<User> for Owner {
+    /// Convert an entry of the owners endpoint, based on its `kind`.
+    fn from(user: User) -> Self {
+        if !user.is_team() {
+            return Self::User(user);
+        }
+        Self::Team(Team {
+            avatar: user.avatar,
+            id: user.id,
+            login: user.login,
+            name: user.name,
+            url: Some(user.url),
+            #[cfg(feature = "extra-fields")]
+            extra: user.extra,
+        })
+    }
+}
+
+/// The owners of a set of crates, see `shared_owners()` on the clients.
+///
+/// Users and teams are told apart by their login, since their ids overlap.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct OwnershipOverlap {
+    /// The owners of every crate.
+    pub owners: std::collections::BTreeMap<String, Vec<User>>,
+}
+
+impl OwnershipOverlap {
+    pub(crate) fn new(owners: impl IntoIterator<Item = (String, Vec<User>)>) -> Self {
+        Self {
+            owners: owners.into_iter().collect(),
+        }
+    }
+
+    /// Get the owners of all of the crates, by login.
+    pub fn shared(&self) -> Vec<&User> {
+        self.all()
+            .into_iter()
+            .filter(|user| self.crates_of(&user.login).len() == self.owners.len())
+            .collect()
+    }
+
+    /// Get the owners of any of the crates, by login.
+    pub fn all(&self) -> Vec<&User> {
+        let mut users: Vec<&User> = self.owners.values().flatten().collect();
+        users.sort_by(|a, b| a.login.cmp(&b.login));
+        users.dedup_by(|a, b| a.login == b.login);
+        users
+    }
+
+    /// Get the crates owned by the user or team with the given login,
+    /// alphabetically.
+    pub fn crates_of(&self, login: &str) -> Vec<&str> {
+        self.owners
+            .iter()
+            .filter(|(_, owners)| owners.iter().any(|owner| owner.login == login))
+            .map(|(crate_name, _)| crate_name.as_str())
+            .collect()
+    }
+
+    /// Get every owner with the number of the crates they own, most crates
+    /// first.
+    pub fn by_crate_count(&self) -> Vec<(&User, usize)> {
+        let mut counts: Vec<_> = self
+            .all()
+            .into_iter()
+            .map(|user| (user, self.crates_of(&user.login).len()))
+            .collect();
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        counts
+    }
+}
+
+/// API response for adding or removing owners with `PUT` or `DELETE` on
+/// `/crates/{name}/owners`.
+///
+/// The clients don't change owners, this type decodes the responses for
+/// tools that do. A successful response can still contain a different
+/// outcome per login, see [`Self::outcomes`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct OwnerChangeResponse {
+    /// Whether the change was applied.
+    pub ok: bool,
+    /// The messages for the individual logins, joined with commas.
+    #[serde(default)]
+    pub msg: String,
+}
+
+/// The outcome of an owner change for a single login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OwnerChangeOutcome {
+    /// The user was invited, and becomes an owner once they accept.
+    Invited,
+    /// The user already had a pending invitation.
+    AlreadyInvited,
+    /// The team was added as an owner right away.
+    Added,
+    /// The owner was removed.
+    Removed,
+    /// The login was rejected with the given message, and the whole change
+    /// was rolled back.
+    Failed(String),
+    /// The change was rolled back because of another login.
+    NotApplied,
+    /// The response doesn't mention the login.
+    Unknown,
+}
+
+/// The outcome of an owner change for one of the requested logins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OwnerChange {
+    /// The requested login.
+    pub login: String,
+    /// What happened to it.
+    pub outcome: OwnerChangeOutcome,
+}
+
+impl OwnerChangeResponse {
+    /// Get the outcome for each of the requested `logins`.
+    ///
+    /// Set `removed` if owners were removed, which crates.io confirms with a
+    /// single message for all of them.
+    pub fn outcomes(&self, logins: &[&str], removed: bool) -> Vec<OwnerChange> {
+        logins
+            .iter()
+            .map(|login| {
+                let outcome = if !self.ok {
+                    OwnerChangeOutcome::NotApplied
+                } else if removed {
+                    OwnerChangeOutcome::Removed
+                } else {
+                    self.msg
+                        .split(',')
+                        .map(str::trim)
+                        .find_map(|msg| Self::outcome(msg, login))
+                        .unwrap_or(OwnerChangeOutcome::Unknown)
+                };
+                OwnerChange {
+                    login: login.to_string(),
+                    outcome,
+                }
+            })
+            .collect()
+    }
+
+    /// Get the outcome for each of the requested `logins` of a change that
+    /// failed with `errors`.
+    ///
+    /// crates.io rejects the whole change at the first invalid login, and
+    /// names it in backticks in the error.
+    pub fn failures(errors: &ApiErrors, logins: &[&str]) -> Vec<OwnerChange> {
+        logins
+            .iter()
+            .map(|login| {
+                let quoted = format!("`{}`", login);
+                let outcome = errors
+                    .errors
+                    .iter()
+                    .filter_map(|err| err.detail.as_deref())
+                    .find(|detail| detail.contains(&quoted))
+                    .map_or(OwnerChangeOutcome::NotApplied, |detail| {
+                        OwnerChangeOutcome::Failed(detail.to_string())
+                    });
+                OwnerChange {
+                    login: login.to_string(),
+                    outcome,
+                }
+            })
+            .collect()
+    }
+
+    fn outcome(msg: &str, login: &str) -> Option<OwnerChangeOutcome> {
+        let rest = msg
+            .strip_prefix("user ")
+            .or_else(|| msg.strip_prefix("team "))?
+            .strip_prefix(login)?
+            .strip_prefix(' ')?;
+        if rest.starts_with("has been invited") {
+            Some(OwnerChangeOutcome::Invited)
+        } else if rest.starts_with("already has a pending invitation") {
+            Some(OwnerChangeOutcome::AlreadyInvited)
+        } else if rest.starts_with("has been added") {
+            Some(OwnerChangeOutcome::Added)
+        } else {
+            None
+        }
+    }
+}
+
+/// A crates.io team.
+///
+/// Teams are GitHub teams that own crates, identified by a login like
+/// `github:rust-lang:libs`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub struct Team {
+    pub avatar: Option<String>,
+    pub id: u64,
+    pub login: String,
+    pub name: Option<String>,
+    pub url: Option<String>,
+    /// Fields returned by the API that this crate doesn't model yet.
+    ///
+    /// Requires the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Team {
+    /// Construct a team with the given id and login, like
+    /// `github:rust-lang:libs`.
+    pub fn new(id: u64, login: &str) -> Self {
+        Self {
+            avatar: None,
+            id,
+            login: login.to_string(),
+            name: None,
+            url: None,
+            #[cfg(feature = "extra-fields")]
+            extra: HashMap::new(),
+        }
+    }
 }
 
 /// Additional crate author metadata.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct AuthorsMeta {
     pub names: Vec<String>,
 }
@@ -445,58 +1932,309 @@ pub struct AuthorsMeta {
 /// API Response for authors data.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
-pub(crate) struct AuthorsResponse {
+#[non_exhaustive]
+pub struct AuthorsResponse {
     pub meta: AuthorsMeta,
 }
 
 /// Crate author names.
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Authors {
     pub names: Vec<String>,
+    /// Whether crates.io no longer provides the authors of this version.
+    ///
+    /// Author names are deprecated, and the endpoint is being removed. If it
+    /// responds with 404 or 410, `names` is empty and this is set.
+    pub unavailable: bool,
+}
+
+impl Authors {
+    /// Convert the response of the authors endpoint, treating a removed
+    /// endpoint as an empty list.
+    pub(crate) fn from_response(res: Result<Vec<String>, Error>) -> Result<Self, Error> {
+        match res {
+            Ok(names) => Ok(Self {
+                names,
+                unavailable: false,
+            }),
+            Err(Error::NotFound(_)) => Ok(Self {
+                names: Vec::new(),
+                unavailable: true,
+            }),
+            Err(Error::Http(err)) if err.status() == Some(reqwest::StatusCode::GONE) => Ok(Self {
+                names: Vec::new(),
+                unavailable: true,
+            }),
+            Err(err) => Err(err),
+        }
+    }
 }
 
 /// Crate owners.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Owners {
     pub users: Vec<User>,
 }
 
+/// The kind of a dependency.
+///
+/// Serialized as the lowercase name used by the API, eg `"dev"`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(from = "String", into = "String")]
+pub enum DependencyKind {
+    /// A regular dependency.
+    #[default]
+    Normal,
+    /// A dependency of the build script.
+    Build,
+    /// A dependency of tests, examples and benchmarks.
+    Dev,
+    /// A kind this crate doesn't know about.
+    Other(String),
+}
+
+impl DependencyKind {
+    /// Get the name used by the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Normal => "normal",
+            Self::Build => "build",
+            Self::Dev => "dev",
+            Self::Other(kind) => kind,
+        }
+    }
+}
+
+impl From<String> for DependencyKind {
+    fn from(kind: String) -> Self {
+        match kind.as_str() {
+            "normal" => Self::Normal,
+            "build" => Self::Build,
+            "dev" => Self::Dev,
+            _ => Self::Other(kind),
+        }
+    }
+}
+
+impl From<&str> for DependencyKind {
+    fn from(kind: &str) -> Self {
+        Self::from(kind.to_string())
+    }
+}
+
+impl From<DependencyKind> for String {
+    fn from(kind: DependencyKind) -> Self {
+        match kind {
+            DependencyKind::Other(kind) => kind,
+            kind => kind.as_str().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// A crate dependency.
 /// Specifies the crate and features.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Dependency {
     pub crate_id: String,
     pub default_features: bool,
     pub downloads: u64,
     pub features: Vec<String>,
     pub id: u64,
-    pub kind: String,
+    pub kind: DependencyKind,
     pub optional: bool,
     pub req: String,
     pub target: Option<String>,
     pub version_id: u64,
+    /// Fields returned by the API that this crate doesn't model yet.
+    ///
+    /// Requires the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Dependency {
+    /// Construct a normal dependency with default features.
+    ///
+    /// All other fields are empty or zero, and can be set afterwards. Mostly
+    /// useful for tests and mocks.
+    pub fn new(crate_id: &str, req: &str) -> Self {
+        Self {
+            crate_id: crate_id.to_string(),
+            default_features: true,
+            downloads: 0,
+            features: Vec::new(),
+            id: 0,
+            kind: DependencyKind::Normal,
+            optional: false,
+            req: req.to_string(),
+            target: None,
+            version_id: 0,
+            #[cfg(feature = "extra-fields")]
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Parse the version requirement.
+    ///
+    /// Some very old crates have requirements that are no longer accepted by
+    /// cargo, these return an error instead of matching nothing.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    pub fn semver_req(&self) -> Result<crate::semver::VersionReq, crate::InvalidVersionError> {
+        crate::semver::parse_req(&self.req)
+    }
 }
 
 /// List of dependencies of a crate.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct Dependencies {
     pub dependencies: Vec<Dependency>,
 }
 
+/// Differences between the dependencies of two crate versions.
+///
+/// Dependencies are identified by crate, kind and target, so moving a
+/// dependency to a different kind shows up as a removal and an addition.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct DependencyDiff {
+    /// Dependencies only present in the new version.
+    pub added: Vec<Dependency>,
+    /// Dependencies only present in the old version.
+    pub removed: Vec<Dependency>,
+    /// Dependencies present in both versions, with a different requirement
+    /// or features.
+    pub changed: Vec<DependencyChange>,
+}
+
+/// A dependency that changed between two crate versions.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[non_exhaustive]
+pub struct DependencyChange {
+    /// The dependency in the old version.
+    pub old: Dependency,
+    /// The dependency in the new version.
+    pub new: Dependency,
+}
+
+impl DependencyChange {
+    /// Check whether the version requirement changed.
+    pub fn req_changed(&self) -> bool {
+        self.old.req != self.new.req
+    }
+}
+
+impl DependencyDiff {
+    /// Compare two lists of dependencies.
+    ///
+    /// All lists in the result are sorted by crate name.
+    pub fn new(old: &[Dependency], new: &[Dependency]) -> Self {
+        fn key(dep: &Dependency) -> (&str, &DependencyKind, Option<&str>) {
+            (&dep.crate_id, &dep.kind, dep.target.as_deref())
+        }
+
+        let old_deps: HashMap<_, _> = old.iter().map(|dep| (key(dep), dep)).collect();
+        let new_deps: HashMap<_, _> = new.iter().map(|dep| (key(dep), dep)).collect();
+
+        let mut diff = DependencyDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+        for dep in new {
+            match old_deps.get(&key(dep)) {
+                None => diff.added.push(dep.clone()),
+                Some(old) => {
+                    let mut old_features = old.features.clone();
+                    let mut new_features = dep.features.clone();
+                    old_features.sort();
+                    new_features.sort();
+                    if old.req != dep.req
+                        || old.optional != dep.optional
+                        || old.default_features != dep.default_features
+                        || old_features != new_features
+                    {
+                        diff.changed.push(DependencyChange {
+                            old: (*old).clone(),
+                            new: dep.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        diff.removed = old
+            .iter()
+            .filter(|dep| !new_deps.contains_key(&key(dep)))
+            .cloned()
+            .collect();
+
+        diff.added.sort_by(|a, b| a.crate_id.cmp(&b.crate_id));
+        diff.removed.sort_by(|a, b| a.crate_id.cmp(&b.crate_id));
+        diff.changed
+            .sort_by(|a, b| a.new.crate_id.cmp(&b.new.crate_id));
+        diff
+    }
+
+    /// Check whether the dependencies are identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
 /// Single reverse dependency (aka a dependent) of a crate.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct ReverseDependency {
     pub crate_version: Version,
     pub dependency: Dependency,
 }
 
-// This is how reverse dependencies are received
+impl ReverseDependency {
+    /// Get the name of the dependent crate.
+    pub fn crate_name(&self) -> &str {
+        &self.crate_version.crate_name
+    }
+
+    /// Get the dependent version.
+    pub fn version(&self) -> &str {
+        &self.crate_version.num
+    }
+
+    /// Get the total downloads of the dependent crate.
+    pub fn downloads(&self) -> u64 {
+        self.dependency.downloads
+    }
+
+    /// Get the version requirement the dependent uses.
+    pub fn req(&self) -> &str {
+        &self.dependency.req
+    }
+}
+
+/// API response for a page of reverse dependencies.
+///
+/// Use [`ReverseDependencies`] for the dependencies joined with their
+/// versions.
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub(super) struct ReverseDependenciesAsReceived {
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub struct ReverseDependenciesAsReceived {
     pub dependencies: Vec<Dependency>,
     pub versions: Vec<Version>,
     pub meta: Meta,
@@ -505,6 +2243,7 @@ pub(super) struct ReverseDependenciesAsReceived {
 /// Full list of reverse dependencies for a crate (version).
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct ReverseDependencies {
     pub dependencies: Vec<ReverseDependency>,
     pub meta: Meta,
@@ -513,29 +2252,78 @@ pub struct ReverseDependencies {
 impl ReverseDependencies {
     /// Fills the dependencies field from a ReverseDependenciesAsReceived struct.
     pub(crate) fn extend(&mut self, rdeps: ReverseDependenciesAsReceived) {
+        let versions: HashMap<_, _> = rdeps.versions.into_iter().map(|v| (v.id, v)).collect();
         for d in rdeps.dependencies {
-            for v in &rdeps.versions {
-                if v.id == d.version_id {
-                    // Right now it iterates over the full vector for each vector element.
-                    // For large vectors, it may be faster to remove each matched element
-                    // using the drain_filter() method once it's stabilized:
-                    // https://doc.rust-lang.org/nightly/std/vec/struct.Vec.html#method.drain_filter
-                    self.dependencies.push(ReverseDependency {
-                        crate_version: v.clone(),
-                        dependency: d.clone(),
-                    });
+            // Dependencies without a matching version are dropped.
+            if let Some(v) = versions.get(&d.version_id) {
+                self.dependencies.push(ReverseDependency {
+                    crate_version: v.clone(),
+                    dependency: d,
+                });
+            }
+        }
+    }
+
+    /// Get one reverse dependency per dependent crate, from its newest
+    /// version.
+    ///
+    /// The API returns an entry for every dependent version, while the
+    /// question is usually which crates depend on a crate. With the `semver`
+    /// feature versions are compared as semver, falling back to the
+    /// publication date if they can't be parsed. Without it only the
+    /// publication date is compared. The order of the first entry of every
+    /// crate is kept.
+    pub fn by_crate(&self) -> Vec<&ReverseDependency> {
+        let mut newest: Vec<&ReverseDependency> = Vec::new();
+        let mut index = HashMap::new();
+        for rdep in &self.dependencies {
+            match index.get(rdep.crate_name()) {
+                None => {
+                    index.insert(rdep.crate_name(), newest.len());
+                    newest.push(rdep);
+                }
+                Some(&i) if is_newer(&rdep.crate_version, &newest[i].crate_version) => {
+                    newest[i] = rdep;
                 }
+                Some(_) => {}
             }
         }
+        newest
+    }
+
+    /// Sort the reverse dependencies by the downloads of the dependent crate,
+    /// most downloaded first.
+    ///
+    /// Ties are broken by crate name.
+    pub fn sort_by_downloads(&mut self) {
+        self.dependencies.sort_by(|a, b| {
+            b.downloads()
+                .cmp(&a.downloads())
+                .then_with(|| a.crate_name().cmp(b.crate_name()))
+        });
     }
 }
 
+#[cfg(feature = "semver")]
+fn is_newer(a: &Version, b: &Version) -> bool {
+    match (a.semver(), b.semver()) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => a.created_at > b.created_at,
+    }
+}
+
+#[cfg(not(feature = "semver"))]
+fn is_newer(a: &Version, b: &Version) -> bool {
+    a.created_at > b.created_at
+}
+
 /// Complete information for a crate version.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct FullVersion {
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     pub dl_path: String,
     pub downloads: u64,
     pub features: HashMap<String, Vec<String>>,
@@ -545,14 +2333,110 @@ pub struct FullVersion {
     pub license: Option<String>,
     pub readme_path: Option<String>,
     pub links: VersionLinks,
+    /// The minimum supported Rust version, if declared.
+    pub rust_version: Option<String>,
+    /// The Rust edition, if declared.
+    pub edition: Option<String>,
+    /// The size of the `.crate` file in bytes.
+    pub crate_size: Option<u64>,
+    /// The SHA256 checksum of the `.crate` file.
+    pub checksum: Option<String>,
+    /// Whether the version has a library target, if known.
+    pub has_lib: Option<bool>,
+    /// The names of the binary targets, if known.
+    pub bin_names: Option<Vec<String>>,
+    /// The user who published the version, if known.
+    #[serde(default)]
+    pub published_by: Option<User>,
 
     pub author_names: Vec<String>,
+    /// Whether crates.io no longer provides the authors of this version, see
+    /// [`Authors::unavailable`].
+    ///
+    /// Unlike an empty `author_names`, this tells a version without authors
+    /// apart from one whose authors can't be known. It is `false` if the
+    /// authors were not requested.
+    #[serde(default)]
+    pub authors_unavailable: bool,
     pub dependencies: Vec<Dependency>,
 }
 
+impl FullVersion {
+    /// Move the fields of a version into a full version.
+    pub(crate) fn new(
+        version: Version,
+        authors: Option<Authors>,
+        dependencies: Vec<Dependency>,
+    ) -> Self {
+        let (author_names, authors_unavailable) = authors
+            .map(|authors| (authors.names, authors.unavailable))
+            .unwrap_or_default();
+        Self {
+            created_at: version.created_at,
+            updated_at: version.updated_at,
+            dl_path: version.dl_path,
+            downloads: version.downloads,
+            features: version.features,
+            id: version.id,
+            num: version.num,
+            yanked: version.yanked,
+            license: version.license,
+            links: version.links,
+            readme_path: version.readme_path,
+            rust_version: version.rust_version,
+            edition: version.edition,
+            crate_size: version.crate_size,
+            checksum: version.checksum,
+            has_lib: version.has_lib,
+            bin_names: version.bin_names,
+            published_by: version.published_by,
+
+            author_names,
+            authors_unavailable,
+            dependencies,
+        }
+    }
+}
+
+/// An event of a crawl that survives crates disappearing, see
+/// `full_crates_events()` on the clients.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum CrawlEvent<T> {
+    /// The details of a listed crate.
+    Item(T),
+    /// A listed crate was not found when its details were fetched, eg
+    /// because it was deleted or renamed during the crawl.
+    Skipped {
+        /// The listed name of the crate.
+        crate_name: String,
+    },
+}
+
+impl<T> CrawlEvent<T> {
+    /// Convert the result of fetching the details of a crate, turning
+    /// [`Error::NotFound`] into [`CrawlEvent::Skipped`].
+    pub(crate) fn from_result(crate_name: String, res: Result<T, Error>) -> Result<Self, Error> {
+        match res {
+            Ok(item) => Ok(CrawlEvent::Item(item)),
+            Err(Error::NotFound(_)) => Ok(CrawlEvent::Skipped { crate_name }),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get the item, or `None` if the crate was skipped.
+    pub fn into_item(self) -> Option<T> {
+        match self {
+            CrawlEvent::Item(item) => Some(item),
+            CrawlEvent::Skipped { .. } => None,
+        }
+    }
+}
+
 /// Complete information for a crate.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct FullCrate {
     pub id: String,
     pub name: String,
@@ -565,19 +2449,1143 @@ pub struct FullCrate {
     pub recent_downloads: Option<u64>,
     pub max_version: String,
     pub max_stable_version: Option<String>,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
 
     pub categories: Vec<Category>,
     pub keywords: Vec<Keyword>,
     pub downloads: CrateDownloads,
     pub owners: Vec<User>,
+    /// The reverse dependencies, empty with a total of 0 if they were not
+    /// fetched, see `reverse_dependencies_fetched`.
     pub reverse_dependencies: ReverseDependencies,
+    /// Whether the reverse dependencies were fetched, see
+    /// [`FullCrateOptions::reverse_dependencies()`].
+    ///
+    /// If not, `reverse_dependencies` is empty even if the crate has
+    /// dependents.
+    #[serde(default = "default_fetched")]
+    pub reverse_dependencies_fetched: bool,
 
     pub versions: Vec<FullVersion>,
 }
 
+/// Full crates serialized before `reverse_dependencies_fetched` always had
+/// their reverse dependencies.
+fn default_fetched() -> bool {
+    true
+}
+
+impl FullCrate {
+    /// Move the fields of a crate response into a full crate.
+    ///
+    /// The versions of the response are ignored, they must have been taken
+    /// out before to build `versions`.
+    pub(crate) fn new(
+        krate: CrateResponse,
+        license: Option<String>,
+        downloads: CrateDownloads,
+        owners: Vec<User>,
+        reverse_dependencies: Option<ReverseDependencies>,
+        versions: Vec<FullVersion>,
+    ) -> Self {
+        let data = krate.crate_data;
+        let reverse_dependencies_fetched = reverse_dependencies.is_some();
+        let reverse_dependencies = reverse_dependencies.unwrap_or(ReverseDependencies {
+            dependencies: Vec::new(),
+            meta: Meta::new(0),
+        });
+        Self {
+            id: data.id,
+            name: data.name,
+            description: data.description,
+            license,
+            documentation: data.documentation,
+            homepage: data.homepage,
+            repository: data.repository,
+            total_downloads: data.downloads,
+            recent_downloads: data.recent_downloads,
+            max_version: data.max_version,
+            max_stable_version: data.max_stable_version,
+            created_at: data.created_at,
+            updated_at: data.updated_at,
+
+            categories: krate.categories,
+            keywords: krate.keywords,
+            downloads,
+            owners,
+            reverse_dependencies,
+            reverse_dependencies_fetched,
+            versions,
+        }
+    }
+}
+
+/// Options that control which parts of a [`FullCrate`] are fetched.
+///
+/// Every part requires extra requests, so skipping the parts you don't need
+/// can speed up retrieval considerably, especially for popular crates.
+///
+/// By default, everything is fetched for all versions.
+#[derive(Clone)]
+pub struct FullCrateOptions {
+    pub(crate) versions: VersionFilter,
+    pub(crate) authors: bool,
+    pub(crate) dependencies: bool,
+    pub(crate) reverse_dependencies: bool,
+    pub(crate) concurrency: Option<usize>,
+    pub(crate) progress: Option<ProgressCallback>,
+}
+
+impl std::fmt::Debug for FullCrateOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FullCrateOptions")
+            .field("versions", &self.versions)
+            .field("authors", &self.authors)
+            .field("dependencies", &self.dependencies)
+            .field("reverse_dependencies", &self.reverse_dependencies)
+            .field("concurrency", &self.concurrency)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+/// Selects the versions of a crate to fetch details for.
+#[derive(Debug, Clone)]
+pub enum VersionFilter {
+    /// All versions.
+    All,
+    /// The newest `n` versions.
+    Latest(usize),
+    /// All versions that match the requirement.
+    ///
+    /// Versions that are not valid semver never match.
+    ///
+    /// Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    Matching(crate::semver::VersionReq),
+}
+
+impl VersionFilter {
+    /// Check if the given version number is selected by a requirement.
+    ///
+    /// `All` and `Latest` filters select every version number.
+    #[cfg_attr(not(feature = "semver"), allow(unused_variables))]
+    pub fn matches(&self, num: &str) -> bool {
+        match self {
+            Self::All | Self::Latest(_) => true,
+            #[cfg(feature = "semver")]
+            Self::Matching(req) => crate::semver::parse_version(num)
+                .map(|v| req.matches(&v))
+                .unwrap_or(false),
+        }
+    }
+
+    /// Select versions from a list sorted newest first.
+    pub(crate) fn select(&self, versions: Vec<Version>) -> Vec<Version> {
+        match self {
+            Self::All => versions,
+            Self::Latest(n) => versions.into_iter().take(*n).collect(),
+            #[cfg(feature = "semver")]
+            Self::Matching(_) => versions
+                .into_iter()
+                .filter(|v| self.matches(&v.num))
+                .collect(),
+        }
+    }
+}
+
+type ProgressCallback = Arc<dyn Fn(&FullCrateProgress) + Send + Sync>;
+
+/// Progress of a [`FullCrate`] retrieval, see [`FullCrateOptions::on_progress`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct FullCrateProgress {
+    /// Version that was just completed.
+    pub version: String,
+    /// Number of versions completed so far.
+    pub completed_versions: usize,
+    /// Total number of versions that will be fetched.
+    pub total_versions: usize,
+}
+
+impl FullCrateOptions {
+    /// Construct the default options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            versions: VersionFilter::All,
+            authors: true,
+            dependencies: true,
+            reverse_dependencies: true,
+            concurrency: None,
+            progress: None,
+        }
+    }
+
+    /// Only fetch details for the newest `max` versions (`None` for all).
+    ///
+    /// Each version requires up to two extra requests.
+    #[must_use]
+    pub fn max_versions(mut self, max: Option<usize>) -> Self {
+        self.versions = match max {
+            Some(max) => VersionFilter::Latest(max),
+            None => VersionFilter::All,
+        };
+        self
+    }
+
+    /// Only fetch details for the versions selected by the filter.
+    ///
+    /// Each version requires up to two extra requests.
+    #[must_use]
+    pub fn versions(mut self, filter: VersionFilter) -> Self {
+        self.versions = filter;
+        self
+    }
+
+    /// Fetch the author names of each version.
+    #[must_use]
+    pub fn authors(mut self, enabled: bool) -> Self {
+        self.authors = enabled;
+        self
+    }
+
+    /// Fetch the dependencies of each version.
+    #[must_use]
+    pub fn dependencies(mut self, enabled: bool) -> Self {
+        self.dependencies = enabled;
+        self
+    }
+
+    /// Fetch all reverse dependencies.
+    ///
+    /// Requires one request per 100 reverse dependencies.
+    #[must_use]
+    pub fn reverse_dependencies(mut self, enabled: bool) -> Self {
+        self.reverse_dependencies = enabled;
+        self
+    }
+
+    /// Fetch the details of at most `n` versions at the same time.
+    ///
+    /// Versions are always fetched newest first, and all requests wait for
+    /// the rate limit of the client, so this only bounds the number of
+    /// pending requests.
+    #[must_use]
+    pub fn concurrency(mut self, n: usize) -> Self {
+        self.concurrency = Some(n.max(1));
+        self
+    }
+
+    /// Call the given function every time the details of a version have been
+    /// fetched.
+    ///
+    /// Useful to show progress for crates with many versions.
+    #[must_use]
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(&FullCrateProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Report a completed version to the progress callback.
+    pub(crate) fn report_progress(&self, completed: &AtomicUsize, total: usize, version: &str) {
+        let completed = completed.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Some(callback) = &self.progress {
+            callback(&FullCrateProgress {
+                version: version.to_string(),
+                completed_versions: completed,
+                total_versions: total,
+            });
+        }
+    }
+}
+
+impl Default for FullCrateOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The requests needed to retrieve a [`FullCrate`], see `full_crate_plan()`
+/// on the clients.
+///
+/// Planning fetches the crate itself, so that the versions are known up
+/// front. Pass the plan to `full_crate_from_plan()` to fetch the rest.
+#[derive(Debug, Clone)]
+pub struct FullCratePlan {
+    krate: CrateResponse,
+    license: Option<String>,
+    selected: Vec<Version>,
+    requests: u64,
+    eta: std::time::Duration,
+}
+
+impl FullCratePlan {
+    pub(crate) fn new(
+        mut krate: CrateResponse,
+        options: &FullCrateOptions,
+        rate_limit: std::time::Duration,
+    ) -> Self {
+        let license = krate.versions.first().and_then(|v| v.license.clone());
+        let selected = options.versions.select(std::mem::take(&mut krate.versions));
+        let per_version = u64::from(options.authors) + u64::from(options.dependencies);
+        // Downloads and owners, and the first page of reverse dependencies.
+        let requests =
+            2 + u64::from(options.reverse_dependencies) + per_version * selected.len() as u64;
+        Self {
+            krate,
+            license,
+            selected,
+            requests,
+            eta: rate_limit * requests.min(u64::from(u32::MAX)) as u32,
+        }
+    }
+
+    /// Get the name of the crate.
+    pub fn crate_name(&self) -> &str {
+        &self.krate.crate_data.name
+    }
+
+    /// Get the versions whose details will be fetched, in the order they are
+    /// fetched.
+    pub fn versions(&self) -> impl Iterator<Item = &str> {
+        self.selected.iter().map(|v| v.num.as_str())
+    }
+
+    /// Get the number of requests that are still needed.
+    ///
+    /// Reverse dependencies are counted as a single page, crates with more
+    /// than 100 of them need one more request per 100.
+    pub fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// Get how long the remaining requests take at the rate limit of the
+    /// client, without network latency.
+    pub fn eta(&self) -> std::time::Duration {
+        self.eta
+    }
+
+    pub(crate) fn into_parts(self) -> (CrateResponse, Option<String>, Vec<Version>) {
+        (self.krate, self.license, self.selected)
+    }
+}
+
+/// API response for a single user.
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub(crate) struct UserResponse {
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub struct UserResponse {
     pub user: User,
 }
+
+/// API response for a single keyword.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub struct KeywordResponse {
+    pub keyword: Keyword,
+}
+
+/// API response for a single category.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub struct CategoryResponse {
+    pub category: Category,
+}
+
+/// API response for a single team.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub struct TeamResponse {
+    pub team: Team,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn query_string(query: &CratesQuery) -> String {
+        query.to_string()
+    }
+
+    #[test]
+    fn test_crates_query_round_trip() {
+        let query = CratesQuery::builder()
+            .search("http client")
+            .category("web-programming")
+            .keyword("async")
+            .keyword("tls")
+            .id("reqwest")
+            .user_id(7)
+            .sort(Sort::RecentDownloads)
+            .page(2)
+            .build();
+        let string = query.to_string();
+        assert_eq!(
+            string,
+            "page=2&per_page=30&sort=recent-downloads&user_id=7&q=http+client&category=web-programming&all_keywords=async+tls&ids%5B%5D=reqwest"
+        );
+        assert_eq!(
+            query.to_url().as_str(),
+            format!("https://crates.io/api/v1/crates?{}", string)
+        );
+
+        let parsed: CratesQuery = query.to_url().as_str().parse().unwrap();
+        assert_eq!(parsed.to_string(), string);
+        let parsed: CratesQuery = "?q=serde".parse().unwrap();
+        assert_eq!(parsed.search().map(String::as_str), Some("serde"));
+        assert_eq!(parsed.page_size(), 30);
+
+        assert!("sort=loudest".parse::<CratesQuery>().is_err());
+        assert!("page=x".parse::<CratesQuery>().is_err());
+        assert!("per_page=500".parse::<CratesQuery>().is_err());
+        assert!("cursor=abc".parse::<CratesQuery>().is_err());
+
+        let mut query = CratesQuery::builder()
+            .sort(Sort::Alphabetical)
+            .page_size(100)
+            .build();
+        query.seek = Some("WzEsMl0=".to_string());
+        let string = query.to_string();
+        assert_eq!(string, "seek=WzEsMl0%3D&per_page=100&sort=alpha");
+        let parsed: CratesQuery = string.parse().unwrap();
+        assert_eq!(parsed.seek.as_deref(), Some("WzEsMl0="));
+        assert_eq!(parsed.to_string(), string);
+        let mut meta = Meta::new(1);
+        meta.next_page = Some(format!("?{}", string));
+        let parsed: CratesQuery = meta.next_page.unwrap().parse().unwrap();
+        assert_eq!(parsed.to_string(), string);
+    }
+
+    #[test]
+    fn test_crates_query_sort() {
+        let expected = [
+            (Sort::Alphabetical, "alpha"),
+            (Sort::Relevance, "relevance"),
+            (Sort::Downloads, "downloads"),
+            (Sort::RecentDownloads, "recent-downloads"),
+            (Sort::RecentUpdates, "recent-updates"),
+            (Sort::NewlyAdded, "new"),
+        ];
+        assert_eq!(expected.len(), Sort::ALL.len());
+
+        for (sort, value) in expected {
+            let query = CratesQuery::builder().sort(sort).build();
+            assert_eq!(
+                query_string(&query),
+                format!("page=1&per_page=30&sort={value}")
+            );
+        }
+    }
+
+    #[test]
+    fn test_crates_query_all_keywords() {
+        let query = CratesQuery::builder()
+            .keyword("api")
+            .keyword("http")
+            .build();
+        assert_eq!(
+            query_string(&query),
+            "page=1&per_page=30&sort=recent-updates&all_keywords=api+http"
+        );
+    }
+
+    #[test]
+    fn test_crates_query_validate() {
+        assert!(CratesQuery::builder().page_size(100).try_build().is_ok());
+        assert!(CratesQuery::builder().page_size(0).try_build().is_err());
+        assert!(CratesQuery::builder().page_size(101).try_build().is_err());
+        assert!(CratesQuery::builder().page(0).try_build().is_err());
+        assert!(CratesQuery::builder()
+            .user_id(1)
+            .team_id(2)
+            .try_build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_crates_query_encoding() {
+        let query = CratesQuery::builder()
+            .search("a&b=c d")
+            .category("no/slash")
+            .build();
+        assert_eq!(
+            query_string(&query),
+            "page=1&per_page=30&sort=recent-updates&q=a%26b%3Dc+d&category=no%2Fslash"
+        );
+    }
+
+    #[test]
+    fn test_full_crate_options_progress() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let options = FullCrateOptions::new().on_progress({
+            let reports = reports.clone();
+            move |p| {
+                reports.lock().unwrap().push((
+                    p.version.clone(),
+                    p.completed_versions,
+                    p.total_versions,
+                ))
+            }
+        });
+
+        let completed = AtomicUsize::new(0);
+        options.report_progress(&completed, 2, "0.2.0");
+        options.report_progress(&completed, 2, "0.1.0");
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            vec![("0.2.0".to_string(), 1, 2), ("0.1.0".to_string(), 2, 2)]
+        );
+    }
+
+    fn dependency(name: &str, req: &str, kind: &str) -> Dependency {
+        let mut dep = Dependency::new(name, req);
+        dep.kind = kind.into();
+        dep
+    }
+
+    #[test]
+    fn test_dependency_diff() {
+        let mut features = dependency("serde", "^1", "normal");
+        features.features = vec!["derive".into(), "std".into()];
+        let old = vec![
+            dependency("url", "^2", "normal"),
+            dependency("log", "^0.4", "normal"),
+            dependency("tokio", "^1", "dev"),
+            features.clone(),
+        ];
+        features.features.reverse();
+        let new = vec![
+            features,
+            dependency("tokio", "^1", "normal"),
+            dependency("log", "^0.4.14", "normal"),
+            dependency("url", "^2", "normal"),
+        ];
+
+        let diff = DependencyDiff::new(&old, &new);
+        let names = |deps: &[Dependency]| -> Vec<String> {
+            deps.iter()
+                .map(|d| format!("{} {}", d.crate_id, d.kind))
+                .collect()
+        };
+        assert_eq!(names(&diff.added), ["tokio normal"]);
+        assert_eq!(names(&diff.removed), ["tokio dev"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].new.crate_id, "log");
+        assert!(diff.changed[0].req_changed());
+
+        assert!(DependencyDiff::new(&old, &old).is_empty());
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_version_filter() {
+        let filter = VersionFilter::Matching("^1.2".parse().unwrap());
+        assert!(filter.matches("1.3.0"));
+        assert!(!filter.matches("2.0.0"));
+        assert!(!filter.matches("1.3.0-rc.1"));
+        assert!(!filter.matches("not-semver"));
+        assert!(VersionFilter::Latest(1).matches("not-semver"));
+    }
+
+    fn crate_response(versions: &[(&str, bool)]) -> CrateResponse {
+        let versions = versions
+            .iter()
+            .enumerate()
+            .map(|(id, (num, yanked))| {
+                let mut version = Version::new("a", num);
+                version.id = id as u64;
+                version.yanked = *yanked;
+                version
+            })
+            .collect();
+        CrateResponse::new(Crate::new("a", "1.0.0"), versions)
+    }
+
+    #[test]
+    fn test_constructors() {
+        let mut version = Version::new("a", "1.0.0");
+        version.yanked = true;
+        let krate = CrateResponse::new(Crate::new("a", "1.0.0"), vec![version]);
+        let json = serde_json::to_value(&krate).unwrap();
+        let krate: CrateResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(krate.crate_data.name, "a");
+        assert_eq!(krate.versions[0].dl_path, "/api/v1/crates/a/1.0.0/download");
+        assert!(krate.versions[0].yanked);
+
+        let page = CratesPage::new(vec![krate.crate_data], 10);
+        assert_eq!(page.meta.total, 10);
+        assert_eq!(User::new(1, "octocat").url, "https://github.com/octocat");
+    }
+
+    #[test]
+    fn test_owner_change_outcomes() {
+        let response = OwnerChangeResponse {
+            ok: true,
+            msg: "user alice has been invited to be an owner of crate foo,team github:org:team has been added as an owner of crate foo,user bob already has a pending invitation to be an owner of crate foo".to_string(),
+        };
+        let outcomes: Vec<_> = response
+            .outcomes(&["alice", "github:org:team", "bob", "al"], false)
+            .into_iter()
+            .map(|change| change.outcome)
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                OwnerChangeOutcome::Invited,
+                OwnerChangeOutcome::Added,
+                OwnerChangeOutcome::AlreadyInvited,
+                OwnerChangeOutcome::Unknown,
+            ]
+        );
+        assert_eq!(
+            response.outcomes(&["alice"], true)[0].outcome,
+            OwnerChangeOutcome::Removed
+        );
+
+        let errors = ApiErrors::new("could not find user with login `nobody`");
+        let outcomes = OwnerChangeResponse::failures(&errors, &["alice", "nobody"]);
+        assert_eq!(outcomes[0].outcome, OwnerChangeOutcome::NotApplied);
+        assert_eq!(
+            outcomes[1].outcome,
+            OwnerChangeOutcome::Failed("could not find user with login `nobody`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_meta_links() {
+        let meta: Meta = serde_json::from_value(serde_json::json!({
+            "total": 250,
+            "next_page": "?page=3&per_page=100&q=serde",
+            "prev_page": "?page=1&per_page=100&q=serde",
+        }))
+        .unwrap();
+        assert!(meta.has_next_page());
+        assert_eq!(meta.next_page_number(), Some(3));
+        assert_eq!(meta.next_seek(), None);
+
+        let meta: Meta = serde_json::from_value(serde_json::json!({
+            "total": 250,
+            "next_page": "?seek=WzEsMl0%3D",
+        }))
+        .unwrap();
+        assert_eq!(meta.next_seek().as_deref(), Some("WzEsMl0="));
+        assert_eq!(meta.next_page_number(), None);
+
+        let meta: Meta = serde_json::from_str(r#"{"total":1}"#).unwrap();
+        assert_eq!(meta, Meta::new(1));
+        assert!(!meta.has_next_page());
+    }
+
+    #[cfg(feature = "extra-fields")]
+    #[test]
+    fn test_extra_fields() {
+        let mut json = serde_json::to_value(Crate::new("a", "1.0.0")).unwrap();
+        json["trustpub_only"] = true.into();
+        let krate: Crate = serde_json::from_value(json).unwrap();
+        assert_eq!(krate.extra["trustpub_only"], true);
+        assert!(!krate.extra.contains_key("name"));
+        assert_eq!(serde_json::to_value(&krate).unwrap()["trustpub_only"], true);
+    }
+
+    #[test]
+    fn test_dependency_kind() {
+        let kinds: Vec<DependencyKind> =
+            serde_json::from_value(serde_json::json!(["normal", "build", "dev", "peer"])).unwrap();
+        assert_eq!(
+            kinds,
+            [
+                DependencyKind::Normal,
+                DependencyKind::Build,
+                DependencyKind::Dev,
+                DependencyKind::Other("peer".into()),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&kinds).unwrap(),
+            serde_json::json!(["normal", "build", "dev", "peer"])
+        );
+    }
+
+    #[test]
+    fn test_owner_from_user() {
+        let user = User::new(1, "octocat");
+        let mut team = User::new(2, "github:rust-lang:libs");
+        team.kind = Some("team".to_string());
+        team.name = Some("libs".to_string());
+        let owners = Owners {
+            users: vec![user, team],
+        };
+        let owners: Vec<Owner> = owners.users.into_iter().map(Owner::from).collect();
+        assert!(matches!(&owners[0], Owner::User(user) if user.login == "octocat"));
+        match &owners[1] {
+            Owner::Team(team) => {
+                assert_eq!(team.login, "github:rust-lang:libs");
+                assert_eq!(team.name.as_deref(), Some("libs"));
+            }
+            owner => panic!("expected a team, got {:?}", owner),
+        }
+        assert_eq!(owners[1].login(), "github:rust-lang:libs");
+    }
+
+    #[test]
+    fn test_version_metadata_fields() {
+        let mut json = serde_json::to_value(Version::new("a", "1.0.0")).unwrap();
+        json["rust_version"] = "1.60".into();
+        json["edition"] = "2021".into();
+        json["crate_size"] = 1024.into();
+        json["checksum"] = "abc".into();
+        let version: Version = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(version.rust_version.as_deref(), Some("1.60"));
+        assert_eq!(version.edition.as_deref(), Some("2021"));
+        assert_eq!(version.crate_size, Some(1024));
+        assert_eq!(version.checksum.as_deref(), Some("abc"));
+
+        json.as_object_mut().unwrap().remove("edition");
+        json.as_object_mut().unwrap().remove("checksum");
+        let version: Version = serde_json::from_value(json).unwrap();
+        assert!(version.edition.is_none() && version.checksum.is_none());
+        assert!(!version.has_bins());
+    }
+
+    #[test]
+    fn test_version_targets() {
+        let mut json = serde_json::to_value(Version::new("a", "1.0.0")).unwrap();
+        json["has_lib"] = false.into();
+        json["bin_names"] = serde_json::json!(["a-cli"]);
+        let version: Version = serde_json::from_value(json).unwrap();
+        assert_eq!(version.has_lib, Some(false));
+        assert!(version.has_bins());
+    }
+
+    #[test]
+    fn test_default_version() {
+        let mut krate = crate_response(&[("2.0.0-rc.1", false), ("1.0.0", false)]);
+        assert!(krate.default_version().is_none());
+        assert!(krate.crate_data.num_versions.is_none());
+
+        krate.crate_data.default_version = Some("1.0.0".into());
+        assert_eq!(krate.default_version().unwrap().num, "1.0.0");
+    }
+
+    fn reverse_dependencies(deps: &[(&str, u64, u64)]) -> ReverseDependencies {
+        let received = ReverseDependenciesAsReceived {
+            dependencies: deps
+                .iter()
+                .map(|(_, version_id, downloads)| {
+                    let mut dep = Dependency::new("a", "^1");
+                    dep.version_id = *version_id;
+                    dep.downloads = *downloads;
+                    dep
+                })
+                .collect(),
+            versions: deps
+                .iter()
+                .map(|(name, version_id, _)| {
+                    let mut version = Version::new(name, "1.0.0");
+                    version.id = *version_id;
+                    version
+                })
+                .collect(),
+            meta: Meta::new(deps.len() as u64),
+        };
+        let mut rdeps = ReverseDependencies {
+            dependencies: Vec::new(),
+            meta: received.meta.clone(),
+        };
+        rdeps.extend(received);
+        rdeps
+    }
+
+    #[test]
+    fn test_reverse_dependencies() {
+        let mut rdeps = reverse_dependencies(&[("b", 1, 10), ("c", 2, 500), ("d", 3, 10)]);
+        let rdep = &rdeps.dependencies[0];
+        assert_eq!(
+            (
+                rdep.crate_name(),
+                rdep.version(),
+                rdep.req(),
+                rdep.downloads()
+            ),
+            ("b", "1.0.0", "^1", 10)
+        );
+
+        rdeps.sort_by_downloads();
+        let names: Vec<_> = rdeps.dependencies.iter().map(|d| d.crate_name()).collect();
+        assert_eq!(names, ["c", "b", "d"]);
+    }
+
+    #[test]
+    fn test_reverse_dependencies_by_crate() {
+        let mut rdeps = reverse_dependencies(&[("b", 1, 10), ("c", 2, 5), ("b", 3, 10)]);
+        rdeps.dependencies[0].crate_version.num = "1.10.0".into();
+        rdeps.dependencies[2].crate_version.num = "1.9.0".into();
+        rdeps.dependencies[2].dependency.req = "^0.9".into();
+
+        let by_crate = rdeps.by_crate();
+        let deps: Vec<_> = by_crate
+            .iter()
+            .map(|d| (d.crate_name(), d.version(), d.req()))
+            .collect();
+        assert_eq!(deps, [("b", "1.10.0", "^1"), ("c", "1.0.0", "^1")]);
+    }
+
+    #[test]
+    fn test_request_options() {
+        let url = reqwest::Url::parse("https://crates.io/api/v1/crates?page=1").unwrap();
+        assert_eq!(RequestOptions::new().apply_query(&url), url);
+
+        let options = RequestOptions::new()
+            .query("include", "")
+            .query("x", "a b")
+            .header(
+                reqwest::header::HeaderName::from_static("traceparent"),
+                reqwest::header::HeaderValue::from_static("00-1-2-01"),
+            );
+        assert_eq!(
+            options.apply_query(&url).as_str(),
+            "https://crates.io/api/v1/crates?page=1&include=&x=a+b"
+        );
+        assert_eq!(options.headers["traceparent"], "00-1-2-01");
+    }
+
+    #[test]
+    fn test_authors_from_response() {
+        let authors = Authors::from_response(Ok(vec!["A <a@b.c>".to_string()])).unwrap();
+        assert_eq!(authors.names, ["A <a@b.c>"]);
+        assert!(!authors.unavailable);
+
+        let not_found = Error::NotFound(crate::NotFoundError {
+            url: "https://crates.io/api/v1/crates/a/1.0.0/authors".into(),
+        });
+        let authors = Authors::from_response(Err(not_found)).unwrap();
+        assert!(authors.names.is_empty() && authors.unavailable);
+
+        let invalid = Error::InvalidQuery(InvalidQueryError::new("test"));
+        assert!(Authors::from_response(Err(invalid)).is_err());
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_semver_accessors() {
+        let mut krate = crate_response(&[("1.0.0", false)]).crate_data;
+        assert_eq!(
+            krate.max_semver().unwrap(),
+            crate::semver::Version::new(1, 0, 0)
+        );
+        assert!(krate.newest_semver().is_none());
+        krate.max_stable_version = Some("0.1".into());
+        assert!(krate.max_stable_semver().unwrap().is_err());
+
+        let dep = dependency("serde", "0.9.*", "normal");
+        let req = dep.semver_req().unwrap();
+        assert!(req.matches(&"0.9.3".parse().unwrap()));
+        assert!(dependency("serde", "latest", "normal")
+            .semver_req()
+            .is_err());
+    }
+
+    #[test]
+    fn test_crate_response_serialize_roundtrip() {
+        let krate = crate_response(&[("1.0.0", false), ("0.1.0", true)]);
+        let json = serde_json::to_value(&krate).unwrap();
+        let again: CrateResponse = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), json);
+        assert_eq!(json["crate"]["name"], "a");
+        assert_eq!(json["versions"][1]["yanked"], true);
+    }
+
+    #[test]
+    fn test_timestamp_fields() {
+        let category: Category = serde_json::from_value(serde_json::json!({
+            "category": "Parsing",
+            "crates_cnt": 1,
+            "created_at": "2017-01-06T14:23:11.123456+00:00",
+            "description": "",
+            "id": "parsing",
+            "slug": "parsing",
+        }))
+        .unwrap();
+        assert_eq!(
+            category.created_at,
+            timestamp("2017-01-06T14:23:11.123456Z")
+        );
+        assert_eq!(category.created_at.unix_timestamp(), 1483712591);
+
+        let json = serde_json::to_value(&category).unwrap();
+        let again: Category = serde_json::from_value(json).unwrap();
+        assert_eq!(again.created_at, category.created_at);
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_crate_response_semver_helpers() {
+        let krate = crate_response(&[
+            ("2.0.0-rc.1", false),
+            ("1.10.0", true),
+            ("1.9.0", false),
+            ("1.2.0", false),
+            ("not-semver", false),
+            ("0.9.0", false),
+        ]);
+        let nums = |versions: Vec<&Version>| -> Vec<String> {
+            versions.into_iter().map(|v| v.num.clone()).collect()
+        };
+
+        let req = "^1.2".parse().unwrap();
+        assert_eq!(nums(krate.versions_matching(&req)), ["1.9.0", "1.2.0"]);
+        assert_eq!(krate.latest_matching(&req).unwrap().num, "1.9.0");
+        assert_eq!(krate.latest_stable().unwrap().num, "1.9.0");
+
+        let req = ">=2.0.0-rc.1".parse().unwrap();
+        assert_eq!(krate.latest_matching(&req).unwrap().num, "2.0.0-rc.1");
+        assert!(krate.latest_matching(&"^3".parse().unwrap()).is_none());
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_update_suggestion() {
+        let krate = crate_response(&[
+            ("2.1.0-rc.1", false),
+            ("2.0.0", false),
+            ("1.9.0", true),
+            ("1.3.0", false),
+            ("1.2.5", false),
+            ("1.2.0", false),
+        ]);
+        let suggest = |current: &str| UpdateSuggestion::new(&current.parse().unwrap(), &krate);
+        let num = |v: &Option<SuggestedVersion>| v.as_ref().map(|v| (v.num.clone(), v.kind));
+
+        let suggestion = suggest("1.2.0");
+        assert_eq!(
+            num(&suggestion.compatible),
+            Some(("1.3.0".into(), UpdateKind::Minor))
+        );
+        assert_eq!(
+            num(&suggestion.incompatible),
+            Some(("2.0.0".into(), UpdateKind::Major))
+        );
+        assert!(!suggestion.incompatible.unwrap().prerelease);
+
+        let suggestion = suggest("2.0.0");
+        let compatible = suggestion.compatible.unwrap();
+        assert_eq!(
+            (compatible.num.as_str(), compatible.prerelease),
+            ("2.1.0-rc.1", true)
+        );
+        assert!(suggestion.incompatible.is_none());
+
+        let suggestion = suggest("1.3.0");
+        assert!(suggestion.compatible.is_none());
+        assert_eq!(
+            serde_json::to_value(&suggestion.incompatible).unwrap()["kind"],
+            "major"
+        );
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_update_check() {
+        let krate = crate_response(&[("2.0.0", false), ("1.9.0", false), ("1.2.0", false)]);
+        let check = |req: &str| UpdateCheck::new(req, &req.parse().unwrap(), &krate);
+
+        let outdated = check("^1.2");
+        assert_eq!(outdated.latest_matching.as_deref(), Some("1.9.0"));
+        assert_eq!(outdated.latest.as_deref(), Some("2.0.0"));
+        assert!(outdated.is_outdated());
+
+        assert!(!check("2").is_outdated());
+        assert!(check("^3").is_outdated());
+    }
+
+    fn date(s: &str) -> Date {
+        s.parse().unwrap()
+    }
+
+    fn crate_downloads() -> CrateDownloads {
+        let version_downloads = |day: &str, downloads: u64, version: u64| VersionDownloads {
+            date: date(day),
+            downloads,
+            version,
+        };
+        CrateDownloads {
+            version_downloads: vec![
+                version_downloads("2022-01-28", 1, 1),
+                version_downloads("2022-01-28", 2, 2),
+                version_downloads("2022-01-31", 4, 1),
+                version_downloads("2022-02-02", 8, 2),
+            ],
+            meta: CrateDownloadsMeta {
+                extra_downloads: vec![ExtraDownloads {
+                    date: date("2022-01-31"),
+                    downloads: 16,
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn test_crate_downloads_daily() {
+        let daily: Vec<_> = crate_downloads()
+            .daily()
+            .into_iter()
+            .map(|d| (d.start.to_string(), d.downloads))
+            .collect();
+        let expected = [
+            ("2022-01-28", 3),
+            ("2022-01-29", 0),
+            ("2022-01-30", 0),
+            ("2022-01-31", 20),
+            ("2022-02-01", 0),
+            ("2022-02-02", 8),
+        ];
+        let expected: Vec<_> = expected.iter().map(|(d, n)| (d.to_string(), *n)).collect();
+        assert_eq!(daily, expected);
+    }
+
+    #[test]
+    fn test_ownership_overlap() {
+        let overlap = OwnershipOverlap::new(vec![
+            (
+                "a".to_string(),
+                vec![User::new(1, "alice"), User::new(2, "bob")],
+            ),
+            ("b".to_string(), vec![User::new(1, "alice")]),
+            (
+                "c".to_string(),
+                vec![User::new(1, "alice"), User::new(1, "github:o:t")],
+            ),
+        ]);
+        let logins = |users: Vec<&User>| -> Vec<_> {
+            users.into_iter().map(|user| user.login.clone()).collect()
+        };
+        assert_eq!(logins(overlap.shared()), ["alice"]);
+        assert_eq!(logins(overlap.all()), ["alice", "bob", "github:o:t"]);
+        assert_eq!(overlap.crates_of("alice"), ["a", "b", "c"]);
+        assert_eq!(overlap.crates_of("bob"), ["a"]);
+        let counts: Vec<_> = overlap
+            .by_crate_count()
+            .into_iter()
+            .map(|(user, count)| (user.login.as_str(), count))
+            .collect();
+        assert_eq!(counts, [("alice", 3), ("bob", 1), ("github:o:t", 1)]);
+    }
+
+    #[test]
+    fn test_crate_downloads_total_by_date() {
+        let days = crate_downloads().total_by_date();
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0].date, date("2022-01-28"));
+        assert_eq!((days[0].versions, days[0].extra), (3, 0));
+        assert_eq!(days[1].date, date("2022-01-31"));
+        assert_eq!((days[1].versions, days[1].extra), (4, 16));
+        assert_eq!(days[1].total(), 20);
+        assert_eq!(days[2].total(), 8);
+    }
+
+    #[test]
+    fn test_crate_downloads_aggregation() {
+        let downloads = crate_downloads();
+        assert_eq!(downloads.total(), 31);
+        assert_eq!(downloads.trailing_total(3), 28);
+        assert_eq!(downloads.trailing_total(90), 31);
+
+        let weekly = downloads.weekly();
+        assert_eq!(
+            weekly,
+            [
+                DownloadPeriod {
+                    start: date("2022-01-24"),
+                    downloads: 3
+                },
+                DownloadPeriod {
+                    start: date("2022-01-31"),
+                    downloads: 28
+                },
+            ]
+        );
+        let monthly = downloads.monthly();
+        assert_eq!(monthly[0].start, date("2022-01-01"));
+        assert_eq!(monthly[0].downloads, 23);
+        assert_eq!(monthly[1].start, date("2022-02-01"));
+        assert_eq!(monthly[1].downloads, 8);
+    }
+
+    #[test]
+    fn test_crate_downloads_trends() {
+        let downloads = crate_downloads();
+        let between = downloads.between(date("2022-01-29"), date("2022-01-31"));
+        assert_eq!(between.total(), 20);
+        assert_eq!(between.daily().len(), 1);
+
+        // 2022-01-31..2022-02-02 (28) compared to 2022-01-28..2022-01-30 (3).
+        let rate = downloads.period_over_period(3).unwrap();
+        assert!((rate - 25.0 / 3.0).abs() < 1e-9);
+        assert_eq!(downloads.period_over_period(4), None);
+        assert_eq!(downloads.week_over_week(), None);
+
+        let rates = DownloadPeriod::growth_rates(&downloads.weekly());
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0], None);
+        assert!((rates[1].unwrap() - 25.0 / 3.0).abs() < 1e-9);
+        assert!(DownloadPeriod::growth_rates(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_crates_query_ids() {
+        let query = CrateComparison::query(&["serde", "tokio"]);
+        assert_eq!(
+            query_string(&query),
+            "page=1&per_page=100&sort=recent-updates&ids%5B%5D=serde&ids%5B%5D=tokio"
+        );
+    }
+
+    #[test]
+    fn test_crate_comparison_match_crates() {
+        let krate = |name: &str| -> Crate {
+            let mut krate = crate_response(&[]).crate_data;
+            krate.name = name.to_string();
+            krate
+        };
+        let found = vec![krate("tokio"), krate("async-std"), krate("serde")];
+        let (crates, missing) =
+            CrateComparison::match_crates(&["serde", "missing", "async_std"], found.clone());
+        let names: Vec<_> = crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["serde", "async-std"]);
+        assert_eq!(missing, ["missing"]);
+
+        let (crates, missing) = CrateComparison::match_crates(
+            &[
+                "serde",
+                "async-std",
+                "serde",
+                "missing",
+                "async_std",
+                "missing",
+            ],
+            found,
+        );
+        let names: Vec<_> = crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["serde", "async-std"]);
+        assert_eq!(missing, ["missing"]);
+    }
+
+    #[test]
+    fn test_crates_query_team_id() {
+        let query = CratesQuery::builder().team_id(42).build();
+        assert_eq!(
+            query_string(&query),
+            "page=1&per_page=30&sort=recent-updates&team_id=42"
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! Polling-based monitoring of crate releases.
+
+use futures::prelude::*;
+use futures::stream::BoxStream;
+use serde_derive::*;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{AsyncClient, CrateResponse, CratesQuery, Error, Sort};
+
+/// The crates monitored by a [`Watcher`].
+#[derive(Debug, Clone)]
+pub enum WatchTarget {
+    /// A fixed list of crates.
+    Crates(Vec<String>),
+    /// All crates in a category, identified by its slug.
+    ///
+    /// The list of crates is refreshed on every poll.
+    Category(String),
+}
+
+/// An event emitted by a [`Watcher`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A new version was published.
+    NewVersion {
+        /// The crate name.
+        crate_name: String,
+        /// The new version.
+        version: String,
+    },
+    /// A version was yanked.
+    Yanked {
+        /// The crate name.
+        crate_name: String,
+        /// The yanked version.
+        version: String,
+    },
+    /// A previously yanked version was restored.
+    Unyanked {
+        /// The crate name.
+        crate_name: String,
+        /// The restored version.
+        version: String,
+    },
+    /// The owners of a crate changed.
+    OwnerChanged {
+        /// The crate name.
+        crate_name: String,
+        /// Logins of the new owners.
+        added: Vec<String>,
+        /// Logins of the removed owners.
+        removed: Vec<String>,
+    },
+}
+
+/// The known state of all watched crates.
+///
+/// Can be persisted with serde and restored with [`Watcher::with_state`],
+/// so that no events are lost or repeated across restarts.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WatcherState {
+    /// The state of every crate seen so far.
+    pub crates: BTreeMap<String, CrateState>,
+}
+
+/// The known state of a single crate.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrateState {
+    /// All versions, with their yanked status.
+    pub versions: BTreeMap<String, bool>,
+    /// Logins of all owners, if owners are watched.
+    pub owners: Option<BTreeSet<String>>,
+}
+
+impl CrateState {
+    /// Get the state of a fetched crate, with the logins of its owners if
+    /// they are watched.
+    pub(crate) fn new(krate: &CrateResponse, owners: Option<BTreeSet<String>>) -> Self {
+        Self {
+            versions: krate
+                .versions
+                .iter()
+                .map(|v| (v.num.clone(), v.yanked))
+                .collect(),
+            owners,
+        }
+    }
+
+    /// Get the events that lead from this state to a newer one.
+    pub fn diff(&self, crate_name: &str, new: &CrateState) -> Vec<WatchEvent> {
+        let mut events = Vec::new();
+        for (version, yanked) in &new.versions {
+            let event = match (self.versions.get(version), yanked) {
+                (None, _) => WatchEvent::NewVersion {
+                    crate_name: crate_name.to_string(),
+                    version: version.clone(),
+                },
+                (Some(false), true) => WatchEvent::Yanked {
+                    crate_name: crate_name.to_string(),
+                    version: version.clone(),
+                },
+                (Some(true), false) => WatchEvent::Unyanked {
+                    crate_name: crate_name.to_string(),
+                    version: version.clone(),
+                },
+                _ => continue,
+            };
+            events.push(event);
+        }
+
+        if let (Some(old), Some(new)) = (&self.owners, &new.owners) {
+            if old != new {
+                events.push(WatchEvent::OwnerChanged {
+                    crate_name: crate_name.to_string(),
+                    added: new.difference(old).cloned().collect(),
+                    removed: old.difference(new).cloned().collect(),
+                });
+            }
+        }
+        events
+    }
+}
+
+type StateCallback = Arc<dyn Fn(&WatcherState) + Send + Sync>;
+
+/// Monitors crates for new releases, yanks and owner changes.
+///
+/// Crates that are seen for the first time only establish a baseline and
+/// don't emit events.
+///
+/// Every poll requires one request per watched crate, plus one more if owners
+/// are watched. All requests go through the rate limit of the client, so the
+/// poll interval is the pause between the end of one poll and the start of
+/// the next.
+///
+/// ```rust,no_run
+/// # use crates_io_api::{AsyncClient, Error};
+/// # use crates_io_api::watcher::{Watcher, WatchTarget};
+/// # use futures::prelude::*;
+/// # async fn run(client: AsyncClient) -> Result<(), Error> {
+/// let target = WatchTarget::Crates(vec!["serde".to_string()]);
+/// let mut events = Watcher::new(client, target)
+///     .interval(std::time::Duration::from_secs(600))
+///     .into_stream();
+/// while let Some(event) = events.next().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Watcher {
+    client: AsyncClient,
+    target: WatchTarget,
+    interval: Duration,
+    owners: bool,
+    state: WatcherState,
+    on_state_change: Option<StateCallback>,
+}
+
+impl Watcher {
+    /// Construct a watcher with a poll interval of 5 minutes, which also
+    /// watches owners.
+    pub fn new(client: AsyncClient, target: WatchTarget) -> Self {
+        Self {
+            client,
+            target,
+            interval: Duration::from_secs(300),
+            owners: true,
+            state: WatcherState::default(),
+            on_state_change: None,
+        }
+    }
+
+    /// Set the pause between two polls.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Watch for owner changes.
+    #[must_use]
+    pub fn owners(mut self, watch: bool) -> Self {
+        self.owners = watch;
+        self
+    }
+
+    /// Resume from a previously persisted state.
+    #[must_use]
+    pub fn with_state(mut self, state: WatcherState) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Register a callback that is invoked with the new state after every poll,
+    /// eg to persist it.
+    #[must_use]
+    pub fn on_state_change<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&WatcherState) + Send + Sync + 'static,
+    {
+        self.on_state_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// Get the current state.
+    pub fn state(&self) -> &WatcherState {
+        &self.state
+    }
+
+    /// Poll all watched crates once, and return the events since the last
+    /// poll.
+    ///
+    /// Errors are reported per crate, and the state of failed crates is left
+    /// untouched so they are checked again on the next poll.
+    pub async fn poll(&mut self) -> Vec<Result<WatchEvent, Error>> {
+        let names = match &self.target {
+            WatchTarget::Crates(names) => names.clone(),
+            WatchTarget::Category(slug) => {
+                let query = CratesQuery::builder()
+                    .category(slug)
+                    .sort(Sort::Alphabetical)
+                    .page_size(CratesQuery::MAX_PAGE_SIZE)
+                    .build();
+                match self
+                    .client
+                    .crates_stream(query)
+                    .map_ok(|krate| krate.name)
+                    .try_collect()
+                    .await
+                {
+                    Ok(names) => names,
+                    Err(err) => return vec![Err(err)],
+                }
+            }
+        };
+
+        let mut events = Vec::new();
+        for name in names {
+            match self.fetch(&name).await {
+                Ok(new) => {
+                    if let Some(old) = self.state.crates.get(&name) {
+                        events.extend(old.diff(&name, &new).into_iter().map(Ok));
+                    }
+                    self.state.crates.insert(name, new);
+                }
+                Err(err) => events.push(Err(err)),
+            }
+        }
+
+        if let Some(callback) = &self.on_state_change {
+            callback(&self.state);
+        }
+        events
+    }
+
+    async fn fetch(&self, crate_name: &str) -> Result<CrateState, Error> {
+        let krate = self.client.get_crate(crate_name).await?;
+        let owners = if self.owners {
+            let owners = self.client.crate_owners(crate_name).await?;
+            Some(owners.into_iter().map(|user| user.login).collect())
+        } else {
+            None
+        };
+        Ok(CrateState::new(&krate, owners))
+    }
+
+    /// Convert the watcher into an endless stream of events.
+    ///
+    /// The first poll happens right away.
+    pub fn into_stream(self) -> BoxStream<'static, Result<WatchEvent, Error>> {
+        stream::unfold(
+            (self, VecDeque::new(), true),
+            |(mut watcher, mut pending, mut first)| async move {
+                while pending.is_empty() {
+                    if !first {
+                        tokio::time::sleep(watcher.interval).await;
+                    }
+                    first = false;
+                    pending.extend(watcher.poll().await);
+                }
+                let event = pending.pop_front()?;
+                Some((event, (watcher, pending, first)))
+            },
+        )
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn state(versions: &[(&str, bool)], owners: Option<&[&str]>) -> CrateState {
+        CrateState {
+            versions: versions
+                .iter()
+                .map(|(num, yanked)| (num.to_string(), *yanked))
+                .collect(),
+            owners: owners.map(|owners| owners.iter().map(|o| o.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn test_crate_state_diff() {
+        let old = state(&[("1.0.0", false), ("1.1.0", true)], Some(&["a", "b"]));
+        let new = state(
+            &[("1.0.0", true), ("1.1.0", false), ("1.2.0", false)],
+            Some(&["b", "c"]),
+        );
+
+        let events = old.diff("krate", &new);
+        assert_eq!(
+            events,
+            [
+                WatchEvent::Yanked {
+                    crate_name: "krate".into(),
+                    version: "1.0.0".into(),
+                },
+                WatchEvent::Unyanked {
+                    crate_name: "krate".into(),
+                    version: "1.1.0".into(),
+                },
+                WatchEvent::NewVersion {
+                    crate_name: "krate".into(),
+                    version: "1.2.0".into(),
+                },
+                WatchEvent::OwnerChanged {
+                    crate_name: "krate".into(),
+                    added: vec!["c".into()],
+                    removed: vec!["a".into()],
+                },
+            ]
+        );
+        assert!(new.diff("krate", &new).is_empty());
+    }
+
+    #[test]
+    fn test_crate_state_diff_unwatched_owners() {
+        let old = state(&[("1.0.0", false)], None);
+        let new = state(&[("1.0.0", false)], Some(&["a"]));
+        assert!(old.diff("krate", &new).is_empty());
+    }
+}